@@ -0,0 +1,115 @@
+//! A behaviour plugin built on the public API: pets get the zoomies.
+//!
+//! It reacts two ways, one per extension point. Poking a pet that is already
+//! jumping sends it dashing to the other side of the screen: a system reads
+//! the [`Intent`] and writes the pet's interrupt and target itself. And every
+//! so often an [`InterruptSource`] sends whichever pet has settled down
+//! furthest from the middle on a dash of its own.
+//!
+//! Run with `cargo run --example zoomies`. It is the whole app plus this
+//! plugin, so every command-line option works as it does for `batates`.
+
+use std::time::{Duration, Instant};
+
+use batates::{
+    AddInterruptSource, Intent, InterruptSource, MoveTarget, PendingInterrupt, Pet, PetBrain,
+    PetSnapshot, PetState, PetSystems, Raised, Say,
+};
+use bevy::prelude::*;
+
+fn main() {
+    batates::run(ZoomiesPlugin);
+}
+
+struct ZoomiesPlugin;
+
+impl Plugin for ZoomiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_interrupt_source(Zoomies::every(Duration::from_secs(90)))
+            .add_systems(
+                Update,
+                // Where every reaction goes: after this frame's intents are
+                // known, before the brain acts on interrupts.
+                dash_on_poke
+                    .after(PetSystems::Normalize)
+                    .before(PetSystems::Brain),
+            );
+    }
+}
+
+/// The same height on the other side of the screen.
+fn far_side(position: Vec2) -> Vec2 {
+    Vec2::new(-position.x, position.y)
+}
+
+fn dash_on_poke(
+    mut intents: MessageReader<Intent>,
+    mut says: MessageWriter<Say>,
+    mut pets: Query<
+        (
+            &PetBrain,
+            &Transform,
+            &mut PendingInterrupt,
+            &mut MoveTarget,
+        ),
+        With<Pet>,
+    >,
+) {
+    for intent in intents.read() {
+        let Intent::Poke { pet } = *intent else {
+            continue;
+        };
+        let Ok((brain, transform, mut interrupt, mut target)) = pets.get_mut(pet) else {
+            continue;
+        };
+        // The app has already answered the poke with a jump; this overrides
+        // it, which is fine for a pet that was jumping anyway.
+        if brain.locked() || brain.state() != PetState::Jumping {
+            continue;
+        }
+        target.0 = Some(far_side(transform.translation.truncate()));
+        interrupt.0 = Some(PetState::Walking);
+        says.write(Say {
+            pet: Some(pet),
+            text: "Zoom!".to_string(),
+        });
+    }
+}
+
+/// Now and then, the resting pet furthest from the middle dashes across.
+struct Zoomies {
+    every: Duration,
+    last: Instant,
+}
+
+impl Zoomies {
+    fn every(every: Duration) -> Self {
+        Self {
+            every,
+            last: Instant::now(),
+        }
+    }
+}
+
+impl InterruptSource for Zoomies {
+    fn poll(&mut self, pets: &[PetSnapshot]) -> Vec<Raised> {
+        if self.last.elapsed() < self.every {
+            return Vec::new();
+        }
+        self.last = Instant::now();
+        // Locked and busy pets are filtered out after this anyway; skipping
+        // them here means a free pet gets the turn instead.
+        let resting = pets.iter().filter(|pet| {
+            !pet.locked && !pet.pending && matches!(pet.state, PetState::Idle | PetState::Sitting)
+        });
+        resting
+            .max_by(|a, b| a.position.x.abs().total_cmp(&b.position.x.abs()))
+            .map(|pet| Raised {
+                pet: pet.pet,
+                state: PetState::Walking,
+                walk_to: Some(far_side(pet.position)),
+            })
+            .into_iter()
+            .collect()
+    }
+}
@@ -0,0 +1,351 @@
+//! The command line, and the app it starts.
+
+use bevy::app::{PluginGroupBuilder, Plugins};
+use bevy::prelude::*;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::camera::CameraPlugin;
+use crate::config::{self, Config};
+use crate::core::items::ItemKind;
+use crate::core::shortcuts::Shortcut;
+use crate::core::timers::{DEFAULT_STATE, POMODORO_REST, POMODORO_WORK, TimerKind, TimerSpec};
+use crate::interrupts::InterruptPlugin;
+use crate::items::ItemPlugin;
+use crate::load::LoadPlugin;
+use crate::menu::MenuPlugin;
+use crate::notify::NotifyPlugin;
+use crate::pet::PetPlugin;
+use crate::platform::{self, BackendPlugin, window_plugin};
+use crate::script::ScriptPlugin;
+use crate::shell::ipc::IpcCommand;
+use crate::shell::{self, ShellPlugin};
+use crate::shortcuts::ShortcutPlugin;
+use crate::skin::build::BuildOptions;
+use crate::skin::{self, SkinSource};
+use crate::sound::SoundPlugin;
+use crate::speech::SpeechPlugin;
+use crate::timers::TimerPlugin;
+use crate::typing::TypingPlugin;
+
+/// Exit code for a config the user must fix.
+const EXIT_BAD_CONFIG: i32 = 2;
+
+/// Exit code for a session that cannot host the overlay at all.
+#[cfg(target_os = "linux")]
+const EXIT_UNSUPPORTED_SESSION: i32 = 3;
+
+/// A desktop pet.
+#[derive(Parser, Debug)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Ask a running instance to exit, then quit.
+    #[arg(long)]
+    quit: bool,
+
+    /// Drop food, a ball, or yarn for a running instance's pets.
+    #[arg(long, value_name = "ITEM", conflicts_with = "quit")]
+    drop: Option<ItemKind>,
+
+    /// Have a running instance's pet say something, such as "build finished".
+    #[arg(long, value_name = "TEXT", conflicts_with_all = ["quit", "drop"])]
+    say: Option<String>,
+
+    /// With --say: say it in this many minutes rather than now.
+    #[arg(
+        long = "in",
+        value_name = "MINUTES",
+        requires = "say",
+        conflicts_with = "every",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    after: Option<u64>,
+
+    /// With --say: say it every this many minutes, such as a reminder to
+    /// stretch.
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        requires = "say",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    every: Option<u64>,
+
+    /// Start a pomodoro in a running instance: 25 minutes of work, then 5 of
+    /// rest, over and over.
+    #[arg(long, conflicts_with_all = ["quit", "drop", "say"])]
+    pomodoro: bool,
+
+    /// Act on a running instance's pets as a keyboard shortcut would:
+    /// select-next, select-previous, pet, poke or summon. For binding keys in
+    /// a Wayland compositor.
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = ["quit", "drop", "say", "pomodoro"]
+    )]
+    shortcut: Option<Shortcut>,
+}
+
+impl Cli {
+    /// What to ask of a running instance, other than quitting.
+    fn request(self) -> Option<IpcCommand> {
+        if let Some(kind) = self.drop {
+            return Some(IpcCommand::Drop(kind));
+        }
+        if let Some(shortcut) = self.shortcut {
+            return Some(IpcCommand::Shortcut(shortcut));
+        }
+        if self.pomodoro {
+            return Some(IpcCommand::Timer(TimerSpec::pomodoro(
+                POMODORO_WORK,
+                POMODORO_REST,
+            )));
+        }
+        let text = self.say?;
+        let minutes = |n: u64| Duration::from_secs(n.saturating_mul(60));
+        let kind = match (self.after, self.every) {
+            (Some(after), _) => TimerKind::Once {
+                after: minutes(after),
+            },
+            (None, Some(period)) => TimerKind::Every {
+                period: minutes(period),
+            },
+            (None, None) => return Some(IpcCommand::Say(text)),
+        };
+        Some(IpcCommand::Timer(TimerSpec {
+            kind,
+            message: text,
+            state: DEFAULT_STATE,
+        }))
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Make, validate and list skins.
+    #[command(subcommand)]
+    Skin(SkinCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum SkinCommand {
+    /// Pack a folder of `<state>_<frame>.png` files into a skin directory.
+    Build {
+        /// Folder of frames, e.g. `walk_03.png`.
+        input: PathBuf,
+        /// Skin directory to write `sheet.png` and `skin.ron` into.
+        #[arg(long)]
+        out: PathBuf,
+        /// Side of each square frame, in pixels.
+        #[arg(long, default_value_t = 50)]
+        frame_size: u32,
+        #[arg(long, default_value_t = 12)]
+        fps: u8,
+    },
+    /// Report everything wrong with a skin, without starting the app.
+    Check {
+        /// An installed skin's name, or a path to a skin directory or
+        /// Aseprite file.
+        skin: String,
+    },
+    /// List the installed skins.
+    List,
+}
+
+/// Runs the app as the `batates` binary does, command line and all, with
+/// `plugins` added after the built-in ones.
+///
+/// This is the whole of the binary's `main`, so a build with extra plugins
+/// behaves the same in every other way:
+///
+/// ```no_run
+/// batates::run(());
+/// ```
+pub fn run<M>(plugins: impl Plugins<M>) {
+    // Before parsing, which prints `--help` and any mistake in the arguments.
+    platform::console::attach();
+    let mut cli = Cli::parse();
+
+    if let Some(Command::Skin(command)) = cli.command.take() {
+        std::process::exit(run_skin_command(command));
+    }
+
+    if cli.quit {
+        match shell::ipc::send_command(&IpcCommand::Quit) {
+            Ok(true) => println!("batates: asked the running instance to quit"),
+            Ok(false) => println!("batates: no instance is running"),
+            Err(error) => {
+                eprintln!("batates: could not reach a running instance: {error}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(command) = cli.request() {
+        // Unlike quitting, asking anything else of nothing has not succeeded.
+        match shell::ipc::send_command(&command) {
+            Ok(true) => return,
+            Ok(false) => eprintln!("batates: no instance is running"),
+            Err(error) => eprintln!("batates: could not reach a running instance: {error}"),
+        }
+        std::process::exit(1);
+    }
+
+    // Refuse early and legibly on a session that cannot host an overlay,
+    // rather than failing somewhere inside the renderer.
+    #[cfg(target_os = "linux")]
+    {
+        use crate::platform::wayland::probe;
+        let check = probe::check_session();
+        if check != probe::SessionCheck::Ok {
+            eprintln!("{}", probe::explain(&check));
+            std::process::exit(EXIT_UNSUPPORTED_SESSION);
+        }
+    }
+
+    // One overlay is enough, and several would fight over the same screen.
+    if shell::ipc::instance_running() {
+        eprintln!("batates: already running. Use `batates --quit` to stop it.");
+        std::process::exit(1);
+    }
+
+    let config = load_config_or_exit();
+
+    App::new()
+        .insert_resource(ClearColor(Color::NONE))
+        .insert_resource(config)
+        .add_plugins(setup_plugins())
+        .add_plugins((
+            CameraPlugin,
+            BackendPlugin,
+            PetPlugin,
+            ItemPlugin,
+            MenuPlugin,
+            ShellPlugin,
+            SoundPlugin,
+            SpeechPlugin,
+            TimerPlugin,
+            NotifyPlugin,
+            LoadPlugin,
+            ScriptPlugin,
+            InterruptPlugin,
+            TypingPlugin,
+            ShortcutPlugin,
+        ))
+        .add_plugins(plugins)
+        .run();
+}
+
+/// Runs a `batates skin` subcommand, returning the exit code.
+fn run_skin_command(command: SkinCommand) -> i32 {
+    match command {
+        SkinCommand::Build {
+            input,
+            out,
+            frame_size,
+            fps,
+        } => match skin::build::build(&input, &out, BuildOptions { frame_size, fps }) {
+            Ok(built) => {
+                for name in &built.ignored {
+                    eprintln!("batates: ignoring {name}: its prefix names no state");
+                }
+                println!(
+                    "wrote {} ({} columns x {} rows) and skin.ron",
+                    out.join("sheet.png").display(),
+                    built.columns,
+                    built.frames.len()
+                );
+                for (state, frames) in built.frames {
+                    println!("  {state:?}: {frames} frames");
+                }
+                0
+            }
+            Err(error) => {
+                eprintln!("batates: {error}");
+                1
+            }
+        },
+        SkinCommand::Check { skin } => {
+            let source = skin_source_for(&skin);
+            let problems = skin::check::check(&source);
+            for problem in &problems {
+                eprintln!("{problem}");
+            }
+            let errors = problems.iter().filter(|p| p.is_error()).count();
+            if errors == 0 {
+                println!("{skin}: ok");
+                0
+            } else {
+                eprintln!("\n{skin}: {errors} error(s)");
+                1
+            }
+        }
+        SkinCommand::List => {
+            let skins_dir = config::paths::skins_dir();
+            for name in config::skin_names(&skins_dir) {
+                let probe = Config {
+                    skin: name.clone(),
+                    ..default()
+                };
+                match probe.skin_source(&skins_dir) {
+                    SkinSource::Builtin => println!("{name}\t(built in)"),
+                    SkinSource::Directory(path) | SkinSource::Aseprite(path) => {
+                        println!("{name}\t{}", path.display());
+                    }
+                }
+            }
+            0
+        }
+    }
+}
+
+/// What `batates skin check <skin>` means by `skin`: a path if one exists
+/// there, otherwise a name resolved like the config's `skin`.
+fn skin_source_for(skin: &str) -> SkinSource {
+    let path = Path::new(skin);
+    if path.is_dir() {
+        return SkinSource::Directory(path.to_path_buf());
+    }
+    if path.is_file() {
+        return SkinSource::Aseprite(path.to_path_buf());
+    }
+    Config {
+        skin: skin.to_string(),
+        ..default()
+    }
+    .skin_source(&config::paths::skins_dir())
+}
+
+/// Loads config before Bevy starts, so a bad file produces a readable message
+/// rather than a panic inside a system.
+///
+/// A missing file is normal and yields defaults; a malformed one is the user's
+/// typo and is reported with the parser's line and column.
+fn load_config_or_exit() -> Config {
+    let path = config::config_path();
+    match config::load_config(&path) {
+        Ok(Some(config)) => config,
+        Ok(None) => Config::default(),
+        Err(error) => {
+            eprintln!("batates: {error}");
+            eprintln!("\nFix the file or delete it to fall back to defaults.");
+            std::process::exit(EXIT_BAD_CONFIG);
+        }
+    }
+}
+
+fn setup_plugins() -> PluginGroupBuilder {
+    DefaultPlugins
+        .set(ImagePlugin::default_nearest())
+        .set(window_plugin())
+        .set(AssetPlugin {
+            mode: AssetMode::Unprocessed,
+            ..default()
+        })
+}
@@ -0,0 +1,161 @@
+//! Writing settings changed at runtime back to the config file.
+//!
+//! The file is edited in place with `toml_edit` rather than regenerated from
+//! [`Config`](super::Config): it is hand-written, and a serde round trip would
+//! throw away the user's comments and key order.
+
+use std::path::Path;
+
+use toml_edit::{DocumentMut, Item, Value};
+
+use super::ConfigError;
+
+/// One setting the app can change on the user's behalf.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Setting {
+    Skin(String),
+    Scale(f32),
+    ClickToSummon(bool),
+    Volume(f32),
+    Muted(bool),
+    DebugOverlay(bool),
+}
+
+impl Setting {
+    /// The table and key this setting is stored under.
+    fn key(&self) -> (&'static str, &'static str) {
+        match self {
+            Setting::Skin(_) => ("app", "skin"),
+            Setting::Scale(_) => ("app", "scale"),
+            Setting::ClickToSummon(_) => ("behavior", "click_to_summon"),
+            Setting::Volume(_) => ("sound", "volume"),
+            Setting::Muted(_) => ("sound", "muted"),
+            Setting::DebugOverlay(_) => ("debug", "overlay"),
+        }
+    }
+
+    fn value(&self) -> Value {
+        match self {
+            Setting::Skin(name) => name.as_str().into(),
+            Setting::Scale(scale) | Setting::Volume(scale) => f64::from(*scale).into(),
+            Setting::ClickToSummon(on) | Setting::Muted(on) | Setting::DebugOverlay(on) => {
+                (*on).into()
+            }
+        }
+    }
+}
+
+/// Returns `text` with `setting` applied, leaving everything else untouched.
+///
+/// The replaced value keeps its decoration, so a trailing comment on the same
+/// line survives. A missing table is appended.
+pub fn apply_setting(text: &str, setting: &Setting) -> Result<String, ConfigError> {
+    let mut document: DocumentMut = text.parse().map_err(|source| ConfigError::Edit {
+        source: Box::new(source),
+    })?;
+
+    let (table_name, key) = setting.key();
+    let table = document
+        .entry(table_name)
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or(ConfigError::NotATable { table: table_name })?;
+
+    let mut value = setting.value();
+    match table.get_mut(key) {
+        Some(Item::Value(existing)) => {
+            *value.decor_mut() = existing.decor().clone();
+            *existing = value;
+        }
+        _ => {
+            table.insert(key, Item::Value(value));
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+/// Applies `setting` to the config file at `path`, creating it if needed.
+pub fn save_setting(path: &Path, setting: &Setting) -> Result<(), ConfigError> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(source) if source.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(source) => {
+            return Err(ConfigError::Read {
+                path: path.display().to_string(),
+                source,
+            });
+        }
+    };
+
+    let updated = apply_setting(&text, setting)?;
+
+    let write = |source| ConfigError::Write {
+        path: path.display().to_string(),
+        source,
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(write)?;
+    }
+    std::fs::write(path, updated).map_err(write)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, RawConfig};
+
+    fn reload(text: &str) -> Config {
+        let raw: RawConfig = toml::from_str(text).expect("still valid TOML");
+        Config::try_from(raw).expect("still a valid config")
+    }
+
+    /// The file is the user's, so a change from the tray must not eat their
+    /// comments.
+    #[test]
+    fn comments_and_order_survive() {
+        let text = "# mine\n[app]\n# which pet\nskin = \"koala\" # the default\npets = 2\n";
+        let updated = apply_setting(text, &Setting::Skin("panda".into())).expect("applies");
+        assert_eq!(
+            updated,
+            "# mine\n[app]\n# which pet\nskin = \"panda\" # the default\npets = 2\n"
+        );
+    }
+
+    #[test]
+    fn missing_tables_are_created() {
+        let updated =
+            apply_setting("[app]\npets = 2\n", &Setting::DebugOverlay(true)).expect("applies");
+        let config = reload(&updated);
+        assert!(config.debug_overlay);
+        assert_eq!(config.pets.0.get(), 2);
+    }
+
+    #[test]
+    fn an_empty_file_takes_every_setting() {
+        let mut text = String::new();
+        for setting in [
+            Setting::Skin("panda".into()),
+            Setting::Scale(2.0),
+            Setting::ClickToSummon(false),
+            Setting::Volume(0.5),
+            Setting::Muted(true),
+        ] {
+            text = apply_setting(&text, &setting).expect("applies");
+        }
+        let config = reload(&text);
+        assert_eq!(config.skin, "panda");
+        assert_eq!(config.scale.0, 2.0);
+        assert!(!config.click_to_summon);
+        assert_eq!(config.sound.volume, 0.5);
+        assert!(config.sound.muted);
+    }
+
+    #[test]
+    fn a_key_where_a_table_belongs_is_an_error() {
+        assert!(matches!(
+            apply_setting("app = 3\n", &Setting::Scale(2.0)),
+            Err(ConfigError::NotATable { table: "app" })
+        ));
+    }
+}
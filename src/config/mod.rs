@@ -6,6 +6,7 @@
 //! Two types: [`RawConfig`] mirrors the file and is all-optional, [`Config`] is
 //! validated and concrete. Nothing downstream ever sees an unchecked number.
 
+pub mod edit;
 pub mod paths;
 
 use bevy::prelude::*;
@@ -15,13 +16,24 @@ use std::path::{Path, PathBuf};
 use std::time::Duration;
 use thiserror::Error;
 
-use crate::core::input::GestureConfig;
+use crate::core::brain::PetState;
+use crate::core::idle::IdleConfig;
+use crate::core::input::{GestureAction, GestureConfig};
+use crate::core::movement::FollowConfig;
+use crate::core::notify::NotifyConfig;
 use crate::core::rng::Seed;
+use crate::core::shortcuts::{KeyCombo, KeyComboError, Shortcut, ShortcutConfig};
+use crate::core::sound::SoundConfig;
+use crate::core::timers::{self, TimerKind, TimerSpec};
+use crate::core::typing::TypingConfig;
 use crate::skin::SkinSource;
 
 /// The skin that ships in the binary.
 pub const BUILTIN_SKIN: &str = "koala";
 
+/// Extensions a skin in the skins directory may have as an Aseprite file.
+const ASEPRITE_EXTENSIONS: [&str; 2] = ["aseprite", "ase"];
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("could not read config at {path}: {source}")]
@@ -40,10 +52,41 @@ pub enum ConfigError {
     PetCount { got: u32 },
     #[error("scale must be greater than 0 and at most 16, got {got}")]
     Scale { got: f32 },
+    #[error("volume must be between 0 and 1, got {got}")]
+    Volume { got: f32 },
     #[error("{field} must be greater than zero")]
     NotPositive { field: &'static str },
     #[error("skin name must not be empty or a path")]
     SkinName,
+    #[error("could not write config at {path}: {source}")]
+    Write {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("could not edit config: {source}")]
+    Edit {
+        #[source]
+        source: Box<toml_edit::TomlError>,
+    },
+    #[error("[{table}] in the config is not a table")]
+    NotATable { table: &'static str },
+    #[error("{field} cannot be {state:?}: that state is owned by the pointer")]
+    HeldState {
+        field: &'static str,
+        state: PetState,
+    },
+    #[error("shortcuts.{field}: {source}")]
+    KeyCombo {
+        field: &'static str,
+        #[source]
+        source: KeyComboError,
+    },
+    #[error("{combo} is bound to more than one shortcut")]
+    SameCombo { combo: KeyCombo },
+    /// `index` counts `[[timer]]` entries from 1, as a reader of the file would.
+    #[error("timer {index}: {reason}")]
+    Timer { index: usize, reason: &'static str },
 }
 
 /// How many pets to spawn.
@@ -63,7 +106,107 @@ pub struct RawConfig {
     #[serde(default)]
     pub behavior: RawBehavior,
     #[serde(default)]
+    pub gestures: RawGestures,
+    #[serde(default)]
+    pub idle: RawIdle,
+    #[serde(default)]
+    pub sound: RawSound,
+    #[serde(default)]
+    pub notifications: RawNotifications,
+    #[serde(default)]
+    pub system: RawSystem,
+    #[serde(default)]
+    pub typing: RawTyping,
+    #[serde(default)]
+    pub shortcuts: RawShortcuts,
+    #[serde(default)]
     pub debug: RawDebug,
+    /// `[[timer]]`, one per timer.
+    #[serde(default)]
+    pub timer: Vec<RawTimer>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawTimer {
+    pub kind: RawTimerKind,
+    pub minutes: Option<u64>,
+    pub break_minutes: Option<u64>,
+    pub message: Option<String>,
+    pub state: Option<PetState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RawTimerKind {
+    Once,
+    Every,
+    Pomodoro,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawSound {
+    pub volume: Option<f32>,
+    pub muted: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawNotifications {
+    pub enabled: Option<bool>,
+    pub state: Option<PetState>,
+    pub show_summary: Option<bool>,
+    pub allow: Option<Vec<String>>,
+    pub deny: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawSystem {
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawGestures {
+    pub long_press_ms: Option<u64>,
+    pub long_press: Option<GestureAction>,
+    pub middle_click: Option<GestureAction>,
+    pub right_drag: Option<GestureAction>,
+    pub scroll_up: Option<GestureAction>,
+    pub scroll_down: Option<GestureAction>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawTyping {
+    pub enabled: Option<bool>,
+    pub furious_keys_per_second: Option<f32>,
+    pub cheer_state: Option<PetState>,
+    pub beside_state: Option<PetState>,
+    pub nap_after_minutes: Option<u64>,
+    pub nap_state: Option<PetState>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawShortcuts {
+    pub enabled: Option<bool>,
+    pub select_next: Option<String>,
+    pub select_previous: Option<String>,
+    pub pet: Option<String>,
+    pub poke: Option<String>,
+    pub summon: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawIdle {
+    pub enabled: Option<bool>,
+    pub away_after_minutes: Option<u64>,
+    pub asleep_state: Option<PetState>,
+    pub greeting_state: Option<PetState>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -87,6 +230,7 @@ pub struct RawBehavior {
     pub click_to_summon: Option<bool>,
     pub double_click_ms: Option<u64>,
     pub drag_threshold_ms: Option<u64>,
+    pub follow_distance: Option<f32>,
 }
 
 /// Validated configuration.
@@ -98,6 +242,21 @@ pub struct Config {
     pub seed: Seed,
     pub click_to_summon: bool,
     pub gestures: GestureConfig,
+    pub follow: FollowConfig,
+    pub idle: IdleConfig,
+    pub sound: SoundConfig,
+    pub notifications: NotifyConfig,
+    /// Whether to sample CPU, memory and battery for the skin's load rules.
+    /// Off by default: it is a poll of the whole machine, every few seconds.
+    pub system_load: bool,
+    /// Off by default: counting keystrokes needs a permission on every
+    /// platform, and is not something to do unasked.
+    pub typing: TypingConfig,
+    /// Off by default: a registered shortcut is taken from every other
+    /// application.
+    pub shortcuts: ShortcutConfig,
+    /// Started when the app starts.
+    pub timers: Vec<TimerSpec>,
     /// Draws each pet's hitbox and the cursor the app believes in.
     pub debug_overlay: bool,
 }
@@ -111,6 +270,14 @@ impl Default for Config {
             seed: Seed(0),
             click_to_summon: true,
             gestures: GestureConfig::default(),
+            follow: FollowConfig::default(),
+            idle: IdleConfig::default(),
+            sound: SoundConfig::default(),
+            notifications: NotifyConfig::default(),
+            system_load: false,
+            typing: TypingConfig::default(),
+            shortcuts: ShortcutConfig::default(),
+            timers: Vec::new(),
             debug_overlay: false,
         }
     }
@@ -166,10 +333,201 @@ impl TryFrom<RawConfig> for Config {
             positive_millis(raw.behavior.drag_threshold_ms, "drag_threshold_ms")?
                 .unwrap_or(config.gestures.drag_threshold);
 
+        let gestures = raw.gestures;
+        config.gestures.long_press = positive_millis(gestures.long_press_ms, "long_press_ms")?
+            .unwrap_or(config.gestures.long_press);
+        let map = &mut config.gestures.map;
+        for (action, slot) in [
+            (gestures.long_press, &mut map.long_press),
+            (gestures.middle_click, &mut map.middle_click),
+            (gestures.right_drag, &mut map.right_drag),
+            (gestures.scroll_up, &mut map.scroll_up),
+            (gestures.scroll_down, &mut map.scroll_down),
+        ] {
+            if let Some(action) = action {
+                *slot = action;
+            }
+        }
+
+        if let Some(distance) = raw.behavior.follow_distance {
+            if !(distance.is_finite() && distance > 0.0) {
+                return Err(ConfigError::NotPositive {
+                    field: "follow_distance",
+                });
+            }
+            config.follow.distance = distance;
+        }
+
+        if let Some(enabled) = raw.idle.enabled {
+            config.idle.enabled = enabled;
+        }
+        config.idle.away_after =
+            positive_minutes(raw.idle.away_after_minutes, "away_after_minutes")?
+                .unwrap_or(config.idle.away_after);
+        if let Some(state) = raw.idle.asleep_state {
+            config.idle.asleep = not_held(state, "asleep_state")?;
+        }
+        if let Some(state) = raw.idle.greeting_state {
+            config.idle.greeting = not_held(state, "greeting_state")?;
+        }
+
+        if let Some(volume) = raw.sound.volume {
+            if !(0.0..=1.0).contains(&volume) {
+                return Err(ConfigError::Volume { got: volume });
+            }
+            config.sound.volume = volume;
+        }
+        if let Some(muted) = raw.sound.muted {
+            config.sound.muted = muted;
+        }
+
+        let notifications = raw.notifications;
+        if let Some(enabled) = notifications.enabled {
+            config.notifications.enabled = enabled;
+        }
+        if let Some(state) = notifications.state {
+            config.notifications.state = not_held(state, "notifications.state")?;
+        }
+        if let Some(show) = notifications.show_summary {
+            config.notifications.show_summary = show;
+        }
+        config.notifications.allow = notifications.allow.unwrap_or_default();
+        config.notifications.deny = notifications.deny.unwrap_or_default();
+
+        if let Some(enabled) = raw.system.enabled {
+            config.system_load = enabled;
+        }
+
+        let typing = raw.typing;
+        if let Some(enabled) = typing.enabled {
+            config.typing.enabled = enabled;
+        }
+        if let Some(rate) = typing.furious_keys_per_second {
+            if !(rate.is_finite() && rate > 0.0) {
+                return Err(ConfigError::NotPositive {
+                    field: "typing.furious_keys_per_second",
+                });
+            }
+            config.typing.furious = rate;
+        }
+        if let Some(state) = typing.cheer_state {
+            config.typing.cheer = not_held(state, "typing.cheer_state")?;
+        }
+        if let Some(state) = typing.beside_state {
+            config.typing.beside = not_held(state, "typing.beside_state")?;
+        }
+        config.typing.nap_after =
+            positive_minutes(typing.nap_after_minutes, "typing.nap_after_minutes")?
+                .unwrap_or(config.typing.nap_after);
+        if let Some(state) = typing.nap_state {
+            config.typing.nap = not_held(state, "typing.nap_state")?;
+        }
+
+        let shortcuts = raw.shortcuts;
+        if let Some(enabled) = shortcuts.enabled {
+            config.shortcuts.enabled = enabled;
+        }
+        for (shortcut, field, text) in [
+            (Shortcut::SelectNext, "select_next", shortcuts.select_next),
+            (
+                Shortcut::SelectPrevious,
+                "select_previous",
+                shortcuts.select_previous,
+            ),
+            (Shortcut::Pet, "pet", shortcuts.pet),
+            (Shortcut::Poke, "poke", shortcuts.poke),
+            (Shortcut::Summon, "summon", shortcuts.summon),
+        ] {
+            let Some(text) = text else { continue };
+            let combo = text
+                .parse()
+                .map_err(|source| ConfigError::KeyCombo { field, source })?;
+            for binding in &mut config.shortcuts.bindings {
+                if binding.0 == shortcut {
+                    binding.1 = combo;
+                }
+            }
+        }
+        // Only one registration of a combo can succeed.
+        let bindings = &config.shortcuts.bindings;
+        for (at, (_, combo)) in bindings.iter().enumerate() {
+            if bindings[..at].iter().any(|(_, earlier)| earlier == combo) {
+                return Err(ConfigError::SameCombo { combo: *combo });
+            }
+        }
+
+        config.timers = raw
+            .timer
+            .into_iter()
+            .enumerate()
+            .map(|(index, raw)| timer_spec(index + 1, raw))
+            .collect::<Result<_, _>>()?;
+
         Ok(config)
     }
 }
 
+/// Validates the `index`th `[[timer]]`, counting from 1.
+fn timer_spec(index: usize, raw: RawTimer) -> Result<TimerSpec, ConfigError> {
+    let invalid = |reason| ConfigError::Timer { index, reason };
+    let minutes = positive_minutes(raw.minutes, "minutes")?;
+    let break_minutes = positive_minutes(raw.break_minutes, "break_minutes")?;
+
+    let mut spec = match raw.kind {
+        RawTimerKind::Pomodoro => {
+            if raw.message.is_some() {
+                return Err(invalid("a pomodoro says its own messages"));
+            }
+            TimerSpec::pomodoro(
+                minutes.unwrap_or(timers::POMODORO_WORK),
+                break_minutes.unwrap_or(timers::POMODORO_REST),
+            )
+        }
+        RawTimerKind::Once | RawTimerKind::Every => {
+            if break_minutes.is_some() {
+                return Err(invalid("only a pomodoro takes break_minutes"));
+            }
+            let minutes = minutes.ok_or_else(|| invalid("minutes is required"))?;
+            TimerSpec {
+                kind: if raw.kind == RawTimerKind::Once {
+                    TimerKind::Once { after: minutes }
+                } else {
+                    TimerKind::Every { period: minutes }
+                },
+                message: raw
+                    .message
+                    .unwrap_or_else(|| timers::DEFAULT_MESSAGE.to_string()),
+                state: timers::DEFAULT_STATE,
+            }
+        }
+    };
+    if let Some(state) = raw.state {
+        spec.state = not_held(state, "state")?;
+    }
+    Ok(spec)
+}
+
+/// Rejects `Dragged` where a reaction state is expected. It is a locked,
+/// pointer-positioned state, so entering it with nothing holding the pet would
+/// leave it stuck until the user happened to grab and release it.
+fn not_held(state: PetState, field: &'static str) -> Result<PetState, ConfigError> {
+    match state {
+        PetState::Dragged => Err(ConfigError::HeldState { field, state }),
+        other => Ok(other),
+    }
+}
+
+fn positive_minutes(
+    value: Option<u64>,
+    field: &'static str,
+) -> Result<Option<Duration>, ConfigError> {
+    match value {
+        None => Ok(None),
+        Some(0) => Err(ConfigError::NotPositive { field }),
+        Some(minutes) => Ok(Some(Duration::from_secs(minutes.saturating_mul(60)))),
+    }
+}
+
 fn positive_millis(
     value: Option<u64>,
     field: &'static str,
@@ -184,14 +542,21 @@ fn positive_millis(
 impl Config {
     /// Where to load this config's skin from.
     ///
-    /// A user skin directory wins; otherwise the built-in name resolves to the
-    /// embedded skin. An unknown name still resolves to a directory so the
-    /// loader reports a real "not found" rather than silently substituting.
+    /// A user skin directory wins, then an Aseprite file of the same name;
+    /// otherwise the built-in name resolves to the embedded skin. An unknown
+    /// name still resolves to a directory so the loader reports a real "not
+    /// found" rather than silently substituting.
     pub fn skin_source(&self, skins_dir: &Path) -> SkinSource {
         let dir = skins_dir.join(&self.skin);
         if dir.join("skin.ron").is_file() {
             return SkinSource::Directory(dir);
         }
+        for extension in ASEPRITE_EXTENSIONS {
+            let file = skins_dir.join(format!("{}.{extension}", self.skin));
+            if file.is_file() {
+                return SkinSource::Aseprite(file);
+            }
+        }
         if self.skin == BUILTIN_SKIN {
             return SkinSource::Builtin;
         }
@@ -199,6 +564,31 @@ impl Config {
     }
 }
 
+/// Every skin name a config could choose: the built-in one plus each directory
+/// under `skins_dir` holding a manifest and each Aseprite file there, sorted
+/// and without duplicates.
+///
+/// An unreadable directory lists only the built-in skin, which always loads.
+pub fn skin_names(skins_dir: &Path) -> Vec<String> {
+    let mut names = vec![BUILTIN_SKIN.to_string()];
+    if let Ok(entries) = std::fs::read_dir(skins_dir) {
+        names.extend(entries.flatten().filter_map(|entry| {
+            let path = entry.path();
+            if path.join("skin.ron").is_file() {
+                return entry.file_name().into_string().ok();
+            }
+            let extension = path.extension()?.to_str()?;
+            if path.is_file() && ASEPRITE_EXTENSIONS.contains(&extension) {
+                return path.file_stem()?.to_str().map(str::to_string);
+            }
+            None
+        }));
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
 /// Reads the config file if it is there.
 ///
 /// `Ok(None)` means the file is absent, which is expected and yields defaults.
@@ -276,6 +666,7 @@ mod tests {
             click_to_summon = false
             double_click_ms = 300
             drag_threshold_ms = 100
+            follow_distance = 90.0
             "#,
         )
         .expect("valid");
@@ -285,6 +676,7 @@ mod tests {
         assert_eq!(config.seed, Seed(99));
         assert!(!config.click_to_summon);
         assert_eq!(config.gestures.double_click, Duration::from_millis(300));
+        assert_eq!(config.follow.distance, 90.0);
     }
 
     /// A typo must be an error, not a silently ignored default.
@@ -338,6 +730,287 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn follow_distance_must_be_positive() {
+        for bad in ["0.0", "-5.0", "nan"] {
+            assert!(
+                matches!(
+                    parse(&format!("[behavior]\nfollow_distance = {bad}\n")),
+                    Err(ConfigError::NotPositive {
+                        field: "follow_distance"
+                    })
+                ),
+                "should reject {bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn idle_section_parses() {
+        let config = parse(
+            r#"
+            [idle]
+            enabled = false
+            away_after_minutes = 10
+            asleep_state = "Chilling"
+            greeting_state = "Jumping"
+            "#,
+        )
+        .expect("valid");
+        assert!(!config.idle.enabled);
+        assert_eq!(config.idle.away_after, Duration::from_secs(600));
+        assert_eq!(config.idle.asleep, PetState::Chilling);
+        assert_eq!(config.idle.greeting, PetState::Jumping);
+    }
+
+    #[test]
+    fn idle_thresholds_are_validated() {
+        assert!(matches!(
+            parse("[idle]\naway_after_minutes = 0\n"),
+            Err(ConfigError::NotPositive {
+                field: "away_after_minutes"
+            })
+        ));
+        assert!(matches!(
+            parse("[idle]\nasleep_state = \"Dragged\"\n"),
+            Err(ConfigError::HeldState {
+                field: "asleep_state",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("[idle]\nasleep_state = \"Napping\"\n"),
+            Err(ConfigError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn sound_section_parses_and_volume_is_a_fraction() {
+        let config = parse("[sound]\nvolume = 0.25\nmuted = true\n").expect("valid");
+        assert_eq!(config.sound.volume, 0.25);
+        assert!(config.sound.muted);
+        assert!(!parse("").expect("valid").sound.muted);
+
+        for bad in ["1.5", "-0.1", "nan"] {
+            assert!(
+                matches!(
+                    parse(&format!("[sound]\nvolume = {bad}\n")),
+                    Err(ConfigError::Volume { .. })
+                ),
+                "should reject {bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn notifications_section_parses() {
+        let config = parse(
+            r#"
+            [notifications]
+            enabled = true
+            state = "SendingLove"
+            show_summary = true
+            allow = ["Thunderbird", "Signal"]
+            deny = ["Signal"]
+            "#,
+        )
+        .expect("valid");
+        let notifications = &config.notifications;
+        assert!(notifications.enabled);
+        assert_eq!(notifications.state, PetState::SendingLove);
+        assert!(notifications.show_summary);
+        assert!(notifications.admits("Thunderbird"));
+        assert!(!notifications.admits("Signal"));
+
+        assert!(matches!(
+            parse("[notifications]\nstate = \"Dragged\"\n"),
+            Err(ConfigError::HeldState {
+                field: "notifications.state",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn system_load_is_opt_in() {
+        assert!(!parse("").expect("valid").system_load);
+        assert!(
+            parse("[system]\nenabled = true\n")
+                .expect("valid")
+                .system_load
+        );
+    }
+
+    #[test]
+    fn gestures_section_parses() {
+        let config = parse(
+            r#"
+            [gestures]
+            long_press_ms = 600
+            middle_click = "Play"
+            scroll_down = "Nothing"
+            "#,
+        )
+        .expect("valid");
+        let gestures = &config.gestures;
+        assert_eq!(gestures.long_press, Duration::from_millis(600));
+        assert_eq!(gestures.map.middle_click, GestureAction::Play);
+        assert_eq!(gestures.map.scroll_down, GestureAction::Nothing);
+        assert_eq!(
+            gestures.map.scroll_up,
+            GestureAction::Grow,
+            "unset keeps its default"
+        );
+
+        assert!(matches!(
+            parse("[gestures]\nlong_press_ms = 0\n"),
+            Err(ConfigError::NotPositive {
+                field: "long_press_ms"
+            })
+        ));
+        assert!(
+            parse("[gestures]\nright_drag = \"Remove\"\n").is_err(),
+            "a gesture cannot remove a pet"
+        );
+    }
+
+    #[test]
+    fn typing_is_opt_in_and_validated() {
+        let config = parse("").expect("valid");
+        assert!(!config.typing.enabled);
+
+        let config = parse(
+            r#"
+            [typing]
+            enabled = true
+            furious_keys_per_second = 9.5
+            beside_state = "Chilling"
+            nap_after_minutes = 10
+            "#,
+        )
+        .expect("valid");
+        let typing = &config.typing;
+        assert!(typing.enabled);
+        assert_eq!(typing.furious, 9.5);
+        assert_eq!(typing.beside, PetState::Chilling);
+        assert_eq!(typing.nap_after, Duration::from_secs(600));
+        assert_eq!(typing.cheer, PetState::Jumping, "unset keeps its default");
+
+        assert!(matches!(
+            parse("[typing]\nfurious_keys_per_second = 0\n"),
+            Err(ConfigError::NotPositive {
+                field: "typing.furious_keys_per_second"
+            })
+        ));
+        assert!(matches!(
+            parse("[typing]\nnap_state = \"Dragged\"\n"),
+            Err(ConfigError::HeldState {
+                field: "typing.nap_state",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn shortcuts_are_opt_in_and_validated() {
+        let config = parse("").expect("valid");
+        assert!(!config.shortcuts.enabled);
+
+        let config = parse(
+            r#"
+            [shortcuts]
+            enabled = true
+            poke = "Super+F9"
+            "#,
+        )
+        .expect("valid");
+        let bindings = &config.shortcuts.bindings;
+        assert!(config.shortcuts.enabled);
+        assert!(bindings.contains(&(Shortcut::Poke, "Super+F9".parse().unwrap())));
+        assert!(
+            bindings.contains(&(Shortcut::Pet, "Ctrl+Alt+P".parse().unwrap())),
+            "unset keeps its default"
+        );
+
+        assert!(matches!(
+            parse("[shortcuts]\nsummon = \"S\"\n"),
+            Err(ConfigError::KeyCombo {
+                field: "summon",
+                source: KeyComboError::NoModifier
+            })
+        ));
+        assert!(matches!(
+            parse("[shortcuts]\npet = \"Ctrl+Alt+N\"\n"),
+            Err(ConfigError::SameCombo { .. })
+        ));
+    }
+
+    #[test]
+    fn timers_parse_with_defaults() {
+        let config = parse(
+            r#"
+            [[timer]]
+            kind = "every"
+            minutes = 45
+            message = "Stretch!"
+            state = "SendingLove"
+
+            [[timer]]
+            kind = "once"
+            minutes = 10
+
+            [[timer]]
+            kind = "pomodoro"
+            break_minutes = 10
+            "#,
+        )
+        .expect("valid");
+        assert_eq!(
+            config.timers[0],
+            TimerSpec {
+                kind: TimerKind::Every {
+                    period: Duration::from_secs(45 * 60)
+                },
+                message: "Stretch!".to_string(),
+                state: PetState::SendingLove,
+            }
+        );
+        assert_eq!(config.timers[1].message, timers::DEFAULT_MESSAGE);
+        assert_eq!(config.timers[1].state, timers::DEFAULT_STATE);
+        assert_eq!(
+            config.timers[2].kind,
+            TimerKind::Pomodoro {
+                work: Duration::from_secs(25 * 60),
+                rest: Duration::from_secs(10 * 60),
+            }
+        );
+        assert!(parse("").expect("valid").timers.is_empty());
+    }
+
+    #[test]
+    fn timers_are_validated() {
+        assert!(matches!(
+            parse("[[timer]]\nkind = \"once\"\n"),
+            Err(ConfigError::Timer { index: 1, .. })
+        ));
+        assert!(matches!(
+            parse("[[timer]]\nkind = \"every\"\nminutes = 0\n"),
+            Err(ConfigError::NotPositive { field: "minutes" })
+        ));
+        assert!(matches!(
+            parse("[[timer]]\nkind = \"pomodoro\"\nmessage = \"hi\"\n"),
+            Err(ConfigError::Timer { .. })
+        ));
+        assert!(matches!(
+            parse("[[timer]]\nkind = \"once\"\nminutes = 5\nstate = \"Dragged\"\n"),
+            Err(ConfigError::HeldState { field: "state", .. })
+        ));
+        assert!(matches!(
+            parse("[[timer]]\nkind = \"daily\"\n"),
+            Err(ConfigError::Parse { .. })
+        ));
+    }
+
     /// A skin name is a directory entry, never a path, so a config cannot point
     /// outside the skins directory.
     #[test]
@@ -364,6 +1037,13 @@ mod tests {
         assert_eq!(source, SkinSource::Builtin);
     }
 
+    #[test]
+    fn skin_names_list_the_builtin_and_installed_skins() {
+        let skins = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/skins");
+        assert_eq!(skin_names(&skins), ["koala", "panda"]);
+        assert_eq!(skin_names(Path::new("/nonexistent/skins")), ["koala"]);
+    }
+
     #[test]
     fn a_user_skin_directory_wins() {
         let skins = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/skins");
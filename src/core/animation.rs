@@ -1,15 +1,19 @@
 //! Sprite-sheet frame stepping.
 //!
-//! The sheet is a strict `rows x columns` grid built by `scripts/make_sprite.py`,
+//! The sheet is a strict `rows x columns` grid built by `batates skin build`,
 //! where the row is the state's position in [`PetState::ALL`]. That makes every
 //! frame index arithmetic, replacing the hand-maintained `match` of absolute
 //! indices which had drifted: `Chilling` was declared `(1, 60)` when row 0
 //! starts at 0, so its first frame had never rendered.
+//!
+//! A skin may add rows after those eight, drawing a state facing left, up or
+//! down; the state's own row faces right.
 
 use bevy::prelude::*;
 use std::time::Duration;
 
 use super::brain::{PetState, Playback};
+use super::movement::{Facing, Heading};
 
 /// Per-pet animation cursor.
 #[derive(Component, Debug, Clone)]
@@ -39,14 +43,87 @@ impl AnimationCursor {
     }
 }
 
-/// Absolute atlas index of frame `n` of the state on `row`.
+/// How long each frame of a state stays on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameTiming<'a> {
+    /// Every frame lasts `1 / fps` seconds. Zero holds the first frame.
+    Fps(u8),
+    /// One duration per frame, so hand-animated sprites can hold key poses.
+    PerFrame(&'a [Duration]),
+}
+
+impl FrameTiming<'_> {
+    /// How long `frame` is shown, or `None` if the animation never advances.
+    fn frame_duration(self, frame: u32) -> Option<Duration> {
+        match self {
+            FrameTiming::Fps(0) => None,
+            FrameTiming::Fps(fps) => Some(Duration::from_secs_f32(1.0 / f32::from(fps))),
+            FrameTiming::PerFrame(durations) => durations.get(frame as usize).copied(),
+        }
+    }
+
+    /// How long `frames` frames take to play through once.
+    pub fn total(self, frames: u32) -> Duration {
+        match self {
+            FrameTiming::Fps(0) => Duration::ZERO,
+            FrameTiming::Fps(fps) => Duration::from_secs_f32(frames as f32 / f32::from(fps)),
+            FrameTiming::PerFrame(durations) => durations.iter().take(frames as usize).sum(),
+        }
+    }
+}
+
+/// Index of frame `n` of the state on `row`, counting every row as
+/// `columns` wide.
+///
+/// On a grid sheet this is the cell itself. A packed atlas keeps no such
+/// padding, so there it is only a key: the skin looks up which sheet and
+/// rect the frame really lives at.
 pub fn frame_index(row: u32, columns: u32, n: u32) -> usize {
     (row * columns + n) as usize
 }
 
-/// Atlas index for a state's current cursor position.
-pub fn atlas_index(state: PetState, columns: u32, cursor: &AnimationCursor) -> usize {
-    frame_index(state.row(), columns, cursor.frame)
+/// Frame index for the cursor's position in the state drawn on `row`.
+pub fn atlas_index(row: u32, columns: u32, cursor: &AnimationCursor) -> usize {
+    frame_index(row, columns, cursor.frame)
+}
+
+/// A skin's extra rows: which state each draws, heading which way.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirectionRows(Vec<(PetState, Heading, u32)>);
+
+impl DirectionRows {
+    pub fn new(rows: Vec<(PetState, Heading, u32)>) -> Self {
+        Self(rows)
+    }
+
+    fn get(&self, state: PetState, heading: Heading) -> Option<u32> {
+        self.0
+            .iter()
+            .find(|(s, h, _)| *s == state && *h == heading)
+            .map(|(.., row)| *row)
+    }
+}
+
+/// The row to draw `state` from, and whether to mirror it.
+///
+/// Art drawn for `heading` wins. Without it, up and down fall back to the side
+/// the pet last faced, and facing left mirrors the right-facing row, which is
+/// all a skin without extra rows ever does.
+pub fn sheet_row(
+    state: PetState,
+    heading: Heading,
+    facing: Facing,
+    rows: &DirectionRows,
+) -> (u32, bool) {
+    if let Some(row) = rows.get(state, heading) {
+        return (row, false);
+    }
+    if facing.flip_x()
+        && let Some(row) = rows.get(state, Heading::Left)
+    {
+        return (row, false);
+    }
+    (state.row(), facing.flip_x())
 }
 
 /// Advances the cursor by `dt`.
@@ -57,25 +134,26 @@ pub fn atlas_index(state: PetState, columns: u32, cursor: &AnimationCursor) -> u
 pub fn step_animation(
     cursor: &mut AnimationCursor,
     frames: u32,
-    fps: u8,
+    timing: FrameTiming<'_>,
     playback: Playback,
     dt: Duration,
 ) {
     debug_assert!(frames >= 1, "a state needs at least one frame");
-    if fps == 0 || frames <= 1 {
+    if timing.frame_duration(0).is_none() || frames <= 1 {
         // A single-frame state (Sitting) has nothing to advance; marking it
         // finished lets `Once` single-frame states still terminate.
         cursor.finished = playback == Playback::Once;
         return;
     }
 
-    let per_frame = Duration::from_secs_f32(1.0 / f32::from(fps));
     cursor.elapsed += dt;
 
     // A loop rather than a single step so a long frame (a stall, a breakpoint)
     // does not silently slow the animation down.
-    while cursor.elapsed >= per_frame {
-        cursor.elapsed -= per_frame;
+    while let Some(shown) = timing.frame_duration(cursor.frame)
+        && cursor.elapsed >= shown
+    {
+        cursor.elapsed -= shown;
         match playback {
             Playback::Loop => {
                 cursor.frame = (cursor.frame + 1) % frames;
@@ -129,12 +207,33 @@ mod tests {
         assert_eq!(frame_index(PetState::Walking.row(), 49, 0), 343);
     }
 
+    #[test]
+    fn direction_rows_win_and_fall_back_to_a_flip() {
+        let rows = DirectionRows::new(vec![
+            (PetState::Walking, Heading::Left, 8),
+            (PetState::Walking, Heading::Up, 9),
+        ]);
+        let walking = |heading, facing| sheet_row(PetState::Walking, heading, facing, &rows);
+
+        assert_eq!(walking(Heading::Right, Facing::Right), (7, false));
+        assert_eq!(walking(Heading::Left, Facing::Left), (8, false));
+        assert_eq!(walking(Heading::Up, Facing::Left), (9, false));
+        // No down art: the side view, using the left-facing row it has.
+        assert_eq!(walking(Heading::Down, Facing::Left), (8, false));
+
+        // A state without extra rows behaves as before: mirror to face left.
+        assert_eq!(
+            sheet_row(PetState::Idle, Heading::Up, Facing::Left, &rows),
+            (PetState::Idle.row(), true)
+        );
+    }
+
     #[test]
     fn loop_wraps_to_start() {
         let mut c = AnimationCursor::default();
         // 12 fps => 1 frame per ~83.3ms. Eight frames then wrap.
         for _ in 0..8 {
-            step_animation(&mut c, 8, 12, Playback::Loop, ms(84));
+            step_animation(&mut c, 8, FrameTiming::Fps(12), Playback::Loop, ms(84));
         }
         assert_eq!(c.frame, 0);
         assert!(!c.finished, "looping animations never finish");
@@ -144,7 +243,7 @@ mod tests {
     fn once_stops_on_last_frame_and_reports_finished() {
         let mut c = AnimationCursor::default();
         for _ in 0..50 {
-            step_animation(&mut c, 11, 12, Playback::Once, ms(84));
+            step_animation(&mut c, 11, FrameTiming::Fps(12), Playback::Once, ms(84));
         }
         assert_eq!(c.frame, 10, "stops on the last frame, not past it");
         assert!(c.finished);
@@ -153,7 +252,7 @@ mod tests {
     #[test]
     fn single_frame_state_does_not_advance() {
         let mut c = AnimationCursor::default();
-        step_animation(&mut c, 1, 12, Playback::Loop, ms(500));
+        step_animation(&mut c, 1, FrameTiming::Fps(12), Playback::Loop, ms(500));
         assert_eq!(c.frame, 0);
     }
 
@@ -164,11 +263,23 @@ mod tests {
     #[test]
     fn a_long_stall_catches_up_rather_than_slowing_down() {
         let mut one_tick = AnimationCursor::default();
-        step_animation(&mut one_tick, 61, 12, Playback::Loop, ms(500));
+        step_animation(
+            &mut one_tick,
+            61,
+            FrameTiming::Fps(12),
+            Playback::Loop,
+            ms(500),
+        );
 
         let mut many_ticks = AnimationCursor::default();
         for _ in 0..10 {
-            step_animation(&mut many_ticks, 61, 12, Playback::Loop, ms(50));
+            step_animation(
+                &mut many_ticks,
+                61,
+                FrameTiming::Fps(12),
+                Playback::Loop,
+                ms(50),
+            );
         }
 
         assert_eq!(one_tick.frame, many_ticks.frame);
@@ -179,10 +290,30 @@ mod tests {
         );
     }
 
+    /// A long hold on the middle pose: the cursor sits on it for its whole
+    /// duration, not the uniform 1/fps.
+    #[test]
+    fn per_frame_durations_hold_key_poses() {
+        let durations = [ms(100), ms(600), ms(100)];
+        let timing = FrameTiming::PerFrame(&durations);
+        let mut c = AnimationCursor::default();
+
+        step_animation(&mut c, 3, timing, Playback::Once, ms(150));
+        assert_eq!(c.frame, 1);
+        step_animation(&mut c, 3, timing, Playback::Once, ms(500));
+        assert_eq!(c.frame, 1, "still holding at 650ms");
+        step_animation(&mut c, 3, timing, Playback::Once, ms(100));
+        assert_eq!(c.frame, 2);
+        assert!(!c.finished, "the last frame is shown for its own duration");
+        step_animation(&mut c, 3, timing, Playback::Once, ms(50));
+        assert!(c.finished);
+        assert_eq!(timing.total(3), ms(800));
+    }
+
     #[test]
     fn restart_clears_finished() {
         let mut c = AnimationCursor::default();
-        step_animation(&mut c, 2, 12, Playback::Once, ms(500));
+        step_animation(&mut c, 2, FrameTiming::Fps(12), Playback::Once, ms(500));
         assert!(c.finished);
         c.restart();
         assert_eq!(c.frame, 0);
@@ -0,0 +1,455 @@
+//! Behaviour trees: plans longer than one transition.
+//!
+//! The state machine only knows where a pet may go from the state it is in,
+//! so it cannot say "walk somewhere, sit, then eat". A skin's tree can. It
+//! sits above the machine: whenever a state ends on its own, the tree may
+//! choose the next one in place of the transitions. Its leaves play the
+//! skin's own states, with their frames, durations and clips, so a tree adds
+//! an order to the states and nothing else.
+//!
+//! A tree cannot stall a pet. A sequence or selector cannot be built empty,
+//! so every pass through the tree reaches a leaf, and each time a state ends
+//! the tree finishes at most one leaf and starts exactly one more. That leaf
+//! either enters a state, which ends by that state's own rules, or waits,
+//! which hands the pet back to the transitions, which never dead-end.
+
+use bevy::prelude::*;
+use std::time::Duration;
+use thiserror::Error;
+
+use super::brain::PetState;
+use super::rng::PetRng;
+
+/// Where a `WalkTo` leaf sends the pet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    /// Wherever a wandering pet would go.
+    Anywhere,
+    /// A point as a fraction of the surface from its middle: `(-1, -1)` is
+    /// the bottom-left corner, as far as the pet fits.
+    Spot(Vec2),
+}
+
+/// One node of a tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// Runs its children in order; fails as soon as one does.
+    Sequence(Vec<Node>),
+    /// Tries its children in order until one succeeds.
+    Selector(Vec<Node>),
+    /// Leaves the pet to the skin's transitions for a duration drawn from
+    /// this inclusive range. Always succeeds.
+    Wait(Duration, Duration),
+    /// Walks to `Goal`. Fails if the walk ends without arriving.
+    WalkTo(Goal),
+    /// Plays a state through. Fails if an interrupt cuts it short.
+    Play(PetState),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TreeError {
+    #[error("a sequence or selector must have at least one child")]
+    Empty,
+    #[error("Play(Dragged) is not allowed: that state is owned by the pointer")]
+    Held,
+    #[error("a wait's range must run from a minimum of zero or more up to its maximum")]
+    BadWait,
+    #[error("a spot is a fraction of the screen, from -1 to 1 on each axis")]
+    OffScreen,
+}
+
+/// A validated tree. As with a transition table, construction is the only
+/// place the rules are checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BehaviourTree {
+    root: Node,
+}
+
+impl BehaviourTree {
+    pub fn new(root: Node) -> Result<Self, TreeError> {
+        check(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Every state a leaf of the tree can enter.
+    pub fn plays(&self) -> Vec<PetState> {
+        let mut states = Vec::new();
+        collect_plays(&self.root, &mut states);
+        states
+    }
+
+    /// The state to enter now that the pet's last one has ended on its own,
+    /// or `None` to let the transitions choose.
+    ///
+    /// Finishes the leaf that was running, if any, and starts the next one,
+    /// going back to the root when the whole tree is done.
+    pub fn next(
+        &self,
+        cursor: &mut TreeCursor,
+        ended: Ended,
+        rng: &mut PetRng,
+    ) -> Option<PetState> {
+        let path = match cursor.running.take() {
+            Some(mut running) => {
+                let succeeded = match self.node(&running.path) {
+                    Some(Node::Wait(..)) => {
+                        running.waited += ended.elapsed;
+                        if running.waited < running.wait_for {
+                            cursor.running = Some(running);
+                            return None;
+                        }
+                        true
+                    }
+                    Some(Node::WalkTo(_)) => !running.interrupted && ended.arrived,
+                    Some(Node::Play(_)) => !running.interrupted,
+                    // A path from some other tree, as after a skin change.
+                    _ => true,
+                };
+                self.after(running.path, succeeded)
+            }
+            None => Vec::new(),
+        };
+        self.start(path, cursor, rng)
+    }
+
+    fn node(&self, path: &[usize]) -> Option<&Node> {
+        path.iter().try_fold(&self.root, |node, &index| match node {
+            Node::Sequence(children) | Node::Selector(children) => children.get(index),
+            _ => None,
+        })
+    }
+
+    /// Where to carry on once the node at `path` has finished: its next
+    /// sibling if its parent wants one, and otherwise whatever follows the
+    /// parent. The empty path, the root, once the whole tree is done.
+    fn after(&self, mut path: Vec<usize>, succeeded: bool) -> Vec<usize> {
+        while let Some(index) = path.pop() {
+            let (children, carry_on) = match self.node(&path) {
+                Some(Node::Sequence(children)) => (children, succeeded),
+                Some(Node::Selector(children)) => (children, !succeeded),
+                _ => return Vec::new(),
+            };
+            if carry_on && index + 1 < children.len() {
+                path.push(index + 1);
+                return path;
+            }
+        }
+        path
+    }
+
+    /// Starts the first leaf under the node at `path`.
+    fn start(
+        &self,
+        mut path: Vec<usize>,
+        cursor: &mut TreeCursor,
+        rng: &mut PetRng,
+    ) -> Option<PetState> {
+        let mut node = match self.node(&path) {
+            Some(node) => node,
+            None => {
+                path.clear();
+                &self.root
+            }
+        };
+        while let Node::Sequence(children) | Node::Selector(children) = node {
+            path.push(0);
+            node = &children[0];
+        }
+        let mut running = Running {
+            path,
+            interrupted: false,
+            waited: Duration::ZERO,
+            wait_for: Duration::ZERO,
+        };
+        let next = match *node {
+            Node::Wait(lo, hi) => {
+                running.wait_for = rng.range_duration(lo, hi);
+                None
+            }
+            Node::WalkTo(goal) => {
+                cursor.goal = Some(goal);
+                Some(PetState::Walking)
+            }
+            Node::Play(state) => Some(state),
+            Node::Sequence(_) | Node::Selector(_) => unreachable!("descended to a leaf"),
+        };
+        cursor.running = Some(running);
+        next
+    }
+}
+
+fn check(node: &Node) -> Result<(), TreeError> {
+    match node {
+        Node::Sequence(children) | Node::Selector(children) => {
+            if children.is_empty() {
+                return Err(TreeError::Empty);
+            }
+            children.iter().try_for_each(check)
+        }
+        Node::Wait(lo, hi) if lo > hi => Err(TreeError::BadWait),
+        Node::WalkTo(Goal::Spot(at)) if !(at.is_finite() && at.abs().max_element() <= 1.0) => {
+            Err(TreeError::OffScreen)
+        }
+        // Entering Dragged with nothing holding the pet would leave it stuck.
+        Node::Play(PetState::Dragged) => Err(TreeError::Held),
+        Node::Wait(..) | Node::WalkTo(_) | Node::Play(_) => Ok(()),
+    }
+}
+
+fn collect_plays(node: &Node, states: &mut Vec<PetState>) {
+    let state = match node {
+        Node::Sequence(children) | Node::Selector(children) => {
+            children
+                .iter()
+                .for_each(|child| collect_plays(child, states));
+            return;
+        }
+        Node::Wait(..) => return,
+        Node::WalkTo(_) => PetState::Walking,
+        Node::Play(state) => *state,
+    };
+    if !states.contains(&state) {
+        states.push(state);
+    }
+}
+
+/// How the state a leaf was waiting on came to an end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ended {
+    /// How long the state lasted.
+    pub elapsed: Duration,
+    /// Whether a walk got where it was going.
+    pub arrived: bool,
+}
+
+/// One pet's place in the tree. Empty until the tree first picks a state.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeCursor {
+    running: Option<Running>,
+    goal: Option<Goal>,
+}
+
+impl TreeCursor {
+    /// Marks the running leaf as cut short, so it fails when the interrupt's
+    /// state ends. A wait is not a plan, and carries on.
+    pub fn interrupt(&mut self) {
+        if let Some(running) = &mut self.running {
+            running.interrupted = true;
+        }
+        self.goal = None;
+    }
+
+    /// Where the walk the tree just started should go, once.
+    pub fn take_goal(&mut self) -> Option<Goal> {
+        self.goal.take()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Running {
+    /// Child indices from the root down to the leaf.
+    path: Vec<usize>,
+    interrupted: bool,
+    waited: Duration,
+    wait_for: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rng::Seed;
+    use PetState::*;
+
+    const DONE: Ended = Ended {
+        elapsed: Duration::from_secs(1),
+        arrived: true,
+    };
+
+    fn tree(root: Node) -> BehaviourTree {
+        BehaviourTree::new(root).expect("valid tree")
+    }
+
+    #[test]
+    fn invalid_trees_are_rejected() {
+        let secs = Duration::from_secs;
+        let cases = [
+            (Node::Selector(vec![]), TreeError::Empty),
+            (
+                Node::Sequence(vec![Node::Play(Idle), Node::Sequence(vec![])]),
+                TreeError::Empty,
+            ),
+            (Node::Play(Dragged), TreeError::Held),
+            (Node::Wait(secs(5), secs(1)), TreeError::BadWait),
+            (
+                Node::WalkTo(Goal::Spot(Vec2::new(0.0, 1.5))),
+                TreeError::OffScreen,
+            ),
+        ];
+        for (root, error) in cases {
+            assert_eq!(BehaviourTree::new(root).unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn a_sequence_plays_in_order_then_starts_over() {
+        let tree = tree(Node::Sequence(vec![
+            Node::WalkTo(Goal::Anywhere),
+            Node::Play(Sitting),
+            Node::Play(Eating),
+        ]));
+        let mut cursor = TreeCursor::default();
+        let mut rng = PetRng::from_seed(Seed(1));
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Walking));
+        assert_eq!(cursor.take_goal(), Some(Goal::Anywhere));
+        assert_eq!(cursor.take_goal(), None);
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Sitting));
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Eating));
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Walking));
+    }
+
+    /// "Sit, then eat, unless interrupted": a selector falls back to chilling.
+    #[test]
+    fn an_interrupt_fails_the_plan_and_the_selector_falls_back() {
+        let tree = tree(Node::Selector(vec![
+            Node::Sequence(vec![Node::Play(Sitting), Node::Play(Eating)]),
+            Node::Play(Chilling),
+        ]));
+        let mut cursor = TreeCursor::default();
+        let mut rng = PetRng::from_seed(Seed(1));
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Sitting));
+        cursor.interrupt();
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Chilling));
+        // The selector succeeded, so the tree starts over.
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Sitting));
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Eating));
+    }
+
+    #[test]
+    fn a_walk_that_never_arrives_fails() {
+        let tree = tree(Node::Selector(vec![
+            Node::WalkTo(Goal::Spot(Vec2::new(1.0, -1.0))),
+            Node::Play(Sitting),
+        ]));
+        let mut cursor = TreeCursor::default();
+        let mut rng = PetRng::from_seed(Seed(1));
+        let lost = Ended {
+            arrived: false,
+            ..DONE
+        };
+        assert_eq!(tree.next(&mut cursor, lost, &mut rng), Some(Walking));
+        assert_eq!(tree.next(&mut cursor, lost, &mut rng), Some(Sitting));
+        assert_eq!(tree.next(&mut cursor, lost, &mut rng), Some(Walking));
+        assert_eq!(tree.next(&mut cursor, DONE, &mut rng), Some(Walking));
+    }
+
+    #[test]
+    fn a_wait_leaves_the_pet_to_its_transitions() {
+        let five = Duration::from_secs(5);
+        let tree = tree(Node::Sequence(vec![
+            Node::Wait(five, five),
+            Node::Play(Eating),
+        ]));
+        let mut cursor = TreeCursor::default();
+        let mut rng = PetRng::from_seed(Seed(1));
+        let three = Ended {
+            elapsed: Duration::from_secs(3),
+            arrived: true,
+        };
+        assert_eq!(tree.next(&mut cursor, three, &mut rng), None);
+        // Interrupts come and go while it waits.
+        cursor.interrupt();
+        assert_eq!(tree.next(&mut cursor, three, &mut rng), None);
+        assert_eq!(tree.next(&mut cursor, three, &mut rng), Some(Eating));
+    }
+
+    #[test]
+    fn a_cursor_from_another_tree_starts_over() {
+        let old = tree(Node::Sequence(vec![Node::Play(Idle), Node::Play(Sitting)]));
+        let new = tree(Node::Play(Chilling));
+        let mut cursor = TreeCursor::default();
+        let mut rng = PetRng::from_seed(Seed(1));
+        old.next(&mut cursor, DONE, &mut rng);
+        old.next(&mut cursor, DONE, &mut rng);
+        assert_eq!(new.next(&mut cursor, DONE, &mut rng), Some(Chilling));
+    }
+
+    const LONGEST_WAIT: Duration = Duration::from_secs(10);
+    const SHORTEST_STATE: Duration = Duration::from_millis(100);
+
+    /// A valid tree of at most `depth` levels, every leaf kind included.
+    fn random_node(rng: &mut PetRng, depth: u32) -> Node {
+        let leaf = depth == 0 || rng.roll(3) == 0;
+        match rng.roll(if leaf { 3 } else { 5 }) {
+            0 => {
+                let lo = rng.range_duration(Duration::ZERO, LONGEST_WAIT);
+                Node::Wait(lo, rng.range_duration(lo, LONGEST_WAIT))
+            }
+            1 => match rng.roll(2) {
+                0 => Node::WalkTo(Goal::Anywhere),
+                _ => Node::WalkTo(Goal::Spot(rng.point_in(Vec2::ONE))),
+            },
+            2 => {
+                let playable: Vec<_> = PetState::ALL
+                    .into_iter()
+                    .filter(|&s| s != Dragged)
+                    .collect();
+                Node::Play(playable[rng.roll(playable.len() as u32) as usize])
+            }
+            kind => {
+                let children = (0..1 + rng.roll(4))
+                    .map(|_| random_node(rng, depth - 1))
+                    .collect();
+                match kind {
+                    3 => Node::Sequence(children),
+                    _ => Node::Selector(children),
+                }
+            }
+        }
+    }
+
+    /// However states end and whenever interrupts land, every call either
+    /// enters a state the tree plays or is a wait that is running out: no
+    /// tree leaves a pet stuck with nothing to do.
+    #[test]
+    fn random_trees_never_stall() {
+        let mut rng = PetRng::from_seed(Seed(7));
+        let stuck_for = (LONGEST_WAIT.as_millis() / SHORTEST_STATE.as_millis()) as u32;
+        for _ in 0..500 {
+            let tree = tree(random_node(&mut rng, 4));
+            let plays = tree.plays();
+            let mut cursor = TreeCursor::default();
+            let mut waiting = 0;
+            for _ in 0..200 {
+                if rng.roll(4) == 0 {
+                    cursor.interrupt();
+                }
+                let ended = Ended {
+                    elapsed: rng.range_duration(SHORTEST_STATE, Duration::from_secs(3)),
+                    arrived: rng.roll(2) == 0,
+                };
+                let entered = tree.next(&mut cursor, ended, &mut rng);
+                let running = cursor.running.as_ref().expect("a leaf is always running");
+                match entered {
+                    Some(state) => {
+                        assert!(plays.contains(&state), "{state:?} is not in {tree:?}");
+                        waiting = 0;
+                    }
+                    None => {
+                        assert!(
+                            matches!(tree.node(&running.path), Some(Node::Wait(..))),
+                            "no state and no wait in {tree:?}"
+                        );
+                        // A new wait starts from nothing; one carrying on has
+                        // a bounded way to go.
+                        waiting = if running.waited.is_zero() {
+                            0
+                        } else {
+                            waiting + 1
+                        };
+                        assert!(waiting <= stuck_for, "a wait never ended in {tree:?}");
+                    }
+                }
+            }
+        }
+    }
+}
@@ -10,9 +10,12 @@
 
 use bevy::prelude::*;
 use serde::Deserialize;
+use std::borrow::Cow;
 use std::time::Duration;
 use thiserror::Error;
 
+use super::animation::FrameTiming;
+use super::behaviour::{BehaviourTree, Ended, TreeCursor};
 use super::rng::PetRng;
 
 /// The eight animation states. Discriminants are the sprite-sheet row.
@@ -117,44 +120,130 @@ impl<T: Copy> WeightedTable<T> {
     }
 }
 
+impl WeightedTable<PetState> {
+    /// This table with each destination's weight scaled by `bias`.
+    ///
+    /// Factors are positive, and a scaled weight never rounds down to zero,
+    /// so every exit the skin wrote stays an exit: bias shifts the odds
+    /// without ever cutting the graph the skin was checked against.
+    pub fn biased(&self, bias: &TransitionBias) -> Cow<'_, Self> {
+        if bias.0.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let entries: Vec<(PetState, u16)> = self
+            .entries
+            .iter()
+            .map(|&(state, weight)| {
+                let scaled = (f32::from(weight) * bias.factor(state)).round();
+                let scaled = scaled.clamp(1.0, f32::from(u16::MAX)) as u16;
+                (state, if weight == 0 { 0 } else { scaled })
+            })
+            .collect();
+        let total = entries.iter().map(|(_, w)| u32::from(*w)).sum();
+        Cow::Owned(Self { entries, total })
+    }
+}
+
+/// Multipliers on transition weights by destination, for a mood that lasts
+/// longer than any one state, such as a busy machine making pets restless.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransitionBias(Vec<(PetState, f32)>);
+
+impl TransitionBias {
+    /// Leaves every table as the skin wrote it.
+    pub const NONE: TransitionBias = TransitionBias(Vec::new());
+
+    /// Multiplies `state`'s factor by `factor`, which must be positive.
+    pub fn scale(&mut self, state: PetState, factor: f32) {
+        debug_assert!(factor > 0.0, "a zero factor would cut an exit");
+        match self.0.iter_mut().find(|(known, _)| *known == state) {
+            Some((_, existing)) => *existing *= factor,
+            None => self.0.push((state, factor)),
+        }
+    }
+
+    fn factor(&self, state: PetState) -> f32 {
+        self.0
+            .iter()
+            .find(|(known, _)| *known == state)
+            .map_or(1.0, |(_, factor)| *factor)
+    }
+}
+
 /// Everything the machine needs to know about one state.
 #[derive(Debug, Clone)]
 pub struct StateDef {
     pub frames: u32,
     pub fps: u8,
+    /// Hand-timed length of each frame, one per frame. Overrides `fps`.
+    pub frame_durations: Option<Vec<Duration>>,
     pub playback: Playback,
     /// Inclusive range the state's duration is drawn from.
     pub duration: (Duration, Duration),
     pub locomotion: Locomotion,
     pub transitions: WeightedTable<PetState>,
+    /// Clips played on the way out of this state, at most one per destination.
+    pub clips: Vec<Clip>,
+    /// Things a pet may say on entering this state.
+    pub phrases: Vec<String>,
 }
 
-/// All eight state definitions, indexed by [`PetState::row`].
+/// A one-shot animation bridging two states, such as standing up between
+/// `Sitting` and `Walking`. Drawn on its own sheet row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub to: PetState,
+    pub row: u32,
+    pub frames: u32,
+    pub fps: u8,
+}
+
+/// All eight state definitions, indexed by [`PetState::row`], and the
+/// behaviour tree over them if the skin has one.
 #[derive(Resource, Debug, Clone)]
 pub struct StateTable {
     defs: Vec<StateDef>,
+    tree: Option<BehaviourTree>,
 }
 
 impl StateTable {
     /// `defs` must be in [`PetState::ALL`] order.
     pub fn new(defs: Vec<StateDef>) -> Self {
         assert_eq!(defs.len(), PetState::ALL.len(), "one def per state");
-        Self { defs }
+        Self { defs, tree: None }
+    }
+
+    pub fn with_tree(self, tree: Option<BehaviourTree>) -> Self {
+        Self { tree, ..self }
     }
 
     pub fn get(&self, state: PetState) -> &StateDef {
         &self.defs[state.row() as usize]
     }
+
+    pub fn tree(&self) -> Option<&BehaviourTree> {
+        self.tree.as_ref()
+    }
 }
 
 /// Per-pet machine state.
+///
+/// Only the brain systems change it; plugins read it through [`state`] and
+/// [`locked`], and steer the pet with a `PendingInterrupt`.
+///
+/// [`state`]: PetBrain::state
+/// [`locked`]: PetBrain::locked
 #[derive(Component, Debug, Clone)]
 pub struct PetBrain {
-    pub state: PetState,
-    pub elapsed: Duration,
-    pub planned: Duration,
+    pub(crate) state: PetState,
+    pub(crate) elapsed: Duration,
+    pub(crate) planned: Duration,
     /// While set, timeouts do not fire: an interaction owns the pet.
-    pub locked: bool,
+    pub(crate) locked: bool,
+    /// Index into the current state's `clips` while one plays on the way out.
+    pub(crate) clip: Option<usize>,
+    /// Where the pet is in the skin's behaviour tree, if it has one.
+    pub(crate) plan: TreeCursor,
 }
 
 impl PetBrain {
@@ -164,14 +253,42 @@ impl PetBrain {
             elapsed: Duration::ZERO,
             planned,
             locked: false,
+            clip: None,
+            plan: TreeCursor::default(),
         }
     }
+
+    /// The state the pet is in.
+    pub fn state(&self) -> PetState {
+        self.state
+    }
+
+    /// Whether an interaction owns the pet: it is held by the user, or asleep
+    /// while they are away.
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+}
+
+/// What one pet's frame brought, as far as its brain is concerned.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BrainInputs {
+    /// A state to enter now, whatever the pet is doing.
+    pub interrupt: Option<PetState>,
+    /// The state's animation, or the clip playing, has reached its end.
+    pub playback_finished: bool,
+    /// A walk has reached its target.
+    pub locomotion_finished: bool,
+    pub dt: Duration,
 }
 
 /// Result of one brain tick.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BrainStep {
     Stay,
+    /// Leaving through one of the state's clips; the destination is entered
+    /// once it has played.
+    PlayClip,
     Enter(PetState),
 }
 
@@ -188,17 +305,30 @@ fn locks(state: PetState) -> bool {
 /// pet that reached where it was sent should stop, not keep playing a walk
 /// animation on the spot until an unrelated timer expires.
 ///
-/// A locked state ignores everything but an interrupt.
+/// Where a state that ends goes is up to `tree` when the skin has one, and
+/// otherwise, or when the tree leaves it to them, drawn from the state's
+/// transitions, scaled by `bias`. An interrupt cuts short whatever the tree
+/// was doing.
+///
+/// A locked state ignores everything but an interrupt. So does a clip, which
+/// otherwise always plays to its end: there is no timer to cut it short, only
+/// the interrupt that still wins.
 pub fn step_brain(
     brain: &mut PetBrain,
     def: &StateDef,
-    interrupt: Option<PetState>,
-    playback_finished: bool,
-    locomotion_finished: bool,
-    dt: Duration,
+    bias: &TransitionBias,
+    tree: Option<&BehaviourTree>,
+    inputs: BrainInputs,
     rng: &mut PetRng,
 ) -> BrainStep {
+    let BrainInputs {
+        interrupt,
+        playback_finished,
+        locomotion_finished,
+        dt,
+    } = inputs;
     if let Some(next) = interrupt {
+        brain.plan.interrupt();
         return enter(brain, next);
     }
 
@@ -206,6 +336,14 @@ pub fn step_brain(
         return BrainStep::Stay;
     }
 
+    if let Some(clip) = brain.clip.and_then(|index| def.clips.get(index)) {
+        return if playback_finished {
+            enter(brain, clip.to)
+        } else {
+            BrainStep::Stay
+        };
+    }
+
     brain.elapsed += dt;
 
     let animation_done = def.playback == Playback::Once && playback_finished;
@@ -216,23 +354,43 @@ pub fn step_brain(
         return BrainStep::Stay;
     }
 
-    let roll = rng.roll(def.transitions.total());
-    enter(brain, def.transitions.pick(roll))
+    let ended = Ended {
+        elapsed: brain.elapsed,
+        arrived: locomotion_finished,
+    };
+    let planned = tree.and_then(|tree| tree.next(&mut brain.plan, ended, rng));
+    let next = planned.unwrap_or_else(|| {
+        let transitions = def.transitions.biased(bias);
+        transitions.pick(rng.roll(transitions.total()))
+    });
+    if let Some(index) = def.clips.iter().position(|clip| clip.to == next) {
+        brain.clip = Some(index);
+        return BrainStep::PlayClip;
+    }
+    enter(brain, next)
 }
 
 fn enter(brain: &mut PetBrain, next: PetState) -> BrainStep {
     brain.state = next;
     brain.elapsed = Duration::ZERO;
     brain.locked = locks(next);
+    brain.clip = None;
     BrainStep::Enter(next)
 }
 
+impl StateDef {
+    /// How long each frame is shown.
+    pub fn timing(&self) -> FrameTiming<'_> {
+        match &self.frame_durations {
+            Some(durations) => FrameTiming::PerFrame(durations),
+            None => FrameTiming::Fps(self.fps),
+        }
+    }
+}
+
 /// How long this state's animation takes to play through once.
 pub fn animation_length(def: &StateDef) -> Duration {
-    if def.fps == 0 {
-        return Duration::ZERO;
-    }
-    Duration::from_secs_f32(def.frames as f32 / f32::from(def.fps))
+    def.timing().total(def.frames)
 }
 
 /// Draws the duration for a freshly entered state.
@@ -252,6 +410,7 @@ pub fn plan_duration(def: &StateDef, rng: &mut PetRng) -> Duration {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::behaviour::{Goal, Node};
     use crate::core::rng::Seed;
 
     fn secs(s: f32) -> Duration {
@@ -262,16 +421,38 @@ mod tests {
         WeightedTable::new(entries.to_vec()).expect("valid table")
     }
 
+    /// One tick with no bias and no tree, as most of these tests want.
+    fn step(
+        brain: &mut PetBrain,
+        def: &StateDef,
+        interrupt: Option<PetState>,
+        playback_finished: bool,
+        locomotion_finished: bool,
+        dt: Duration,
+        rng: &mut PetRng,
+    ) -> BrainStep {
+        let inputs = BrainInputs {
+            interrupt,
+            playback_finished,
+            locomotion_finished,
+            dt,
+        };
+        step_brain(brain, def, &TransitionBias::NONE, None, inputs, rng)
+    }
+
     /// A table mirroring the shipped default, used to prove no state dead-ends.
     fn test_table() -> StateTable {
         use PetState::*;
         let def = |frames, playback, lo, hi, locomotion, transitions| StateDef {
             frames,
             fps: 12,
+            frame_durations: None,
             playback,
             duration: (secs(lo), secs(hi)),
             locomotion,
             transitions,
+            clips: Vec::new(),
+            phrases: Vec::new(),
         };
         StateTable::new(vec![
             def(
@@ -366,13 +547,28 @@ mod tests {
         assert_eq!(t.pick(3), PetState::Walking);
     }
 
+    #[test]
+    fn bias_shifts_the_odds_but_keeps_every_exit() {
+        let t = table(&[(PetState::Idle, 30), (PetState::Walking, 10)]);
+        assert_eq!(*t.biased(&TransitionBias::NONE), t);
+
+        let mut bias = TransitionBias::default();
+        bias.scale(PetState::Walking, 2.0);
+        bias.scale(PetState::Walking, 1.5);
+        bias.scale(PetState::Idle, 0.001);
+        let biased = t.biased(&bias);
+        assert_eq!(biased.total(), 1 + 30, "Idle shrinks to 1, never 0");
+        assert_eq!(biased.pick(0), PetState::Idle);
+        assert_eq!(biased.pick(1), PetState::Walking);
+    }
+
     #[test]
     fn interrupt_beats_timeout() {
         let table = test_table();
         let def = table.get(PetState::Idle);
         let mut brain = PetBrain::new(PetState::Idle, secs(100.0));
         let mut rng = PetRng::from_seed(Seed(1));
-        let step = step_brain(
+        let step = step(
             &mut brain,
             def,
             Some(PetState::Dragged),
@@ -385,6 +581,64 @@ mod tests {
         assert!(brain.locked, "Dragged must lock the pet");
     }
 
+    /// Sitting -> Walking with a stand-up clip in between.
+    fn sitting_with_clip() -> StateDef {
+        let mut def = test_table().get(PetState::Sitting).clone();
+        def.transitions = table(&[(PetState::Walking, 1)]);
+        def.clips = vec![Clip {
+            to: PetState::Walking,
+            row: 8,
+            frames: 4,
+            fps: 12,
+        }];
+        def
+    }
+
+    #[test]
+    fn a_clip_plays_before_its_destination_is_entered() {
+        let def = sitting_with_clip();
+        let mut brain = PetBrain::new(PetState::Sitting, Duration::ZERO);
+        let mut rng = PetRng::from_seed(Seed(1));
+        let tick = |brain: &mut PetBrain, finished, rng: &mut PetRng| {
+            step(brain, &def, None, finished, false, secs(0.016), rng)
+        };
+
+        assert_eq!(tick(&mut brain, false, &mut rng), BrainStep::PlayClip);
+        assert_eq!(brain.state, PetState::Sitting);
+        assert_eq!(brain.clip, Some(0));
+
+        // The clip's cursor has not finished, however long the state overran.
+        brain.elapsed = secs(999.0);
+        assert_eq!(tick(&mut brain, false, &mut rng), BrainStep::Stay);
+
+        assert_eq!(
+            tick(&mut brain, true, &mut rng),
+            BrainStep::Enter(PetState::Walking)
+        );
+        assert_eq!(brain.clip, None);
+    }
+
+    #[test]
+    fn an_interrupt_cuts_a_clip_short() {
+        let def = sitting_with_clip();
+        let mut brain = PetBrain::new(PetState::Sitting, Duration::ZERO);
+        let mut rng = PetRng::from_seed(Seed(1));
+        step(&mut brain, &def, None, false, false, secs(0.016), &mut rng);
+        assert_eq!(brain.clip, Some(0));
+
+        let step = step(
+            &mut brain,
+            &def,
+            Some(PetState::Dragged),
+            false,
+            false,
+            secs(0.016),
+            &mut rng,
+        );
+        assert_eq!(step, BrainStep::Enter(PetState::Dragged));
+        assert_eq!(brain.clip, None);
+    }
+
     #[test]
     fn locked_state_ignores_timeout() {
         let table = test_table();
@@ -393,7 +647,7 @@ mod tests {
         brain.locked = true;
         let mut rng = PetRng::from_seed(Seed(1));
         for _ in 0..1000 {
-            let step = step_brain(&mut brain, def, None, true, false, secs(0.016), &mut rng);
+            let step = step(&mut brain, def, None, true, false, secs(0.016), &mut rng);
             assert_eq!(step, BrainStep::Stay);
         }
         assert_eq!(brain.state, PetState::Dragged);
@@ -407,12 +661,12 @@ mod tests {
         let mut rng = PetRng::from_seed(Seed(1));
         // Not finished: stays despite a tiny elapsed time.
         assert_eq!(
-            step_brain(&mut brain, def, None, false, false, secs(0.016), &mut rng),
+            step(&mut brain, def, None, false, false, secs(0.016), &mut rng),
             BrainStep::Stay
         );
         // Finished: leaves even though `planned` is far away.
         assert_eq!(
-            step_brain(&mut brain, def, None, true, false, secs(0.016), &mut rng),
+            step(&mut brain, def, None, true, false, secs(0.016), &mut rng),
             BrainStep::Enter(PetState::Idle)
         );
     }
@@ -434,7 +688,7 @@ mod tests {
                 // drag is ended by an interrupt, which this loop does not model.
                 brain.locked = false;
                 if let BrainStep::Enter(next) =
-                    step_brain(&mut brain, def, None, true, false, secs(0.016), &mut rng)
+                    step(&mut brain, def, None, true, false, secs(0.016), &mut rng)
                 {
                     seen.insert(next);
                     brain.planned = plan_duration(table.get(next), &mut rng);
@@ -448,6 +702,78 @@ mod tests {
         }
     }
 
+    /// A tree of any shape, up to `depth` composites deep.
+    fn random_node(rng: &mut PetRng, depth: u32) -> Node {
+        let kind = rng.roll(if depth == 0 { 3 } else { 5 });
+        match kind {
+            0 => Node::Wait(secs(0.0), secs(rng.roll(20) as f32)),
+            1 => Node::WalkTo(Goal::Anywhere),
+            2 => match PetState::ALL[rng.roll(8) as usize] {
+                PetState::Dragged => Node::Play(PetState::Idle),
+                state => Node::Play(state),
+            },
+            _ => {
+                let children = (0..=rng.roll(3))
+                    .map(|_| random_node(rng, depth - 1))
+                    .collect();
+                if kind == 3 {
+                    Node::Sequence(children)
+                } else {
+                    Node::Selector(children)
+                }
+            }
+        }
+    }
+
+    /// Whatever the tree, and whatever interrupts and failed walks get in its
+    /// way, a pet never stays in a state longer than the longest the table
+    /// plans for: the tree cannot deadlock the machine under it.
+    #[test]
+    fn no_behaviour_tree_stalls_a_pet() {
+        let table = test_table();
+        // Sitting's 20s, in 0.1s ticks, and one to notice.
+        let longest = 201;
+        for seed in 1..=200 {
+            let mut rng = PetRng::from_seed(Seed(seed));
+            let tree = BehaviourTree::new(random_node(&mut rng, 3)).expect("valid tree");
+            let mut brain = PetBrain::new(PetState::Idle, secs(0.0));
+            let mut ticks = 0;
+            for _ in 0..5_000 {
+                brain.locked = false;
+                let interrupt = (rng.roll(50) == 0).then_some(PetState::Jumping);
+                let arrived = rng.roll(2) == 0;
+                let inputs = BrainInputs {
+                    interrupt,
+                    playback_finished: true,
+                    locomotion_finished: arrived,
+                    dt: secs(0.1),
+                };
+                let def = table.get(brain.state);
+                let step = step_brain(
+                    &mut brain,
+                    def,
+                    &TransitionBias::NONE,
+                    Some(&tree),
+                    inputs,
+                    &mut rng,
+                );
+                match step {
+                    BrainStep::Stay => ticks += 1,
+                    BrainStep::PlayClip => {}
+                    BrainStep::Enter(next) => {
+                        ticks = 0;
+                        brain.planned = plan_duration(table.get(next), &mut rng);
+                    }
+                }
+                assert!(
+                    ticks <= longest,
+                    "seed {seed}: stuck in {:?} under {tree:?}",
+                    brain.state
+                );
+            }
+        }
+    }
+
     /// Regression test for a Once animation being cut off by its timer: a
     /// 61-frame reaction at 12fps needs ~5.1s, but the manifest asks for 2s.
     #[test]
@@ -465,6 +791,19 @@ mod tests {
         }
     }
 
+    /// A hold on one pose lengthens the animation, and the plan must cover it.
+    #[test]
+    fn once_states_plan_for_per_frame_durations() {
+        let mut def = test_table().get(PetState::Jumping).clone();
+        def.frames = 3;
+        let ms = Duration::from_millis;
+        def.frame_durations = Some(vec![ms(100), ms(9000), ms(100)]);
+        assert_eq!(animation_length(&def), ms(9200));
+
+        let mut rng = PetRng::from_seed(Seed(5));
+        assert!(plan_duration(&def, &mut rng) >= ms(9200));
+    }
+
     #[test]
     fn a_once_state_plays_to_the_end_before_leaving() {
         let table = test_table();
@@ -475,14 +814,14 @@ mod tests {
         // Tick well past the drawn 2s duration without the animation finishing.
         let mut elapsed = Duration::ZERO;
         while elapsed < secs(4.0) {
-            let step = step_brain(&mut brain, def, None, false, false, secs(0.05), &mut rng);
+            let step = step(&mut brain, def, None, false, false, secs(0.05), &mut rng);
             assert_eq!(step, BrainStep::Stay, "left early at {elapsed:?}");
             elapsed += secs(0.05);
         }
 
         // It leaves as soon as the animation reports finishing.
         assert_eq!(
-            step_brain(&mut brain, def, None, true, false, secs(0.05), &mut rng),
+            step(&mut brain, def, None, true, false, secs(0.05), &mut rng),
             BrainStep::Enter(PetState::Idle)
         );
     }
@@ -498,12 +837,12 @@ mod tests {
 
         // Still travelling: the long duration keeps it walking.
         assert_eq!(
-            step_brain(&mut brain, def, None, false, false, secs(0.05), &mut rng),
+            step(&mut brain, def, None, false, false, secs(0.05), &mut rng),
             BrainStep::Stay
         );
         // Arrived: it leaves at once rather than waiting out the clock.
         assert!(matches!(
-            step_brain(&mut brain, def, None, false, true, secs(0.05), &mut rng),
+            step(&mut brain, def, None, false, true, secs(0.05), &mut rng),
             BrainStep::Enter(_)
         ));
     }
@@ -517,7 +856,7 @@ mod tests {
         let mut rng = PetRng::from_seed(Seed(8));
         let mut brain = PetBrain::new(PetState::Idle, secs(999.0));
         assert_eq!(
-            step_brain(&mut brain, def, None, false, true, secs(0.05), &mut rng),
+            step(&mut brain, def, None, false, true, secs(0.05), &mut rng),
             BrainStep::Stay
         );
     }
@@ -533,7 +872,7 @@ mod tests {
                 let def = table.get(brain.state);
                 brain.locked = false;
                 if let BrainStep::Enter(next) =
-                    step_brain(&mut brain, def, None, true, false, secs(0.1), &mut rng)
+                    step(&mut brain, def, None, true, false, secs(0.1), &mut rng)
                 {
                     trace.push(next);
                     brain.planned = plan_duration(table.get(next), &mut rng);
@@ -5,6 +5,11 @@
 //! makes the containment test unsatisfiable, so the pet silently became
 //! unclickable. Here the extent comes from the frame size and a positive scale,
 //! and facing is not part of the calculation at all.
+//!
+//! The box only bounds the pet. Whether a point actually lands on it is
+//! decided by the current frame's [`AlphaMask`], so the transparent corners of
+//! a frame neither grab the pet nor, on Wayland, swallow clicks meant for the
+//! window beneath.
 
 use bevy::prelude::*;
 
@@ -23,14 +28,170 @@ pub fn pet_rect_world(centre: Vec2, frame_size: Vec2, scale: f32) -> Rect {
     }
 }
 
-/// The topmost pet containing `p`.
+/// Side of the square cells an [`AlphaMask`]'s cover is built from, in frame
+/// pixels. Coarser than the mask itself so a pet's input region stays a
+/// handful of rects rather than one per row.
+pub const COVER_CELL: u32 = 5;
+
+/// Which pixels of one animation frame are solid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphaMask {
+    size: UVec2,
+    /// Row-major from the frame's top-left, like the image it came from.
+    solid: Vec<bool>,
+    /// Rects covering every solid pixel, in frame pixels from the top-left.
+    cover: Vec<URect>,
+}
+
+impl AlphaMask {
+    /// Builds a mask by asking `solid_at` about each pixel, `(0, 0)` being the
+    /// frame's top-left.
+    pub fn from_fn(size: UVec2, solid_at: impl Fn(UVec2) -> bool) -> Self {
+        let solid: Vec<bool> = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| UVec2::new(x, y)))
+            .map(solid_at)
+            .collect();
+        let cover = cover_rects(size, &solid, COVER_CELL);
+        Self { size, solid, cover }
+    }
+
+    /// Whether the pixel at `px` is solid. Outside the frame is not.
+    pub fn is_solid(&self, px: UVec2) -> bool {
+        px.x < self.size.x && px.y < self.size.y && self.solid[(px.y * self.size.x + px.x) as usize]
+    }
+
+    /// Rects that together cover every solid pixel and little else.
+    pub fn cover(&self) -> &[URect] {
+        &self.cover
+    }
+}
+
+/// Covers the solid pixels with runs of `cell`-sized cells.
 ///
-/// `candidates` is `(entity, z, rect)`. Ties break on the higher entity index
+/// A cell counts if any pixel in it is solid, so the cover never misses part
+/// of the pet. Each row of cells becomes one rect per run, and a run is
+/// extended downwards while the next row has exactly the same run.
+fn cover_rects(size: UVec2, solid: &[bool], cell: u32) -> Vec<URect> {
+    let cells = (size + (cell - 1)) / cell;
+    let cell_is_solid = |cx: u32, cy: u32| {
+        let min = UVec2::new(cx, cy) * cell;
+        let max = (min + cell).min(size);
+        (min.y..max.y).any(|y| (min.x..max.x).any(|x| solid[(y * size.x + x) as usize]))
+    };
+
+    let mut rects: Vec<URect> = Vec::new();
+    // Rects whose bottom edge is the row above, which may still grow.
+    let mut open: Vec<usize> = Vec::new();
+    for cy in 0..cells.y {
+        let mut runs = Vec::new();
+        let mut cx = 0;
+        while cx < cells.x {
+            if !cell_is_solid(cx, cy) {
+                cx += 1;
+                continue;
+            }
+            let start = cx;
+            while cx < cells.x && cell_is_solid(cx, cy) {
+                cx += 1;
+            }
+            runs.push((start * cell, (cx * cell).min(size.x)));
+        }
+
+        let top = cy * cell;
+        let bottom = (top + cell).min(size.y);
+        let mut still_open = Vec::new();
+        for (left, right) in runs {
+            let above = open
+                .iter()
+                .copied()
+                .find(|&i| rects[i].min.x == left && rects[i].max.x == right);
+            match above {
+                Some(i) => {
+                    rects[i].max.y = bottom;
+                    still_open.push(i);
+                }
+                None => {
+                    still_open.push(rects.len());
+                    rects.push(URect::new(left, top, right, bottom));
+                }
+            }
+        }
+        open = still_open;
+    }
+    rects
+}
+
+/// A pet as picking and the input region see it: its box and, when known,
+/// the frame it is showing.
+#[derive(Debug, Clone, Copy)]
+pub struct PetShape<'a> {
+    pub rect: Rect,
+    /// The current frame's mask. Without one, the whole box counts.
+    pub mask: Option<&'a AlphaMask>,
+    /// Whether the frame is drawn mirrored, as `Sprite::flip_x` says.
+    pub flip_x: bool,
+}
+
+impl PetShape<'_> {
+    /// Whether `p` lands on a solid pixel of the pet.
+    pub fn contains(&self, p: World2d) -> bool {
+        if !self.rect.contains(p.0) {
+            return false;
+        }
+        let Some(mask) = self.mask else {
+            return true;
+        };
+        // Fractions across the box, from the image's top-left: world Y is up,
+        // image Y is down.
+        let size = self.rect.size();
+        let mut u = (p.0.x - self.rect.min.x) / size.x;
+        let v = (self.rect.max.y - p.0.y) / size.y;
+        if self.flip_x {
+            u = 1.0 - u;
+        }
+        // `Rect::contains` includes the far edges, which would index one past
+        // the last pixel.
+        let last = mask.size - 1;
+        let px = (Vec2::new(u, v) * mask.size.as_vec2())
+            .floor()
+            .as_uvec2()
+            .min(last);
+        mask.is_solid(px)
+    }
+
+    /// World-space rects covering the solid part of the pet.
+    pub fn cover(&self) -> Vec<Rect> {
+        let Some(mask) = self.mask else {
+            return vec![self.rect];
+        };
+        let per_pixel = self.rect.size() / mask.size.as_vec2();
+        mask.cover()
+            .iter()
+            .map(|cell| {
+                let (left, right) = if self.flip_x {
+                    (mask.size.x - cell.max.x, mask.size.x - cell.min.x)
+                } else {
+                    (cell.min.x, cell.max.x)
+                };
+                Rect::new(
+                    self.rect.min.x + left as f32 * per_pixel.x,
+                    self.rect.max.y - cell.max.y as f32 * per_pixel.y,
+                    self.rect.min.x + right as f32 * per_pixel.x,
+                    self.rect.max.y - cell.min.y as f32 * per_pixel.y,
+                )
+            })
+            .collect()
+    }
+}
+
+/// The topmost pet with a solid pixel under `p`.
+///
+/// `candidates` is `(entity, z, shape)`. Ties break on the higher entity index
 /// so picking is deterministic rather than dependent on query order.
-pub fn pick_topmost(candidates: &[(Entity, f32, Rect)], p: World2d) -> Option<Entity> {
+pub fn pick_topmost(candidates: &[(Entity, f32, PetShape)], p: World2d) -> Option<Entity> {
     candidates
         .iter()
-        .filter(|(_, _, rect)| rect.contains(p.0))
+        .filter(|(_, _, shape)| shape.contains(p))
         .max_by(|a, b| {
             a.1.partial_cmp(&b.1)
                 .unwrap_or(std::cmp::Ordering::Equal)
@@ -55,6 +216,8 @@ pub struct DesiredInputRegion {
 ///
 /// Rounded outward and padded so a pet is never a pixel harder to click than it
 /// looks; the compositor unions overlapping rects, so no merging is needed.
+/// Pets are passed as their [`PetShape::cover`], so the region hugs what is
+/// drawn rather than the whole frame.
 #[allow(dead_code)]
 pub fn aggregate_input_region(
     pets: impl Iterator<Item = Rect>,
@@ -87,6 +250,20 @@ mod tests {
         pet_rect_world(Vec2::new(cx, cy), Vec2::splat(50.0), 1.5)
     }
 
+    /// A pet with no mask, so its whole box counts.
+    fn boxed(cx: f32, cy: f32) -> PetShape<'static> {
+        PetShape {
+            rect: rect(cx, cy),
+            mask: None,
+            flip_x: false,
+        }
+    }
+
+    /// A 50x50 frame whose left half is solid and right half transparent.
+    fn left_half() -> AlphaMask {
+        AlphaMask::from_fn(UVec2::splat(50), |px| px.x < 25)
+    }
+
     #[test]
     fn rect_is_centred_and_scaled() {
         let r = rect(0.0, 0.0);
@@ -108,7 +285,7 @@ mod tests {
     #[test]
     fn pick_returns_none_when_nothing_is_hit() {
         let e = Entity::from_raw_u32(1).unwrap();
-        let candidates = [(e, 0.0, rect(0.0, 0.0))];
+        let candidates = [(e, 0.0, boxed(0.0, 0.0))];
         assert_eq!(
             pick_topmost(&candidates, World2d(Vec2::new(500.0, 500.0))),
             None
@@ -119,7 +296,7 @@ mod tests {
     fn pick_prefers_higher_z() {
         let low = Entity::from_raw_u32(1).unwrap();
         let high = Entity::from_raw_u32(2).unwrap();
-        let candidates = [(low, 0.0, rect(0.0, 0.0)), (high, 5.0, rect(10.0, 0.0))];
+        let candidates = [(low, 0.0, boxed(0.0, 0.0)), (high, 5.0, boxed(10.0, 0.0))];
         // Point inside both boxes must resolve to the higher z.
         assert_eq!(
             pick_topmost(&candidates, World2d(Vec2::new(5.0, 0.0))),
@@ -131,12 +308,87 @@ mod tests {
     fn pick_breaks_ties_deterministically() {
         let a = Entity::from_raw_u32(1).unwrap();
         let b = Entity::from_raw_u32(2).unwrap();
-        let candidates = [(a, 1.0, rect(0.0, 0.0)), (b, 1.0, rect(0.0, 0.0))];
-        let reversed = [(b, 1.0, rect(0.0, 0.0)), (a, 1.0, rect(0.0, 0.0))];
+        let candidates = [(a, 1.0, boxed(0.0, 0.0)), (b, 1.0, boxed(0.0, 0.0))];
+        let reversed = [(b, 1.0, boxed(0.0, 0.0)), (a, 1.0, boxed(0.0, 0.0))];
         let p = World2d(Vec2::ZERO);
         assert_eq!(pick_topmost(&candidates, p), pick_topmost(&reversed, p));
     }
 
+    /// Clicks on a frame's transparent corners must fall through.
+    #[test]
+    fn transparent_pixels_are_not_hit() {
+        let mask = left_half();
+        let shape = PetShape {
+            rect: rect(0.0, 0.0),
+            mask: Some(&mask),
+            flip_x: false,
+        };
+        assert!(shape.contains(World2d(Vec2::new(-20.0, 0.0))));
+        assert!(!shape.contains(World2d(Vec2::new(20.0, 0.0))));
+    }
+
+    /// A pet facing the other way is drawn mirrored, so its mask must be too.
+    #[test]
+    fn flipped_frames_are_hit_where_they_are_drawn() {
+        let mask = left_half();
+        let shape = PetShape {
+            rect: rect(0.0, 0.0),
+            mask: Some(&mask),
+            flip_x: true,
+        };
+        assert!(!shape.contains(World2d(Vec2::new(-20.0, 0.0))));
+        assert!(shape.contains(World2d(Vec2::new(20.0, 0.0))));
+        // The far edges are inside the box and must not index past the mask.
+        assert!(shape.contains(World2d(rect(0.0, 0.0).max)));
+    }
+
+    /// Image rows run downwards and world Y runs up.
+    #[test]
+    fn mask_rows_map_top_down() {
+        let top_only = AlphaMask::from_fn(UVec2::splat(50), |px| px.y < 10);
+        let shape = PetShape {
+            rect: rect(0.0, 0.0),
+            mask: Some(&top_only),
+            flip_x: false,
+        };
+        assert!(shape.contains(World2d(Vec2::new(0.0, 35.0))));
+        assert!(!shape.contains(World2d(Vec2::new(0.0, -35.0))));
+    }
+
+    #[test]
+    fn cover_merges_identical_rows() {
+        let mask = left_half();
+        assert_eq!(mask.cover(), [URect::new(0, 0, 25, 50)]);
+
+        let empty = AlphaMask::from_fn(UVec2::splat(50), |_| false);
+        assert!(empty.cover().is_empty());
+    }
+
+    /// A lone solid pixel still gets a whole cell, so the region never misses
+    /// any of the pet.
+    #[test]
+    fn cover_rounds_out_to_whole_cells() {
+        let dot = AlphaMask::from_fn(UVec2::splat(50), |px| px == UVec2::new(12, 31));
+        assert_eq!(dot.cover(), [URect::new(10, 30, 15, 35)]);
+    }
+
+    #[test]
+    fn region_follows_the_mask_and_its_flip() {
+        let size = Vec2::new(800.0, 600.0);
+        let mask = left_half();
+        let region = |flip_x| {
+            let shape = PetShape {
+                rect: rect(0.0, 0.0),
+                mask: Some(&mask),
+                flip_x,
+            };
+            aggregate_input_region(shape.cover().into_iter(), size, 0.0).rects
+        };
+        // The left half of the full-box rect, (362..438, 262..338).
+        assert_eq!(region(false), [IRect::new(362, 262, 400, 338)]);
+        assert_eq!(region(true), [IRect::new(400, 262, 438, 338)]);
+    }
+
     #[test]
     fn empty_region_for_no_pets() {
         let region = aggregate_input_region(std::iter::empty(), Vec2::new(800.0, 600.0), 0.0);
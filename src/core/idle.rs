@@ -0,0 +1,147 @@
+//! Whether anyone is at the keyboard.
+//!
+//! Platforms disagree on how they know. Windows and macOS answer "how long
+//! since the last input?" whenever asked, while Wayland's `ext-idle-notify-v1`
+//! only says "idled" and "resumed" around a timeout fixed when the notification
+//! is created. Backends report whichever they have as [`UserActivity`], and one
+//! pure function turns either into presence changes.
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+use super::brain::PetState;
+use super::input::Intent;
+
+/// What a backend observed about user input.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserActivity {
+    /// Time since the last input, polled from the OS.
+    // Polled on macOS and Windows; Wayland only says when it idles.
+    #[allow(dead_code)]
+    IdleFor(Duration),
+    /// No input for the configured timeout. Wayland reports this itself.
+    Idled,
+    /// Input arrived after [`UserActivity::Idled`].
+    Resumed,
+}
+
+/// Whether the user is at the machine, as of the last [`UserActivity`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Presence {
+    #[default]
+    Present,
+    Away,
+}
+
+/// How pets react to the user leaving and coming back.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct IdleConfig {
+    pub enabled: bool,
+    /// How long without input before the user counts as away.
+    pub away_after: Duration,
+    /// Held for as long as the user is away.
+    pub asleep: PetState,
+    /// Played once when the user returns.
+    pub greeting: PetState,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            away_after: Duration::from_secs(5 * 60),
+            asleep: PetState::Sitting,
+            greeting: PetState::SendingLove,
+        }
+    }
+}
+
+/// Folds one observation into the current presence.
+///
+/// Only a change produces an intent: a poll every second while the user is away
+/// must not re-send every pet to sleep, restarting the animation each time.
+pub fn step_presence(
+    current: Presence,
+    activity: UserActivity,
+    away_after: Duration,
+) -> (Presence, Option<Intent>) {
+    let next = match activity {
+        UserActivity::IdleFor(idle) if idle >= away_after => Presence::Away,
+        UserActivity::IdleFor(_) => Presence::Present,
+        UserActivity::Idled => Presence::Away,
+        UserActivity::Resumed => Presence::Present,
+    };
+
+    let intent = match (current, next) {
+        (Presence::Present, Presence::Away) => Some(Intent::UserAway),
+        (Presence::Away, Presence::Present) => Some(Intent::UserReturned),
+        _ => None,
+    };
+    (next, intent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mins(n: u64) -> Duration {
+        Duration::from_secs(n * 60)
+    }
+
+    #[test]
+    fn polling_past_the_threshold_goes_away_once() {
+        let (presence, intent) =
+            step_presence(Presence::Present, UserActivity::IdleFor(mins(6)), mins(5));
+        assert_eq!(presence, Presence::Away);
+        assert_eq!(intent, Some(Intent::UserAway));
+
+        // Still idle on the next poll: no second intent.
+        let (presence, intent) = step_presence(presence, UserActivity::IdleFor(mins(7)), mins(5));
+        assert_eq!(presence, Presence::Away);
+        assert_eq!(intent, None);
+    }
+
+    #[test]
+    fn polling_below_the_threshold_stays_present() {
+        let (presence, intent) = step_presence(
+            Presence::Present,
+            UserActivity::IdleFor(Duration::from_secs(30)),
+            mins(5),
+        );
+        assert_eq!(presence, Presence::Present);
+        assert_eq!(intent, None);
+    }
+
+    /// The idle counter resets on input, so a small reading after a large one
+    /// is how a polled backend sees the user come back.
+    #[test]
+    fn a_reset_idle_counter_is_a_return() {
+        let (presence, intent) = step_presence(
+            Presence::Away,
+            UserActivity::IdleFor(Duration::from_millis(200)),
+            mins(5),
+        );
+        assert_eq!(presence, Presence::Present);
+        assert_eq!(intent, Some(Intent::UserReturned));
+    }
+
+    /// Wayland's notification already applied the threshold, so the event is
+    /// taken at its word whatever `away_after` says.
+    #[test]
+    fn compositor_events_need_no_threshold() {
+        let (presence, intent) = step_presence(Presence::Present, UserActivity::Idled, mins(60));
+        assert_eq!((presence, intent), (Presence::Away, Some(Intent::UserAway)));
+
+        let (presence, intent) = step_presence(presence, UserActivity::Resumed, mins(60));
+        assert_eq!(
+            (presence, intent),
+            (Presence::Present, Some(Intent::UserReturned))
+        );
+    }
+
+    #[test]
+    fn resuming_while_present_is_not_a_return() {
+        let (_, intent) = step_presence(Presence::Present, UserActivity::Resumed, mins(5));
+        assert_eq!(intent, None, "no greeting without having been away");
+    }
+}
@@ -8,9 +8,11 @@
 //! made the old `handle_clicks` impossible to test.
 
 use bevy::prelude::*;
+use serde::Deserialize;
 use std::time::Duration;
 
 use super::coords::{ScreenLogical, ScreenPhysical, SurfaceLogical, World2d};
+use super::menu::PetAction;
 
 /// What a backend can physically deliver.
 ///
@@ -55,6 +57,9 @@ pub enum PointerAt {
 pub struct PointerSample {
     pub at: PointerAt,
     pub buttons: ButtonMask,
+    /// Lines scrolled since the last sample, positive away from the user.
+    /// Fractional from a touchpad; zero where the platform cannot tell.
+    pub scroll: f32,
     /// Monotonic app time, from `Time::elapsed`. Never wall-clock.
     pub at_time: Duration,
 }
@@ -66,6 +71,12 @@ pub enum Intent {
     Summon {
         to: World2d,
     },
+    /// Walk one particular pet to a point. Comes from the tray rather than the
+    /// pointer, so it works in every tier.
+    SummonPet {
+        pet: Entity,
+        to: World2d,
+    },
     Grab {
         pet: Entity,
         offset: Vec2,
@@ -85,13 +96,125 @@ pub enum Intent {
     Poke {
         pet: Entity,
     },
+    /// A right click on the pet, at `at`.
+    ContextMenu {
+        pet: Entity,
+        at: World2d,
+    },
+    /// A click on a pet's speech bubble, which hides it.
+    DismissSpeech {
+        pet: Entity,
+    },
+    /// An action chosen for one pet, from its context menu, the tray, or a
+    /// gesture mapped to it.
+    Act {
+        pet: Entity,
+        action: PetAction,
+    },
+    /// Make one pet a step bigger or smaller. Comes from a gesture.
+    Rescale {
+        pet: Entity,
+        grow: bool,
+    },
+    /// Nobody has touched the machine for a while. Every pet reacts, so this
+    /// names none; it comes from the idle source rather than the pointer.
+    UserAway,
+    /// Input resumed after [`Intent::UserAway`].
+    UserReturned,
+}
+
+/// How far the cursor may wander, in logical pixels, before a press counts as
+/// having moved: enough to absorb a hand on a mouse, not a deliberate drag.
+pub const MOVE_SLOP: f32 = 6.0;
+
+/// What a gesture on a pet does, when it is mapped to anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum GestureAction {
+    /// Leaves the gesture to whatever it did before there was a map: a long
+    /// press stays a drag, a middle click or a scroll goes unnoticed.
+    Nothing,
+    Feed,
+    Pet,
+    Play,
+    Sleep,
+    Follow,
+    /// Make this pet, and only this one, a step bigger.
+    Grow,
+    Shrink,
 }
 
-/// Timing thresholds, injected so tests can pin them.
+impl GestureAction {
+    /// The intent this action raises for `pet`, if any.
+    pub fn intent(self, pet: Entity) -> Option<Intent> {
+        let action = match self {
+            GestureAction::Nothing => return None,
+            GestureAction::Grow => return Some(Intent::Rescale { pet, grow: true }),
+            GestureAction::Shrink => return Some(Intent::Rescale { pet, grow: false }),
+            GestureAction::Feed => PetAction::Feed,
+            GestureAction::Pet => PetAction::Pet,
+            GestureAction::Play => PetAction::Play,
+            GestureAction::Sleep => PetAction::Sleep,
+            GestureAction::Follow => PetAction::Follow,
+        };
+        Some(Intent::Act { pet, action })
+    }
+}
+
+/// Each rescale step multiplies a pet's size by this, or divides it by it.
+pub const RESCALE_STEP: f32 = 1.25;
+
+/// How far one pet can be rescaled from the configured size, either way.
+pub const RESCALE_LIMIT: f32 = 2.0;
+
+/// A pet's scale after one step from `current`, kept within
+/// [`RESCALE_LIMIT`] of `base`, the scale every pet is configured with.
+pub fn rescaled(current: f32, base: f32, grow: bool) -> f32 {
+    let next = if grow {
+        current * RESCALE_STEP
+    } else {
+        current / RESCALE_STEP
+    };
+    next.clamp(base / RESCALE_LIMIT, base * RESCALE_LIMIT)
+}
+
+/// Which action each gesture beyond click, double-click and drag performs.
+/// Those three keep their meanings: they are how a pet is petted, poked and
+/// carried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureMap {
+    /// Holding the left button on a pet without moving. By then the press has
+    /// long since picked the pet up, so anything but `Nothing` puts it down
+    /// again: off unless asked for.
+    pub long_press: GestureAction,
+    pub middle_click: GestureAction,
+    /// Pressing the right button on a pet and moving before letting go. Once
+    /// set, a pet's menu waits for a right click to end without moving, so
+    /// the two never both happen.
+    pub right_drag: GestureAction,
+    pub scroll_up: GestureAction,
+    pub scroll_down: GestureAction,
+}
+
+impl Default for GestureMap {
+    fn default() -> Self {
+        Self {
+            long_press: GestureAction::Nothing,
+            middle_click: GestureAction::Feed,
+            right_drag: GestureAction::Nothing,
+            scroll_up: GestureAction::Grow,
+            scroll_down: GestureAction::Shrink,
+        }
+    }
+}
+
+/// Timing thresholds and the gesture map, injected so tests can pin them.
 #[derive(Resource, Debug, Clone, Copy)]
 pub struct GestureConfig {
     pub double_click: Duration,
     pub drag_threshold: Duration,
+    /// How long a press must be held without moving to be a long press.
+    pub long_press: Duration,
+    pub map: GestureMap,
 }
 
 impl Default for GestureConfig {
@@ -99,6 +222,8 @@ impl Default for GestureConfig {
         Self {
             double_click: Duration::from_millis(250),
             drag_threshold: Duration::from_millis(125),
+            long_press: Duration::from_millis(800),
+            map: GestureMap::default(),
         }
     }
 }
@@ -114,9 +239,24 @@ pub struct GestureState {
     pub dragging: Option<Entity>,
     /// The pet the current press started on.
     pub pressed_on: Option<Entity>,
+    /// Where the current left press started, until it moves.
+    pub press_at: Option<World2d>,
+    /// The pet a right press started on and where, until it moves or the
+    /// menu opens.
+    pub right_press: Option<(Entity, World2d)>,
+    /// Scrolling over a pet not yet a whole line, carried to the next sample.
+    pub scrolled: f32,
     pub cursor: Option<World2d>,
 }
 
+/// Whether the cursor is still within [`MOVE_SLOP`] of where a press began.
+fn still(from: Option<World2d>, to: Option<World2d>) -> bool {
+    match (from, to) {
+        (Some(from), Some(to)) => from.0.distance(to.0) <= MOVE_SLOP,
+        _ => false,
+    }
+}
+
 /// Turns one sample into zero or more intents.
 ///
 /// `hit` is the topmost pet under the cursor, resolved by the caller so this
@@ -141,10 +281,61 @@ pub fn classify(
 
     let now = sample.at_time;
 
+    // A right press on a pet asks for its menu. Anywhere else it is left to
+    // the desktop, whose own menu is what the user expects there. With a
+    // right drag mapped, the menu waits for the button to come up unmoved:
+    // the press might yet be a drag.
+    let right_pressed =
+        !state.buttons.contains(ButtonMask::RIGHT) && sample.buttons.contains(ButtonMask::RIGHT);
+    if right_pressed && let (Some(pet), Some(at)) = (hit, cursor_world) {
+        if cfg.map.right_drag == GestureAction::Nothing {
+            intents.push(Intent::ContextMenu { pet, at });
+        } else {
+            next.right_press = Some((pet, at));
+        }
+    }
+    // Moving with the right button held turns it into a right drag, once;
+    // letting go first is a right click.
+    if let Some((pet, at)) = state.right_press {
+        if !sample.buttons.contains(ButtonMask::RIGHT) {
+            next.right_press = None;
+            intents.push(Intent::ContextMenu { pet, at });
+        } else if !still(Some(at), cursor_world) {
+            next.right_press = None;
+            intents.extend(cfg.map.right_drag.intent(pet));
+        }
+    }
+
+    let middle_pressed =
+        !state.buttons.contains(ButtonMask::MIDDLE) && sample.buttons.contains(ButtonMask::MIDDLE);
+    if middle_pressed && let Some(pet) = hit {
+        intents.extend(cfg.map.middle_click.intent(pet));
+    }
+
+    // Each whole line scrolled over a pet is one step. Leaving the pet drops
+    // whatever was left over, so it does not add to scrolling somewhere else.
+    match hit {
+        Some(pet) if sample.scroll != 0.0 => {
+            next.scrolled = state.scrolled + sample.scroll;
+            let action = if next.scrolled > 0.0 {
+                cfg.map.scroll_up
+            } else {
+                cfg.map.scroll_down
+            };
+            while next.scrolled.abs() >= 1.0 {
+                next.scrolled -= next.scrolled.signum();
+                intents.extend(action.intent(pet));
+            }
+        }
+        Some(_) => {}
+        None => next.scrolled = 0.0,
+    }
+
     // Press.
     if !was_down && is_down {
         next.press_started_at = Some(now);
         next.pressed_on = hit;
+        next.press_at = cursor_world;
 
         match hit {
             Some(pet) => {
@@ -170,6 +361,29 @@ pub fn classify(
         }
     }
 
+    // Held without moving for long enough: a long press, which puts down a
+    // pet already picked up and ends the press, so letting go does nothing
+    // more.
+    if was_down && is_down {
+        if !still(state.press_at, cursor_world) {
+            next.press_at = None;
+        } else if cfg.map.long_press != GestureAction::Nothing
+            && let (Some(pet), Some(started)) = (state.pressed_on, state.press_started_at)
+            && now.saturating_sub(started) >= cfg.long_press
+        {
+            if let Some(held) = state.dragging {
+                intents.push(Intent::Release { pet: held });
+            }
+            intents.extend(cfg.map.long_press.intent(pet));
+            next.dragging = None;
+            next.pressed_on = None;
+            next.press_started_at = None;
+            next.press_at = None;
+            next.last_click_at = None;
+            return (next, intents);
+        }
+    }
+
     // Held: promote to a drag once past the threshold, then follow the cursor.
     if was_down && is_down {
         if let (Some(pet), Some(started), None) =
@@ -199,6 +413,7 @@ pub fn classify(
         next.dragging = None;
         next.pressed_on = None;
         next.press_started_at = None;
+        next.press_at = None;
     }
 
     (next, intents)
@@ -224,6 +439,7 @@ mod tests {
             } else {
                 ButtonMask::empty()
             },
+            scroll: 0.0,
             at_time,
         }
     }
@@ -237,12 +453,19 @@ mod tests {
         steps: &[(bool, u64, Option<Entity>)],
         tier: InteractionTier,
     ) -> (GestureState, Vec<Intent>) {
-        let cfg = GestureConfig::default();
+        run_with(steps, tier, &GestureConfig::default())
+    }
+
+    fn run_with(
+        steps: &[(bool, u64, Option<Entity>)],
+        tier: InteractionTier,
+        cfg: &GestureConfig,
+    ) -> (GestureState, Vec<Intent>) {
         let mut state = GestureState::default();
         let mut all = Vec::new();
         for (down, t, hit) in steps {
             let (next, intents) =
-                classify(&state, &sample(*down, ms(*t)), world(), *hit, tier, &cfg);
+                classify(&state, &sample(*down, ms(*t)), world(), *hit, tier, cfg);
             state = next;
             all.extend(intents);
         }
@@ -363,6 +586,231 @@ mod tests {
         assert!(intents.is_empty(), "{intents:?}");
     }
 
+    #[test]
+    fn right_click_on_a_pet_opens_its_menu_once() {
+        let pet = pet_entity();
+        let cfg = GestureConfig::default();
+        let right = |at_time| PointerSample {
+            buttons: ButtonMask::RIGHT,
+            ..sample(false, at_time)
+        };
+
+        let (state, intents) = classify(
+            &GestureState::default(),
+            &right(ms(10)),
+            world(),
+            Some(pet),
+            InteractionTier::PetOnly,
+            &cfg,
+        );
+        assert_eq!(
+            intents,
+            vec![Intent::ContextMenu {
+                pet,
+                at: World2d(Vec2::new(5.0, 5.0))
+            }]
+        );
+
+        // Still held on the next sample: not a second menu.
+        let (_, intents) = classify(
+            &state,
+            &right(ms(30)),
+            world(),
+            Some(pet),
+            InteractionTier::PetOnly,
+            &cfg,
+        );
+        assert!(intents.is_empty(), "{intents:?}");
+    }
+
+    #[test]
+    fn right_click_on_bare_desktop_is_ignored() {
+        let cfg = GestureConfig::default();
+        let press = PointerSample {
+            buttons: ButtonMask::RIGHT,
+            ..sample(false, ms(10))
+        };
+        let (_, intents) = classify(
+            &GestureState::default(),
+            &press,
+            world(),
+            None,
+            InteractionTier::ClickToSummon,
+            &cfg,
+        );
+        assert!(intents.is_empty(), "{intents:?}");
+    }
+
+    /// A pet picked up and held still stays held: the long press is opt-in.
+    #[test]
+    fn holding_still_carries_the_pet_by_default() {
+        let pet = pet_entity();
+        let (state, intents) = run(
+            &[
+                (false, 0, Some(pet)),
+                (true, 10, Some(pet)),
+                (true, 200, Some(pet)),
+                (true, 900, Some(pet)),
+                (true, 950, Some(pet)),
+            ],
+            InteractionTier::ClickToSummon,
+        );
+        assert!(
+            intents
+                .iter()
+                .all(|i| matches!(i, Intent::Grab { .. } | Intent::DragTo { .. })),
+            "{intents:?}"
+        );
+        assert_eq!(state.dragging, Some(pet));
+    }
+
+    #[test]
+    fn holding_still_is_a_long_press_that_puts_the_pet_down() {
+        let pet = pet_entity();
+        let mut cfg = GestureConfig::default();
+        cfg.map.long_press = GestureAction::Sleep;
+        let (state, intents) = run_with(
+            &[
+                (false, 0, Some(pet)),
+                (true, 10, Some(pet)),
+                (true, 200, Some(pet)),
+                (true, 900, Some(pet)),
+                (true, 950, Some(pet)),
+                (false, 1000, Some(pet)),
+            ],
+            InteractionTier::ClickToSummon,
+            &cfg,
+        );
+        let after_grab: Vec<_> = intents
+            .iter()
+            .filter(|i| !matches!(i, Intent::Grab { .. } | Intent::DragTo { .. }))
+            .collect();
+        assert_eq!(
+            after_grab,
+            [
+                &Intent::Release { pet },
+                &Intent::Act {
+                    pet,
+                    action: PetAction::Sleep
+                }
+            ]
+        );
+        assert_eq!(state.dragging, None);
+    }
+
+    #[test]
+    fn moving_while_held_is_a_drag_not_a_long_press() {
+        let pet = pet_entity();
+        let cfg = GestureConfig::default();
+        let mut state = GestureState::default();
+        let mut intents = Vec::new();
+        for (down, t, x) in [
+            (false, 0, 0.0),
+            (true, 10, 0.0),
+            (true, 200, 40.0),
+            (true, 900, 40.0),
+        ] {
+            let at = Some(World2d(Vec2::new(x, 0.0)));
+            let (next, produced) = classify(
+                &state,
+                &sample(down, ms(t)),
+                at,
+                Some(pet),
+                InteractionTier::PetOnly,
+                &cfg,
+            );
+            state = next;
+            intents.extend(produced);
+        }
+        assert!(
+            !intents.iter().any(|i| matches!(i, Intent::Act { .. })),
+            "{intents:?}"
+        );
+        assert_eq!(state.dragging, Some(pet));
+    }
+
+    #[test]
+    fn other_buttons_and_the_wheel_follow_the_map() {
+        let pet = pet_entity();
+        let mut cfg = GestureConfig::default();
+        cfg.map.right_drag = GestureAction::Pet;
+        let step = |state: &GestureState, buttons, scroll, x: f32| {
+            let sample = PointerSample {
+                buttons,
+                scroll,
+                ..sample(false, ms(10))
+            };
+            let at = Some(World2d(Vec2::new(x, 0.0)));
+            classify(
+                state,
+                &sample,
+                at,
+                Some(pet),
+                InteractionTier::PetOnly,
+                &cfg,
+            )
+        };
+
+        let (_, intents) = step(&GestureState::default(), ButtonMask::MIDDLE, 0.0, 0.0);
+        assert_eq!(
+            intents,
+            [Intent::Act {
+                pet,
+                action: PetAction::Feed
+            }]
+        );
+
+        // With a right drag mapped, a right press waits to see which it is:
+        // moving with it held is a drag, and opens no menu.
+        let (state, intents) = step(&GestureState::default(), ButtonMask::RIGHT, 0.0, 0.0);
+        assert!(intents.is_empty(), "{intents:?}");
+        let (state, intents) = step(&state, ButtonMask::RIGHT, 0.0, 20.0);
+        assert_eq!(
+            intents,
+            [Intent::Act {
+                pet,
+                action: PetAction::Pet
+            }]
+        );
+        let (state, intents) = step(&state, ButtonMask::RIGHT, 0.0, 60.0);
+        assert!(intents.is_empty(), "only once per press: {intents:?}");
+        let (_, intents) = step(&state, ButtonMask::empty(), 0.0, 60.0);
+        assert!(intents.is_empty(), "{intents:?}");
+
+        // Letting go without moving is a right click after all.
+        let (state, _) = step(&GestureState::default(), ButtonMask::RIGHT, 0.0, 0.0);
+        let (_, intents) = step(&state, ButtonMask::empty(), 0.0, 2.0);
+        assert_eq!(
+            intents,
+            [Intent::ContextMenu {
+                pet,
+                at: World2d(Vec2::ZERO)
+            }]
+        );
+
+        // Half a line is carried over; a line and a half is one step.
+        let (state, intents) = step(&GestureState::default(), ButtonMask::empty(), 0.5, 0.0);
+        assert!(intents.is_empty(), "{intents:?}");
+        let (_, intents) = step(&state, ButtonMask::empty(), 1.0, 0.0);
+        assert_eq!(intents, [Intent::Rescale { pet, grow: true }]);
+        let (_, intents) = step(&GestureState::default(), ButtonMask::empty(), -2.0, 0.0);
+        assert_eq!(
+            intents,
+            [
+                Intent::Rescale { pet, grow: false },
+                Intent::Rescale { pet, grow: false }
+            ]
+        );
+    }
+
+    #[test]
+    fn rescaling_stays_near_the_configured_size() {
+        assert_eq!(rescaled(1.0, 1.0, true), 1.25);
+        assert_eq!(rescaled(1.25, 1.0, false), 1.0);
+        assert_eq!(rescaled(1.9, 1.0, true), 2.0);
+        assert_eq!(rescaled(0.55, 1.0, false), 0.5);
+    }
+
     #[test]
     fn slow_second_click_is_not_a_double() {
         let pet = pet_entity();
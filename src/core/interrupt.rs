@@ -0,0 +1,106 @@
+//! Interrupts raised from outside the app: the hook for third-party sources.
+//!
+//! Everything that changes a pet's state from outside its brain does it the
+//! same way, by writing the pet's pending interrupt between
+//! [`PetSystems::Normalize`](super::PetSystems::Normalize) and
+//! [`PetSystems::Brain`](super::PetSystems::Brain). A plugin can do exactly
+//! that with its own system. An [`InterruptSource`] is the shorter way for
+//! one that only needs to look at the pets and say what they should do: it is
+//! polled once a frame and held to the rules the built-in sources follow.
+
+use bevy::prelude::*;
+
+use super::brain::PetState;
+
+/// One pet, as a source sees it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PetSnapshot {
+    pub pet: Entity,
+    pub state: PetState,
+    /// World space, the origin at the middle of the surface.
+    pub position: Vec2,
+    /// Held by the user, or asleep while they are away. Such a pet takes no
+    /// interrupts from a source.
+    pub locked: bool,
+    /// Already about to change state this frame, for some other reason.
+    pub pending: bool,
+}
+
+/// An interrupt a source asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Raised {
+    pub pet: Entity,
+    pub state: PetState,
+    /// Where to walk to, for `Walking`; ignored otherwise. A walk without one
+    /// wanders.
+    pub walk_to: Option<Vec2>,
+}
+
+/// Something outside the app that tells pets what to do.
+pub trait InterruptSource: Send + Sync + 'static {
+    /// The interrupts to raise this frame, given every pet as it is now.
+    fn poll(&mut self, pets: &[PetSnapshot]) -> Vec<Raised>;
+}
+
+/// The raised interrupts that may go ahead: one per pet, the first asked for,
+/// and none for a pet that is locked, already has an interrupt pending, or is
+/// not one of `pets`. `Dragged` belongs to the pointer and is never raised.
+pub fn admitted(pets: &[PetSnapshot], raised: Vec<Raised>) -> Vec<Raised> {
+    let mut taken: Vec<Entity> = Vec::new();
+    raised
+        .into_iter()
+        .filter(|raised| {
+            let free = pets
+                .iter()
+                .any(|pet| pet.pet == raised.pet && !pet.locked && !pet.pending);
+            let fresh = !taken.contains(&raised.pet);
+            if free && fresh && raised.state != PetState::Dragged {
+                taken.push(raised.pet);
+                return true;
+            }
+            false
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pet(id: u32, locked: bool, pending: bool) -> PetSnapshot {
+        PetSnapshot {
+            pet: Entity::from_raw_u32(id).unwrap(),
+            state: PetState::Idle,
+            position: Vec2::ZERO,
+            locked,
+            pending,
+        }
+    }
+
+    fn raise(pet: &PetSnapshot, state: PetState) -> Raised {
+        Raised {
+            pet: pet.pet,
+            state,
+            walk_to: None,
+        }
+    }
+
+    #[test]
+    fn only_free_pets_take_one_interrupt_each() {
+        let free = pet(1, false, false);
+        let held = pet(2, true, false);
+        let busy = pet(3, false, true);
+        let gone = pet(4, false, false);
+        let pets = [free, held, busy];
+
+        let raised = vec![
+            raise(&held, PetState::Jumping),
+            raise(&busy, PetState::Jumping),
+            raise(&gone, PetState::Jumping),
+            raise(&free, PetState::Dragged),
+            raise(&free, PetState::Eating),
+            raise(&free, PetState::Sitting),
+        ];
+        assert_eq!(admitted(&pets, raised), [raise(&free, PetState::Eating)]);
+    }
+}
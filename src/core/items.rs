@@ -0,0 +1,212 @@
+//! Food and toys the user drops onto the desktop.
+//!
+//! A dropped item is claimed by one pet, which walks to it with the ordinary
+//! `Walking` state and reacts once it gets there. Which reaction, and what the
+//! item looks like, is up to the skin; here is only who fetches what, and when
+//! an errand is over.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::brain::PetState;
+
+/// How far from an item a pet notices it, in world units.
+pub const NOTICE_RADIUS: f32 = 300.0;
+
+/// How close counts as having reached an item. Steering lands exactly on its
+/// target, so this only absorbs float error.
+pub const REACH: f32 = 2.0;
+
+/// How long an item lies unclaimed before it is cleared away. One dropped out
+/// of every pet's reach, or that the skin has no art for, is never fetched.
+pub const ITEM_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Something that can be dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum ItemKind {
+    Food,
+    Ball,
+    Yarn,
+}
+
+impl ItemKind {
+    pub const ALL: [ItemKind; 3] = [ItemKind::Food, ItemKind::Ball, ItemKind::Yarn];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ItemKind::Food => "Food",
+            ItemKind::Ball => "Ball",
+            ItemKind::Yarn => "Yarn",
+        }
+    }
+}
+
+impl FromStr for ItemKind {
+    type Err = String;
+
+    /// Case-insensitive, since this is what a user types after `--drop`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        ItemKind::ALL
+            .into_iter()
+            .find(|kind| kind.label().eq_ignore_ascii_case(text))
+            .ok_or_else(|| format!("unknown item {text:?}; expected food, ball or yarn"))
+    }
+}
+
+/// An item lying on the desktop.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Item {
+    pub kind: ItemKind,
+    /// The pet on its way to it. Claimed items are not offered to other pets,
+    /// so two pets never race for the same one.
+    pub claimed_by: Option<Entity>,
+    /// When it was dropped, or last let go of by a pet that was fetching it.
+    pub since: Duration,
+}
+
+impl Item {
+    /// Whether it has gone unclaimed for [`ITEM_LIFETIME`]. A claimed item
+    /// never is, so a pet is not left walking to nothing.
+    pub fn stale(&self, now: Duration) -> bool {
+        self.claimed_by.is_none() && now.saturating_sub(self.since) >= ITEM_LIFETIME
+    }
+}
+
+/// A pet walking to an item, and what it does on arrival.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Errand {
+    pub item: Entity,
+    pub then: PetState,
+}
+
+/// Where an errand stands after a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrandStep {
+    /// Still walking to the item.
+    Underway,
+    /// At the item: react and use it up.
+    Arrived,
+    /// Something else took over the pet. The item is free again.
+    Abandoned,
+}
+
+/// The pet that goes for an item at `item_at`.
+///
+/// `free` lists pets with nothing better to do and where they stand. The
+/// nearest within [`NOTICE_RADIUS`] wins; ties break on the entity so the
+/// answer does not depend on query order.
+pub fn pick_fetcher(item_at: Vec2, free: &[(Entity, Vec2)]) -> Option<Entity> {
+    free.iter()
+        .map(|&(pet, at)| (pet, at.distance(item_at)))
+        .filter(|&(_, distance)| distance <= NOTICE_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)))
+        .map(|(pet, _)| pet)
+}
+
+/// Advances an errand.
+///
+/// `heading` is whether the pet still has a move target. Locomotion clears it
+/// on arrival, but so does anything else that stops a walk, so distance
+/// decides which it was.
+pub fn step_errand(state: PetState, heading: bool, distance: f32) -> ErrandStep {
+    if state != PetState::Walking {
+        // Grabbed, put to sleep, or otherwise interrupted on the way.
+        return ErrandStep::Abandoned;
+    }
+    if heading {
+        return ErrandStep::Underway;
+    }
+    if distance <= REACH {
+        ErrandStep::Arrived
+    } else {
+        ErrandStep::Abandoned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pet(n: u32) -> Entity {
+        Entity::from_raw_u32(n).unwrap()
+    }
+
+    #[test]
+    fn the_nearest_free_pet_fetches() {
+        let free = [
+            (pet(1), Vec2::new(200.0, 0.0)),
+            (pet(2), Vec2::new(50.0, 0.0)),
+        ];
+        assert_eq!(pick_fetcher(Vec2::ZERO, &free), Some(pet(2)));
+    }
+
+    #[test]
+    fn pets_too_far_away_do_not_notice() {
+        let free = [(pet(1), Vec2::new(NOTICE_RADIUS + 1.0, 0.0))];
+        assert_eq!(pick_fetcher(Vec2::ZERO, &free), None);
+    }
+
+    #[test]
+    fn equal_distances_break_on_the_entity() {
+        let free = [(pet(2), Vec2::X * 10.0), (pet(1), Vec2::NEG_X * 10.0)];
+        // `Entity`'s order is not its index's, so whichever sorts first.
+        let first = pet(1).min(pet(2));
+        assert_eq!(pick_fetcher(Vec2::ZERO, &free), Some(first));
+        let swapped = [free[1], free[0]];
+        assert_eq!(pick_fetcher(Vec2::ZERO, &swapped), Some(first));
+    }
+
+    #[test]
+    fn an_errand_ends_on_arrival() {
+        assert_eq!(
+            step_errand(PetState::Walking, true, 80.0),
+            ErrandStep::Underway
+        );
+        assert_eq!(
+            step_errand(PetState::Walking, false, 0.0),
+            ErrandStep::Arrived
+        );
+    }
+
+    /// A walk that stopped short was cut off, not completed, and must not eat
+    /// an item from across the screen.
+    #[test]
+    fn stopping_short_abandons_the_errand() {
+        assert_eq!(
+            step_errand(PetState::Walking, false, 80.0),
+            ErrandStep::Abandoned
+        );
+    }
+
+    #[test]
+    fn being_grabbed_abandons_the_errand() {
+        assert_eq!(
+            step_errand(PetState::Dragged, true, 80.0),
+            ErrandStep::Abandoned
+        );
+    }
+
+    #[test]
+    fn unclaimed_items_are_cleared_after_their_lifetime() {
+        let since = Duration::from_secs(5);
+        let mut item = Item {
+            kind: ItemKind::Ball,
+            claimed_by: None,
+            since,
+        };
+        assert!(!item.stale(since + ITEM_LIFETIME - Duration::from_millis(1)));
+        assert!(item.stale(since + ITEM_LIFETIME));
+
+        item.claimed_by = Some(pet(1));
+        assert!(!item.stale(since + ITEM_LIFETIME * 2));
+    }
+
+    #[test]
+    fn item_names_parse_case_insensitively() {
+        assert_eq!("food".parse(), Ok(ItemKind::Food));
+        assert_eq!("Yarn".parse(), Ok(ItemKind::Yarn));
+        assert!("cake".parse::<ItemKind>().is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! How busy the machine is, and how pets react to it.
+//!
+//! A platform samples CPU, memory and battery into [`SystemLoad`]; the skin
+//! says what each condition does through its [`LoadRule`]s. A rule can nudge
+//! which states pets drift into for as long as it holds, or send every pet
+//! into a state once as it starts to hold, or both.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::brain::{PetState, TransitionBias};
+
+/// How far a reading must fall back past a threshold before a rule lets go.
+/// Without it a CPU hovering at the threshold would flick a rule on and off,
+/// and restart its entry state, every sample.
+pub const HYSTERESIS: f32 = 0.05;
+
+/// The machine as last sampled. A reading the platform could not take is
+/// `None`, and no rule about it holds.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemLoad {
+    /// Fraction of CPU time spent busy since the previous sample.
+    pub cpu: Option<f32>,
+    /// Fraction of memory in use.
+    pub memory: Option<f32>,
+    pub battery: Option<Battery>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Battery {
+    /// Fraction of a full charge.
+    pub level: f32,
+    /// Running on the battery rather than on mains.
+    pub discharging: bool,
+}
+
+/// Cumulative CPU time, in whatever unit the platform counts in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+impl CpuTimes {
+    /// The fraction of time spent busy between `earlier` and this sample, or
+    /// `None` if no time passed between them.
+    pub fn usage_since(self, earlier: CpuTimes) -> Option<f32> {
+        let total = self.total.checked_sub(earlier.total)?;
+        let busy = self.busy.saturating_sub(earlier.busy);
+        (total > 0).then(|| (busy as f32 / total as f32).min(1.0))
+    }
+}
+
+/// When a [`LoadRule`] holds. Thresholds are fractions, from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum LoadCondition {
+    CpuAbove(f32),
+    MemoryAbove(f32),
+    /// Only while discharging: a low battery on the charger is recovering.
+    BatteryBelow(f32),
+}
+
+impl LoadCondition {
+    pub fn threshold(self) -> f32 {
+        match self {
+            LoadCondition::CpuAbove(at)
+            | LoadCondition::MemoryAbove(at)
+            | LoadCondition::BatteryBelow(at) => at,
+        }
+    }
+
+    /// Whether the condition holds for `load`. One that already `held` keeps
+    /// holding until the reading is [`HYSTERESIS`] back past the threshold.
+    pub fn holds(self, load: &SystemLoad, held: bool) -> bool {
+        let slack = if held { HYSTERESIS } else { 0.0 };
+        match self {
+            LoadCondition::CpuAbove(at) => load.cpu.is_some_and(|cpu| cpu > at - slack),
+            LoadCondition::MemoryAbove(at) => load.memory.is_some_and(|used| used > at - slack),
+            LoadCondition::BatteryBelow(at) => load
+                .battery
+                .is_some_and(|battery| battery.discharging && battery.level < at + slack),
+        }
+    }
+}
+
+/// A skin's reaction to one condition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadRule {
+    pub when: LoadCondition,
+    /// Transition weight multipliers while the condition holds.
+    pub bias: Vec<(PetState, f32)>,
+    /// Entered by every pet as the condition starts to hold.
+    pub enter: Option<PetState>,
+}
+
+/// Which of the skin's rules hold, and the bias they add up to.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LoadMood {
+    holding: Vec<bool>,
+    pub bias: TransitionBias,
+}
+
+impl LoadMood {
+    /// Folds a new sample in. Returns the states rules ask for as they start
+    /// to hold, in rule order.
+    pub fn update(&mut self, rules: &[LoadRule], load: &SystemLoad) -> Vec<PetState> {
+        self.holding.resize(rules.len(), false);
+        self.bias = TransitionBias::NONE;
+        let mut entered = Vec::new();
+        for (rule, holding) in rules.iter().zip(&mut self.holding) {
+            let now = rule.when.holds(load, *holding);
+            if now && !*holding {
+                entered.extend(rule.enter);
+            }
+            *holding = now;
+            if now {
+                for &(state, factor) in &rule.bias {
+                    self.bias.scale(state, factor);
+                }
+            }
+        }
+        entered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpu(at: f32) -> SystemLoad {
+        SystemLoad {
+            cpu: Some(at),
+            ..Default::default()
+        }
+    }
+
+    fn busy_rule() -> LoadRule {
+        LoadRule {
+            when: LoadCondition::CpuAbove(0.8),
+            bias: vec![(PetState::Walking, 3.0)],
+            enter: Some(PetState::Jumping),
+        }
+    }
+
+    #[test]
+    fn cpu_usage_is_the_busy_share_of_elapsed_time() {
+        let earlier = CpuTimes {
+            busy: 100,
+            total: 1000,
+        };
+        let later = CpuTimes {
+            busy: 175,
+            total: 1100,
+        };
+        assert_eq!(later.usage_since(earlier), Some(0.75));
+        assert_eq!(earlier.usage_since(earlier), None);
+        assert_eq!(earlier.usage_since(later), None, "counters went backwards");
+    }
+
+    #[test]
+    fn a_rule_holds_until_the_reading_falls_well_back() {
+        let when = LoadCondition::CpuAbove(0.8);
+        assert!(!when.holds(&cpu(0.78), false));
+        assert!(when.holds(&cpu(0.85), false));
+        assert!(when.holds(&cpu(0.78), true), "within the hysteresis");
+        assert!(!when.holds(&cpu(0.7), true));
+        assert!(!when.holds(&SystemLoad::default(), true), "no reading");
+    }
+
+    #[test]
+    fn a_low_battery_only_counts_when_discharging() {
+        let when = LoadCondition::BatteryBelow(0.2);
+        let battery = |level, discharging| SystemLoad {
+            battery: Some(Battery { level, discharging }),
+            ..Default::default()
+        };
+        assert!(when.holds(&battery(0.1, true), false));
+        assert!(!when.holds(&battery(0.1, false), false));
+        assert!(!when.holds(&battery(0.5, true), false));
+    }
+
+    #[test]
+    fn entry_states_fire_once_and_bias_lasts() {
+        let rules = [busy_rule()];
+        let mut mood = LoadMood::default();
+        assert_eq!(mood.update(&rules, &cpu(0.9)), [PetState::Jumping]);
+        assert!(mood.update(&rules, &cpu(0.95)).is_empty(), "still holding");
+        assert_ne!(mood.bias, TransitionBias::NONE);
+
+        assert!(mood.update(&rules, &cpu(0.1)).is_empty());
+        assert_eq!(mood.bias, TransitionBias::NONE);
+        assert_eq!(mood.update(&rules, &cpu(0.9)), [PetState::Jumping]);
+    }
+}
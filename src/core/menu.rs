@@ -0,0 +1,141 @@
+//! The per-pet context menu, opened by right-clicking a pet.
+//!
+//! Drawn in the overlay rather than by the OS, so it works where the tray does
+//! not: on Linux the tray needs a StatusNotifierItem host, and plenty of
+//! sessions run without one. Layout lives here so the renderer, the hit-test,
+//! and the Wayland input region all agree on where the items are.
+
+use bevy::prelude::*;
+
+use super::coords::World2d;
+
+/// Size of one menu item in logical pixels.
+pub const ITEM_SIZE: Vec2 = Vec2::new(110.0, 24.0);
+
+/// Something the user can do to one pet from its menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PetAction {
+    Feed,
+    Pet,
+    Play,
+    Sleep,
+    /// Start or stop trailing the cursor.
+    Follow,
+    Remove,
+}
+
+impl PetAction {
+    /// Every action, in menu order.
+    pub const ALL: [PetAction; 6] = [
+        PetAction::Feed,
+        PetAction::Pet,
+        PetAction::Play,
+        PetAction::Sleep,
+        PetAction::Follow,
+        PetAction::Remove,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PetAction::Feed => "Feed",
+            PetAction::Pet => "Pet",
+            PetAction::Play => "Play",
+            PetAction::Sleep => "Sleep",
+            PetAction::Follow => "Follow",
+            PetAction::Remove => "Remove",
+        }
+    }
+}
+
+/// A menu on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpenMenu {
+    /// The pet the menu acts on.
+    pub pet: Entity,
+    /// Where the right-click landed. The menu's top-left corner sits here
+    /// unless that would push it off the surface.
+    pub anchor: World2d,
+}
+
+/// The menu currently open, if any. At most one exists at a time.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+pub struct PetMenu(pub Option<OpenMenu>);
+
+/// Each item's world-space rect, top to bottom.
+///
+/// Opens down and to the right of the anchor like a desktop context menu, and
+/// is shifted back inside the surface when it would overflow an edge.
+pub fn menu_layout(anchor: World2d, surface_size: Vec2) -> Vec<(PetAction, Rect)> {
+    let size = Vec2::new(ITEM_SIZE.x, ITEM_SIZE.y * PetAction::ALL.len() as f32);
+    let half = surface_size * 0.5;
+
+    // World Y is up, so "below" the anchor is a smaller Y.
+    let left = anchor.0.x.min(half.x - size.x).max(-half.x);
+    let top = anchor.0.y.max(-half.y + size.y).min(half.y);
+
+    PetAction::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, &action)| {
+            let item_top = top - i as f32 * ITEM_SIZE.y;
+            let rect = Rect::new(left, item_top - ITEM_SIZE.y, left + ITEM_SIZE.x, item_top);
+            (action, rect)
+        })
+        .collect()
+}
+
+/// The item under `p`, if any.
+pub fn item_at(items: &[(PetAction, Rect)], p: World2d) -> Option<PetAction> {
+    items
+        .iter()
+        .find(|(_, rect)| rect.contains(p.0))
+        .map(|(action, _)| *action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SURFACE: Vec2 = Vec2::new(800.0, 600.0);
+
+    #[test]
+    fn items_stack_down_from_the_anchor() {
+        let items = menu_layout(World2d(Vec2::new(10.0, 20.0)), SURFACE);
+        assert_eq!(items.len(), PetAction::ALL.len());
+
+        let (first, rect) = items[0];
+        assert_eq!(first, PetAction::Feed);
+        assert_eq!(rect.min, Vec2::new(10.0, 20.0 - ITEM_SIZE.y));
+        assert_eq!(rect.max, Vec2::new(10.0 + ITEM_SIZE.x, 20.0));
+
+        for pair in items.windows(2) {
+            assert_eq!(pair[0].1.min.y, pair[1].1.max.y, "items must not overlap");
+        }
+    }
+
+    /// Right-clicking a pet in the bottom-right corner must not open a menu
+    /// that is half off-screen.
+    #[test]
+    fn the_menu_stays_on_the_surface() {
+        let items = menu_layout(World2d(Vec2::new(395.0, -295.0)), SURFACE);
+        let half = SURFACE * 0.5;
+        for (action, rect) in items {
+            assert!(
+                rect.min.x >= -half.x && rect.max.x <= half.x,
+                "{action:?} at {rect:?}"
+            );
+            assert!(
+                rect.min.y >= -half.y && rect.max.y <= half.y,
+                "{action:?} at {rect:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn hits_resolve_to_the_item_under_the_pointer() {
+        let items = menu_layout(World2d(Vec2::ZERO), SURFACE);
+        let second_row = World2d(Vec2::new(5.0, -ITEM_SIZE.y * 1.5));
+        assert_eq!(item_at(&items, second_row), Some(PetAction::Pet));
+        assert_eq!(item_at(&items, World2d(Vec2::new(-5.0, -5.0))), None);
+    }
+}
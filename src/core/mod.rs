@@ -6,12 +6,25 @@
 //! is what makes the bulk of the app unit-testable.
 
 pub mod animation;
+pub mod behaviour;
 pub mod brain;
 pub mod coords;
 pub mod hitbox;
+pub mod idle;
 pub mod input;
+pub mod interrupt;
+pub mod items;
+pub mod load;
+pub mod menu;
 pub mod movement;
+pub mod notify;
 pub mod rng;
+pub mod shortcuts;
+pub mod sound;
+pub mod speech;
+pub mod timers;
+pub mod touch;
+pub mod typing;
 
 /// Ordering for one frame of pet simulation.
 ///
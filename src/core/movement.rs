@@ -21,10 +21,12 @@ impl Facing {
     }
 }
 
-/// Facing from horizontal velocity, with a dead zone so a pet drifting at
-/// near-zero speed does not flicker between directions.
+/// Speeds below this, in world units per second, leave facing unchanged, so a
+/// pet drifting at near-zero speed does not flicker between directions.
+const DEAD_ZONE: f32 = 1.0;
+
+/// Facing from horizontal velocity, with a dead zone.
 pub fn facing_from_velocity(vx: f32, current: Facing) -> Facing {
-    const DEAD_ZONE: f32 = 1.0;
     if vx > DEAD_ZONE {
         Facing::Right
     } else if vx < -DEAD_ZONE {
@@ -34,6 +36,48 @@ pub fn facing_from_velocity(vx: f32, current: Facing) -> Facing {
     }
 }
 
+/// Which of four ways the pet is moving, for skins that draw more than a
+/// side view.
+///
+/// Kept beside [`Facing`] rather than replacing it: a skin without up or down
+/// art still needs to know which side a pet walking upward last faced.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Heading {
+    #[default]
+    Right,
+    Left,
+    Up,
+    Down,
+}
+
+impl From<Facing> for Heading {
+    fn from(facing: Facing) -> Self {
+        match facing {
+            Facing::Right => Heading::Right,
+            Facing::Left => Heading::Left,
+        }
+    }
+}
+
+/// Heading from velocity: whichever axis dominates, with the same dead zone
+/// as [`facing_from_velocity`]. World Y is up.
+pub fn heading_from_velocity(velocity: Vec2, current: Heading) -> Heading {
+    if velocity.abs().max_element() <= DEAD_ZONE {
+        return current;
+    }
+    if velocity.y.abs() > velocity.x.abs() {
+        if velocity.y > 0.0 {
+            Heading::Up
+        } else {
+            Heading::Down
+        }
+    } else if velocity.x > 0.0 {
+        Heading::Right
+    } else {
+        Heading::Left
+    }
+}
+
 /// Velocity that carries the pet toward `target`, or `None` once it is there.
 ///
 /// The final frame is clamped so the pet lands exactly on the target instead of
@@ -80,6 +124,68 @@ pub fn travel_time(distance: f32, speed: f32) -> Duration {
     Duration::from_secs_f32(distance / speed)
 }
 
+/// Marks a pet that trails the cursor.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Following;
+
+/// How following behaves.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct FollowConfig {
+    /// How far from the cursor a follower stops, in world units. Close enough
+    /// to read as following, far enough not to sit under the pointer.
+    pub distance: f32,
+}
+
+impl Default for FollowConfig {
+    fn default() -> Self {
+        Self { distance: 60.0 }
+    }
+}
+
+/// How much further than [`FollowConfig::distance`] the cursor must move
+/// before a settled follower sets off again. Without the gap, a cursor resting
+/// right at the boundary would start and stop the walk every frame.
+pub const FOLLOW_SLACK: f32 = 40.0;
+
+/// What a follower should do this frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FollowStep {
+    /// Walk to this point, `distance` short of the cursor.
+    Walk(Vec2),
+    /// Close enough: stop walking.
+    Settle,
+    /// Keep doing whatever it is doing.
+    Stay,
+}
+
+/// One frame of following.
+///
+/// `walking` is whether the follower is already on its way; a walking pet
+/// stops at `distance`, a settled one waits for `distance` plus
+/// [`FOLLOW_SLACK`]. The walk target is recomputed every frame, so the pet
+/// curves after a moving cursor instead of chasing where it used to be.
+pub fn follow_step(pet: Vec2, cursor: Vec2, walking: bool, distance: f32) -> FollowStep {
+    let offset = cursor - pet;
+    let gap = offset.length();
+
+    // Steering lands exactly on its target, but the target is `distance` from
+    // the cursor only up to float error.
+    const LANDED: f32 = 0.5;
+
+    let toward = || FollowStep::Walk(cursor - offset.normalize_or_zero() * distance);
+    if walking {
+        if gap <= distance + LANDED {
+            FollowStep::Settle
+        } else {
+            toward()
+        }
+    } else if gap > distance + FOLLOW_SLACK {
+        toward()
+    } else {
+        FollowStep::Stay
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,6 +199,19 @@ mod tests {
         assert_eq!(facing_from_velocity(-0.2, Facing::Right), Facing::Right);
     }
 
+    #[test]
+    fn heading_follows_the_dominant_axis() {
+        let heading = |x, y| heading_from_velocity(Vec2::new(x, y), Heading::Right);
+        assert_eq!(heading(-50.0, 10.0), Heading::Left);
+        assert_eq!(heading(10.0, 50.0), Heading::Up);
+        assert_eq!(heading(-10.0, -50.0), Heading::Down);
+        // Stopping keeps the last heading rather than snapping back.
+        assert_eq!(
+            heading_from_velocity(Vec2::new(0.5, -0.5), Heading::Up),
+            Heading::Up
+        );
+    }
+
     #[test]
     fn facing_maps_to_flip_x() {
         assert!(!Facing::Right.flip_x());
@@ -176,6 +295,57 @@ mod tests {
         assert_eq!(travel_time(f32::NAN, 140.0), Duration::ZERO);
     }
 
+    #[test]
+    fn a_far_cursor_is_walked_to_at_a_distance() {
+        let step = follow_step(Vec2::ZERO, Vec2::new(300.0, 0.0), false, 60.0);
+        assert_eq!(step, FollowStep::Walk(Vec2::new(240.0, 0.0)));
+    }
+
+    #[test]
+    fn a_walking_follower_settles_once_close() {
+        let step = follow_step(Vec2::new(240.0, 0.0), Vec2::new(300.0, 0.0), true, 60.0);
+        assert_eq!(step, FollowStep::Settle);
+    }
+
+    /// The hysteresis: just past the stopping distance is not far enough to
+    /// set off again, or a still cursor would make the pet stutter.
+    #[test]
+    fn a_settled_follower_waits_for_the_slack() {
+        let cursor = Vec2::new(60.0 + FOLLOW_SLACK * 0.5, 0.0);
+        assert_eq!(
+            follow_step(Vec2::ZERO, cursor, false, 60.0),
+            FollowStep::Stay
+        );
+
+        let cursor = Vec2::new(60.0 + FOLLOW_SLACK + 1.0, 0.0);
+        assert!(matches!(
+            follow_step(Vec2::ZERO, cursor, false, 60.0),
+            FollowStep::Walk(_)
+        ));
+    }
+
+    /// Following the same cursor with the real steering must end settled, not
+    /// oscillating around the stopping point.
+    #[test]
+    fn following_a_still_cursor_converges() {
+        let cursor = Vec2::new(-180.0, 90.0);
+        let mut pet = Vec2::ZERO;
+        let mut walking = true;
+        for _ in 0..1000 {
+            match follow_step(pet, cursor, walking, 60.0) {
+                FollowStep::Walk(to) => {
+                    if let Some(v) = steer_toward(pet, to, 140.0, ms(16)) {
+                        pet += v * ms(16).as_secs_f32();
+                    }
+                }
+                FollowStep::Settle => walking = false,
+                FollowStep::Stay => {}
+            }
+        }
+        assert!(!walking, "never settled");
+        assert!((pet.distance(cursor) - 60.0).abs() < 1.0, "{pet:?}");
+    }
+
     /// Arrival must not depend on frame rate: the same journey ends in the same
     /// place whether it is simulated in long frames or short ones.
     #[test]
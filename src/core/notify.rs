@@ -0,0 +1,170 @@
+//! Reacting to desktop notifications.
+//!
+//! Each platform hears about notifications its own way, if at all. Whatever
+//! listens implements [`NotificationSource`] and hands over plain
+//! [`Notification`]s; here is only which of them a pet reacts to, and how.
+
+use super::brain::PetState;
+
+/// A notification some application showed.
+// Only the Linux source hears any.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// The application's own name for itself, such as "Firefox".
+    pub app: String,
+    pub summary: String,
+}
+
+/// Something that hears about notifications.
+///
+/// Listening blocks on every platform that allows it, so a source is expected
+/// to listen on its own thread and hand over what it heard when polled.
+pub trait NotificationSource: Send + Sync {
+    /// Everything heard since the last poll, oldest first.
+    fn poll(&mut self) -> Vec<Notification>;
+}
+
+/// How pets react to notifications. Off unless the user turns it on: to
+/// hear one, the app reads every application's notifications.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotifyConfig {
+    pub enabled: bool,
+    /// Played by the pet that noticed.
+    pub state: PetState,
+    /// Whether the pet also says the summary. Off by default: a summary can
+    /// be a private message, and the pets are on screen for anyone to read.
+    pub show_summary: bool,
+    /// Applications to react to. Empty means all of them.
+    pub allow: Vec<String>,
+    /// Applications never to react to, whatever `allow` says.
+    pub deny: Vec<String>,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            state: PetState::Jumping,
+            show_summary: false,
+            allow: Vec::new(),
+            deny: Vec::new(),
+        }
+    }
+}
+
+/// What a pet does about a notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reaction {
+    pub state: PetState,
+    pub say: Option<String>,
+}
+
+impl NotifyConfig {
+    /// Whether notifications from `app` get a reaction. Names are compared
+    /// ignoring case, since applications are not consistent about it.
+    pub fn admits(&self, app: &str) -> bool {
+        let listed = |names: &[String]| names.iter().any(|name| name.eq_ignore_ascii_case(app));
+        !listed(&self.deny) && (self.allow.is_empty() || listed(&self.allow))
+    }
+
+    /// The reaction to `notification`, if it gets one.
+    pub fn reaction(&self, notification: &Notification) -> Option<Reaction> {
+        if !self.enabled || !self.admits(&notification.app) {
+            return None;
+        }
+        let say = Some(notification.summary.trim())
+            .filter(|summary| self.show_summary && !summary.is_empty())
+            .map(str::to_string);
+        Some(Reaction {
+            state: self.state,
+            say,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from(app: &str) -> Notification {
+        Notification {
+            app: app.to_string(),
+            summary: "New message".to_string(),
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn every_app_is_admitted_by_default() {
+        let config = NotifyConfig::default();
+        assert!(config.admits("Firefox"));
+        assert!(config.admits(""));
+    }
+
+    #[test]
+    fn an_allow_list_admits_only_its_apps() {
+        let config = NotifyConfig {
+            allow: names(&["Thunderbird"]),
+            ..Default::default()
+        };
+        assert!(config.admits("thunderbird"), "case is ignored");
+        assert!(!config.admits("Slack"));
+    }
+
+    #[test]
+    fn the_deny_list_wins() {
+        let config = NotifyConfig {
+            allow: names(&["Slack", "Signal"]),
+            deny: names(&["slack"]),
+            ..Default::default()
+        };
+        assert!(!config.admits("Slack"));
+        assert!(config.admits("Signal"));
+    }
+
+    #[test]
+    fn the_summary_is_only_said_when_asked_for() {
+        let quiet = NotifyConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            quiet.reaction(&from("Signal")),
+            Some(Reaction {
+                state: PetState::Jumping,
+                say: None,
+            })
+        );
+
+        let chatty = NotifyConfig {
+            show_summary: true,
+            ..quiet
+        };
+        let said = chatty.reaction(&from("Signal")).and_then(|r| r.say);
+        assert_eq!(said.as_deref(), Some("New message"));
+
+        let blank = Notification {
+            summary: "  ".to_string(),
+            ..from("Signal")
+        };
+        assert_eq!(chatty.reaction(&blank).and_then(|r| r.say), None);
+    }
+
+    #[test]
+    fn listening_is_opt_in() {
+        assert_eq!(NotifyConfig::default().reaction(&from("Signal")), None);
+    }
+
+    #[test]
+    fn disabled_means_no_reaction() {
+        let config = NotifyConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert_eq!(config.reaction(&from("Signal")), None);
+    }
+}
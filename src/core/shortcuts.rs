@@ -0,0 +1,374 @@
+//! Keyboard shortcuts, for using pets without a pointer.
+//!
+//! The overlay never takes keyboard focus, so a shortcut is always global:
+//! one the platform hands over from wherever the user is typing, or one the
+//! user binds in their compositor to run `batates --shortcut`. Either way it
+//! arrives as a [`Shortcut`] and means the same thing. One pet is selected at
+//! a time, shortcuts act on it, and a ring drawn around it shows which it is
+//! for a few seconds after each one.
+
+use bevy::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+
+use super::coords::World2d;
+use super::input::Intent;
+
+/// How long the selection ring stays up after a shortcut.
+pub const RING_SHOWN: Duration = Duration::from_secs(4);
+
+/// Something a shortcut can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shortcut {
+    /// Select the next pet to the right, wrapping round.
+    SelectNext,
+    SelectPrevious,
+    Pet,
+    Poke,
+    /// Walk the selected pet to the cursor.
+    Summon,
+}
+
+impl Shortcut {
+    pub const ALL: [Shortcut; 5] = [
+        Shortcut::SelectNext,
+        Shortcut::SelectPrevious,
+        Shortcut::Pet,
+        Shortcut::Poke,
+        Shortcut::Summon,
+    ];
+
+    /// As typed after `--shortcut` and sent over the control socket.
+    pub fn label(self) -> &'static str {
+        match self {
+            Shortcut::SelectNext => "select-next",
+            Shortcut::SelectPrevious => "select-previous",
+            Shortcut::Pet => "pet",
+            Shortcut::Poke => "poke",
+            Shortcut::Summon => "summon",
+        }
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Shortcut::ALL
+            .into_iter()
+            .find(|shortcut| shortcut.label().eq_ignore_ascii_case(text))
+            .ok_or_else(|| {
+                format!(
+                    "unknown shortcut {text:?}; expected select-next, select-previous, \
+                     pet, poke or summon"
+                )
+            })
+    }
+}
+
+/// A shortcut pressed, from the platform or the control socket.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShortcutPressed(pub Shortcut);
+
+bitflags::bitflags! {
+    /// Modifier keys held with a shortcut's key.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Modifiers: u8 {
+        const CTRL = 1 << 0;
+        const ALT = 1 << 1;
+        const SHIFT = 1 << 2;
+        /// The Windows key, or Command on a Mac.
+        const SUPER = 1 << 3;
+    }
+}
+
+/// The key a shortcut is pressed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// An ASCII letter, upper case, or a digit.
+    Char(char),
+    /// A function key, 1 to 12.
+    F(u8),
+}
+
+/// A key and the modifiers held with it, such as `Ctrl+Alt+N`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum KeyComboError {
+    #[error("unknown modifier {0:?}; expected Ctrl, Alt, Shift or Super")]
+    Modifier(String),
+    #[error("unknown key {0:?}; expected a letter, a digit or F1 to F12")]
+    Key(String),
+    /// A bare key would be taken from every application the user types in.
+    #[error("a shortcut needs Ctrl, Alt or Super")]
+    NoModifier,
+}
+
+impl FromStr for KeyCombo {
+    type Err = KeyComboError;
+
+    /// Parts joined with `+`, the key last, case-insensitive: `ctrl+alt+n`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+        let mut modifiers = Modifiers::empty();
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CTRL,
+                "alt" | "option" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                "super" | "win" | "cmd" | "command" => Modifiers::SUPER,
+                _ => return Err(KeyComboError::Modifier(part.to_string())),
+            };
+        }
+        let key = parse_key(key).ok_or_else(|| KeyComboError::Key(key.to_string()))?;
+        // Shift alone only changes what a key types.
+        if (modifiers - Modifiers::SHIFT).is_empty() {
+            return Err(KeyComboError::NoModifier);
+        }
+        Ok(KeyCombo { modifiers, key })
+    }
+}
+
+fn parse_key(text: &str) -> Option<Key> {
+    let mut chars = text.chars();
+    if let (Some(c), None) = (chars.next(), chars.next())
+        && c.is_ascii_alphanumeric()
+    {
+        return Some(Key::Char(c.to_ascii_uppercase()));
+    }
+    let n: u8 = text.strip_prefix(['F', 'f'])?.parse().ok()?;
+    (1..=12).contains(&n).then_some(Key::F(n))
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, name) in [
+            (Modifiers::CTRL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(flag) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.key {
+            Key::Char(c) => write!(f, "{c}"),
+            Key::F(n) => write!(f, "F{n}"),
+        }
+    }
+}
+
+/// Whether the platform's own shortcuts are registered, and with which keys.
+/// Off unless the user turns it on: a registered key no longer reaches the
+/// application it was typed in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortcutConfig {
+    pub enabled: bool,
+    pub bindings: Vec<(Shortcut, KeyCombo)>,
+}
+
+impl Default for ShortcutConfig {
+    fn default() -> Self {
+        let combo = |modifiers, c| KeyCombo {
+            modifiers,
+            key: Key::Char(c),
+        };
+        let ctrl_alt = Modifiers::CTRL | Modifiers::ALT;
+        Self {
+            enabled: false,
+            bindings: vec![
+                (Shortcut::SelectNext, combo(ctrl_alt, 'N')),
+                (
+                    Shortcut::SelectPrevious,
+                    combo(ctrl_alt | Modifiers::SHIFT, 'N'),
+                ),
+                (Shortcut::Pet, combo(ctrl_alt, 'P')),
+                (Shortcut::Poke, combo(ctrl_alt, 'O')),
+                (Shortcut::Summon, combo(ctrl_alt, 'S')),
+            ],
+        }
+    }
+}
+
+/// Something that hears the platform's registered shortcuts.
+///
+/// As for keystrokes, a source is expected to listen on its own thread and
+/// hand over what it heard when polled.
+pub trait ShortcutSource: Send + Sync {
+    /// Shortcuts pressed since the last poll, oldest first.
+    fn poll(&mut self) -> Vec<Shortcut>;
+}
+
+/// The pet shortcuts act on.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct Selection {
+    pub pet: Option<Entity>,
+    /// When the last shortcut came, for the ring.
+    used_at: Option<Duration>,
+}
+
+impl Selection {
+    /// Acts on one shortcut. `pets` are every pet with its x, and `cursor`
+    /// where the pointer is, when known.
+    ///
+    /// A pet that is gone is no longer selected, and acting with nothing
+    /// selected selects the leftmost pet first, so one pet needs no cycling.
+    pub fn press(
+        &mut self,
+        shortcut: Shortcut,
+        pets: &[(Entity, f32)],
+        cursor: Option<World2d>,
+        now: Duration,
+    ) -> Option<Intent> {
+        self.used_at = Some(now);
+        if pets.is_empty() {
+            self.pet = None;
+            return None;
+        }
+        let mut order = pets.to_vec();
+        order.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        let current = self
+            .pet
+            .and_then(|pet| order.iter().position(|&(entity, _)| entity == pet));
+        let step = |by: usize| {
+            let at = match current {
+                Some(at) => (at + by) % order.len(),
+                None => 0,
+            };
+            order[at].0
+        };
+
+        match shortcut {
+            Shortcut::SelectNext => {
+                self.pet = Some(step(1));
+                return None;
+            }
+            Shortcut::SelectPrevious => {
+                self.pet = Some(step(order.len() - 1));
+                return None;
+            }
+            Shortcut::Pet | Shortcut::Poke | Shortcut::Summon => {}
+        }
+        let pet = step(0);
+        self.pet = Some(pet);
+        match shortcut {
+            Shortcut::Pet => Some(Intent::Pet { pet }),
+            Shortcut::Poke => Some(Intent::Poke { pet }),
+            Shortcut::Summon => cursor.map(|to| Intent::SummonPet { pet, to }),
+            Shortcut::SelectNext | Shortcut::SelectPrevious => None,
+        }
+    }
+
+    /// The pet to draw the ring around as of `now`, if any.
+    pub fn ring(&self, now: Duration) -> Option<Entity> {
+        let used_at = self.used_at?;
+        (now.saturating_sub(used_at) < RING_SHOWN)
+            .then_some(self.pet)
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pet(n: u32) -> Entity {
+        Entity::from_raw_u32(n).unwrap()
+    }
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test]
+    fn combos_parse_and_print() {
+        let combo: KeyCombo = "ctrl + Alt+n".parse().unwrap();
+        assert_eq!(combo.modifiers, Modifiers::CTRL | Modifiers::ALT);
+        assert_eq!(combo.key, Key::Char('N'));
+        assert_eq!(combo.to_string(), "Ctrl+Alt+N");
+        assert_eq!("Super+F12".parse::<KeyCombo>().unwrap().key, Key::F(12));
+
+        assert_eq!(
+            "Shift+N".parse::<KeyCombo>(),
+            Err(KeyComboError::NoModifier)
+        );
+        assert_eq!(
+            "Hyper+N".parse::<KeyCombo>(),
+            Err(KeyComboError::Modifier("Hyper".to_string()))
+        );
+        assert_eq!(
+            "Ctrl+F13".parse::<KeyCombo>(),
+            Err(KeyComboError::Key("F13".to_string()))
+        );
+        assert_eq!(
+            "Ctrl+".parse::<KeyCombo>(),
+            Err(KeyComboError::Key(String::new()))
+        );
+    }
+
+    #[test]
+    fn selecting_goes_left_to_right_and_wraps() {
+        let pets = [(pet(1), 300.0), (pet(2), -50.0), (pet(3), 10.0)];
+        let mut selection = Selection::default();
+        let mut next = |shortcut| {
+            selection.press(shortcut, &pets, None, secs(0));
+            selection.pet
+        };
+        assert_eq!(next(Shortcut::SelectNext), Some(pet(2)), "leftmost first");
+        assert_eq!(next(Shortcut::SelectNext), Some(pet(3)));
+        assert_eq!(next(Shortcut::SelectNext), Some(pet(1)));
+        assert_eq!(next(Shortcut::SelectNext), Some(pet(2)), "wraps");
+        assert_eq!(next(Shortcut::SelectPrevious), Some(pet(1)), "and back");
+    }
+
+    #[test]
+    fn actions_fall_on_the_selected_or_leftmost_pet() {
+        let pets = [(pet(1), 0.0), (pet(2), 100.0)];
+        let cursor = World2d(Vec2::new(5.0, 5.0));
+        let mut selection = Selection::default();
+        assert_eq!(
+            selection.press(Shortcut::Pet, &pets, None, secs(0)),
+            Some(Intent::Pet { pet: pet(1) })
+        );
+        selection.press(Shortcut::SelectNext, &pets, None, secs(1));
+        assert_eq!(
+            selection.press(Shortcut::Summon, &pets, Some(cursor), secs(2)),
+            Some(Intent::SummonPet {
+                pet: pet(2),
+                to: cursor
+            })
+        );
+        assert_eq!(
+            selection.press(Shortcut::Summon, &pets, None, secs(3)),
+            None,
+            "no cursor, nowhere to go"
+        );
+
+        // The selected pet went away.
+        assert_eq!(
+            selection.press(Shortcut::Poke, &pets[..1], None, secs(4)),
+            Some(Intent::Poke { pet: pet(1) })
+        );
+        assert_eq!(selection.press(Shortcut::Poke, &[], None, secs(5)), None);
+        assert_eq!(selection.pet, None);
+    }
+
+    #[test]
+    fn the_ring_shows_for_a_while_after_a_shortcut() {
+        let pets = [(pet(1), 0.0)];
+        let mut selection = Selection::default();
+        assert_eq!(selection.ring(secs(0)), None);
+        selection.press(Shortcut::SelectNext, &pets, None, secs(10));
+        assert_eq!(selection.ring(secs(11)), Some(pet(1)));
+        assert_eq!(selection.ring(secs(10) + RING_SHOWN), None);
+    }
+}
@@ -0,0 +1,114 @@
+//! When pets make noise, and how much of it gets through.
+//!
+//! A skin attaches a clip to a [`SoundCue`]; what the clip sounds like is its
+//! business. Here is only the volume the user chose and the limit that stops
+//! a crowd of pets from playing the same clip on top of itself.
+
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::brain::PetState;
+
+/// The same cue cannot start again sooner than this. Ten pets entering a state
+/// on one frame are heard once, not ten times louder.
+pub const MIN_REPEAT: Duration = Duration::from_millis(150);
+
+/// Something a skin can play a sound for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum SoundCue {
+    /// A pet entered this state, whatever made it.
+    Enter(PetState),
+    Pet,
+    Poke,
+    Grab,
+    Release,
+}
+
+/// The user's master volume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoundConfig {
+    /// From 0 to 1, applied to every clip.
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self {
+            volume: 0.8,
+            muted: false,
+        }
+    }
+}
+
+impl SoundConfig {
+    /// The volume a clip plays at, or `None` when nothing should play at all.
+    pub fn level(&self) -> Option<f32> {
+        (!self.muted && self.volume > 0.0).then_some(self.volume)
+    }
+}
+
+/// When each cue last started.
+#[derive(Debug, Default)]
+pub struct SoundLimiter {
+    last: Vec<(SoundCue, Duration)>,
+}
+
+impl SoundLimiter {
+    /// Whether `cue` may start at `now`, recording it if so.
+    pub fn allow(&mut self, cue: SoundCue, now: Duration) -> bool {
+        match self.last.iter_mut().find(|(seen, _)| *seen == cue) {
+            Some((_, at)) if now.saturating_sub(*at) < MIN_REPEAT => false,
+            Some((_, at)) => {
+                *at = now;
+                true
+            }
+            None => {
+                self.last.push((cue, now));
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_cue_is_heard_once_however_many_pets_raise_it() {
+        let mut limiter = SoundLimiter::default();
+        let now = Duration::from_secs(10);
+        let heard = (0..10)
+            .filter(|_| limiter.allow(SoundCue::Enter(PetState::Jumping), now))
+            .count();
+        assert_eq!(heard, 1);
+        // Another cue is not held up by it.
+        assert!(limiter.allow(SoundCue::Pet, now));
+    }
+
+    #[test]
+    fn a_cue_plays_again_once_the_gap_has_passed() {
+        let mut limiter = SoundLimiter::default();
+        let start = Duration::from_secs(1);
+        assert!(limiter.allow(SoundCue::Poke, start));
+        assert!(!limiter.allow(SoundCue::Poke, start + MIN_REPEAT / 2));
+        assert!(limiter.allow(SoundCue::Poke, start + MIN_REPEAT));
+    }
+
+    #[test]
+    fn muting_or_zero_volume_plays_nothing() {
+        let loud = SoundConfig::default();
+        assert_eq!(loud.level(), Some(0.8));
+        let muted = SoundConfig {
+            muted: true,
+            ..loud
+        };
+        assert_eq!(muted.level(), None);
+        let silent = SoundConfig {
+            volume: 0.0,
+            ..loud
+        };
+        assert_eq!(silent.level(), None);
+    }
+}
@@ -0,0 +1,258 @@
+//! Speech bubbles: what a pet says, how it is wrapped, and where it sits.
+//!
+//! Layout lives here, as the menu's does, so the renderer, the click that
+//! dismisses a bubble, and the Wayland input region all agree on where it is.
+//! Text is measured in characters: the default font is monospaced, so a
+//! character count is a width.
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+use super::coords::World2d;
+use super::rng::PetRng;
+
+pub const FONT_SIZE: f32 = 13.0;
+/// The default font's advance, which is the same for every character.
+pub const CHAR_WIDTH: f32 = FONT_SIZE * 0.6;
+/// Bevy's default line height.
+pub const LINE_HEIGHT: f32 = FONT_SIZE * 1.2;
+/// Space between the text and the bubble's edge.
+pub const PADDING: f32 = 6.0;
+/// Space between the bubble and the top of the pet's frame.
+pub const GAP: f32 = 4.0;
+
+/// Characters per line before wrapping.
+pub const MAX_LINE_CHARS: usize = 28;
+/// Lines shown; anything longer is cut short with an ellipsis.
+pub const MAX_LINES: usize = 4;
+
+/// Every bubble is shown at least this long, plus [`PER_CHAR`] for each
+/// character, up to [`MAX_SHOWN`].
+const MIN_SHOWN: Duration = Duration::from_secs(2);
+const PER_CHAR: Duration = Duration::from_millis(60);
+const MAX_SHOWN: Duration = Duration::from_secs(10);
+
+/// One in this many entries into a state with phrases says one. Otherwise a
+/// pet that walks every few seconds would never stop talking.
+pub const SPEAK_ODDS: u32 = 3;
+
+/// What a pet is saying right now.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Speech {
+    /// Already wrapped to [`MAX_LINE_CHARS`].
+    pub lines: Vec<String>,
+    /// Until the bubble goes away by itself, or `None` if it stays until
+    /// clicked.
+    pub remaining: Option<Duration>,
+}
+
+impl Speech {
+    /// A bubble for `text`, or `None` if there is nothing to show.
+    pub fn new(text: &str) -> Option<Self> {
+        let mut speech = Self::pinned(text)?;
+        let chars = speech
+            .lines
+            .iter()
+            .map(|line| line.chars().count())
+            .sum::<usize>();
+        speech.remaining = Some((MIN_SHOWN + PER_CHAR * chars as u32).min(MAX_SHOWN));
+        Some(speech)
+    }
+
+    /// A bubble for `text` that stays until clicked, for messages that must
+    /// not be missed.
+    pub fn pinned(text: &str) -> Option<Self> {
+        let lines = wrap(text, MAX_LINE_CHARS, MAX_LINES);
+        if lines.is_empty() {
+            return None;
+        }
+        Some(Self {
+            lines,
+            remaining: None,
+        })
+    }
+
+    /// Counts the bubble down by `dt`. Returns whether it is still shown.
+    pub fn tick(&mut self, dt: Duration) -> bool {
+        let Some(remaining) = &mut self.remaining else {
+            return true;
+        };
+        *remaining = remaining.saturating_sub(dt);
+        !remaining.is_zero()
+    }
+
+    /// The bubble's world-space rect over a pet whose frame is `pet_rect`,
+    /// kept on the surface.
+    pub fn rect(&self, pet_rect: Rect, surface_size: Vec2) -> Rect {
+        let widest = self
+            .lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let size = Vec2::new(
+            widest as f32 * CHAR_WIDTH,
+            self.lines.len() as f32 * LINE_HEIGHT,
+        ) + Vec2::splat(PADDING * 2.0);
+        let half = surface_size * 0.5;
+
+        // Centred over the pet, unless that would push it off an edge. A pet
+        // at the top of the surface has its bubble overlap it rather than
+        // vanish.
+        let centre_x = pet_rect.center().x;
+        let left = (centre_x - size.x * 0.5).min(half.x - size.x).max(-half.x);
+        let bottom = (pet_rect.max.y + GAP).min(half.y - size.y).max(-half.y);
+        Rect::from_corners(Vec2::new(left, bottom), Vec2::new(left, bottom) + size)
+    }
+}
+
+/// Splits `text` into lines of at most `width` characters at spaces, breaking
+/// words that are longer than a line. Control characters become spaces, so
+/// a message cannot lay itself out.
+pub fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let cleaned: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in cleaned.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let used = line.chars().count();
+        if used > 0 && used + 1 + word.len() <= width {
+            line.push(' ');
+            line.extend(&word);
+            continue;
+        }
+        if used > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            lines.push(word.drain(..width).collect());
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = lines.last_mut().expect("max_lines is non-zero");
+        let kept: String = last.chars().take(width.saturating_sub(3)).collect();
+        *last = format!("{}...", kept.trim_end());
+    }
+    lines
+}
+
+/// A phrase for a pet that has just entered a state, if it says anything this
+/// time.
+pub fn pick_phrase<'a>(phrases: &'a [String], rng: &mut PetRng) -> Option<&'a str> {
+    if phrases.is_empty() || rng.roll(SPEAK_ODDS) != 0 {
+        return None;
+    }
+    Some(&phrases[rng.roll(phrases.len() as u32) as usize])
+}
+
+/// The pet whose bubble is under `p`, the topmost if several overlap.
+pub fn bubble_at(bubbles: &[(Entity, f32, Rect)], p: World2d) -> Option<Entity> {
+    bubbles
+        .iter()
+        .filter(|(_, _, rect)| rect.contains(p.0))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(pet, _, _)| *pet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rng::Seed;
+
+    const SURFACE: Vec2 = Vec2::new(800.0, 600.0);
+
+    #[test]
+    fn text_wraps_at_spaces_and_breaks_long_words() {
+        assert_eq!(wrap("the build finished", 10, 4), ["the build", "finished"]);
+        assert_eq!(wrap("abcdefghijkl", 5, 4), ["abcde", "fghij", "kl"]);
+        assert_eq!(wrap("  two\nlines\t", 20, 4), ["two lines"]);
+        assert!(wrap(" \n ", 20, 4).is_empty());
+    }
+
+    #[test]
+    fn overlong_text_is_cut_short() {
+        let lines = wrap("one two three four five six", 5, 3);
+        assert_eq!(lines, ["one", "two", "th..."]);
+    }
+
+    #[test]
+    fn longer_messages_stay_longer_within_limits() {
+        let short = Speech::new("hi").expect("not empty");
+        let long = Speech::new("the build finished with no warnings").expect("not empty");
+        assert!(short.remaining >= Some(MIN_SHOWN));
+        assert!(long.remaining > short.remaining);
+        // Timed by what is shown: cut to `MAX_LINES`, it is a full bubble.
+        let essay = Speech::new(&"word ".repeat(100)).expect("not empty");
+        assert_eq!(essay.lines.len(), MAX_LINES);
+        assert!(essay.remaining > long.remaining);
+        assert!(essay.remaining <= Some(MAX_SHOWN));
+        assert_eq!(Speech::new("   "), None);
+    }
+
+    #[test]
+    fn a_bubble_times_out() {
+        let mut speech = Speech::new("hi").expect("not empty");
+        assert!(speech.tick(Duration::from_secs(1)));
+        assert!(!speech.tick(MAX_SHOWN));
+    }
+
+    #[test]
+    fn a_pinned_bubble_waits_to_be_clicked() {
+        let mut speech = Speech::pinned("stretch").expect("not empty");
+        assert!(speech.tick(MAX_SHOWN * 100));
+        assert_eq!(Speech::pinned(""), None);
+    }
+
+    #[test]
+    fn the_bubble_sits_above_the_pet_and_on_the_surface() {
+        let speech = Speech::new("hello there").expect("not empty");
+        let pet = Rect::from_center_size(Vec2::new(0.0, 0.0), Vec2::splat(60.0));
+        let rect = speech.rect(pet, SURFACE);
+        assert_eq!(rect.min.y, pet.max.y + GAP);
+        assert_eq!(rect.center().x, 0.0);
+        assert_eq!(
+            rect.width(),
+            11.0 * CHAR_WIDTH + PADDING * 2.0,
+            "as wide as its longest line"
+        );
+
+        // In the top-right corner it is pushed back inside.
+        let cornered = Rect::from_center_size(Vec2::new(390.0, 290.0), Vec2::splat(60.0));
+        let rect = speech.rect(cornered, SURFACE);
+        assert!(rect.max.x <= SURFACE.x * 0.5 && rect.max.y <= SURFACE.y * 0.5);
+    }
+
+    #[test]
+    fn the_topmost_bubble_is_hit() {
+        let low = Entity::from_raw_u32(1).unwrap();
+        let high = Entity::from_raw_u32(2).unwrap();
+        let rect = Rect::new(0.0, 0.0, 50.0, 20.0);
+        let bubbles = [(low, 0.0, rect), (high, 1.0, rect)];
+        assert_eq!(
+            bubble_at(&bubbles, World2d(Vec2::new(10.0, 10.0))),
+            Some(high)
+        );
+        assert_eq!(bubble_at(&bubbles, World2d(Vec2::new(60.0, 10.0))), None);
+    }
+
+    #[test]
+    fn phrases_are_only_sometimes_said() {
+        let phrases = vec!["yum".to_string(), "tasty".to_string()];
+        let mut rng = PetRng::from_seed(Seed(7));
+        let said = (0..300)
+            .filter_map(|_| pick_phrase(&phrases, &mut rng))
+            .count();
+        assert!((50..150).contains(&said), "said {said} of 300");
+        assert_eq!(pick_phrase(&[], &mut rng), None);
+    }
+}
@@ -0,0 +1,236 @@
+//! Pomodoro cycles, recurring reminders and one-shot timers.
+//!
+//! Time is whatever `now` the caller passes: the app passes real time since
+//! startup, which keeps running while the pets are paused, and tests pass
+//! what they like. What a firing timer makes a pet do is up to the caller;
+//! here is only when each one fires and what it says.
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+use super::brain::PetState;
+
+/// Played by a pet delivering a timer, unless the timer names another state.
+pub const DEFAULT_STATE: PetState = PetState::Jumping;
+/// Said by a one-shot or recurring timer that was given no message.
+pub const DEFAULT_MESSAGE: &str = "Time's up!";
+/// Said when a pomodoro's work period ends.
+pub const WORK_OVER: &str = "Time for a break!";
+/// Said when a pomodoro's break ends.
+pub const BREAK_OVER: &str = "Back to work!";
+
+/// A pomodoro's periods when nobody says otherwise.
+pub const POMODORO_WORK: Duration = Duration::from_secs(25 * 60);
+pub const POMODORO_REST: Duration = Duration::from_secs(5 * 60);
+
+/// How a timer repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerKind {
+    /// Fires once, `after` from when it is set.
+    Once { after: Duration },
+    /// Fires every `period`, such as a reminder to stretch.
+    Every { period: Duration },
+    /// Alternates `work` and `rest`, firing at the end of each.
+    Pomodoro { work: Duration, rest: Duration },
+}
+
+/// A timer as configured or requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerSpec {
+    pub kind: TimerKind,
+    /// Ignored by a pomodoro, which says [`WORK_OVER`] and [`BREAK_OVER`].
+    pub message: String,
+    /// Played by the pet that delivers it.
+    pub state: PetState,
+}
+
+impl TimerSpec {
+    /// A pomodoro of `work` and `rest`, delivered with the default state.
+    pub fn pomodoro(work: Duration, rest: Duration) -> Self {
+        Self {
+            kind: TimerKind::Pomodoro { work, rest },
+            message: String::new(),
+            state: DEFAULT_STATE,
+        }
+    }
+}
+
+/// A timer that has gone off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fired {
+    pub message: String,
+    pub state: PetState,
+}
+
+/// The timers running right now.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Timers {
+    running: Vec<Running>,
+}
+
+#[derive(Debug, Clone)]
+struct Running {
+    spec: TimerSpec,
+    due: Duration,
+    /// For a pomodoro: whether the period running now is the break.
+    resting: bool,
+}
+
+impl Timers {
+    /// Starts `spec` at `now`.
+    pub fn add(&mut self, spec: TimerSpec, now: Duration) {
+        let first = match spec.kind {
+            TimerKind::Once { after } => after,
+            TimerKind::Every { period } => period,
+            TimerKind::Pomodoro { work, .. } => work,
+        };
+        self.running.push(Running {
+            spec,
+            due: now + first,
+            resting: false,
+        });
+    }
+
+    /// Every timer due by `now`, in the order they were added. One-shot
+    /// timers are done with; the rest are rescheduled.
+    ///
+    /// A timer overdue by several periods, because the machine slept, fires
+    /// once and starts counting again from `now` rather than firing for each
+    /// period it missed.
+    pub fn due(&mut self, now: Duration) -> Vec<Fired> {
+        let mut fired = Vec::new();
+        self.running.retain_mut(|timer| {
+            if timer.due > now {
+                return true;
+            }
+            let spec = &timer.spec;
+            let (message, next) = match spec.kind {
+                TimerKind::Once { .. } => (spec.message.as_str(), None),
+                TimerKind::Every { period } => (spec.message.as_str(), Some(period)),
+                TimerKind::Pomodoro { work, rest } => {
+                    let (message, next) = if timer.resting {
+                        (BREAK_OVER, work)
+                    } else {
+                        (WORK_OVER, rest)
+                    };
+                    timer.resting = !timer.resting;
+                    (message, Some(next))
+                }
+            };
+            fired.push(Fired {
+                message: message.to_string(),
+                state: spec.state,
+            });
+            match next {
+                Some(next) => {
+                    timer.due = now + next;
+                    true
+                }
+                None => false,
+            }
+        });
+        fired
+    }
+}
+
+/// A pet called to the middle of the screen to deliver a timer.
+///
+/// It walks there with the ordinary `Walking` state, like an errand, and is
+/// over the same way; see [`step_errand`](super::items::step_errand).
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Summons {
+    pub message: String,
+    pub then: PetState,
+}
+
+/// The pet that delivers a timer: the one nearest the middle of the screen,
+/// where it will go to say it. Ties go to the lower entity, so the choice does
+/// not depend on query order.
+pub fn pick_messenger(pets: &[(Entity, Vec2)]) -> Option<Entity> {
+    pets.iter()
+        .min_by(|a, b| {
+            a.1.length_squared()
+                .total_cmp(&b.1.length_squared())
+                .then_with(|| a.0.cmp(&b.0))
+        })
+        .map(|(pet, _)| *pet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mins(n: u64) -> Duration {
+        Duration::from_secs(n * 60)
+    }
+
+    fn spec(kind: TimerKind, message: &str) -> TimerSpec {
+        TimerSpec {
+            kind,
+            message: message.to_string(),
+            state: DEFAULT_STATE,
+        }
+    }
+
+    fn messages(fired: Vec<Fired>) -> Vec<String> {
+        fired.into_iter().map(|fired| fired.message).collect()
+    }
+
+    #[test]
+    fn a_one_shot_fires_once() {
+        let mut timers = Timers::default();
+        timers.add(spec(TimerKind::Once { after: mins(10) }, "tea"), mins(1));
+        assert!(
+            timers.due(mins(10)).is_empty(),
+            "counted from when it was set"
+        );
+        assert_eq!(messages(timers.due(mins(11))), ["tea"]);
+        assert_eq!(timers.running.len(), 0);
+        assert!(timers.due(mins(30)).is_empty());
+    }
+
+    #[test]
+    fn a_reminder_recurs() {
+        let mut timers = Timers::default();
+        timers.add(
+            spec(TimerKind::Every { period: mins(45) }, "stretch"),
+            Duration::ZERO,
+        );
+        assert_eq!(messages(timers.due(mins(45))), ["stretch"]);
+        assert!(timers.due(mins(60)).is_empty());
+        assert_eq!(messages(timers.due(mins(90))), ["stretch"]);
+        assert_eq!(timers.running.len(), 1);
+    }
+
+    #[test]
+    fn a_pomodoro_alternates_work_and_rest() {
+        let mut timers = Timers::default();
+        timers.add(TimerSpec::pomodoro(mins(25), mins(5)), Duration::ZERO);
+        assert_eq!(messages(timers.due(mins(25))), [WORK_OVER]);
+        assert_eq!(messages(timers.due(mins(30))), [BREAK_OVER]);
+        assert!(timers.due(mins(54)).is_empty());
+        assert_eq!(messages(timers.due(mins(55))), [WORK_OVER]);
+    }
+
+    #[test]
+    fn the_pet_nearest_the_middle_delivers() {
+        let far = Entity::from_raw_u32(1).unwrap();
+        let near = Entity::from_raw_u32(2).unwrap();
+        let pets = [(far, Vec2::new(-300.0, 0.0)), (near, Vec2::new(40.0, 90.0))];
+        assert_eq!(pick_messenger(&pets), Some(near));
+        assert_eq!(pick_messenger(&[]), None);
+    }
+
+    /// Waking from a night's sleep is one reminder, not sixteen.
+    #[test]
+    fn missed_periods_fire_once() {
+        let mut timers = Timers::default();
+        timers.add(
+            spec(TimerKind::Every { period: mins(30) }, "water"),
+            Duration::ZERO,
+        );
+        assert_eq!(messages(timers.due(mins(8 * 60))), ["water"]);
+        assert!(timers.due(mins(8 * 60 + 29)).is_empty());
+        assert_eq!(timers.due(mins(8 * 60 + 30)).len(), 1);
+    }
+}
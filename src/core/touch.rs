@@ -0,0 +1,236 @@
+//! Touch points in, the same gameplay intents as the mouse out.
+//!
+//! A finger is not a mouse with one button: several can be down at once, each
+//! on its own pet, and a finger held still has no cursor to hover with. So
+//! each touch point is tracked by the id its backend gives it, and means what
+//! a finger on a phone would: a tap pets, holding still picks the pet up, and
+//! moving drags it. A pen on a tablet is one more touch point.
+//!
+//! Holding still produces no events at all, which is why long presses are
+//! found by [`TouchState::hold`], run once a frame, rather than by any sample.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::coords::World2d;
+use super::input::{GestureConfig, Intent, MOVE_SLOP, PointerAt};
+
+/// What happened to one touch point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchPhase {
+    Down,
+    Moved,
+    Up,
+    /// Taken back by the system, e.g. for a gesture of its own. Nothing the
+    /// touch was doing counts as finished: a held pet is put down, a tap is
+    /// not a tap.
+    Cancelled,
+}
+
+/// One event for one touch point.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct TouchSample {
+    /// Stable from `Down` to `Up` or `Cancelled`, and unique among the points
+    /// down at the same time. A backend may reuse it afterwards.
+    pub id: u64,
+    pub phase: TouchPhase,
+    /// In the space its platform speaks, as for [`super::input::PointerSample`].
+    pub at: PointerAt,
+    /// Monotonic app time, from `Time::elapsed`. Never wall-clock.
+    pub at_time: Duration,
+}
+
+/// A touch point that went down on a pet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Touch {
+    pet: Entity,
+    started: Duration,
+    from: World2d,
+    at: World2d,
+    /// Picked up, by holding still or by moving.
+    grabbed: bool,
+}
+
+/// Every touch point currently down on a pet. Points that went down anywhere
+/// else are not tracked: there is nothing on bare desktop for them to do.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct TouchState {
+    touches: HashMap<u64, Touch>,
+}
+
+impl TouchState {
+    /// Folds in one event. `at` is the point in world space, when known, and
+    /// `hit` the topmost pet under it, resolved by the caller.
+    pub fn touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        at: Option<World2d>,
+        hit: Option<Entity>,
+        now: Duration,
+    ) -> Vec<Intent> {
+        let mut intents = Vec::new();
+        match phase {
+            TouchPhase::Down => {
+                // A second finger on a pet already held does nothing; it
+                // would only fight the first for where the pet goes.
+                let (Some(pet), Some(at)) = (hit, at) else {
+                    return intents;
+                };
+                if self.touches.values().any(|touch| touch.pet == pet) {
+                    return intents;
+                }
+                self.touches.insert(
+                    id,
+                    Touch {
+                        pet,
+                        started: now,
+                        from: at,
+                        at,
+                        grabbed: false,
+                    },
+                );
+            }
+            TouchPhase::Moved => {
+                let (Some(touch), Some(at)) = (self.touches.get_mut(&id), at) else {
+                    return intents;
+                };
+                touch.at = at;
+                if !touch.grabbed && touch.from.0.distance(at.0) > MOVE_SLOP {
+                    touch.grabbed = true;
+                    intents.push(Intent::Grab {
+                        pet: touch.pet,
+                        offset: at.0,
+                    });
+                }
+                if touch.grabbed {
+                    intents.push(Intent::DragTo {
+                        pet: touch.pet,
+                        to: at,
+                    });
+                }
+            }
+            TouchPhase::Up | TouchPhase::Cancelled => {
+                let Some(touch) = self.touches.remove(&id) else {
+                    return intents;
+                };
+                if touch.grabbed {
+                    intents.push(Intent::Release { pet: touch.pet });
+                } else if phase == TouchPhase::Up {
+                    intents.push(Intent::Pet { pet: touch.pet });
+                }
+            }
+        }
+        intents
+    }
+
+    /// Picks up every pet a finger has held still on for a long press.
+    pub fn hold(&mut self, now: Duration, cfg: &GestureConfig) -> Vec<Intent> {
+        let mut intents = Vec::new();
+        for touch in self.touches.values_mut() {
+            if !touch.grabbed && now.saturating_sub(touch.started) >= cfg.long_press {
+                touch.grabbed = true;
+                intents.push(Intent::Grab {
+                    pet: touch.pet,
+                    offset: touch.at.0,
+                });
+            }
+        }
+        intents
+    }
+
+    /// Forgets touch points on a pet that is gone.
+    pub fn retain_pets(&mut self, alive: impl Fn(Entity) -> bool) {
+        self.touches.retain(|_, touch| alive(touch.pet));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn pet(n: u32) -> Entity {
+        Entity::from_raw_u32(n).unwrap()
+    }
+
+    fn at(x: f32) -> Option<World2d> {
+        Some(World2d(Vec2::new(x, 0.0)))
+    }
+
+    #[test]
+    fn a_tap_pets() {
+        let mut touches = TouchState::default();
+        assert!(
+            touches
+                .touch(7, TouchPhase::Down, at(0.0), Some(pet(1)), ms(0))
+                .is_empty()
+        );
+        assert_eq!(
+            touches.touch(7, TouchPhase::Up, at(0.0), Some(pet(1)), ms(90)),
+            [Intent::Pet { pet: pet(1) }]
+        );
+    }
+
+    #[test]
+    fn holding_still_grabs_and_moving_drags() {
+        let cfg = GestureConfig::default();
+        let mut touches = TouchState::default();
+        touches.touch(1, TouchPhase::Down, at(0.0), Some(pet(1)), ms(0));
+        assert!(touches.hold(ms(100), &cfg).is_empty());
+        assert_eq!(
+            touches.hold(ms(900), &cfg),
+            [Intent::Grab {
+                pet: pet(1),
+                offset: Vec2::ZERO
+            }]
+        );
+        assert!(touches.hold(ms(950), &cfg).is_empty(), "only once");
+        assert_eq!(
+            touches.touch(1, TouchPhase::Moved, at(3.0), None, ms(960)),
+            [Intent::DragTo {
+                pet: pet(1),
+                to: World2d(Vec2::new(3.0, 0.0))
+            }]
+        );
+        assert_eq!(
+            touches.touch(1, TouchPhase::Up, at(3.0), None, ms(990)),
+            [Intent::Release { pet: pet(1) }]
+        );
+    }
+
+    #[test]
+    fn each_finger_drags_its_own_pet() {
+        let mut touches = TouchState::default();
+        touches.touch(1, TouchPhase::Down, at(0.0), Some(pet(1)), ms(0));
+        touches.touch(2, TouchPhase::Down, at(100.0), Some(pet(2)), ms(10));
+        // A third finger on the first pet is ignored.
+        touches.touch(3, TouchPhase::Down, at(1.0), Some(pet(1)), ms(20));
+
+        let moved = touches.touch(2, TouchPhase::Moved, at(150.0), None, ms(30));
+        assert!(moved.contains(&Intent::Grab {
+            pet: pet(2),
+            offset: Vec2::new(150.0, 0.0)
+        }));
+        assert!(
+            touches
+                .touch(3, TouchPhase::Moved, at(50.0), None, ms(40))
+                .is_empty()
+        );
+
+        // Cancelling puts a held pet down, and is not a tap on the other.
+        assert_eq!(
+            touches.touch(2, TouchPhase::Cancelled, None, None, ms(50)),
+            [Intent::Release { pet: pet(2) }]
+        );
+        assert!(
+            touches
+                .touch(1, TouchPhase::Cancelled, None, None, ms(50))
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! How fast the user is typing, and how pets react to it.
+//!
+//! A platform counts key presses and hands over nothing but the count: no
+//! source ever says which key went down, so there is nothing here to record
+//! even by mistake. The count becomes a rate over the last few seconds, the
+//! rate a pace, and a change of pace a reaction: a pet comes to sit beside the
+//! cursor when typing starts, pets cheer when it turns furious, and they nap
+//! once the keyboard has gone quiet for a while.
+
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::brain::PetState;
+
+/// How far back the rate looks. Long enough that the pauses between words do
+/// not read as stopping, short enough that stopping does.
+pub const WINDOW: Duration = Duration::from_secs(5);
+
+/// Fraction of the furious rate typing must fall below to calm down again, so
+/// a rate hovering at the threshold does not cheer over and over.
+const CALMER: f32 = 0.75;
+
+/// Something that counts key presses.
+///
+/// Counting blocks on a hook or a device on every platform that allows it, so
+/// a source is expected to count on its own thread and hand over the total
+/// when polled.
+pub trait KeystrokeSource: Send + Sync {
+    /// Keys pressed since the last poll. Held keys repeating do not count
+    /// where the platform can tell.
+    fn poll(&mut self) -> u32;
+}
+
+/// Keys pressed per second over the last [`WINDOW`].
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct TypingRate {
+    counts: VecDeque<(Duration, u32)>,
+    pub per_second: f32,
+}
+
+impl TypingRate {
+    /// Adds `keys` pressed by `now`, and forgets presses older than the window.
+    pub fn record(&mut self, now: Duration, keys: u32) {
+        if keys > 0 {
+            self.counts.push_back((now, keys));
+        }
+        while self
+            .counts
+            .front()
+            .is_some_and(|&(at, _)| now.saturating_sub(at) >= WINDOW)
+        {
+            self.counts.pop_front();
+        }
+        let total: u32 = self.counts.iter().map(|&(_, keys)| keys).sum();
+        self.per_second = total as f32 / WINDOW.as_secs_f32();
+    }
+}
+
+/// How the user is typing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pace {
+    /// Not a key in the last [`WINDOW`].
+    #[default]
+    Quiet,
+    Typing,
+    Furious,
+}
+
+/// How pets react to typing. Off unless the user turns it on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypingConfig {
+    pub enabled: bool,
+    /// Keys per second that count as typing furiously.
+    pub furious: f32,
+    /// Played by every free pet as typing turns furious.
+    pub cheer: PetState,
+    /// Played by the pet that comes to sit beside the cursor.
+    pub beside: PetState,
+    /// How long the keyboard must be quiet after typing before pets nap.
+    pub nap_after: Duration,
+    /// Played by every free pet once they nap.
+    pub nap: PetState,
+}
+
+impl Default for TypingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            furious: 6.0,
+            cheer: PetState::Jumping,
+            beside: PetState::Sitting,
+            nap_after: Duration::from_secs(2 * 60),
+            nap: PetState::Chilling,
+        }
+    }
+}
+
+/// What pets do about a change of pace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypingReaction {
+    /// One pet comes to the cursor and plays this there.
+    Beside(PetState),
+    /// Every free pet plays this.
+    Everyone(PetState),
+}
+
+/// The pace as of the last rate, and how long the keyboard has been quiet.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct TypingMood {
+    pub pace: Pace,
+    /// When typing last stopped, until the nap it leads to.
+    quiet_since: Option<Duration>,
+}
+
+impl TypingMood {
+    /// Folds in the rate as of `now`. Returns a reaction when the pace has
+    /// changed, or when a quiet spell has lasted long enough to nap.
+    pub fn update(
+        &mut self,
+        config: &TypingConfig,
+        rate: f32,
+        now: Duration,
+    ) -> Option<TypingReaction> {
+        let calm = if self.pace == Pace::Furious {
+            CALMER
+        } else {
+            1.0
+        };
+        let pace = if rate <= 0.0 {
+            Pace::Quiet
+        } else if rate >= config.furious * calm {
+            Pace::Furious
+        } else {
+            Pace::Typing
+        };
+        let was = std::mem::replace(&mut self.pace, pace);
+        if pace != Pace::Quiet {
+            self.quiet_since = None;
+        }
+
+        match (was, pace) {
+            (Pace::Quiet, Pace::Quiet) => {
+                // Only a quiet spell that followed typing leads to a nap, and
+                // only to one.
+                let since = self.quiet_since?;
+                if now.saturating_sub(since) < config.nap_after {
+                    return None;
+                }
+                self.quiet_since = None;
+                Some(TypingReaction::Everyone(config.nap))
+            }
+            (_, Pace::Quiet) => {
+                self.quiet_since = Some(now);
+                None
+            }
+            (Pace::Furious, Pace::Furious) | (Pace::Typing | Pace::Furious, Pace::Typing) => None,
+            (_, Pace::Furious) => Some(TypingReaction::Everyone(config.cheer)),
+            (Pace::Quiet, Pace::Typing) => Some(TypingReaction::Beside(config.beside)),
+        }
+    }
+}
+
+/// Where a pet at `pet_at` sits beside the cursor: `gap` to the side of it
+/// the pet comes from, so it does not walk across the text being typed.
+pub fn beside_cursor(cursor: Vec2, pet_at: Vec2, gap: f32) -> Vec2 {
+    let side = if pet_at.x < cursor.x { -1.0 } else { 1.0 };
+    cursor + Vec2::X * gap * side
+}
+
+/// A pet on its way to sit beside the cursor, and what it plays there.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Companion {
+    pub at: Vec2,
+    pub then: PetState,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(n: f32) -> Duration {
+        Duration::from_secs_f32(n)
+    }
+
+    #[test]
+    fn the_rate_only_counts_the_window() {
+        let mut rate = TypingRate::default();
+        rate.record(secs(0.0), 10);
+        rate.record(secs(1.0), 15);
+        assert_eq!(rate.per_second, 5.0);
+        rate.record(secs(5.5), 0);
+        assert_eq!(rate.per_second, 3.0, "the first ten have aged out");
+        rate.record(secs(7.0), 0);
+        assert_eq!(rate.per_second, 0.0);
+    }
+
+    #[test]
+    fn typing_brings_a_pet_over_and_fury_makes_them_cheer_once() {
+        let config = TypingConfig::default();
+        let mut mood = TypingMood::default();
+        assert_eq!(mood.update(&config, 0.0, secs(0.0)), None);
+        assert_eq!(
+            mood.update(&config, 2.0, secs(1.0)),
+            Some(TypingReaction::Beside(PetState::Sitting))
+        );
+        assert_eq!(
+            mood.update(&config, 7.0, secs(2.0)),
+            Some(TypingReaction::Everyone(PetState::Jumping))
+        );
+        // Dipping just under the threshold is still furious.
+        assert_eq!(mood.update(&config, 5.0, secs(3.0)), None);
+        assert_eq!(mood.pace, Pace::Furious);
+        assert_eq!(mood.update(&config, 3.0, secs(4.0)), None);
+        assert_eq!(mood.pace, Pace::Typing);
+    }
+
+    #[test]
+    fn a_long_quiet_spell_after_typing_is_one_nap() {
+        let config = TypingConfig::default();
+        let mut mood = TypingMood::default();
+        // Never having typed is not a lull.
+        assert_eq!(mood.update(&config, 0.0, secs(600.0)), None);
+
+        mood.update(&config, 2.0, secs(601.0));
+        assert_eq!(mood.update(&config, 0.0, secs(610.0)), None);
+        assert_eq!(mood.update(&config, 0.0, secs(700.0)), None);
+        assert_eq!(
+            mood.update(&config, 0.0, secs(730.0)),
+            Some(TypingReaction::Everyone(PetState::Chilling))
+        );
+        assert_eq!(mood.update(&config, 0.0, secs(900.0)), None);
+    }
+
+    #[test]
+    fn a_companion_sits_on_its_own_side_of_the_cursor() {
+        let cursor = Vec2::new(100.0, 50.0);
+        assert_eq!(
+            beside_cursor(cursor, Vec2::new(-300.0, 0.0), 60.0),
+            Vec2::new(40.0, 50.0)
+        );
+        assert_eq!(
+            beside_cursor(cursor, Vec2::new(400.0, 0.0), 60.0),
+            Vec2::new(160.0, 50.0)
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! ECS glue for interrupt sources: polling them each frame and passing on
+//! what they raise.
+//!
+//! What a source may raise is decided in `core::interrupt`.
+
+use bevy::prelude::*;
+
+use crate::core::PetSystems;
+use crate::core::brain::{PetBrain, PetState};
+use crate::core::interrupt::{InterruptSource, PetSnapshot, Raised, admitted};
+use crate::pet::{MoveTarget, PendingInterrupt, Pet};
+
+/// Every source added, polled in the order they were.
+#[derive(Resource, Default)]
+struct InterruptSources(Vec<Box<dyn InterruptSource>>);
+
+/// Adds interrupt sources to an [`App`].
+pub trait AddInterruptSource {
+    /// Polls `source` once a frame for as long as the app runs.
+    fn add_interrupt_source(&mut self, source: impl InterruptSource) -> &mut Self;
+}
+
+impl AddInterruptSource for App {
+    fn add_interrupt_source(&mut self, source: impl InterruptSource) -> &mut Self {
+        self.init_resource::<InterruptSources>();
+        self.world_mut()
+            .resource_mut::<InterruptSources>()
+            .0
+            .push(Box::new(source));
+        self
+    }
+}
+
+pub struct InterruptPlugin;
+
+impl Plugin for InterruptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            poll_sources
+                .run_if(resource_exists::<InterruptSources>)
+                .after(PetSystems::Normalize)
+                .before(PetSystems::Brain),
+        );
+    }
+}
+
+fn poll_sources(
+    mut sources: ResMut<InterruptSources>,
+    mut pets: Query<
+        (
+            Entity,
+            &PetBrain,
+            &Transform,
+            &mut PendingInterrupt,
+            &mut MoveTarget,
+        ),
+        With<Pet>,
+    >,
+) {
+    let mut snapshots: Vec<PetSnapshot> = pets
+        .iter()
+        .map(|(pet, brain, transform, interrupt, _)| PetSnapshot {
+            pet,
+            state: brain.state,
+            position: transform.translation.truncate(),
+            locked: brain.locked,
+            pending: interrupt.0.is_some(),
+        })
+        .collect();
+
+    for source in &mut sources.0 {
+        let raised = source.poll(&snapshots);
+        for Raised {
+            pet,
+            state,
+            walk_to,
+        } in admitted(&snapshots, raised)
+        {
+            let Ok((.., mut interrupt, mut target)) = pets.get_mut(pet) else {
+                continue;
+            };
+            interrupt.0 = Some(state);
+            target.0 = walk_to.filter(|_| state == PetState::Walking);
+            // A later source sees this pet as spoken for.
+            if let Some(snapshot) = snapshots.iter_mut().find(|s| s.pet == pet) {
+                snapshot.pending = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! ECS glue for droppable items: spawning them, sending pets after them,
+//! using them up on arrival, and clearing away those nobody fetched.
+//!
+//! Who fetches what and when an errand is over are decided in `core::items`.
+//! The walk itself is the ordinary `Walking` state with a `MoveTarget`, so an
+//! errand steers, times out, and can be interrupted exactly like a summon.
+
+use bevy::prelude::*;
+
+use crate::config::Config;
+use crate::core::PetSystems;
+use crate::core::brain::{PetBrain, PetState};
+use crate::core::coords::SurfaceOrigin;
+use crate::core::items::{Errand, ErrandStep, Item, ItemKind, pick_fetcher, step_errand};
+use crate::core::rng::PetRng;
+use crate::core::timers::Summons;
+use crate::pet::{MoveTarget, PendingInterrupt, Pet};
+use crate::skin::Skin;
+
+/// Items are drawn behind every pet, whose draw order starts at zero.
+const ITEM_Z: f32 = -1.0;
+
+/// Fraction of the surface an item dropped "anywhere" lands within, matching
+/// how initial pets are scattered.
+const DROP_SCATTER: f32 = 0.25;
+
+/// Ask for an item to appear.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct DropItem {
+    pub kind: ItemKind,
+    /// Where to put it, or `None` for somewhere visible.
+    pub at: Option<Vec2>,
+    /// A pet to send straight to it, rather than whichever notices first.
+    pub for_pet: Option<Entity>,
+}
+
+pub struct ItemPlugin;
+
+impl Plugin for ItemPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<DropItem>().add_systems(
+            Update,
+            // Errands are checked before new ones are handed out: a claim
+            // made this frame has not entered `Walking` yet, and would read
+            // as abandoned.
+            (
+                run_errands,
+                spawn_dropped_items,
+                assign_errands,
+                clear_stale_items,
+            )
+                .chain()
+                .after(PetSystems::Normalize)
+                .before(PetSystems::Brain),
+        );
+    }
+}
+
+/// Sends `pet` walking to an item at `at`.
+fn send_on_errand(
+    commands: &mut Commands,
+    pet: Entity,
+    errand: Errand,
+    at: Vec2,
+    interrupt: &mut PendingInterrupt,
+    target: &mut MoveTarget,
+) {
+    target.0 = Some(at);
+    interrupt.0 = Some(PetState::Walking);
+    commands.entity(pet).insert(errand);
+}
+
+/// Spawns items on request. The single path by which an item comes to exist.
+// Bevy systems declare their dependencies as parameters; splitting this into a
+// SystemParam struct would hide them without reducing the coupling.
+#[allow(clippy::too_many_arguments)]
+fn spawn_dropped_items(
+    mut commands: Commands,
+    mut drops: MessageReader<DropItem>,
+    skin: Res<Skin>,
+    config: Res<Config>,
+    time: Res<Time>,
+    surface: Option<Res<SurfaceOrigin>>,
+    mut rng: ResMut<PetRng>,
+    mut pets: Query<(&mut PendingInterrupt, &mut MoveTarget), With<Pet>>,
+) {
+    for drop in drops.read() {
+        let Some(art) = skin.item(drop.kind) else {
+            warn!(
+                "skin {:?} has no {} to drop",
+                skin.geometry.name,
+                drop.kind.label().to_lowercase()
+            );
+            continue;
+        };
+
+        let at = drop.at.unwrap_or_else(|| {
+            let half = surface
+                .as_deref()
+                .map_or(Vec2::ZERO, |s| s.size * DROP_SCATTER);
+            rng.point_in(half)
+        });
+
+        let claimant = drop.for_pet.filter(|pet| pets.contains(*pet));
+        let item = commands
+            .spawn((
+                Item {
+                    kind: drop.kind,
+                    claimed_by: claimant,
+                    since: time.elapsed(),
+                },
+                Sprite::from_image(art.image.clone()),
+                Transform::from_translation(at.extend(ITEM_Z))
+                    .with_scale(Vec3::splat(config.scale.0)),
+            ))
+            .id();
+
+        if let Some(pet) = claimant
+            && let Ok((mut interrupt, mut target)) = pets.get_mut(pet)
+        {
+            let errand = Errand {
+                item,
+                then: art.reaction,
+            };
+            send_on_errand(&mut commands, pet, errand, at, &mut interrupt, &mut target);
+        }
+    }
+}
+
+type FetcherData<'a> = (
+    Entity,
+    &'a PetBrain,
+    Option<&'a Errand>,
+    Has<Summons>,
+    &'a Transform,
+    &'a mut PendingInterrupt,
+    &'a mut MoveTarget,
+);
+
+/// Hands each unclaimed item to the nearest free pet.
+fn assign_errands(
+    mut commands: Commands,
+    skin: Res<Skin>,
+    time: Res<Time>,
+    mut items: Query<(Entity, &mut Item, &Transform), Without<Pet>>,
+    mut pets: Query<FetcherData, With<Pet>>,
+) {
+    // A claim outlives its errand when the pet was interrupted, removed, or
+    // sent somewhere else. Releasing it here, in one place, covers every way
+    // that happens.
+    for (entity, mut item, _) in &mut items {
+        if let Some(pet) = item.claimed_by {
+            let still_going = pets
+                .get(pet)
+                .is_ok_and(|(_, _, errand, ..)| errand.is_some_and(|e| e.item == entity));
+            if !still_going {
+                item.claimed_by = None;
+                item.since = time.elapsed();
+            }
+        }
+    }
+
+    // Free means nothing else owns the pet: not held, not asleep for an
+    // absent user, not already reacting to something, not already fetching
+    // or delivering a timer.
+    let mut free: Vec<(Entity, Vec2)> = pets
+        .iter()
+        .filter(|(_, brain, errand, summons, _, interrupt, _)| {
+            errand.is_none() && !summons && !brain.locked && interrupt.0.is_none()
+        })
+        .map(|(pet, _, _, _, transform, ..)| (pet, transform.translation.truncate()))
+        .collect();
+
+    for (entity, mut item, transform) in &mut items {
+        if item.claimed_by.is_some() {
+            continue;
+        }
+        // The skin may have been switched since this was dropped; the reaction
+        // is the current skin's.
+        let Some(art) = skin.item(item.kind) else {
+            continue;
+        };
+        let at = transform.translation.truncate();
+        let Some(pet) = pick_fetcher(at, &free) else {
+            continue;
+        };
+        free.retain(|(other, _)| *other != pet);
+
+        if let Ok((.., mut interrupt, mut target)) = pets.get_mut(pet) {
+            item.claimed_by = Some(pet);
+            let errand = Errand {
+                item: entity,
+                then: art.reaction,
+            };
+            send_on_errand(&mut commands, pet, errand, at, &mut interrupt, &mut target);
+        }
+    }
+}
+
+type ErrandData<'a> = (
+    Entity,
+    &'a PetBrain,
+    &'a Errand,
+    &'a Transform,
+    &'a MoveTarget,
+    &'a mut PendingInterrupt,
+);
+
+/// Ends errands that arrived or were cut short.
+fn run_errands(
+    mut commands: Commands,
+    items: Query<&Transform, (With<Item>, Without<Pet>)>,
+    mut pets: Query<ErrandData, With<Pet>>,
+) {
+    for (pet, brain, errand, transform, target, mut interrupt) in &mut pets {
+        let Ok(item) = items.get(errand.item) else {
+            // Someone else's errand used it up first.
+            commands.entity(pet).remove::<Errand>();
+            continue;
+        };
+
+        // A pending interrupt is the state the pet is about to be in: the walk
+        // this errand started is still queued on its first frame, and anything
+        // else queued will replace it.
+        let state = interrupt.0.unwrap_or(brain.state);
+        let distance = transform
+            .translation
+            .truncate()
+            .distance(item.translation.truncate());
+
+        match step_errand(state, target.0.is_some(), distance) {
+            ErrandStep::Underway => {}
+            ErrandStep::Arrived => {
+                interrupt.0 = Some(errand.then);
+                commands.entity(errand.item).despawn();
+                commands.entity(pet).remove::<Errand>();
+            }
+            ErrandStep::Abandoned => {
+                commands.entity(pet).remove::<Errand>();
+            }
+        }
+    }
+}
+
+/// Clears away items that went unfetched too long, so one nobody can reach
+/// does not lie on the desktop for good.
+fn clear_stale_items(mut commands: Commands, time: Res<Time>, items: Query<(Entity, &Item)>) {
+    for (entity, item) in &items {
+        if item.stale(time.elapsed()) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
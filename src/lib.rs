@@ -0,0 +1,54 @@
+//! Batates, a desktop pet, as a library.
+//!
+//! The `batates` binary is [`run`] and nothing more. The rest of what is
+//! public here is for plugins that add behaviour of their own, passed to
+//! [`run`] and added after the built-in ones. Those are the extension points,
+//! and the only items this crate promises to keep:
+//!
+//! - [`PetSystems`], the order of one frame of simulation. Anything that
+//!   changes what pets do runs after `Normalize` and before `Brain`.
+//! - [`Intent`], what the user did this frame, as a message to read.
+//! - [`PendingInterrupt`], the state a pet enters next frame, whatever it is
+//!   doing. Writing it is how every reaction in the app works.
+//! - [`MoveTarget`], where a walking pet is heading. Set it together with a
+//!   `Walking` interrupt to send a pet somewhere.
+//! - [`InterruptSource`], for a source that only needs to look at the pets
+//!   and say what they should do, added with
+//!   [`add_interrupt_source`](AddInterruptSource::add_interrupt_source).
+//!
+//! A pet's [`PetBrain`] is read-only outside the crate: [`PetBrain::state`]
+//! is what it is doing, and a pet that is [`PetBrain::locked`] is held by the
+//! user or asleep while they are away. The built-in reactions leave a locked
+//! pet alone, and so should yours.
+//! `examples/zoomies.rs` is a complete plugin.
+
+mod camera;
+mod cli;
+mod config;
+mod core;
+mod interrupts;
+mod items;
+mod load;
+mod menu;
+mod notify;
+mod pet;
+mod platform;
+mod script;
+mod shell;
+mod shortcuts;
+mod skin;
+mod sound;
+mod speech;
+mod timers;
+mod typing;
+
+pub use crate::cli::run;
+pub use crate::core::PetSystems;
+pub use crate::core::brain::{PetBrain, PetState};
+pub use crate::core::coords::World2d;
+pub use crate::core::input::Intent;
+pub use crate::core::interrupt::{InterruptSource, PetSnapshot, Raised};
+pub use crate::core::menu::PetAction;
+pub use crate::interrupts::AddInterruptSource;
+pub use crate::pet::{MoveTarget, Paused, PendingInterrupt, Pet, StateEntered};
+pub use crate::speech::Say;
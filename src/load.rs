@@ -0,0 +1,98 @@
+//! ECS glue for system load: sampling the machine, and pets reacting to it.
+//!
+//! What each reading means is the skin's, through its load rules, and how a
+//! rule holds is decided in `core::load`. The brain reads the bias that comes
+//! out of it from [`LoadMood`]; states a rule enters are sent as interrupts.
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::core::PetSystems;
+use crate::core::brain::PetBrain;
+use crate::core::load::{CpuTimes, LoadMood, SystemLoad};
+use crate::pet::{PendingInterrupt, Pet};
+use crate::platform::load;
+use crate::skin::Skin;
+
+/// How often the machine is sampled. CPU usage is averaged over the gap, so
+/// much shorter would read every compile step as a spike.
+const SAMPLE_EVERY: Duration = Duration::from_secs(2);
+
+pub struct LoadPlugin;
+
+impl Plugin for LoadPlugin {
+    fn build(&self, app: &mut App) {
+        // `LoadMood` is the pet plugin's, since the brain always reads it.
+        app.init_resource::<SystemLoad>().add_systems(
+            Update,
+            (sample_load, react_to_load)
+                .chain()
+                .after(PetSystems::Normalize)
+                .before(PetSystems::Brain),
+        );
+    }
+}
+
+/// Takes a sample every [`SAMPLE_EVERY`] while enabled. Turned off, the load
+/// reads as unknown, so no rule holds.
+fn sample_load(
+    config: Res<Config>,
+    time: Res<Time>,
+    mut load: ResMut<SystemLoad>,
+    mut last_sample: Local<Option<Duration>>,
+    mut last_cpu: Local<Option<CpuTimes>>,
+) {
+    if !config.system_load {
+        *last_sample = None;
+        *last_cpu = None;
+        load.set_if_neq(SystemLoad::default());
+        return;
+    }
+    let now = time.elapsed();
+    if last_sample.is_some_and(|at| now - at < SAMPLE_EVERY) {
+        return;
+    }
+    *last_sample = Some(now);
+
+    let reading = load::read();
+    // The first sample has nothing to compare with, so CPU stays unknown
+    // until the second.
+    let cpu = reading
+        .cpu
+        .zip(*last_cpu)
+        .and_then(|(cpu, earlier)| cpu.usage_since(earlier));
+    *last_cpu = reading.cpu;
+    load.set_if_neq(SystemLoad {
+        cpu,
+        memory: reading.memory,
+        battery: reading.battery,
+    });
+}
+
+/// Refreshes the mood when the load or the skin changes, and sends every pet
+/// that is free to react into the states rules enter.
+fn react_to_load(
+    load: Res<SystemLoad>,
+    skin: Res<Skin>,
+    mut mood: ResMut<LoadMood>,
+    mut pets: Query<(&PetBrain, &mut PendingInterrupt), With<Pet>>,
+) {
+    if skin.is_changed() {
+        // A new skin's rules are numbered afresh; nothing of the old holds.
+        *mood = LoadMood::default();
+    } else if !load.is_changed() {
+        return;
+    }
+    let entered = mood.update(&skin.geometry.load, &load);
+    let Some(&state) = entered.first() else {
+        return;
+    };
+    // A held pet is the user's and a sleeping one stays asleep; a pet already
+    // about to react to something finishes that first.
+    for (brain, mut interrupt) in &mut pets {
+        if !brain.locked && interrupt.0.is_none() {
+            interrupt.0 = Some(state);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Draws the pet context menu with Bevy UI.
+//!
+//! Which menu is open, where its items sit, and what a click on one means are
+//! all decided in `core::menu` and the pet systems. This only mirrors the open
+//! menu as UI nodes, so it never needs Bevy's own picking, which a
+//! click-through overlay would not receive anyway.
+
+use bevy::prelude::*;
+
+use crate::core::coords::{SurfaceOrigin, World2d, world_to_surface};
+use crate::core::menu::{ITEM_SIZE, PetMenu, menu_layout};
+use crate::pet::Pet;
+
+const BACKGROUND: Color = Color::srgba(0.12, 0.12, 0.14, 0.92);
+const LABEL: Color = Color::srgb(0.95, 0.95, 0.95);
+const FONT_SIZE: f32 = 14.0;
+const LABEL_INSET: f32 = 8.0;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, (close_orphaned_menu, draw_menu).chain());
+    }
+}
+
+/// The root node of the drawn menu.
+#[derive(Component)]
+struct MenuRoot;
+
+/// Closes a menu whose pet has gone, whichever way it went.
+fn close_orphaned_menu(mut menu: ResMut<PetMenu>, pets: Query<(), With<Pet>>) {
+    if let Some(open) = menu.0
+        && !pets.contains(open.pet)
+    {
+        menu.0 = None;
+    }
+}
+
+/// Redraws the menu whenever it opens, moves, or closes.
+fn draw_menu(
+    mut commands: Commands,
+    menu: Res<PetMenu>,
+    surface: Option<Res<SurfaceOrigin>>,
+    drawn: Query<Entity, With<MenuRoot>>,
+) {
+    if !menu.is_changed() {
+        return;
+    }
+    for root in &drawn {
+        commands.entity(root).despawn();
+    }
+
+    let (Some(open), Some(surface)) = (menu.0, surface) else {
+        return;
+    };
+    let items = menu_layout(open.anchor, surface.size);
+    let Some((_, first)) = items.first() else {
+        return;
+    };
+
+    // UI is laid out from the window's top-left in logical pixels, which is
+    // exactly surface space.
+    let top_left = world_to_surface(World2d(Vec2::new(first.min.x, first.max.y)), surface.size);
+
+    commands
+        .spawn((
+            MenuRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(top_left.0.x),
+                top: Val::Px(top_left.0.y),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(BACKGROUND),
+        ))
+        .with_children(|root| {
+            for (action, _) in &items {
+                root.spawn(Node {
+                    width: Val::Px(ITEM_SIZE.x),
+                    height: Val::Px(ITEM_SIZE.y),
+                    align_items: AlignItems::Center,
+                    padding: UiRect::horizontal(Val::Px(LABEL_INSET)),
+                    ..default()
+                })
+                .with_child((
+                    Text::new(action.label()),
+                    TextFont::from_font_size(FONT_SIZE),
+                    TextColor(LABEL),
+                ));
+            }
+        });
+}
@@ -0,0 +1,76 @@
+//! ECS glue for desktop notifications: a pet noticing one.
+//!
+//! Which notifications get a reaction is decided in `core::notify`, and hearing
+//! them is the platform's. Where the platform cannot listen there is no source,
+//! and nothing here runs.
+
+use bevy::prelude::*;
+
+use crate::config::Config;
+use crate::core::PetSystems;
+use crate::core::brain::PetBrain;
+use crate::core::notify::NotificationSource;
+use crate::pet::{PendingInterrupt, Pet};
+use crate::platform::notifications;
+use crate::speech::Say;
+
+/// Where notifications come from, for as long as the app listens.
+#[derive(Resource)]
+struct Notifications(Box<dyn NotificationSource>);
+
+pub struct NotifyPlugin;
+
+impl Plugin for NotifyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_listening).add_systems(
+            Update,
+            notice
+                .run_if(resource_exists::<Notifications>)
+                .after(PetSystems::Normalize)
+                .before(PetSystems::Brain),
+        );
+    }
+}
+
+fn start_listening(mut commands: Commands, config: Res<Config>) {
+    if !config.notifications.enabled {
+        return;
+    }
+    if let Some(source) = notifications::listen() {
+        commands.insert_resource(Notifications(source));
+    }
+}
+
+/// Has the oldest pet react to each notification that gets a reaction.
+///
+/// Always the same pet, as for `--say`, so a burst of notifications is one
+/// excited pet rather than the whole group taking turns. A pet that is held,
+/// or already about to react to something, only says the summary.
+fn notice(
+    mut source: ResMut<Notifications>,
+    config: Res<Config>,
+    mut says: MessageWriter<Say>,
+    mut pets: Query<(Entity, &PetBrain, &Transform, &mut PendingInterrupt), With<Pet>>,
+) {
+    for notification in source.0.poll() {
+        let Some(reaction) = config.notifications.reaction(&notification) else {
+            continue;
+        };
+        // Draw order is spawn order, so the lowest is the oldest pet.
+        let Some((pet, brain, _, mut interrupt)) = pets
+            .iter_mut()
+            .min_by(|a, b| a.2.translation.z.total_cmp(&b.2.translation.z))
+        else {
+            continue;
+        };
+        if !brain.locked && interrupt.0.is_none() {
+            interrupt.0 = Some(reaction.state);
+        }
+        if let Some(text) = reaction.say {
+            says.write(Say {
+                pet: Some(pet),
+                text,
+            });
+        }
+    }
+}
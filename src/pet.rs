@@ -5,28 +5,46 @@
 //! branching on pet state.
 
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
 use std::time::Duration;
 
 use crate::config::{Config, paths};
 use crate::core::PetSystems;
-use crate::core::animation::{AnimationCursor, atlas_index, step_animation};
+use crate::core::animation::{
+    AnimationCursor, FrameTiming, atlas_index, sheet_row, step_animation,
+};
+use crate::core::behaviour::{Goal, TreeCursor};
 use crate::core::brain::{
-    BrainStep, Locomotion, PetBrain, PetState, StateTable, plan_duration, step_brain,
+    BrainInputs, BrainStep, Locomotion, PetBrain, PetState, Playback, StateTable, plan_duration,
+    step_brain,
 };
-use crate::core::coords::{SurfaceOrigin, surface_to_world};
+use crate::core::coords::{SurfaceOrigin, World2d, surface_to_world};
 #[cfg(target_os = "linux")]
 use crate::core::hitbox::aggregate_input_region;
-use crate::core::hitbox::{pet_rect_world, pick_topmost};
+use crate::core::hitbox::{PetShape, pet_rect_world, pick_topmost};
+use crate::core::idle::{IdleConfig, Presence, UserActivity, step_presence};
 use crate::core::input::{
-    GestureConfig, GestureState, Intent, InteractionTier, PointerAt, PointerSample,
+    ButtonMask, GestureConfig, GestureState, Intent, InteractionTier, PointerAt, PointerSample,
+    rescaled,
+};
+use crate::core::items::{Errand, ItemKind};
+use crate::core::load::LoadMood;
+use crate::core::menu::{OpenMenu, PetAction, PetMenu, item_at, menu_layout};
+use crate::core::movement::{
+    Facing, FollowConfig, FollowStep, Following, Heading, facing_from_velocity, follow_step,
+    heading_from_velocity, steer_toward, travel_time,
 };
-use crate::core::movement::{Facing, facing_from_velocity, steer_toward, travel_time};
 use crate::core::rng::PetRng;
+use crate::core::speech::{Speech, bubble_at};
+use crate::core::timers::Summons;
+use crate::core::touch::{TouchPhase, TouchSample, TouchState};
+use crate::items::DropItem;
 use crate::skin::{Skin, load_or_builtin};
 
 /// Everything one pet's brain tick touches. Named because the tuple is long
 /// enough that spelling it inline obscures the system's signature.
 type BrainTickData<'a> = (
+    Entity,
     &'a mut PetBrain,
     &'a mut AnimationCursor,
     &'a mut PendingInterrupt,
@@ -73,15 +91,30 @@ pub struct MoveTarget(pub Option<Vec2>);
 #[derive(Component, Debug, Default)]
 pub struct PendingInterrupt(pub Option<PetState>);
 
+/// Freezes every pet where it is.
+///
+/// Input is still read while paused, so a pet can be dragged somewhere and
+/// whatever the user did takes effect on resume.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Paused(pub bool);
+
 pub struct PetPlugin;
 
 impl Plugin for PetPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<GestureState>()
+            .init_resource::<TouchState>()
+            .init_resource::<Presence>()
+            .init_resource::<LoadMood>()
+            .init_resource::<Paused>()
+            .init_resource::<PetMenu>()
             .add_message::<PointerSample>()
+            .add_message::<TouchSample>()
+            .add_message::<UserActivity>()
             .add_message::<Intent>()
             .add_message::<SpawnPet>()
             .add_message::<DespawnPet>()
+            .add_message::<StateEntered>()
             .add_systems(PreStartup, setup_from_config)
             .add_systems(Startup, request_initial_pets)
             .add_systems(Update, (spawn_requested_pets, despawn_requested_pets))
@@ -98,12 +131,36 @@ impl Plugin for PetPlugin {
                 )
                     .chain(),
             )
-            .add_systems(Update, normalize_input.in_set(PetSystems::Normalize))
+            .configure_sets(
+                Update,
+                (
+                    PetSystems::Brain,
+                    PetSystems::Enter,
+                    PetSystems::Locomote,
+                    PetSystems::Integrate,
+                    PetSystems::Animate,
+                )
+                    .run_if(resource_equals(Paused(false))),
+            )
+            .add_systems(Update, apply_settings.before(PetSystems::Sample))
             .add_systems(
                 Update,
-                apply_intents
+                (normalize_input, normalize_touch, track_presence).in_set(PetSystems::Normalize),
+            )
+            .add_systems(
+                Update,
+                (apply_intents, toggle_following)
+                    .in_set(PetSystems::Normalize)
+                    .after(normalize_input)
+                    .after(normalize_touch)
+                    .after(track_presence),
+            )
+            .add_systems(
+                Update,
+                follow_cursor
                     .in_set(PetSystems::Normalize)
-                    .after(normalize_input),
+                    .after(apply_intents)
+                    .after(toggle_following),
             )
             .add_systems(Update, brain_tick.in_set(PetSystems::Brain))
             .add_systems(Update, locomote.in_set(PetSystems::Locomote))
@@ -136,6 +193,14 @@ pub struct DespawnPet {
     pub pet: Entity,
 }
 
+/// A pet entered a state, whether its timer ran out or something interrupted
+/// it.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct StateEntered {
+    pub pet: Entity,
+    pub state: PetState,
+}
+
 /// Turns config into the resources the rest of the app reads.
 ///
 /// Runs in `PreStartup` so everything exists before the first pet spawns.
@@ -144,32 +209,102 @@ fn setup_from_config(
     config: Res<Config>,
     mut images: ResMut<Assets<Image>>,
     mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut audio: ResMut<Assets<AudioSource>>,
 ) {
     let source = config.skin_source(&paths::skins_dir());
-    let (skin, table) = load_or_builtin(&source, &mut images, &mut layouts);
+    let (skin, table) = load_or_builtin(&source, &mut images, &mut layouts, &mut audio);
     info!(
         "loaded skin {:?} ({} columns)",
         skin.geometry.name,
         skin.columns()
     );
 
-    // Click-to-summon needs a global cursor, which only some backends have.
-    // The config can turn it off, but cannot turn it on where it cannot work:
-    // Wayland only ever sees the pointer over our own surface.
-    #[cfg(target_os = "linux")]
-    let tier = InteractionTier::PetOnly;
-    #[cfg(not(target_os = "linux"))]
-    let tier = if config.click_to_summon {
-        InteractionTier::ClickToSummon
-    } else {
-        InteractionTier::PetOnly
-    };
-
     commands.insert_resource(skin);
     commands.insert_resource(table);
     commands.insert_resource(PetRng::from_seed(config.seed));
     commands.insert_resource(config.gestures);
-    commands.insert_resource(tier);
+    commands.insert_resource(config.follow);
+    commands.insert_resource(config.idle);
+    commands.insert_resource(interaction_tier(&config));
+}
+
+/// What the pointer can do under `config` on this platform.
+///
+/// Click-to-summon needs a global cursor, which only some backends have. The
+/// config can turn it off, but cannot turn it on where it cannot work: Wayland
+/// only ever sees the pointer over our own surface.
+fn interaction_tier(config: &Config) -> InteractionTier {
+    if cfg!(target_os = "linux") || !config.click_to_summon {
+        InteractionTier::PetOnly
+    } else {
+        InteractionTier::ClickToSummon
+    }
+}
+
+/// Applies settings changed while running, such as from the tray.
+///
+/// Diffs against the config last applied, so only what actually changed is
+/// redone: reloading a skin restarts every animation and is not free.
+// Bevy systems declare their dependencies as parameters; see normalize_input.
+#[allow(clippy::too_many_arguments)]
+fn apply_settings(
+    config: Res<Config>,
+    mut applied: Local<Option<Config>>,
+    mut tier: ResMut<InteractionTier>,
+    mut skin: ResMut<Skin>,
+    mut table: ResMut<StateTable>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut audio: ResMut<Assets<AudioSource>>,
+    mut pets: Query<
+        (
+            &mut Transform,
+            &mut Sprite,
+            &mut Anchor,
+            &mut AnimationCursor,
+            &mut PetBrain,
+        ),
+        With<Pet>,
+    >,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    // The first run sees the config `setup_from_config` already applied.
+    let Some(previous) = applied.replace(config.clone()) else {
+        return;
+    };
+
+    tier.set_if_neq(interaction_tier(&config));
+
+    if previous.scale != config.scale {
+        for (mut transform, ..) in &mut pets {
+            transform.scale = Vec3::splat(config.scale.0);
+        }
+    }
+
+    if previous.skin != config.skin {
+        let source = config.skin_source(&paths::skins_dir());
+        let (loaded, loaded_table) =
+            load_or_builtin(&source, &mut images, &mut layouts, &mut audio);
+        info!("switched to skin {:?}", loaded.geometry.name);
+
+        // Written in place rather than through `Commands` so no system this
+        // frame pairs the new sprites with the old sheet's column count.
+        *skin = loaded;
+        *table = loaded_table;
+        for (_, mut sprite, mut anchor, mut cursor, mut brain) in &mut pets {
+            // Frame counts differ between skins, so the old frame may not exist.
+            // Nor may the clip, or the pet's place in a behaviour tree: the new
+            // skin's are numbered afresh.
+            cursor.restart();
+            brain.clip = None;
+            brain.plan = TreeCursor::default();
+            let index = atlas_index(brain.state.row(), skin.columns(), &cursor);
+            let flip_x = sprite.flip_x;
+            skin.show(index, flip_x, &mut sprite, &mut anchor);
+        }
+    }
 }
 
 /// Requests the configured number of pets.
@@ -214,6 +349,14 @@ fn spawn_requested_pets(
         let state = PetState::Chilling;
         let cursor = AnimationCursor::default();
         let planned = plan_duration(table.get(state), &mut rng);
+        let mut sprite = Sprite::default();
+        let mut anchor = Anchor::default();
+        skin.show(
+            atlas_index(state.row(), skin.columns(), &cursor),
+            false,
+            &mut sprite,
+            &mut anchor,
+        );
 
         commands.spawn((
             Pet,
@@ -223,15 +366,12 @@ fn spawn_requested_pets(
             MoveTarget::default(),
             PendingInterrupt::default(),
             Facing::default(),
-            Sprite::from_atlas_image(
-                skin.image.clone(),
-                TextureAtlas {
-                    layout: skin.layout.clone(),
-                    index: atlas_index(state, skin.columns(), &cursor),
-                },
-            ),
-            // Scale is set once here and never written again: the old code drove
-            // facing through scale.x, which broke both the size and the hitbox.
+            Heading::default(),
+            sprite,
+            anchor,
+            // Scale is only ever the pet's size: set here, and changed only by
+            // the config or a rescale gesture. The old code drove facing
+            // through scale.x, which broke both the size and the hitbox.
             Transform::from_translation(request.at.extend(next_order as f32))
                 .with_scale(Vec3::splat(config.scale.0)),
         ));
@@ -256,9 +396,17 @@ fn normalize_input(
     tier: Res<InteractionTier>,
     surface: Option<Res<SurfaceOrigin>>,
     skin: Res<Skin>,
-    pets: Query<(Entity, &Transform), With<Pet>>,
+    mut menu: ResMut<PetMenu>,
+    pets: Query<(Entity, &Transform, &Sprite, Option<&Speech>), With<Pet>>,
 ) {
     let Some(surface) = surface else { return };
+    let bubbles: Vec<(Entity, f32, Rect)> = pets
+        .iter()
+        .filter_map(|(pet, transform, _, speech)| {
+            let rect = speech_rect(&skin, transform, speech?, surface.size);
+            Some((pet, transform.translation.z, rect))
+        })
+        .collect();
 
     for sample in samples.read() {
         let cursor_world = match sample.at {
@@ -268,17 +416,26 @@ fn normalize_input(
             _ => None,
         };
 
-        let candidates: Vec<(Entity, f32, Rect)> = pets
+        // Either button: a right press elsewhere closes an open menu as a
+        // left one does, rather than opening a second or leaving it up.
+        let pressed = |button| !gesture.buttons.contains(button) && sample.buttons.contains(button);
+        if (pressed(ButtonMask::LEFT) || pressed(ButtonMask::RIGHT))
+            && let Some(claimed) = claim_press(cursor_world, &mut menu, &bubbles, surface.size)
+        {
+            intents.write_batch(claimed);
+            // Recorded as held, so the matching release is not a click either.
+            gesture.buttons = sample.buttons;
+            gesture.cursor = cursor_world;
+            continue;
+        }
+
+        let candidates: Vec<(Entity, f32, PetShape)> = pets
             .iter()
-            .map(|(entity, transform)| {
+            .map(|(entity, transform, sprite, _)| {
                 (
                     entity,
                     transform.translation.z,
-                    pet_rect_world(
-                        transform.translation.truncate(),
-                        skin.frame_size(),
-                        transform.scale.x,
-                    ),
+                    pet_shape(&skin, transform, sprite),
                 )
             })
             .collect();
@@ -294,9 +451,139 @@ fn normalize_input(
     }
 }
 
+/// Touch points become intents, the same ones the mouse produces.
+// Bevy systems declare their dependencies as parameters; see normalize_input.
+#[allow(clippy::too_many_arguments)]
+fn normalize_touch(
+    mut samples: MessageReader<TouchSample>,
+    mut intents: MessageWriter<Intent>,
+    mut touches: ResMut<TouchState>,
+    cfg: Res<GestureConfig>,
+    time: Res<Time>,
+    surface: Option<Res<SurfaceOrigin>>,
+    skin: Res<Skin>,
+    mut menu: ResMut<PetMenu>,
+    pets: Query<(Entity, &Transform, &Sprite, Option<&Speech>), With<Pet>>,
+) {
+    let Some(surface) = surface else { return };
+    touches.retain_pets(|pet| pets.contains(pet));
+    let bubbles: Vec<(Entity, f32, Rect)> = pets
+        .iter()
+        .filter_map(|(pet, transform, _, speech)| {
+            let rect = speech_rect(&skin, transform, speech?, surface.size);
+            Some((pet, transform.translation.z, rect))
+        })
+        .collect();
+    let candidates: Vec<(Entity, f32, PetShape)> = pets
+        .iter()
+        .map(|(entity, transform, sprite, _)| {
+            (
+                entity,
+                transform.translation.z,
+                pet_shape(&skin, transform, sprite),
+            )
+        })
+        .collect();
+
+    for sample in samples.read() {
+        let at = match sample.at {
+            PointerAt::Surface(p) => Some(surface_to_world(p, surface.size)),
+            _ => None,
+        };
+        // A finger claimed by a menu or a bubble is never tracked, so lifting
+        // it does nothing more.
+        if sample.phase == TouchPhase::Down
+            && let Some(claimed) = claim_press(at, &mut menu, &bubbles, surface.size)
+        {
+            intents.write_batch(claimed);
+            continue;
+        }
+        let hit = at.and_then(|p| pick_topmost(&candidates, p));
+        intents.write_batch(touches.touch(sample.id, sample.phase, at, hit, sample.at_time));
+    }
+    intents.write_batch(touches.hold(time.elapsed(), &cfg));
+}
+
+/// What a press at `at` does when it lands on something drawn over the pets.
+///
+/// While a menu is open, a press of either button belongs to it: on an item
+/// it chooses that item, anywhere else it only closes the menu, as a desktop
+/// context menu would. A bubble is drawn over whatever is behind it, so a press on one only
+/// dismisses it, rather than grabbing a pet underneath. `None` when the press
+/// is left to the pets.
+fn claim_press(
+    at: Option<World2d>,
+    menu: &mut PetMenu,
+    bubbles: &[(Entity, f32, Rect)],
+    surface_size: Vec2,
+) -> Option<Option<Intent>> {
+    if let Some(open) = menu.0.take() {
+        let chosen = at.and_then(|p| item_at(&menu_layout(open.anchor, surface_size), p));
+        return Some(chosen.map(|action| Intent::Act {
+            pet: open.pet,
+            action,
+        }));
+    }
+    let pet = at.and_then(|p| bubble_at(bubbles, p))?;
+    Some(Some(Intent::DismissSpeech { pet }))
+}
+
+/// A pet's box and the frame it is showing right now.
+fn pet_shape<'a>(skin: &'a Skin, transform: &Transform, sprite: &Sprite) -> PetShape<'a> {
+    PetShape {
+        rect: pet_rect_world(
+            transform.translation.truncate(),
+            skin.frame_size(),
+            transform.scale.x,
+        ),
+        mask: skin.mask(sprite),
+        flip_x: sprite.flip_x,
+    }
+}
+
+/// Where `speech` is drawn over the pet at `transform`.
+pub(crate) fn speech_rect(
+    skin: &Skin,
+    transform: &Transform,
+    speech: &Speech,
+    surface_size: Vec2,
+) -> Rect {
+    let pet = pet_rect_world(
+        transform.translation.truncate(),
+        skin.frame_size(),
+        transform.scale.x,
+    );
+    speech.rect(pet, surface_size)
+}
+
+/// Idle readings become presence changes, and those become intents.
+fn track_presence(
+    mut activity: MessageReader<UserActivity>,
+    mut presence: ResMut<Presence>,
+    idle: Res<IdleConfig>,
+    mut intents: MessageWriter<Intent>,
+) {
+    for reading in activity.read() {
+        let (next, intent) = step_presence(*presence, *reading, idle.away_after);
+        // Compared first so change detection only fires on a real transition.
+        presence.set_if_neq(next);
+        if let Some(intent) = intent {
+            intents.write(intent);
+        }
+    }
+}
+
 /// Intents become per-pet interrupts and drag positions.
+// Bevy systems declare their dependencies as parameters; see normalize_input.
+#[allow(clippy::too_many_arguments)]
 fn apply_intents(
     mut intents: MessageReader<Intent>,
+    config: Res<Config>,
+    idle: Res<IdleConfig>,
+    skin: Res<Skin>,
+    mut menu: ResMut<PetMenu>,
+    mut despawns: MessageWriter<DespawnPet>,
+    mut drops: MessageWriter<DropItem>,
     mut pets: Query<
         (
             Entity,
@@ -328,6 +615,12 @@ fn apply_intents(
                     interrupt.0 = Some(PetState::Walking);
                 }
             }
+            Intent::SummonPet { pet, to } => {
+                if let Ok((_, mut interrupt, _, mut target)) = pets.get_mut(pet) {
+                    target.0 = Some(to.0);
+                    interrupt.0 = Some(PetState::Walking);
+                }
+            }
             Intent::Grab { pet, .. } => {
                 if let Ok((_, mut interrupt, _, _)) = pets.get_mut(pet) {
                     interrupt.0 = Some(PetState::Dragged);
@@ -354,6 +647,170 @@ fn apply_intents(
                     interrupt.0 = Some(PetState::Jumping);
                 }
             }
+            Intent::ContextMenu { pet, at } => {
+                menu.0 = Some(OpenMenu { pet, anchor: at });
+            }
+            // The speech plugin owns bubbles.
+            Intent::DismissSpeech { .. } => {}
+            Intent::Act { pet, action } => {
+                // Feeding and playing put something down beside the pet for it
+                // to go and get, when the skin has one to put down.
+                let item = match action {
+                    PetAction::Feed => Some(ItemKind::Food),
+                    PetAction::Play => Some(ItemKind::Ball),
+                    _ => None,
+                };
+                if let Some(kind) = item.filter(|kind| skin.item(*kind).is_some())
+                    && let Ok((_, _, transform, _)) = pets.get(pet)
+                {
+                    let here = transform.translation.truncate();
+                    // Towards the middle, so a pet at the edge does not get its
+                    // food off-screen.
+                    let side = if here.x > 0.0 { -1.0 } else { 1.0 };
+                    let reach = skin.frame_size().x * transform.scale.x;
+                    drops.write(DropItem {
+                        kind,
+                        at: Some(here + Vec2::X * side * reach),
+                        for_pet: Some(pet),
+                    });
+                    continue;
+                }
+
+                let reaction = match action {
+                    PetAction::Feed => PetState::Eating,
+                    PetAction::Pet => PetState::SendingLove,
+                    PetAction::Play => PetState::Jumping,
+                    PetAction::Sleep => idle.asleep,
+                    PetAction::Follow => continue,
+                    PetAction::Remove => {
+                        // As from the tray: the last pet stays.
+                        if pets.contains(pet) && pets.iter().count() > 1 {
+                            despawns.write(DespawnPet { pet });
+                        }
+                        continue;
+                    }
+                };
+                if let Ok((_, mut interrupt, _, mut target)) = pets.get_mut(pet) {
+                    target.0 = None;
+                    interrupt.0 = Some(reaction);
+                }
+            }
+            Intent::Rescale { pet, grow } => {
+                if let Ok((_, _, mut transform, _)) = pets.get_mut(pet) {
+                    let scale = rescaled(transform.scale.x, config.scale.0, grow);
+                    transform.scale = Vec3::splat(scale);
+                }
+            }
+            Intent::UserAway => {
+                for (_, mut interrupt, _, mut target) in &mut pets {
+                    // A pet mid-walk would otherwise finish the trip in its
+                    // sleep once the user comes back.
+                    target.0 = None;
+                    interrupt.0 = Some(idle.asleep);
+                }
+            }
+            Intent::UserReturned => {
+                for (_, mut interrupt, ..) in &mut pets {
+                    interrupt.0 = Some(idle.greeting);
+                }
+            }
+        }
+    }
+}
+
+/// Starts or stops a pet following the cursor.
+///
+/// Separate from `apply_intents` because it adds and removes a component
+/// rather than writing to one.
+fn toggle_following(
+    mut commands: Commands,
+    mut intents: MessageReader<Intent>,
+    tier: Res<InteractionTier>,
+    pets: Query<Has<Following>, With<Pet>>,
+) {
+    for intent in intents.read() {
+        let Intent::Act {
+            pet,
+            action: PetAction::Follow,
+        } = *intent
+        else {
+            continue;
+        };
+        let Ok(following) = pets.get(pet) else {
+            continue;
+        };
+
+        if following {
+            commands.entity(pet).remove::<Following>();
+        } else if *tier == InteractionTier::ClickToSummon {
+            commands.entity(pet).insert(Following);
+        } else {
+            warn!(
+                "following needs the cursor's position everywhere on screen, but this \
+                 session only reports it over the pet itself (Wayland always; elsewhere, \
+                 when click_to_summon is off)"
+            );
+        }
+    }
+}
+
+type FollowerData<'a> = (
+    Entity,
+    &'a PetBrain,
+    &'a Transform,
+    &'a mut PendingInterrupt,
+    &'a mut MoveTarget,
+);
+type FollowerFilter = (
+    With<Pet>,
+    With<Following>,
+    Without<Errand>,
+    Without<Summons>,
+);
+
+/// Keeps each follower walking after the cursor.
+fn follow_cursor(
+    mut commands: Commands,
+    tier: Res<InteractionTier>,
+    follow: Res<FollowConfig>,
+    gesture: Res<GestureState>,
+    // A follower sent for an item or with a timer to deliver finishes that
+    // first, then picks up again.
+    mut pets: Query<FollowerData, FollowerFilter>,
+) {
+    // Turning click-to-summon off takes the global cursor away with it.
+    if *tier != InteractionTier::ClickToSummon {
+        for (pet, ..) in &pets {
+            info!("no longer following the cursor: it is only tracked over the pet now");
+            commands.entity(pet).remove::<Following>();
+        }
+        return;
+    }
+    let Some(cursor) = gesture.cursor else { return };
+
+    for (_, brain, transform, mut interrupt, mut target) in &mut pets {
+        // Held, asleep, or already told to do something else this frame.
+        if brain.locked || interrupt.0.is_some() {
+            continue;
+        }
+        let walking = brain.state == PetState::Walking;
+        match follow_step(
+            transform.translation.truncate(),
+            cursor.0,
+            walking,
+            follow.distance,
+        ) {
+            FollowStep::Walk(to) => {
+                target.0 = Some(to);
+                if !walking {
+                    interrupt.0 = Some(PetState::Walking);
+                }
+            }
+            FollowStep::Settle => {
+                target.0 = None;
+                interrupt.0 = Some(PetState::Idle);
+            }
+            FollowStep::Stay => {}
         }
     }
 }
@@ -362,38 +819,62 @@ fn apply_intents(
 ///
 /// Entry handling lives here rather than in a separate system so the cursor and
 /// the target can never be one frame out of step with the brain.
+// Bevy systems declare their dependencies as parameters; see normalize_input.
+#[allow(clippy::too_many_arguments)]
 fn brain_tick(
     time: Res<Time>,
     table: Res<StateTable>,
     skin: Res<Skin>,
-    config: Res<Config>,
+    idle: Res<IdleConfig>,
+    presence: Res<Presence>,
+    mood: Res<LoadMood>,
     mut rng: ResMut<PetRng>,
     surface: Option<Res<SurfaceOrigin>>,
     mut pets: Query<BrainTickData, With<Pet>>,
+    mut entries: MessageWriter<StateEntered>,
 ) {
     let dt = time.delta();
     let frame_size = skin.frame_size();
-    for (mut brain, mut cursor, mut interrupt, mut target, mut velocity, transform) in &mut pets {
+    for (pet, mut brain, mut cursor, mut interrupt, mut target, mut velocity, transform) in
+        &mut pets
+    {
         let def = table.get(brain.state);
         // Entering a walk always assigns a target, so its absence means
         // `locomote` cleared it on arrival.
-        let arrived = target.0.is_none();
-        let step = step_brain(
-            &mut brain,
-            def,
-            interrupt.0.take(),
-            cursor.finished,
-            arrived,
+        let inputs = BrainInputs {
+            interrupt: interrupt.0.take(),
+            playback_finished: cursor.finished,
+            locomotion_finished: target.0.is_none(),
             dt,
-            &mut rng,
-        );
+        };
+        let step = step_brain(&mut brain, def, &mood.bias, table.tree(), inputs, &mut rng);
 
-        let BrainStep::Enter(entered) = step else {
-            continue;
+        let entered = match step {
+            BrainStep::Stay => continue,
+            // The clip stands in place: whatever the state was doing stops,
+            // and the destination plans its own movement when entered.
+            BrainStep::PlayClip => {
+                cursor.restart();
+                velocity.0 = Vec2::ZERO;
+                target.0 = None;
+                continue;
+            }
+            BrainStep::Enter(entered) => entered,
         };
+        entries.write(StateEntered {
+            pet,
+            state: entered,
+        });
 
         cursor.restart();
         brain.planned = plan_duration(table.get(entered), &mut rng);
+        let goal = brain.plan.take_goal();
+
+        // Asleep for as long as nobody is there: locking stops the state timing
+        // out, and the return greeting is an interrupt, which still wins.
+        if *presence == Presence::Away && entered == idle.asleep {
+            brain.locked = true;
+        }
 
         match table.get(entered).locomotion {
             Locomotion::Still | Locomotion::Held => {
@@ -401,12 +882,17 @@ fn brain_tick(
                 target.0 = None;
             }
             Locomotion::Walk { speed } => {
-                // A summon has already set a target; otherwise wander.
+                // A summon has already set a target; otherwise go where the
+                // behaviour tree says, or wander.
                 if target.0.is_none()
                     && let Some(surface) = surface.as_deref()
                 {
-                    let half = surface.size * 0.5 - frame_size * config.scale.0;
-                    target.0 = Some(rng.point_in(half.max(Vec2::ZERO)));
+                    let half =
+                        (surface.size * 0.5 - frame_size * transform.scale.x).max(Vec2::ZERO);
+                    target.0 = Some(match goal {
+                        Some(Goal::Spot(at)) => half * at,
+                        Some(Goal::Anywhere) | None => rng.point_in(half),
+                    });
                 }
 
                 // Give the walk long enough to actually get there. Without this
@@ -437,6 +923,12 @@ fn locomote(
         let Locomotion::Walk { speed } = table.get(brain.state).locomotion else {
             continue;
         };
+        // A clip out of a walk plays standing still, whatever else sets a
+        // target meanwhile.
+        if brain.clip.is_some() {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
         let Some(to) = target.0 else { continue };
 
         match steer_toward(transform.translation.truncate(), to, speed, dt) {
@@ -449,7 +941,7 @@ fn locomote(
     }
 }
 
-/// Applies velocity to position, and updates facing.
+/// Applies velocity to position, and updates facing and heading.
 fn integrate(
     time: Res<Time>,
     table: Res<StateTable>,
@@ -459,13 +951,13 @@ fn integrate(
             &mut Transform,
             &mut Velocity,
             &mut Facing,
-            &mut Sprite,
+            &mut Heading,
         ),
         With<Pet>,
     >,
 ) {
     let dt = time.delta();
-    for (brain, mut transform, mut velocity, mut facing, mut sprite) in &mut pets {
+    for (brain, mut transform, mut velocity, mut facing, mut heading) in &mut pets {
         // A held pet is positioned by the pointer, not by physics.
         if matches!(table.get(brain.state).locomotion, Locomotion::Held) {
             velocity.0 = Vec2::ZERO;
@@ -479,26 +971,46 @@ fn integrate(
         if next != *facing {
             *facing = next;
         }
-        sprite.flip_x = facing.flip_x();
+        let next = heading_from_velocity(velocity.0, *heading);
+        if next != *heading {
+            *heading = next;
+        }
     }
 }
 
-/// Advances animation frames.
+type AnimateData<'a> = (
+    &'a PetBrain,
+    &'a Facing,
+    &'a Heading,
+    &'a mut AnimationCursor,
+    &'a mut Sprite,
+    &'a mut Anchor,
+);
+
+/// Advances animation frames, and picks the row and mirroring that draw the
+/// pet facing the way it moves.
 fn animate(
     time: Res<Time>,
     table: Res<StateTable>,
     skin: Res<Skin>,
-    mut pets: Query<(&PetBrain, &mut AnimationCursor, &mut Sprite), With<Pet>>,
+    mut pets: Query<AnimateData, With<Pet>>,
 ) {
     let dt = time.delta();
-    for (brain, mut cursor, mut sprite) in &mut pets {
+    for (brain, facing, heading, mut cursor, mut sprite, mut anchor) in &mut pets {
         let def = table.get(brain.state);
-        step_animation(&mut cursor, def.frames, def.fps, def.playback, dt);
-
-        let Some(atlas) = sprite.texture_atlas.as_mut() else {
-            continue;
+        let (row, flip_x) = match brain.clip.and_then(|index| def.clips.get(index)) {
+            Some(clip) => {
+                let timing = FrameTiming::Fps(clip.fps);
+                step_animation(&mut cursor, clip.frames, timing, Playback::Once, dt);
+                (clip.row, facing.flip_x())
+            }
+            None => {
+                step_animation(&mut cursor, def.frames, def.timing(), def.playback, dt);
+                sheet_row(brain.state, *heading, *facing, &skin.geometry.directions)
+            }
         };
-        atlas.index = atlas_index(brain.state, skin.columns(), &cursor);
+        let index = atlas_index(row, skin.columns(), &cursor);
+        skin.show(index, flip_x, &mut sprite, &mut anchor);
     }
 }
 
@@ -511,17 +1023,26 @@ fn animate(
 pub(crate) fn compute_input_region(
     surface: Option<Res<SurfaceOrigin>>,
     skin: Res<Skin>,
-    pets: Query<&Transform, With<Pet>>,
+    menu: Res<PetMenu>,
+    pets: Query<(&Transform, &Sprite, Option<&Speech>), With<Pet>>,
     mut region: ResMut<crate::core::hitbox::DesiredInputRegion>,
 ) {
     let Some(surface) = surface else { return };
-    let rects = pets.iter().map(|transform| {
-        pet_rect_world(
-            transform.translation.truncate(),
-            skin.frame_size(),
-            transform.scale.x,
-        )
+    let pet_rects = pets
+        .iter()
+        .flat_map(|(transform, sprite, _)| pet_shape(&skin, transform, sprite).cover());
+    // Bubbles are clicked to dismiss them.
+    let bubble_rects = pets.iter().filter_map(|(transform, _, speech)| {
+        Some(speech_rect(&skin, transform, speech?, surface.size))
     });
+    // An open menu must take clicks too, or they fall through to the window
+    // beneath it.
+    let menu_rects = menu
+        .0
+        .iter()
+        .flat_map(|open| menu_layout(open.anchor, surface.size))
+        .map(|(_, rect)| rect);
+    let rects = pet_rects.chain(bubble_rects).chain(menu_rects);
     let next = aggregate_input_region(rects, surface.size, INPUT_REGION_PADDING);
     region.set_if_neq(next);
 }
@@ -536,24 +1057,30 @@ fn draw_debug_overlay(
     config: Res<Config>,
     skin: Res<Skin>,
     gesture: Res<GestureState>,
-    pets: Query<(&Transform, &PetBrain), With<Pet>>,
+    pets: Query<(&Transform, &Sprite, &PetBrain), With<Pet>>,
 ) {
     if !config.debug_overlay {
         return;
     }
 
-    for (transform, brain) in &pets {
-        let rect = pet_rect_world(
-            transform.translation.truncate(),
-            skin.frame_size(),
-            transform.scale.x,
-        );
-        // Green box: exactly the region that accepts clicks.
+    for (transform, sprite, brain) in &pets {
+        let shape = pet_shape(&skin, transform, sprite);
+        let rect = shape.rect;
+        // Grey box: the whole frame.
         gizmos.rect_2d(
             Isometry2d::from_translation(rect.center()),
             rect.size(),
-            Color::srgb(0.0, 1.0, 0.2),
+            Color::srgb(0.5, 0.5, 0.5),
         );
+        // Green cells: the input region. Clicks land on the solid pixels
+        // inside them.
+        for cell in shape.cover() {
+            gizmos.rect_2d(
+                Isometry2d::from_translation(cell.center()),
+                cell.size(),
+                Color::srgb(0.0, 1.0, 0.2),
+            );
+        }
         // A dragged pet turns the box magenta so state is visible too.
         if brain.state == PetState::Dragged {
             gizmos.rect_2d(
@@ -584,3 +1111,47 @@ fn draw_debug_overlay(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// A follower chosen to deliver a timer walks to deliver it, rather than
+    /// being pulled back after the cursor every frame.
+    #[test]
+    fn a_summoned_follower_delivers_before_following_again() {
+        let mut world = World::new();
+        world.insert_resource(InteractionTier::ClickToSummon);
+        world.init_resource::<FollowConfig>();
+        world.insert_resource(GestureState {
+            cursor: Some(World2d(Vec2::new(100.0, 0.0))),
+            ..default()
+        });
+        let pet = world
+            .spawn((
+                Pet,
+                PetBrain::new(PetState::Walking, Duration::from_secs(5)),
+                Transform::from_xyz(-300.0, 0.0, 0.0),
+                PendingInterrupt::default(),
+                MoveTarget(Some(Vec2::ZERO)),
+                Following,
+                Summons {
+                    message: "Stretch".to_string(),
+                    then: PetState::SendingLove,
+                },
+            ))
+            .id();
+
+        world.run_system_once(follow_cursor).expect("runs");
+        assert_eq!(world.get::<MoveTarget>(pet).unwrap().0, Some(Vec2::ZERO));
+
+        world.entity_mut(pet).remove::<Summons>();
+        world.run_system_once(follow_cursor).expect("runs");
+        // `FollowConfig::default().distance` short of the cursor.
+        assert_eq!(
+            world.get::<MoveTarget>(pet).unwrap().0,
+            Some(Vec2::new(40.0, 0.0))
+        );
+    }
+}
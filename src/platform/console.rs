@@ -0,0 +1,28 @@
+//! The console the command line prints to.
+//!
+//! The Windows build uses the GUI subsystem, so that starting the pets from
+//! the Start menu does not open a console window behind them. Such a process
+//! has no console, so whatever `batates skin check` or `batates --quit`
+//! prints would go nowhere. Attaching to the console of whatever started it,
+//! if anything did, puts the output back where it was asked for. Everywhere
+//! else a process already has its parent's terminal.
+
+#[cfg(target_os = "windows")]
+const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+#[cfg(target_os = "windows")]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn AttachConsole(process: u32) -> i32;
+}
+
+/// Prints to the console this was started from, if there is one. Call before
+/// printing anything.
+pub fn attach() {
+    // SAFETY: takes no pointers. Fails harmlessly when there is no parent
+    // console, or when this process already has one.
+    #[cfg(target_os = "windows")]
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
@@ -0,0 +1,25 @@
+//! Idle time on macOS.
+//!
+//! `CGEventSourceSecondsSinceLastEventType` with "any input" is the documented
+//! way to ask; it needs no accessibility permission, unlike an event tap.
+
+use std::time::Duration;
+
+/// The HID system state: input as the hardware delivered it, which is what
+/// "is anyone there" should mean. Matches `kCGEventSourceStateHIDSystemState`.
+const HID_SYSTEM_STATE: i32 = 1;
+/// Matches `kCGAnyInputEventType`.
+const ANY_INPUT_EVENT: u32 = u32::MAX;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+unsafe extern "C" {
+    fn CGEventSourceSecondsSinceLastEventType(state_id: i32, event_type: u32) -> f64;
+}
+
+pub fn since_last_input() -> Option<Duration> {
+    // SAFETY: a pure query of global input state; no pointers involved.
+    let seconds =
+        unsafe { CGEventSourceSecondsSinceLastEventType(HID_SYSTEM_STATE, ANY_INPUT_EVENT) };
+    // Negative or NaN would mean the call failed; there is no other error path.
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
@@ -0,0 +1,24 @@
+//! How long the user has been away from the keyboard and mouse.
+//!
+//! Both desktop platforms keep a system-wide "time since last input" counter
+//! and will answer it on demand, so this is a poll rather than a subscription.
+//! It reads input from every process, which is the point: the overlay is
+//! click-through and never sees the user's input itself.
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "macos")]
+use macos as backend;
+#[cfg(target_os = "windows")]
+use windows as backend;
+
+use std::time::Duration;
+
+/// Time since the last keyboard or pointer input anywhere in the session, or
+/// `None` if the platform declined to say.
+pub fn since_last_input() -> Option<Duration> {
+    backend::since_last_input()
+}
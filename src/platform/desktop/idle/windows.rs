@@ -0,0 +1,43 @@
+//! Idle time on Windows.
+//!
+//! `GetLastInputInfo` reports the tick count of the last input event for the
+//! session, so idle time is the distance from `GetTickCount`. Both are 32-bit
+//! millisecond counters that wrap after ~49.7 days, which a wrapping subtract
+//! handles for free.
+
+use std::time::Duration;
+
+#[repr(C)]
+struct LastInputInfo {
+    /// Must be set to the struct's size before the call.
+    cb_size: u32,
+    dw_time: u32,
+}
+
+#[link(name = "user32")]
+unsafe extern "system" {
+    fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+}
+
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetTickCount() -> u32;
+}
+
+pub fn since_last_input() -> Option<Duration> {
+    let mut info = LastInputInfo {
+        cb_size: size_of::<LastInputInfo>() as u32,
+        dw_time: 0,
+    };
+    // SAFETY: `info` is a valid, correctly sized, exclusively borrowed
+    // allocation with `cb_size` set as the API requires.
+    let ok = unsafe { GetLastInputInfo(&mut info) } != 0;
+    if !ok {
+        return None;
+    }
+    // SAFETY: no arguments; reads a system counter.
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(u64::from(
+        now.wrapping_sub(info.dw_time),
+    )))
+}
@@ -1,22 +1,32 @@
 //! The macOS and Windows backend: a fullscreen, click-through winit window.
 //!
 //! Satisfies the backend contract in [`crate::platform`]: it provides
-//! [`ScreenGeometry`], [`SurfaceOrigin`] and [`InteractionTier`], and publishes
-//! one [`PointerSample`] per frame.
+//! [`ScreenGeometry`], [`SurfaceOrigin`] and [`InteractionTier`], publishes
+//! one [`PointerSample`] per frame and a [`TouchSample`] per pen or finger
+//! event, and polls the session's idle time into
+//! [`UserActivity`].
 //!
 //! It does not touch [`crate::core::hitbox::DesiredInputRegion`]: winit's hit
 //! test is all-or-nothing per window, so it cannot express "click-through
 //! except over the pets". Since the pointer is read globally here anyway, the
 //! overlay simply stays click-through for its whole life.
 
+pub mod idle;
 pub mod pointer;
 pub mod window;
 
 use bevy::prelude::*;
+use std::time::Duration;
 
 use crate::core::PetSystems;
 use crate::core::coords::{ScreenGeometry, SurfaceOrigin, physical_to_logical, screen_to_surface};
+use crate::core::idle::{IdleConfig, UserActivity};
 use crate::core::input::{PointerAt, PointerSample};
+use crate::core::touch::TouchSample;
+
+/// How often the idle counter is read. Away thresholds are measured in
+/// minutes, so once a second is plenty and keeps a syscall out of every frame.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 /// How this backend wants its window created.
 ///
@@ -42,7 +52,10 @@ impl Plugin for DesktopBackendPlugin {
                 Update,
                 (window::track_monitors, window::track_surface).before(PetSystems::Sample),
             )
-            .add_systems(Update, sample_pointer.in_set(PetSystems::Sample));
+            .add_systems(
+                Update,
+                (sample_pointer, sample_idle).in_set(PetSystems::Sample),
+            );
     }
 }
 
@@ -57,10 +70,11 @@ fn sample_pointer(
     geometry: Res<ScreenGeometry>,
     surface: Option<Res<SurfaceOrigin>>,
     mut samples: MessageWriter<PointerSample>,
+    mut touches: MessageWriter<TouchSample>,
 ) {
     let Some(surface) = surface else { return };
 
-    let at = match pointer::pointer_position() {
+    let to_surface = |at: PointerAt| match at {
         PointerAt::Global(physical) => {
             let logical = physical_to_logical(physical, &geometry);
             PointerAt::Surface(screen_to_surface(logical, *surface))
@@ -72,9 +86,42 @@ fn sample_pointer(
         other => other,
     };
 
+    let at_time = time.elapsed();
     samples.write(PointerSample {
-        at,
+        at: to_surface(pointer::pointer_position()),
         buttons: pointer::buttons(),
-        at_time: time.elapsed(),
+        scroll: pointer::take_scroll(),
+        at_time,
     });
+    touches.write_batch(
+        pointer::take_touches()
+            .into_iter()
+            .map(|(id, phase, at)| TouchSample {
+                id,
+                phase,
+                at: to_surface(at),
+                at_time,
+            }),
+    );
+}
+
+/// Polls the session's idle time and publishes it.
+fn sample_idle(
+    time: Res<Time>,
+    idle: Res<IdleConfig>,
+    mut last_poll: Local<Option<Duration>>,
+    mut activity: MessageWriter<UserActivity>,
+) {
+    if !idle.enabled {
+        return;
+    }
+    let now = time.elapsed();
+    if last_poll.is_some_and(|at| now.saturating_sub(at) < IDLE_POLL_INTERVAL) {
+        return;
+    }
+    *last_poll = Some(now);
+
+    if let Some(since) = idle::since_last_input() {
+        activity.write(UserActivity::IdleFor(since));
+    }
 }
@@ -1,18 +1,21 @@
 //! Global pointer reads on macOS.
 //!
-//! Declared directly rather than pulled from a crate: this is two calls, and
-//! owning them means the signature can state which coordinate space they return
-//! instead of leaving it to the call site to guess. Guessing is what produced
-//! the DPI bug on Windows.
+//! Declared directly rather than pulled from a crate: this is a handful of
+//! calls, and owning them means the signature can state which coordinate space
+//! they return instead of leaving it to the call site to guess. Guessing is
+//! what produced the DPI bug on Windows.
 //!
 //! `CGEventGetLocation` reports **points**, which are already logical pixels,
 //! with the origin at the top-left of the main display and Y growing downward.
 
 use bevy::prelude::*;
 use std::ffi::c_void;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicI32, Ordering};
 
 use crate::core::coords::ScreenLogical;
 use crate::core::input::{ButtonMask, PointerAt};
+use crate::core::touch::TouchPhase;
 
 #[repr(C)]
 struct CGPoint {
@@ -22,12 +25,40 @@ struct CGPoint {
 
 type CGEventRef = *mut c_void;
 type CGEventSourceRef = *mut c_void;
+type CFMachPortRef = *mut c_void;
+type CFRunLoopSourceRef = *mut c_void;
+type CFRunLoopRef = *mut c_void;
+type CFStringRef = *const c_void;
+type CGEventTapProxy = *mut c_void;
+type CGEventTapCallBack = extern "C" fn(
+    proxy: CGEventTapProxy,
+    kind: u32,
+    event: CGEventRef,
+    user_info: *mut c_void,
+) -> CGEventRef;
 
 /// Combined session state: what the user is actually doing, including input
 /// from other processes. Matches the constant `kCGEventSourceStateCombinedSessionState`.
 const COMBINED_SESSION_STATE: i32 = 0;
 const MOUSE_BUTTON_LEFT: u32 = 0;
 const MOUSE_BUTTON_RIGHT: u32 = 1;
+const MOUSE_BUTTON_CENTER: u32 = 2;
+
+/// `kCGSessionEventTap`, `kCGHeadInsertEventTap` and
+/// `kCGEventTapOptionListenOnly`: watch the session's events, never change
+/// them.
+const SESSION_EVENT_TAP: u32 = 1;
+const HEAD_INSERT: u32 = 0;
+const LISTEN_ONLY: u32 = 1;
+/// `kCGEventScrollWheel`.
+const SCROLL_WHEEL: u32 = 22;
+/// `kCGEventTapDisabledByTimeout`: the tap must be turned back on by hand.
+const DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+/// `kCGScrollWheelEventFixedPtDeltaAxis1`: vertical lines, fractional for a
+/// touchpad, positive away from the user.
+const SCROLL_DELTA_LINES: u32 = 93;
+/// Scrolling is added up in hundredths of a line, so an atomic can hold it.
+const SCROLL_UNITS_PER_LINE: f32 = 100.0;
 
 #[link(name = "CoreGraphics", kind = "framework")]
 unsafe extern "C" {
@@ -35,13 +66,36 @@ unsafe extern "C" {
     fn CGEventCreate(source: CGEventSourceRef) -> CGEventRef;
     fn CGEventGetLocation(event: CGEventRef) -> CGPoint;
     fn CGEventSourceButtonState(state_id: i32, button: u32) -> bool;
+    fn CGEventTapCreate(
+        tap: u32,
+        place: u32,
+        options: u32,
+        events_of_interest: u64,
+        callback: CGEventTapCallBack,
+        user_info: *mut c_void,
+    ) -> CFMachPortRef;
+    fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+    fn CGEventGetDoubleValueField(event: CGEventRef, field: u32) -> f64;
 }
 
 #[link(name = "CoreFoundation", kind = "framework")]
 unsafe extern "C" {
+    static kCFRunLoopCommonModes: CFStringRef;
     fn CFRelease(cf: *const c_void);
+    fn CFMachPortCreateRunLoopSource(
+        allocator: *const c_void,
+        port: CFMachPortRef,
+        order: isize,
+    ) -> CFRunLoopSourceRef;
+    fn CFRunLoopGetCurrent() -> CFRunLoopRef;
+    fn CFRunLoopAddSource(run_loop: CFRunLoopRef, source: CFRunLoopSourceRef, mode: CFStringRef);
+    fn CFRunLoopRun();
 }
 
+/// Scrolling seen since the last [`take_scroll`], in hundredths of a line.
+static SCROLLED: AtomicI32 = AtomicI32::new(0);
+static LISTENING: OnceLock<()> = OnceLock::new();
+
 /// Reads the cursor position, in logical points.
 pub fn pointer_position() -> PointerAt {
     // SAFETY: CGEventCreate(null) is documented to return an autoreleased event
@@ -69,6 +123,73 @@ pub fn buttons() -> ButtonMask {
         if CGEventSourceButtonState(COMBINED_SESSION_STATE, MOUSE_BUTTON_RIGHT) {
             mask |= ButtonMask::RIGHT;
         }
+        if CGEventSourceButtonState(COMBINED_SESSION_STATE, MOUSE_BUTTON_CENTER) {
+            mask |= ButtonMask::MIDDLE;
+        }
     }
     mask
 }
+
+/// Lines scrolled since the last call, positive away from the user.
+pub fn take_scroll() -> f32 {
+    LISTENING.get_or_init(|| {
+        std::thread::spawn(run_scroll_tap);
+    });
+    SCROLLED.swap(0, Ordering::Relaxed) as f32 / SCROLL_UNITS_PER_LINE
+}
+
+/// Always empty: a trackpad is a pointer, and there are no touch screens.
+pub fn take_touches() -> Vec<(u64, TouchPhase, PointerAt)> {
+    Vec::new()
+}
+
+/// Creates a listen-only tap for the wheel on this thread and runs its loop.
+///
+/// Unlike keys, watching the wheel needs no permission. The tap is handed to
+/// its own callback so a timeout can turn it back on.
+fn run_scroll_tap() {
+    let port: *mut CFMachPortRef = Box::into_raw(Box::new(std::ptr::null_mut()));
+    // SAFETY: `port` is leaked, so the pointer handed to the callback stays
+    // valid for as long as the run loop can call it, and is written before
+    // the loop runs. Every Core Foundation object is created and used on
+    // this thread, and lives as long as it.
+    unsafe {
+        let tap = CGEventTapCreate(
+            SESSION_EVENT_TAP,
+            HEAD_INSERT,
+            LISTEN_ONLY,
+            1 << SCROLL_WHEEL,
+            on_scroll,
+            port.cast(),
+        );
+        if tap.is_null() {
+            warn!("not watching the mouse wheel: the event tap was refused");
+            return;
+        }
+        *port = tap;
+        let source = CFMachPortCreateRunLoopSource(std::ptr::null(), tap, 0);
+        CFRunLoopAddSource(CFRunLoopGetCurrent(), source, kCFRunLoopCommonModes);
+        CGEventTapEnable(tap, true);
+        CFRunLoopRun();
+    }
+}
+
+extern "C" fn on_scroll(
+    _proxy: CGEventTapProxy,
+    kind: u32,
+    event: CGEventRef,
+    user_info: *mut c_void,
+) -> CGEventRef {
+    // SAFETY: `user_info` is the tap leaked by `run_scroll_tap`, and `event`
+    // is valid for the duration of the callback.
+    unsafe {
+        if kind == DISABLED_BY_TIMEOUT {
+            CGEventTapEnable(*(user_info as *const CFMachPortRef), true);
+        } else if kind == SCROLL_WHEEL {
+            let lines = CGEventGetDoubleValueField(event, SCROLL_DELTA_LINES);
+            let units = (lines as f32 * SCROLL_UNITS_PER_LINE).round() as i32;
+            SCROLLED.fetch_add(units, Ordering::Relaxed);
+        }
+    }
+    event
+}
@@ -18,6 +18,7 @@ use macos as backend;
 use windows as backend;
 
 use crate::core::input::{ButtonMask, PointerAt};
+use crate::core::touch::TouchPhase;
 
 /// The cursor position, in whichever space this platform reports.
 pub fn pointer_position() -> PointerAt {
@@ -28,3 +29,21 @@ pub fn pointer_position() -> PointerAt {
 pub fn buttons() -> ButtonMask {
     backend::buttons()
 }
+
+/// Lines scrolled since the last call, positive away from the user.
+///
+/// The wheel cannot be polled like the buttons, so the first call starts
+/// listening for it on a thread of its own. Where that is refused this warns
+/// once and scrolling stays zero. The overlay being click-through, the window
+/// under the pointer scrolls as well: the wheel is only watched, never taken.
+pub fn take_scroll() -> f32 {
+    backend::take_scroll()
+}
+
+/// Pen and finger events since the last call, as `(id, phase, where)`.
+///
+/// Windows reports the one contact it promotes to mouse input, through the
+/// same listener as the wheel. Macs have no touch screens, so never any there.
+pub fn take_touches() -> Vec<(u64, TouchPhase, PointerAt)> {
+    backend::take_touches()
+}
@@ -9,11 +9,21 @@
 //! The values are only physical if the process is per-monitor DPI aware. winit
 //! sets that during window creation, so this must not be called before the
 //! window exists.
+//!
+//! Pens and fingers would be `WM_POINTER` messages, but those go to the window
+//! under the contact, and the overlay is click-through, so it is never that
+//! window. What does reach a low-level mouse hook is the mouse input Windows
+//! promotes them to, marked as such. That is one contact at a time, the first
+//! finger down, and it is what [`take_touches`] reports.
 
 use bevy::prelude::*;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Mutex, OnceLock};
 
 use crate::core::coords::ScreenPhysical;
 use crate::core::input::{ButtonMask, PointerAt};
+use crate::core::touch::TouchPhase;
 
 #[repr(C)]
 struct Point {
@@ -21,17 +31,96 @@ struct Point {
     y: i32,
 }
 
+/// `MSLLHOOKSTRUCT`, what a low-level mouse hook is handed.
+#[repr(C)]
+struct MouseHookInfo {
+    pt: Point,
+    /// For a wheel event, the distance in its high word, signed.
+    mouse_data: u32,
+    flags: u32,
+    time: u32,
+    extra_info: usize,
+}
+
+#[repr(C)]
+struct Msg {
+    hwnd: *mut c_void,
+    message: u32,
+    w_param: usize,
+    l_param: isize,
+    time: u32,
+    pt: Point,
+}
+
 const VK_LBUTTON: i32 = 0x01;
 const VK_RBUTTON: i32 = 0x02;
+const VK_MBUTTON: i32 = 0x04;
 /// `GetAsyncKeyState` reports "currently down" in the high-order bit.
 const KEY_DOWN_MASK: i16 = -0x8000;
 
+/// `WH_MOUSE_LL`.
+const MOUSE_LL: i32 = 14;
+const WM_MOUSEMOVE: usize = 0x0200;
+const WM_LBUTTONDOWN: usize = 0x0201;
+const WM_LBUTTONUP: usize = 0x0202;
+const WM_RBUTTONDOWN: usize = 0x0204;
+const WM_RBUTTONUP: usize = 0x0205;
+const WM_MBUTTONDOWN: usize = 0x0207;
+const WM_MBUTTONUP: usize = 0x0208;
+const WM_MOUSEWHEEL: usize = 0x020A;
+/// `MI_WP_SIGNATURE`, in the extra info of mouse input promoted from a pen or
+/// a finger, under `SIGNATURE_MASK`.
+const PROMOTED_SIGNATURE: usize = 0xFF51_5700;
+const SIGNATURE_MASK: usize = 0xFFFF_FF00;
+/// Set in that extra info for a finger rather than a pen.
+const FROM_TOUCH: usize = 0x80;
+/// Touch ids for the one contact that is promoted: a finger, or a pen.
+const FINGER: u64 = 0;
+const PEN: u64 = 1;
+/// `HC_ACTION`: the only code a hook may act on.
+const HC_ACTION: i32 = 0;
+/// Wheel distance per line, `WHEEL_DELTA`. Precise wheels and touchpads send
+/// fractions of it.
+const WHEEL_DELTA: f32 = 120.0;
+
+type HookProc = unsafe extern "system" fn(code: i32, w_param: usize, l_param: isize) -> isize;
+
 #[link(name = "user32")]
 unsafe extern "system" {
     fn GetCursorPos(point: *mut Point) -> i32;
     fn GetAsyncKeyState(key: i32) -> i16;
+    fn SetWindowsHookExW(id: i32, proc: HookProc, module: *mut c_void, thread: u32) -> *mut c_void;
+    fn CallNextHookEx(hook: *mut c_void, code: i32, w_param: usize, l_param: isize) -> isize;
+    fn GetMessageW(msg: *mut Msg, hwnd: *mut c_void, min: u32, max: u32) -> i32;
 }
 
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetModuleHandleW(name: *const u16) -> *mut c_void;
+}
+
+/// Wheel distance seen since the last [`take_scroll`], in `WHEEL_DELTA`s.
+/// A static because the hook cannot carry state.
+static WHEEL: AtomicI32 = AtomicI32::new(0);
+static LISTENING: OnceLock<()> = OnceLock::new();
+
+/// What the hook has seen of pens and fingers since the last
+/// [`take_touches`].
+struct Promoted {
+    /// The contact down, if any.
+    down: Option<u64>,
+    /// Buttons held by the contact rather than a mouse, so [`buttons`] can
+    /// leave them out: a finger is not a left click here.
+    buttons: ButtonMask,
+    events: Vec<(u64, TouchPhase, IVec2)>,
+}
+
+static PROMOTED: Mutex<Promoted> = Mutex::new(Promoted {
+    down: None,
+    buttons: ButtonMask::empty(),
+    events: Vec::new(),
+});
+
 /// Reads the cursor position, in physical pixels.
 pub fn pointer_position() -> PointerAt {
     let mut point = Point { x: 0, y: 0 };
@@ -57,6 +146,111 @@ pub fn buttons() -> ButtonMask {
         if GetAsyncKeyState(VK_RBUTTON) & KEY_DOWN_MASK != 0 {
             mask |= ButtonMask::RIGHT;
         }
+        if GetAsyncKeyState(VK_MBUTTON) & KEY_DOWN_MASK != 0 {
+            mask |= ButtonMask::MIDDLE;
+        }
+    }
+    let promoted = PROMOTED.lock().map_or(ButtonMask::empty(), |p| p.buttons);
+    mask - promoted
+}
+
+/// Lines scrolled since the last call, positive away from the user.
+pub fn take_scroll() -> f32 {
+    watch_mouse();
+    WHEEL.swap(0, Ordering::Relaxed) as f32 / WHEEL_DELTA
+}
+
+/// Pen and finger events since the last call, in physical pixels.
+pub fn take_touches() -> Vec<(u64, TouchPhase, PointerAt)> {
+    watch_mouse();
+    let Ok(mut promoted) = PROMOTED.lock() else {
+        return Vec::new();
+    };
+    promoted
+        .events
+        .drain(..)
+        .map(|(id, phase, at)| (id, phase, PointerAt::Global(ScreenPhysical(at))))
+        .collect()
+}
+
+/// Starts the mouse hook, the first time only.
+fn watch_mouse() {
+    LISTENING.get_or_init(|| {
+        std::thread::spawn(run_mouse_hook);
+    });
+}
+
+/// Installs a low-level mouse hook on this thread and pumps messages for it.
+///
+/// The hook is called from this thread's message loop, so it gets a thread
+/// that does nothing else; Windows drops a hook whose callback is slow.
+fn run_mouse_hook() {
+    // SAFETY: `on_mouse` has the signature `SetWindowsHookExW` expects, and
+    // the module handle of this executable is valid for its lifetime.
+    let hook =
+        unsafe { SetWindowsHookExW(MOUSE_LL, on_mouse, GetModuleHandleW(std::ptr::null()), 0) };
+    if hook.is_null() {
+        let error = std::io::Error::last_os_error();
+        warn!("not watching the mouse wheel, pens or touch: {error}");
+        return;
+    }
+    let mut msg = std::mem::MaybeUninit::<Msg>::uninit();
+    // SAFETY: `msg` is a valid, exclusively borrowed allocation the size of
+    // a MSG. Nothing is posted to this thread, so the loop only ever returns
+    // to run the hook, until the app exits.
+    while unsafe { GetMessageW(msg.as_mut_ptr(), std::ptr::null_mut(), 0, 0) } > 0 {}
+}
+
+unsafe extern "system" fn on_mouse(code: i32, w_param: usize, l_param: isize) -> isize {
+    if code == HC_ACTION {
+        // SAFETY: for `HC_ACTION`, `l_param` points at a MSLLHOOKSTRUCT valid
+        // for the duration of the call.
+        let info = unsafe { &*(l_param as *const MouseHookInfo) };
+        if w_param == WM_MOUSEWHEEL {
+            let delta = (info.mouse_data >> 16) as u16 as i16;
+            WHEEL.fetch_add(i32::from(delta), Ordering::Relaxed);
+        } else if info.extra_info & SIGNATURE_MASK == PROMOTED_SIGNATURE {
+            let id = if info.extra_info & FROM_TOUCH != 0 {
+                FINGER
+            } else {
+                PEN
+            };
+            promote(id, w_param, IVec2::new(info.pt.x, info.pt.y));
+        }
+    }
+    // SAFETY: passing the call on unchanged, as every hook must; the hook
+    // handle is ignored on current Windows.
+    unsafe { CallNextHookEx(std::ptr::null_mut(), code, w_param, l_param) }
+}
+
+/// Records one mouse event promoted from a pen or a finger.
+///
+/// A press and hold is promoted to a right click on lifting; that is Windows
+/// playing mouse, not anything the contact did, so only the buttons it holds
+/// are noted.
+fn promote(id: u64, message: usize, at: IVec2) {
+    let Ok(mut promoted) = PROMOTED.lock() else {
+        return;
+    };
+    match message {
+        WM_LBUTTONDOWN => {
+            promoted.buttons |= ButtonMask::LEFT;
+            promoted.down = Some(id);
+            promoted.events.push((id, TouchPhase::Down, at));
+        }
+        WM_MOUSEMOVE if promoted.down == Some(id) => {
+            promoted.events.push((id, TouchPhase::Moved, at));
+        }
+        WM_LBUTTONUP => {
+            promoted.buttons -= ButtonMask::LEFT;
+            if promoted.down.take() == Some(id) {
+                promoted.events.push((id, TouchPhase::Up, at));
+            }
+        }
+        WM_RBUTTONDOWN => promoted.buttons |= ButtonMask::RIGHT,
+        WM_RBUTTONUP => promoted.buttons -= ButtonMask::RIGHT,
+        WM_MBUTTONDOWN => promoted.buttons |= ButtonMask::MIDDLE,
+        WM_MBUTTONUP => promoted.buttons -= ButtonMask::MIDDLE,
+        _ => {}
     }
-    mask
 }
@@ -0,0 +1,143 @@
+//! Keystrokes on Linux, read from the keyboards' evdev devices.
+//!
+//! Wayland gives a client no input but its own, and `ext-idle-notify-v1`,
+//! which the backend already uses for presence, only says whether there was
+//! any input at all, so a rate has to come from the devices underneath. Their
+//! nodes under `/dev/input` are readable by the `input` group, which a user
+//! must be added to; without that there is no count.
+//!
+//! Reading a device does not grab it: every event still reaches the
+//! compositor as before. Each keyboard is read on its own thread, and all
+//! they keep of an event is whether it was a key going down.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Lists every input device, a blank line between each.
+const DEVICES: &str = "/proc/bus/input/devices";
+
+/// `struct input_event`: a `timeval`, two longs, then type, code and value.
+const EVENT_SIZE: usize = 2 * size_of::<std::ffi::c_long>() + 8;
+const EV_KEY: u16 = 0x01;
+/// The bit for `EV_REP` in a device's event types. Keyboards repeat held
+/// keys; power buttons and mice, which also send `EV_KEY`, do not.
+const EV_REP_BIT: u32 = 1 << 0x14;
+/// Codes from here up are mouse, joystick and other buttons.
+const BTN_MISC: u16 = 0x100;
+/// `value` for a key going down, as opposed to up (0) or repeating (2).
+const PRESSED: i32 = 1;
+
+pub fn count_into(count: Arc<AtomicU32>) -> Result<(), String> {
+    let devices =
+        std::fs::read_to_string(DEVICES).map_err(|error| format!("{DEVICES}: {error}"))?;
+    let names = keyboards(&devices);
+    if names.is_empty() {
+        return Err("no keyboard found".to_string());
+    }
+    let mut opened = 0;
+    let mut last_error = None;
+    for name in names {
+        let path = Path::new("/dev/input").join(&name);
+        match File::open(&path) {
+            Ok(device) => {
+                opened += 1;
+                let count = Arc::clone(&count);
+                std::thread::spawn(move || read_presses(device, &count));
+            }
+            Err(error) => last_error = Some(format!("{}: {error}", path.display())),
+        }
+    }
+    match (opened, last_error) {
+        (0, Some(error)) => Err(format!("{error} (is the user in the `input` group?)")),
+        _ => Ok(()),
+    }
+}
+
+/// Counts presses on one device until it goes away.
+fn read_presses(mut device: File, count: &AtomicU32) {
+    let mut event = [0u8; EVENT_SIZE];
+    while device.read_exact(&mut event).is_ok() {
+        if is_press(&event) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Whether one raw `input_event` is a keyboard key going down.
+fn is_press(event: &[u8; EVENT_SIZE]) -> bool {
+    let at = EVENT_SIZE - 8;
+    let kind = u16::from_ne_bytes([event[at], event[at + 1]]);
+    let code = u16::from_ne_bytes([event[at + 2], event[at + 3]]);
+    let value = i32::from_ne_bytes([event[at + 4], event[at + 5], event[at + 6], event[at + 7]]);
+    kind == EV_KEY && code < BTN_MISC && value == PRESSED
+}
+
+/// The `eventN` node of every keyboard in a `/proc/bus/input/devices` listing.
+fn keyboards(devices: &str) -> Vec<String> {
+    devices
+        .split("\n\n")
+        .filter_map(|device| {
+            let value = |prefix: &str| {
+                device
+                    .lines()
+                    .find_map(|line| line.strip_prefix(prefix))
+                    .map(str::trim)
+            };
+            let handlers = value("H: Handlers=")?;
+            let events = u32::from_str_radix(value("B: EV=")?, 16).ok()?;
+            let keyboard =
+                handlers.split_whitespace().any(|h| h == "kbd") && events & EV_REP_BIT != 0;
+            let node = handlers
+                .split_whitespace()
+                .find(|h| h.starts_with("event"))?;
+            keyboard.then(|| node.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_repeating_kbd_devices_are_keyboards() {
+        let devices = "\
+I: Bus=0019 Vendor=0000 Product=0001 Version=0000
+N: Name=\"Power Button\"
+H: Handlers=kbd event0
+B: EV=3
+
+I: Bus=0011 Vendor=0001 Product=0001 Version=ab41
+N: Name=\"AT Translated Set 2 keyboard\"
+H: Handlers=sysrq kbd leds event3
+B: EV=120013
+
+I: Bus=0003 Vendor=046d Product=c077 Version=0111
+N: Name=\"Logitech USB Optical Mouse\"
+H: Handlers=mouse0 event5
+B: EV=17
+";
+        assert_eq!(keyboards(devices), ["event3"]);
+    }
+
+    #[test]
+    fn only_key_presses_count() {
+        let event = |kind: u16, code: u16, value: i32| {
+            let mut event = [0u8; EVENT_SIZE];
+            let at = EVENT_SIZE - 8;
+            event[at..at + 2].copy_from_slice(&kind.to_ne_bytes());
+            event[at + 2..at + 4].copy_from_slice(&code.to_ne_bytes());
+            event[at + 4..].copy_from_slice(&value.to_ne_bytes());
+            event
+        };
+        // KEY_A down, up and repeating; a left click; a sync report.
+        assert!(is_press(&event(EV_KEY, 30, 1)));
+        assert!(!is_press(&event(EV_KEY, 30, 0)));
+        assert!(!is_press(&event(EV_KEY, 30, 2)));
+        assert!(!is_press(&event(EV_KEY, 0x110, 1)));
+        assert!(!is_press(&event(0, 0, 0)));
+    }
+}
@@ -0,0 +1,148 @@
+//! Keystrokes on macOS, through a listen-only event tap.
+//!
+//! A tap that only listens cannot change or swallow events, and is the kind
+//! the Input Monitoring permission covers. Without it the tap is refused, so
+//! the first run asks for it and counts nothing until the user has granted it
+//! and started the app again.
+//!
+//! The tap runs on a thread of its own with its own run loop. The callback
+//! looks at two things, whether the event is a key going down and whether it
+//! repeats, and returns.
+
+use std::ffi::c_void;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc;
+
+type CFMachPortRef = *mut c_void;
+type CFRunLoopSourceRef = *mut c_void;
+type CFRunLoopRef = *mut c_void;
+type CFStringRef = *const c_void;
+type CGEventRef = *mut c_void;
+type CGEventTapProxy = *mut c_void;
+type CGEventTapCallBack = extern "C" fn(
+    proxy: CGEventTapProxy,
+    kind: u32,
+    event: CGEventRef,
+    user_info: *mut c_void,
+) -> CGEventRef;
+
+/// `kCGSessionEventTap`: events as they reach the login session.
+const SESSION_EVENT_TAP: u32 = 1;
+/// `kCGHeadInsertEventTap`.
+const HEAD_INSERT: u32 = 0;
+/// `kCGEventTapOptionListenOnly`.
+const LISTEN_ONLY: u32 = 1;
+/// `kCGEventKeyDown`.
+const KEY_DOWN: u32 = 10;
+/// `kCGEventTapDisabledByTimeout`: the system turned the tap off because a
+/// callback was slow. It must be turned back on by hand.
+const DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+/// `kCGKeyboardEventAutorepeat`: non-zero for a held key repeating.
+const AUTOREPEAT: u32 = 8;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+unsafe extern "C" {
+    fn CGPreflightListenEventAccess() -> bool;
+    /// Shows the Input Monitoring prompt, once per app.
+    fn CGRequestListenEventAccess() -> bool;
+    fn CGEventTapCreate(
+        tap: u32,
+        place: u32,
+        options: u32,
+        events_of_interest: u64,
+        callback: CGEventTapCallBack,
+        user_info: *mut c_void,
+    ) -> CFMachPortRef;
+    fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+    fn CGEventGetIntegerValueField(event: CGEventRef, field: u32) -> i64;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+unsafe extern "C" {
+    static kCFRunLoopCommonModes: CFStringRef;
+    fn CFMachPortCreateRunLoopSource(
+        allocator: *const c_void,
+        port: CFMachPortRef,
+        order: isize,
+    ) -> CFRunLoopSourceRef;
+    fn CFRunLoopGetCurrent() -> CFRunLoopRef;
+    fn CFRunLoopAddSource(run_loop: CFRunLoopRef, source: CFRunLoopSourceRef, mode: CFStringRef);
+    fn CFRunLoopRun();
+}
+
+/// What the callback is handed: the counter, and the tap so it can turn it
+/// back on. Lives as long as the thread, which is as long as the app.
+struct Tap {
+    count: Arc<AtomicU32>,
+    port: CFMachPortRef,
+}
+
+pub fn count_into(count: Arc<AtomicU32>) -> Result<(), String> {
+    // SAFETY: both are plain queries of this process's permission.
+    if !unsafe { CGPreflightListenEventAccess() } {
+        unsafe { CGRequestListenEventAccess() };
+        return Err("Input Monitoring has not been granted; \
+             allow it in System Settings and start again"
+            .to_string());
+    }
+    let (started, result) = mpsc::channel();
+    std::thread::spawn(move || run_tap(count, started));
+    result
+        .recv()
+        .unwrap_or_else(|_| Err("the event tap thread stopped".to_string()))
+}
+
+/// Creates the tap on this thread and runs its loop, reporting whether the
+/// tap could be created first.
+fn run_tap(count: Arc<AtomicU32>, started: mpsc::Sender<Result<(), String>>) {
+    let tap = Box::into_raw(Box::new(Tap {
+        count,
+        port: std::ptr::null_mut(),
+    }));
+    // SAFETY: `tap` is leaked, so the pointer handed to the callback stays
+    // valid for as long as the run loop can call it. Every Core Foundation
+    // object is created and used on this thread, and lives as long as it.
+    unsafe {
+        let port = CGEventTapCreate(
+            SESSION_EVENT_TAP,
+            HEAD_INSERT,
+            LISTEN_ONLY,
+            1 << KEY_DOWN,
+            on_event,
+            tap.cast(),
+        );
+        if port.is_null() {
+            drop(Box::from_raw(tap));
+            let _ = started.send(Err("the event tap was refused".to_string()));
+            return;
+        }
+        (*tap).port = port;
+        let source = CFMachPortCreateRunLoopSource(std::ptr::null(), port, 0);
+        CFRunLoopAddSource(CFRunLoopGetCurrent(), source, kCFRunLoopCommonModes);
+        CGEventTapEnable(port, true);
+        let _ = started.send(Ok(()));
+        CFRunLoopRun();
+    }
+}
+
+extern "C" fn on_event(
+    _proxy: CGEventTapProxy,
+    kind: u32,
+    event: CGEventRef,
+    user_info: *mut c_void,
+) -> CGEventRef {
+    // SAFETY: `user_info` is the `Tap` leaked by `run_tap`, and `event` is
+    // valid for the duration of the callback.
+    unsafe {
+        let tap = &*(user_info as *const Tap);
+        if kind == DISABLED_BY_TIMEOUT {
+            CGEventTapEnable(tap.port, true);
+        } else if kind == KEY_DOWN && CGEventGetIntegerValueField(event, AUTOREPEAT) == 0 {
+            tap.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    // A listen-only tap's return value is ignored; passing the event on is
+    // what any tap would do.
+    event
+}
@@ -0,0 +1,53 @@
+//! Counting keystrokes, for how fast the user is typing.
+//!
+//! Every platform guards keyboard input from other applications, so each
+//! backend needs something the user grants: on Windows nothing beyond running
+//! in their session, on macOS Input Monitoring, on Linux read access to the
+//! keyboards under `/dev/input`. Where it is not granted there is no source,
+//! and pets simply never react to typing.
+//!
+//! Whatever the backend, it bumps one counter per key press and throws the
+//! event away. Which key it was never leaves the callback that saw it.
+
+#[cfg(target_os = "linux")]
+mod evdev;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "linux")]
+use evdev as backend;
+#[cfg(target_os = "macos")]
+use macos as backend;
+#[cfg(target_os = "windows")]
+use windows as backend;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::core::typing::KeystrokeSource;
+
+/// Key presses counted on a backend's thread since the last poll.
+struct Counter(Arc<AtomicU32>);
+
+impl KeystrokeSource for Counter {
+    fn poll(&mut self) -> u32 {
+        self.0.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// Starts counting key presses, if this platform and the user allow it.
+///
+/// A failure costs the reactions and nothing else, so it warns rather than
+/// aborting startup.
+pub fn listen() -> Option<Box<dyn KeystrokeSource>> {
+    let count = Arc::new(AtomicU32::new(0));
+    match backend::count_into(Arc::clone(&count)) {
+        Ok(()) => Some(Box::new(Counter(count))),
+        Err(reason) => {
+            bevy::log::warn!("not counting keystrokes: {reason}");
+            None
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Keystrokes on Windows, through a low-level keyboard hook.
+//!
+//! `WH_KEYBOARD_LL` needs no permission beyond running in the user's session,
+//! but it is called on the thread that installed it, from that thread's
+//! message loop, so the hook gets a thread that does nothing else. Windows
+//! drops a hook whose callback is slow, and this one only counts.
+//!
+//! The callback cannot carry state, so the counter is a static. The hook can
+//! tell a key going down from one going up, but not a held key repeating, so
+//! repeats count here.
+
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock, mpsc};
+
+/// `WH_KEYBOARD_LL`.
+const KEYBOARD_LL: i32 = 13;
+const WM_KEYDOWN: usize = 0x0100;
+const WM_SYSKEYDOWN: usize = 0x0104;
+/// `HC_ACTION`: the only code a hook may act on; below zero it must just
+/// pass the call on.
+const HC_ACTION: i32 = 0;
+
+type HookProc = unsafe extern "system" fn(code: i32, w_param: usize, l_param: isize) -> isize;
+
+#[repr(C)]
+struct Msg {
+    hwnd: *mut c_void,
+    message: u32,
+    w_param: usize,
+    l_param: isize,
+    time: u32,
+    pt_x: i32,
+    pt_y: i32,
+}
+
+#[link(name = "user32")]
+unsafe extern "system" {
+    fn SetWindowsHookExW(id: i32, proc: HookProc, module: *mut c_void, thread: u32) -> *mut c_void;
+    fn CallNextHookEx(hook: *mut c_void, code: i32, w_param: usize, l_param: isize) -> isize;
+    fn GetMessageW(msg: *mut Msg, hwnd: *mut c_void, min: u32, max: u32) -> i32;
+}
+
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetModuleHandleW(name: *const u16) -> *mut c_void;
+}
+
+static COUNT: OnceLock<Arc<AtomicU32>> = OnceLock::new();
+
+pub fn count_into(count: Arc<AtomicU32>) -> Result<(), String> {
+    COUNT
+        .set(count)
+        .map_err(|_| "keystrokes are already being counted".to_string())?;
+    let (started, result) = mpsc::channel();
+    std::thread::spawn(move || run_hook(started));
+    result
+        .recv()
+        .unwrap_or_else(|_| Err("the keyboard hook thread stopped".to_string()))
+}
+
+/// Installs the hook on this thread and pumps messages for it, reporting
+/// whether it could be installed first.
+fn run_hook(started: mpsc::Sender<Result<(), String>>) {
+    // SAFETY: `on_key` has the signature `SetWindowsHookExW` expects, and the
+    // module handle of this executable is valid for its lifetime.
+    let hook =
+        unsafe { SetWindowsHookExW(KEYBOARD_LL, on_key, GetModuleHandleW(std::ptr::null()), 0) };
+    if hook.is_null() {
+        let error = std::io::Error::last_os_error();
+        let _ = started.send(Err(format!("the keyboard hook was refused: {error}")));
+        return;
+    }
+    let _ = started.send(Ok(()));
+    let mut msg = std::mem::MaybeUninit::<Msg>::uninit();
+    // SAFETY: `msg` is a valid, exclusively borrowed allocation the size of
+    // a MSG. Nothing is posted to this thread, so the loop only ever returns
+    // to run the hook, until the app exits.
+    while unsafe { GetMessageW(msg.as_mut_ptr(), std::ptr::null_mut(), 0, 0) } > 0 {}
+}
+
+unsafe extern "system" fn on_key(code: i32, w_param: usize, l_param: isize) -> isize {
+    if code == HC_ACTION && matches!(w_param, WM_KEYDOWN | WM_SYSKEYDOWN) {
+        if let Some(count) = COUNT.get() {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    // SAFETY: passing the call on unchanged, as every hook must; the hook
+    // handle is ignored on current Windows.
+    unsafe { CallNextHookEx(std::ptr::null_mut(), code, w_param, l_param) }
+}
@@ -0,0 +1,158 @@
+//! System load on Linux, from procfs and sysfs.
+//!
+//! Nothing here needs a permission: `/proc/stat`, `/proc/meminfo` and the
+//! power supply class are readable by every user. The parsers take text, or a
+//! directory, so they can be tested on canned input.
+
+use std::fs;
+use std::path::Path;
+
+use super::Reading;
+use crate::core::load::{Battery, CpuTimes};
+
+const POWER_SUPPLIES: &str = "/sys/class/power_supply";
+
+pub fn read() -> Reading {
+    Reading {
+        cpu: fs::read_to_string("/proc/stat")
+            .ok()
+            .and_then(|stat| cpu_times(&stat)),
+        memory: fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|meminfo| memory_used(&meminfo)),
+        battery: battery(Path::new(POWER_SUPPLIES)),
+    }
+}
+
+/// The aggregate `cpu` line: user, nice, system, idle, iowait, irq, softirq
+/// and steal, in clock ticks. The guest fields after them are already counted
+/// in user and nice. Waiting on a disk is not being busy, so iowait counts as
+/// idle.
+fn cpu_times(stat: &str) -> Option<CpuTimes> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let ticks = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(str::parse)
+        .collect::<Result<Vec<u64>, _>>()
+        .ok()?;
+    let [_, _, _, idle, rest @ ..] = ticks.as_slice() else {
+        return None;
+    };
+    let idle = idle + rest.first().copied().unwrap_or(0);
+    let total: u64 = ticks.iter().sum();
+    Some(CpuTimes {
+        busy: total - idle,
+        total,
+    })
+}
+
+/// Memory in use, counting what the kernel could reclaim for a new program as
+/// free. `MemFree` alone would count the page cache as used and read high on
+/// any machine that has been up for a while.
+fn memory_used(meminfo: &str) -> Option<f32> {
+    let kilobytes = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            value.split_whitespace().next()?.parse::<u64>().ok()
+        })
+    };
+    let total = kilobytes("MemTotal")?;
+    let available = kilobytes("MemAvailable")?.min(total);
+    (total > 0).then(|| 1.0 - available as f32 / total as f32)
+}
+
+/// The first of the machine's own batteries, by name. A wireless mouse or
+/// headset also shows up as a battery, with a `Device` scope, and says nothing
+/// about this machine.
+fn battery(supplies: &Path) -> Option<Battery> {
+    let mut paths: Vec<_> = fs::read_dir(supplies)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    paths.into_iter().find_map(|supply| {
+        let attribute = |name: &str| {
+            fs::read_to_string(supply.join(name))
+                .ok()
+                .map(|value| value.trim().to_string())
+        };
+        if attribute("type")? != "Battery" || attribute("scope").as_deref() == Some("Device") {
+            return None;
+        }
+        let percent: f32 = attribute("capacity")?.parse().ok()?;
+        Some(Battery {
+            level: (percent / 100.0).clamp(0.0, 1.0),
+            discharging: attribute("status").as_deref() == Some("Discharging"),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_times_come_from_the_aggregate_line() {
+        let stat = "cpu  100 10 50 800 40 0 0 0 5 0\n\
+                    cpu0 50 5 25 400 20 0 0 0 0 0\n\
+                    intr 12345\n";
+        assert_eq!(
+            cpu_times(stat),
+            Some(CpuTimes {
+                busy: 160,
+                total: 1000,
+            }),
+            "iowait is idle and guest time is not counted twice"
+        );
+        assert_eq!(cpu_times("cpu0 1 2 3 4\n"), None);
+        assert_eq!(cpu_times("cpu  1 2\n"), None);
+    }
+
+    #[test]
+    fn available_memory_counts_as_free() {
+        let meminfo = "MemTotal:       16000000 kB\n\
+                       MemFree:         1000000 kB\n\
+                       MemAvailable:    4000000 kB\n";
+        assert_eq!(memory_used(meminfo), Some(0.75));
+        assert_eq!(memory_used("MemTotal: 16000000 kB\n"), None);
+    }
+
+    #[test]
+    fn only_the_machines_own_battery_is_read() {
+        let root = std::env::temp_dir().join(format!("batates-power-{}", std::process::id()));
+        let supply = |name: &str, attributes: &[(&str, &str)]| {
+            let dir = root.join(name);
+            fs::create_dir_all(&dir).expect("temp dir");
+            for (attribute, value) in attributes {
+                fs::write(dir.join(attribute), format!("{value}\n")).expect("temp dir");
+            }
+        };
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "15"),
+                ("status", "Discharging"),
+            ],
+        );
+        supply(
+            "hidpp_battery_0",
+            &[("type", "Battery"), ("scope", "Device"), ("capacity", "90")],
+        );
+
+        let found = battery(&root);
+        fs::remove_dir_all(&root).ok();
+        assert_eq!(
+            found,
+            Some(Battery {
+                level: 0.15,
+                discharging: true,
+            })
+        );
+        assert_eq!(battery(&root), None, "no power supplies at all");
+    }
+}
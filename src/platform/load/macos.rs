@@ -0,0 +1,53 @@
+//! System load on macOS.
+//!
+//! `host_statistics` with `HOST_CPU_LOAD_INFO` reports cumulative ticks per
+//! CPU state across all processors. That is the whole reading for now: memory
+//! pressure and the battery live behind `host_statistics64` and IOKit's power
+//! sources, whose layouts are a good deal more to mirror here, so rules about
+//! them never hold on macOS.
+
+use super::Reading;
+use crate::core::load::CpuTimes;
+
+/// Matches `HOST_CPU_LOAD_INFO`.
+const HOST_CPU_LOAD_INFO: i32 = 3;
+/// Matches `CPU_STATE_MAX`: user, system, idle and nice, in that order.
+const CPU_STATES: usize = 4;
+const CPU_STATE_IDLE: usize = 2;
+/// Matches `KERN_SUCCESS`.
+const KERN_SUCCESS: i32 = 0;
+
+unsafe extern "C" {
+    fn mach_host_self() -> u32;
+    fn host_statistics(host: u32, flavor: i32, info: *mut u32, count: *mut u32) -> i32;
+}
+
+pub fn read() -> Reading {
+    Reading {
+        cpu: cpu_times(),
+        ..Default::default()
+    }
+}
+
+fn cpu_times() -> Option<CpuTimes> {
+    let mut ticks = [0u32; CPU_STATES];
+    let mut count = CPU_STATES as u32;
+    // SAFETY: `ticks` is a `host_cpu_load_info` sized buffer and `count` says
+    // how many integers it holds, as the call requires.
+    let result = unsafe {
+        host_statistics(
+            mach_host_self(),
+            HOST_CPU_LOAD_INFO,
+            ticks.as_mut_ptr(),
+            &mut count,
+        )
+    };
+    if result != KERN_SUCCESS {
+        return None;
+    }
+    let total: u64 = ticks.iter().map(|&t| u64::from(t)).sum();
+    Some(CpuTimes {
+        busy: total - u64::from(ticks[CPU_STATE_IDLE]),
+        total,
+    })
+}
@@ -0,0 +1,34 @@
+//! Sampling how busy the machine is.
+//!
+//! Every platform keeps cumulative CPU counters and will hand them over on
+//! demand, so this is a poll, as idle time is. Usage is the difference between
+//! two samples, which `core::load` works out; here is only the reading.
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "linux")]
+use linux as backend;
+#[cfg(target_os = "macos")]
+use macos as backend;
+#[cfg(target_os = "windows")]
+use windows as backend;
+
+use crate::core::load::{Battery, CpuTimes};
+
+/// One sample. Anything the platform could not read is `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Reading {
+    pub cpu: Option<CpuTimes>,
+    /// Fraction of memory in use.
+    pub memory: Option<f32>,
+    pub battery: Option<Battery>,
+}
+
+pub fn read() -> Reading {
+    backend::read()
+}
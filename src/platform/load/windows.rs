@@ -0,0 +1,123 @@
+//! System load on Windows.
+//!
+//! `GetSystemTimes` reports cumulative idle, kernel and user time across all
+//! processors, in 100 ns units. Kernel time includes idle time, so busy time
+//! is kernel plus user less idle.
+
+use super::Reading;
+use crate::core::load::{Battery, CpuTimes};
+
+#[repr(C)]
+#[derive(Default)]
+struct FileTime {
+    low: u32,
+    high: u32,
+}
+
+impl FileTime {
+    fn ticks(&self) -> u64 {
+        (u64::from(self.high) << 32) | u64::from(self.low)
+    }
+}
+
+// Laid out for the API; only some fields are read.
+#[allow(dead_code)]
+#[repr(C)]
+struct MemoryStatusEx {
+    /// Must be set to the struct's size before the call.
+    dw_length: u32,
+    /// Percentage of physical memory in use.
+    dw_memory_load: u32,
+    ull_total_phys: u64,
+    ull_avail_phys: u64,
+    ull_total_page_file: u64,
+    ull_avail_page_file: u64,
+    ull_total_virtual: u64,
+    ull_avail_virtual: u64,
+    ull_avail_extended_virtual: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct SystemPowerStatus {
+    /// 0 on battery, 1 on mains, 255 unknown.
+    ac_line_status: u8,
+    battery_flag: u8,
+    /// Percent, or 255 unknown.
+    battery_life_percent: u8,
+    system_status_flag: u8,
+    battery_life_time: u32,
+    battery_full_life_time: u32,
+}
+
+/// The `battery_flag` bit for a machine with no battery.
+const NO_SYSTEM_BATTERY: u8 = 128;
+/// `battery_flag` when Windows cannot read the battery.
+const UNKNOWN_STATUS: u8 = 255;
+
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn GetSystemTimes(idle: *mut FileTime, kernel: *mut FileTime, user: *mut FileTime) -> i32;
+    fn GlobalMemoryStatusEx(buffer: *mut MemoryStatusEx) -> i32;
+    fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+}
+
+pub fn read() -> Reading {
+    Reading {
+        cpu: cpu_times(),
+        memory: memory_used(),
+        battery: battery(),
+    }
+}
+
+fn cpu_times() -> Option<CpuTimes> {
+    let mut idle = FileTime::default();
+    let mut kernel = FileTime::default();
+    let mut user = FileTime::default();
+    // SAFETY: three valid, exclusively borrowed out-parameters.
+    let ok = unsafe { GetSystemTimes(&mut idle, &mut kernel, &mut user) } != 0;
+    if !ok {
+        return None;
+    }
+    let total = kernel.ticks() + user.ticks();
+    Some(CpuTimes {
+        busy: total.saturating_sub(idle.ticks()),
+        total,
+    })
+}
+
+fn memory_used() -> Option<f32> {
+    let mut status = MemoryStatusEx {
+        dw_length: size_of::<MemoryStatusEx>() as u32,
+        dw_memory_load: 0,
+        ull_total_phys: 0,
+        ull_avail_phys: 0,
+        ull_total_page_file: 0,
+        ull_avail_page_file: 0,
+        ull_total_virtual: 0,
+        ull_avail_virtual: 0,
+        ull_avail_extended_virtual: 0,
+    };
+    // SAFETY: `status` is a valid, correctly sized, exclusively borrowed
+    // allocation with `dw_length` set as the API requires.
+    let ok = unsafe { GlobalMemoryStatusEx(&mut status) } != 0;
+    ok.then(|| status.dw_memory_load.min(100) as f32 / 100.0)
+}
+
+fn battery() -> Option<Battery> {
+    let mut status = SystemPowerStatus::default();
+    // SAFETY: a valid, exclusively borrowed out-parameter.
+    let ok = unsafe { GetSystemPowerStatus(&mut status) } != 0;
+    if !ok
+        || status.battery_flag == UNKNOWN_STATUS
+        || status.battery_flag & NO_SYSTEM_BATTERY != 0
+        || status.battery_life_percent > 100
+    {
+        return None;
+    }
+    Some(Battery {
+        level: f32::from(status.battery_life_percent) / 100.0,
+        discharging: status.ac_line_status == 0,
+    })
+}
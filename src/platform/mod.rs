@@ -17,9 +17,21 @@
 //! Wayland one must bypass winit entirely, because always-on-top, surface
 //! positioning and partial click-through are only reachable through
 //! `zwlr_layer_shell_v1`, which winit does not implement.
+//!
+//! Listening for desktop notifications is not part of the contract: it has
+//! nothing to do with the surface, and where a platform allows it at all it
+//! is its own [`notifications`] module. So is sampling CPU, memory and
+//! battery, in [`load`], counting keystrokes, in [`keyboard`], and global
+//! keyboard shortcuts, in [`shortcuts`]. Nor is finding a console for the
+//! command line to print to, in [`console`].
 
+pub mod console;
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 pub mod desktop;
+pub mod keyboard;
+pub mod load;
+pub mod notifications;
+pub mod shortcuts;
 #[cfg(target_os = "linux")]
 pub mod wayland;
 
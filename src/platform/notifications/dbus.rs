@@ -0,0 +1,186 @@
+//! Notifications on Linux, read off the session bus.
+//!
+//! Applications show a notification by calling `Notify` on
+//! `org.freedesktop.Notifications`. A connection that asks the bus to make it
+//! a monitor is sent a copy of every call matching its rules, without anything
+//! having to own that name or answer for it: the notification daemon carries
+//! on as if nobody were watching.
+//!
+//! A monitor connection can do nothing else, so this one is opened for the
+//! purpose and read on its own thread.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, channel};
+use zbus::blocking::fdo::MonitoringProxy;
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::zvariant::OwnedValue;
+use zbus::{MatchRule, Message};
+
+use crate::core::notify::{Notification, NotificationSource};
+
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+/// The arguments of `Notify`: app name, replaced id, icon, summary, body,
+/// actions, hints and timeout.
+type NotifyArgs = (
+    String,
+    u32,
+    String,
+    String,
+    String,
+    Vec<String>,
+    HashMap<String, OwnedValue>,
+    i32,
+);
+
+/// Notifications heard on a bus.
+///
+/// The receiver is `Send` but not `Sync`; polling takes `&mut self`, so the
+/// mutex is only there to make the source `Sync` and is never locked.
+pub struct DbusMonitor(Mutex<Receiver<Notification>>);
+
+impl DbusMonitor {
+    /// Listens on the session bus.
+    pub fn session() -> zbus::Result<Self> {
+        Self::on(Connection::session()?)
+    }
+
+    /// Turns `connection` into a monitor of `Notify` calls and listens on it.
+    pub fn on(connection: Connection) -> zbus::Result<Self> {
+        // Subscribed before becoming a monitor, so nothing sent in between is
+        // missed.
+        let messages = MessageIterator::from(&connection);
+        let rule = MatchRule::builder()
+            .msg_type(zbus::message::Type::MethodCall)
+            .interface(INTERFACE)?
+            .member("Notify")?
+            .build();
+        MonitoringProxy::new(&connection)?.become_monitor(&[rule], 0)?;
+
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            for message in messages.flatten() {
+                if let Some(notification) = notification(&message)
+                    && sender.send(notification).is_err()
+                {
+                    // The app is gone; nothing left to tell.
+                    break;
+                }
+            }
+        });
+        Ok(Self(Mutex::new(receiver)))
+    }
+}
+
+impl NotificationSource for DbusMonitor {
+    fn poll(&mut self) -> Vec<Notification> {
+        match self.0.get_mut() {
+            Ok(receiver) => receiver.try_iter().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// The notification a message shows, if it is a well-formed `Notify` call.
+fn notification(message: &Message) -> Option<Notification> {
+    if message.header().member()?.as_str() != "Notify" {
+        return None;
+    }
+    let (app, _, _, summary, ..): NotifyArgs = message.body().deserialize().ok()?;
+    Some(Notification { app, summary })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::time::{Duration, Instant};
+    use zbus::blocking::connection::Builder;
+    use zbus::message::Flags;
+
+    /// A private session bus, so the test neither needs nor disturbs the
+    /// user's.
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        fn connect(&self) -> Connection {
+            Builder::address(self.address.as_str())
+                .and_then(Builder::build)
+                .expect("the private bus accepts connections")
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[test]
+    fn notify_calls_are_heard() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("skipped: dbus-daemon is not installed");
+            return;
+        };
+        let mut monitor = DbusMonitor::on(bus.connect()).expect("becomes a monitor");
+
+        // Stands in for the notification daemon, so the call has somewhere to
+        // go. It never answers, which is why the call expects no reply.
+        let daemon = bus.connect();
+        daemon.request_name(INTERFACE).expect("name is free");
+        let args: NotifyArgs = (
+            "Signal".to_string(),
+            0,
+            String::new(),
+            "New message".to_string(),
+            "hello".to_string(),
+            Vec::new(),
+            HashMap::new(),
+            -1,
+        );
+        let call = Message::method_call("/org/freedesktop/Notifications", "Notify")
+            .and_then(|call| call.destination(INTERFACE))
+            .and_then(|call| call.interface(INTERFACE))
+            .and_then(|call| call.with_flags(Flags::NoReplyExpected))
+            .and_then(|call| call.build(&args))
+            .expect("a well-formed call");
+        let app = bus.connect();
+        app.send(&call).expect("sent");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut heard = Vec::new();
+        while heard.is_empty() && Instant::now() < deadline {
+            heard = monitor.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            heard,
+            [Notification {
+                app: "Signal".to_string(),
+                summary: "New message".to_string(),
+            }]
+        );
+    }
+}
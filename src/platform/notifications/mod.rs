@@ -0,0 +1,27 @@
+//! Hearing desktop notifications.
+//!
+//! Only Linux lets one application watch another's notifications without
+//! special permission: they are D-Bus calls on the session bus, which a monitor
+//! may read. macOS has no public API for it, and Windows only offers
+//! `UserNotificationListener` to packaged apps the user has granted access,
+//! which this is not. On both there is no source, and pets simply never react.
+
+#[cfg(target_os = "linux")]
+mod dbus;
+
+use crate::core::notify::NotificationSource;
+
+/// Starts listening for notifications, if this platform allows it.
+///
+/// A failure costs the reactions and nothing else, so it warns rather than
+/// aborting startup.
+pub fn listen() -> Option<Box<dyn NotificationSource>> {
+    #[cfg(target_os = "linux")]
+    {
+        match dbus::DbusMonitor::session() {
+            Ok(monitor) => return Some(Box::new(monitor)),
+            Err(error) => bevy::log::warn!("could not watch for notifications: {error}"),
+        }
+    }
+    None
+}
@@ -0,0 +1,216 @@
+//! Shortcuts on macOS, through Carbon's `RegisterEventHotKey`.
+//!
+//! Still the API for this: AppKit has nothing that hears a key while another
+//! application is frontmost, short of an event tap and the Input Monitoring
+//! permission it needs. A hotkey needs no permission, and its events go to
+//! the application event target, which the main thread's run loop dispatches.
+//! So this must be called on the main thread, and the handler runs there too.
+
+use std::ffi::c_void;
+use std::sync::mpsc;
+
+use crate::core::shortcuts::{Key, KeyCombo, Modifiers, Shortcut};
+
+type OSStatus = i32;
+type EventRef = *mut c_void;
+type EventTargetRef = *mut c_void;
+type EventHandlerCallRef = *mut c_void;
+type EventHotKeyRef = *mut c_void;
+type EventHandlerUPP =
+    extern "C" fn(call: EventHandlerCallRef, event: EventRef, user_data: *mut c_void) -> OSStatus;
+
+#[repr(C)]
+struct EventTypeSpec {
+    class: u32,
+    kind: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct EventHotKeyID {
+    signature: u32,
+    id: u32,
+}
+
+const NO_ERR: OSStatus = 0;
+/// `kEventClassKeyboard`, `'keyb'`.
+const KEYBOARD_CLASS: u32 = u32::from_be_bytes(*b"keyb");
+/// `kEventHotKeyPressed`.
+const HOT_KEY_PRESSED: u32 = 5;
+/// `kEventParamDirectObject`, `'----'`.
+const DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+/// `typeEventHotKeyID`, `'hkid'`.
+const HOT_KEY_ID_TYPE: u32 = u32::from_be_bytes(*b"hkid");
+/// Marks this app's hotkeys.
+const SIGNATURE: u32 = u32::from_be_bytes(*b"btts");
+
+const CMD_KEY: u32 = 0x0100;
+const SHIFT_KEY: u32 = 0x0200;
+const OPTION_KEY: u32 = 0x0800;
+const CONTROL_KEY: u32 = 0x1000;
+
+#[link(name = "Carbon", kind = "framework")]
+unsafe extern "C" {
+    fn GetApplicationEventTarget() -> EventTargetRef;
+    fn InstallEventHandler(
+        target: EventTargetRef,
+        handler: EventHandlerUPP,
+        count: u32,
+        types: *const EventTypeSpec,
+        user_data: *mut c_void,
+        out_ref: *mut *mut c_void,
+    ) -> OSStatus;
+    fn RegisterEventHotKey(
+        key_code: u32,
+        modifiers: u32,
+        id: EventHotKeyID,
+        target: EventTargetRef,
+        options: u32,
+        out_ref: *mut EventHotKeyRef,
+    ) -> OSStatus;
+    fn GetEventParameter(
+        event: EventRef,
+        name: u32,
+        desired_type: u32,
+        actual_type: *mut u32,
+        size: usize,
+        actual_size: *mut usize,
+        data: *mut c_void,
+    ) -> OSStatus;
+}
+
+/// What the handler is handed: where to send what it heard, and what each
+/// hotkey id means. Lives as long as the app.
+struct Hotkeys {
+    heard: mpsc::Sender<Shortcut>,
+    bindings: Vec<Shortcut>,
+}
+
+pub fn register(
+    bindings: &[(Shortcut, KeyCombo)],
+    heard: mpsc::Sender<Shortcut>,
+) -> Result<(), String> {
+    let mut registered = Vec::new();
+    for &(shortcut, combo) in bindings {
+        let Some(key_code) = key_code(combo.key) else {
+            continue;
+        };
+        let id = EventHotKeyID {
+            signature: SIGNATURE,
+            id: registered.len() as u32,
+        };
+        let mut hotkey = std::ptr::null_mut();
+        // SAFETY: a plain registration with the application target, on the
+        // main thread; the returned reference is kept for the app's life by
+        // never unregistering it.
+        let status = unsafe {
+            RegisterEventHotKey(
+                key_code,
+                modifiers(combo.modifiers),
+                id,
+                GetApplicationEventTarget(),
+                0,
+                &mut hotkey,
+            )
+        };
+        if status == NO_ERR {
+            registered.push(shortcut);
+        } else {
+            bevy::log::warn!(
+                "could not register {combo} for {}: error {status}",
+                shortcut.label()
+            );
+        }
+    }
+    if registered.is_empty() {
+        return Err("every shortcut was refused".to_string());
+    }
+
+    let hotkeys = Box::into_raw(Box::new(Hotkeys {
+        heard,
+        bindings: registered,
+    }));
+    let pressed = EventTypeSpec {
+        class: KEYBOARD_CLASS,
+        kind: HOT_KEY_PRESSED,
+    };
+    // SAFETY: `hotkeys` is leaked, so the pointer handed to the handler stays
+    // valid for as long as it can be called. The handler is only ever called
+    // on the main thread, which is this one.
+    let status = unsafe {
+        InstallEventHandler(
+            GetApplicationEventTarget(),
+            on_hotkey,
+            1,
+            &pressed,
+            hotkeys.cast(),
+            std::ptr::null_mut(),
+        )
+    };
+    if status != NO_ERR {
+        // SAFETY: never handed out, since installing failed.
+        drop(unsafe { Box::from_raw(hotkeys) });
+        return Err(format!("the hotkey handler was refused: error {status}"));
+    }
+    Ok(())
+}
+
+extern "C" fn on_hotkey(
+    _call: EventHandlerCallRef,
+    event: EventRef,
+    user_data: *mut c_void,
+) -> OSStatus {
+    let mut id = EventHotKeyID::default();
+    // SAFETY: `user_data` is the `Hotkeys` leaked by `register`, and `event`
+    // is valid for the duration of the call. `id` is the size asked for.
+    unsafe {
+        let hotkeys = &*(user_data as *const Hotkeys);
+        let status = GetEventParameter(
+            event,
+            DIRECT_OBJECT,
+            HOT_KEY_ID_TYPE,
+            std::ptr::null_mut(),
+            size_of::<EventHotKeyID>(),
+            std::ptr::null_mut(),
+            (&raw mut id).cast(),
+        );
+        if status == NO_ERR
+            && id.signature == SIGNATURE
+            && let Some(&shortcut) = hotkeys.bindings.get(id.id as usize)
+        {
+            let _ = hotkeys.heard.send(shortcut);
+        }
+    }
+    NO_ERR
+}
+
+fn modifiers(modifiers: Modifiers) -> u32 {
+    [
+        (Modifiers::CTRL, CONTROL_KEY),
+        (Modifiers::ALT, OPTION_KEY),
+        (Modifiers::SHIFT, SHIFT_KEY),
+        (Modifiers::SUPER, CMD_KEY),
+    ]
+    .into_iter()
+    .filter(|&(flag, _)| modifiers.contains(flag))
+    .fold(0, |bits, (_, bit)| bits | bit)
+}
+
+/// The `kVK_` code of the key in that place on an ANSI keyboard. Carbon
+/// names keys by where they are, not by what they type.
+fn key_code(key: Key) -> Option<u32> {
+    const LETTERS: [u32; 26] = [
+        0x00, 0x0B, 0x08, 0x02, 0x0E, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2E, 0x2D, 0x1F,
+        0x23, 0x0C, 0x0F, 0x01, 0x11, 0x20, 0x09, 0x0D, 0x07, 0x10, 0x06,
+    ];
+    const DIGITS: [u32; 10] = [0x1D, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+    const FUNCTION: [u32; 12] = [
+        0x7A, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6D, 0x67, 0x6F,
+    ];
+    match key {
+        Key::Char(c @ 'A'..='Z') => Some(LETTERS[(c as u8 - b'A') as usize]),
+        Key::Char(c @ '0'..='9') => Some(DIGITS[(c as u8 - b'0') as usize]),
+        Key::Char(_) => None,
+        Key::F(n) => FUNCTION.get(usize::from(n).checked_sub(1)?).copied(),
+    }
+}
@@ -0,0 +1,69 @@
+//! Global keyboard shortcuts, for using pets without a pointer.
+//!
+//! The overlay never has keyboard focus, so a shortcut must be registered
+//! with the system to reach it from whatever the user is typing in: with
+//! `RegisterHotKey` on Windows, and `RegisterEventHotKey` on macOS. Neither
+//! needs a permission, since the app is told about its own keys and nothing
+//! else.
+//!
+//! Wayland has no protocol for an application to register a key of its own;
+//! binding keys is the compositor's job. There the user binds keys to
+//! `batates --shortcut`, which reaches the app over the control socket, and
+//! nothing is registered here.
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "macos")]
+use macos as backend;
+#[cfg(target_os = "windows")]
+use windows as backend;
+
+use std::sync::Mutex;
+use std::sync::mpsc::Receiver;
+
+use crate::core::shortcuts::{KeyCombo, Shortcut, ShortcutSource};
+
+/// Shortcuts a backend heard, sent from wherever its callback runs.
+///
+/// The receiver is `Send` but not `Sync`, so it needs a mutex to live in a
+/// resource. There is exactly one reader, so the lock is never contended.
+// Never built on Linux, where nothing is registered.
+#[allow(dead_code)]
+struct Heard(Mutex<Receiver<Shortcut>>);
+
+impl ShortcutSource for Heard {
+    fn poll(&mut self) -> Vec<Shortcut> {
+        match self.0.get_mut() {
+            Ok(receiver) => receiver.try_iter().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Registers `bindings` with the system, if this platform has a way to.
+///
+/// A combo another application already holds is skipped with a warning, and
+/// the rest still work. Only when none could be registered is there no
+/// source.
+pub fn listen(bindings: &[(Shortcut, KeyCombo)]) -> Option<Box<dyn ShortcutSource>> {
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        match backend::register(bindings, sender) {
+            Ok(()) => return Some(Box::new(Heard(Mutex::new(receiver)))),
+            Err(reason) => bevy::log::warn!("no keyboard shortcuts: {reason}"),
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let _ = bindings;
+        bevy::log::info!(
+            "Wayland has no global shortcuts; bind keys in your compositor to \
+             `batates --shortcut <name>` instead"
+        );
+    }
+    None
+}
@@ -0,0 +1,122 @@
+//! Shortcuts on Windows, through `RegisterHotKey`.
+//!
+//! A hotkey registered without a window is posted to the message queue of
+//! the thread that registered it, so registering and listening happen on a
+//! thread of its own that does nothing but wait for `WM_HOTKEY`.
+
+use std::ffi::c_void;
+use std::sync::mpsc;
+
+use crate::core::shortcuts::{Key, KeyCombo, Modifiers, Shortcut};
+
+const MOD_ALT: u32 = 0x0001;
+const MOD_CONTROL: u32 = 0x0002;
+const MOD_SHIFT: u32 = 0x0004;
+const MOD_WIN: u32 = 0x0008;
+/// Holding the keys down fires once, not once per repeat.
+const MOD_NOREPEAT: u32 = 0x4000;
+const WM_HOTKEY: u32 = 0x0312;
+const VK_F1: u32 = 0x70;
+
+// Laid out for the API; only some fields are read.
+#[allow(dead_code)]
+#[repr(C)]
+struct Msg {
+    hwnd: *mut c_void,
+    message: u32,
+    w_param: usize,
+    l_param: isize,
+    time: u32,
+    pt_x: i32,
+    pt_y: i32,
+}
+
+#[link(name = "user32")]
+unsafe extern "system" {
+    fn RegisterHotKey(hwnd: *mut c_void, id: i32, modifiers: u32, vk: u32) -> i32;
+    fn GetMessageW(msg: *mut Msg, hwnd: *mut c_void, min: u32, max: u32) -> i32;
+}
+
+pub fn register(
+    bindings: &[(Shortcut, KeyCombo)],
+    heard: mpsc::Sender<Shortcut>,
+) -> Result<(), String> {
+    let bindings = bindings.to_vec();
+    let (started, result) = mpsc::channel();
+    std::thread::spawn(move || run_hotkeys(&bindings, heard, started));
+    result
+        .recv()
+        .unwrap_or_else(|_| Err("the shortcut thread stopped".to_string()))
+}
+
+/// Registers every binding on this thread and pumps its messages, reporting
+/// whether any could be registered first. A hotkey's id is its binding's
+/// index.
+fn run_hotkeys(
+    bindings: &[(Shortcut, KeyCombo)],
+    heard: mpsc::Sender<Shortcut>,
+    started: mpsc::Sender<Result<(), String>>,
+) {
+    let mut registered = 0;
+    for (id, (shortcut, combo)) in bindings.iter().enumerate() {
+        // SAFETY: no window, so the hotkey belongs to this thread; the id is
+        // unique among this thread's hotkeys.
+        let ok = unsafe {
+            RegisterHotKey(
+                std::ptr::null_mut(),
+                id as i32,
+                modifiers(combo.modifiers) | MOD_NOREPEAT,
+                virtual_key(combo.key),
+            )
+        } != 0;
+        if ok {
+            registered += 1;
+        } else {
+            let error = std::io::Error::last_os_error();
+            bevy::log::warn!(
+                "could not register {combo} for {}: {error}",
+                shortcut.label()
+            );
+        }
+    }
+    if registered == 0 {
+        let _ = started.send(Err("every shortcut was refused".to_string()));
+        return;
+    }
+    let _ = started.send(Ok(()));
+
+    let mut msg = std::mem::MaybeUninit::<Msg>::uninit();
+    // SAFETY: `msg` is a valid, exclusively borrowed allocation the size of
+    // a MSG.
+    while unsafe { GetMessageW(msg.as_mut_ptr(), std::ptr::null_mut(), 0, 0) } > 0 {
+        // SAFETY: a positive return means `GetMessageW` filled it in.
+        let msg = unsafe { msg.assume_init_ref() };
+        if msg.message == WM_HOTKEY
+            && let Some(&(shortcut, _)) = bindings.get(msg.w_param)
+            && heard.send(shortcut).is_err()
+        {
+            // The app is gone; nothing left to tell.
+            return;
+        }
+    }
+}
+
+fn modifiers(modifiers: Modifiers) -> u32 {
+    [
+        (Modifiers::CTRL, MOD_CONTROL),
+        (Modifiers::ALT, MOD_ALT),
+        (Modifiers::SHIFT, MOD_SHIFT),
+        (Modifiers::SUPER, MOD_WIN),
+    ]
+    .into_iter()
+    .filter(|&(flag, _)| modifiers.contains(flag))
+    .fold(0, |bits, (_, bit)| bits | bit)
+}
+
+/// Letters and digits are their own upper-case ASCII codes.
+fn virtual_key(key: Key) -> u32 {
+    match key {
+        Key::Char(c) => u32::from(c),
+        Key::F(n) => VK_F1 + u32::from(n) - 1,
+    }
+}
@@ -32,25 +32,34 @@ mod state;
 
 use bevy::camera::RenderTarget;
 use bevy::prelude::*;
+use std::time::Duration;
 use wayland_client::protocol::wl_surface;
 use wayland_client::{Connection, EventQueue};
+use wayland_protocols::ext::idle_notify::v1::client::ext_idle_notification_v1;
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 
+use crate::config::Config;
 use crate::core::PetSystems;
 use crate::core::coords::{
     MonitorGeometry, ScreenGeometry, ScreenLogical, SurfaceLogical, SurfaceOrigin,
 };
 use crate::core::hitbox::DesiredInputRegion;
+use crate::core::idle::UserActivity;
 use crate::core::input::{ButtonMask, PointerAt, PointerSample};
+use crate::core::touch::{TouchPhase, TouchSample};
 use crate::shell::shutdown::AppShutdown;
 use handles::WaylandSurfaceHandles;
-use state::{PointerEvent, WaylandState};
+use state::{PointerEvent, TouchEvent, WaylandState};
 
 /// Linux evdev button codes, as `wl_pointer.button` reports them.
 const BTN_LEFT: u32 = 0x110;
 const BTN_RIGHT: u32 = 0x111;
 const BTN_MIDDLE: u32 = 0x112;
 
+/// `wl_pointer.axis` units per line. Only a convention, but the one libinput
+/// follows for a wheel click; a touchpad sends whatever the finger moved.
+const AXIS_PER_LINE: f32 = 15.0;
+
 /// How this backend wants its window created: it does not.
 ///
 /// The overlay is a layer-shell surface this backend owns, so winit must not
@@ -84,16 +93,21 @@ struct WaylandConnection {
     state: WaylandState,
     surface: wl_surface::WlSurface,
     layer_surface: zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+    /// Fires `idled` once the seat has been quiet for the configured time.
+    idle_notification: Option<ext_idle_notification_v1::ExtIdleNotificationV1>,
 }
 
 /// The pointer's last known state, rebuilt from queued `wl_pointer` events
 /// once per frame. A resource rather than inline in the event struct because
 /// `wl_pointer.motion` reports absolute position, so only the latest sample
-/// matters — unlike buttons, which must not be dropped between polls.
+/// matters — unlike buttons, which must not be dropped between polls, and
+/// scrolling, which adds up until the next sample takes it.
 #[derive(Resource, Default)]
 struct WaylandPointerState {
     position: Option<Vec2>,
     buttons: ButtonMask,
+    /// Lines, positive away from the user.
+    scroll: f32,
 }
 
 /// Installs the Wayland backend.
@@ -101,7 +115,14 @@ pub struct WaylandBackendPlugin;
 
 impl Plugin for WaylandBackendPlugin {
     fn build(&self, app: &mut App) {
-        let connection = connect_and_create_surface();
+        // `main` inserts the config before adding any plugin.
+        let idle = app
+            .world()
+            .get_resource::<Config>()
+            .map(|config| config.idle)
+            .filter(|idle| idle.enabled)
+            .map(|idle| idle.away_after);
+        let connection = connect_and_create_surface(idle);
         let (width, height) = connection
             .state
             .configured_size
@@ -155,10 +176,13 @@ impl Plugin for WaylandBackendPlugin {
 /// Connects, binds the globals we need, and blocks until the compositor
 /// configures our layer surface with a size.
 ///
+/// `idle_after`, when set, asks the compositor to report the seat going idle
+/// for that long; see [`watch_idle`].
+///
 /// Blocking here — rather than deferring to a system — mirrors how
 /// [`crate::shell::tray::build_tray`] runs at plugin-build time: the surface
 /// must exist before `Startup` systems run, since they read its size.
-fn connect_and_create_surface() -> WaylandConnection {
+fn connect_and_create_surface(idle_after: Option<Duration>) -> WaylandConnection {
     let connection = Connection::connect_to_env()
         .expect("a Wayland session, already confirmed present by the startup probe");
     let mut queue = connection.new_event_queue::<WaylandState>();
@@ -168,6 +192,10 @@ fn connect_and_create_surface() -> WaylandConnection {
     connection.display().get_registry(&qh, ());
     // First roundtrip: registry globals arrive and get bound.
     queue.roundtrip(&mut state).expect("registry roundtrip");
+    // Pens are announced per seat, so asking needs both globals bound.
+    if let (Some(manager), Some(seat)) = (&state.tablet_manager, &state.seat) {
+        state.tablet_seat = Some(manager.get_tablet_seat(seat, &qh, ()));
+    }
     // Second roundtrip: the bound output/seat send their own events, and any
     // requests those made (like `wl_seat.get_pointer`) are flushed.
     queue
@@ -213,15 +241,40 @@ fn connect_and_create_surface() -> WaylandConnection {
             .expect("the compositor to configure the layer surface");
     }
 
+    let idle_notification = idle_after.and_then(|after| watch_idle(&state, after, &qh));
+
     WaylandConnection {
         connection,
         queue,
         state,
         surface,
         layer_surface,
+        idle_notification,
     }
 }
 
+/// Asks the compositor to say when the seat has been idle for `after`.
+///
+/// Wayland offers no "time since last input" to poll; the threshold is fixed
+/// here, up front, and the compositor reports crossing it in both directions.
+/// The plain (not input-only) notification is deliberate: it honours idle
+/// inhibitors, so someone watching a video does not count as away.
+///
+/// `ext-idle-notify-v1` is optional. Without it the pets just never notice
+/// the user leaving, which is worth a line in the log but nothing more.
+fn watch_idle(
+    state: &WaylandState,
+    after: Duration,
+    qh: &wayland_client::QueueHandle<WaylandState>,
+) -> Option<ext_idle_notification_v1::ExtIdleNotificationV1> {
+    let (Some(notifier), Some(seat)) = (state.idle_notifier.as_ref(), state.seat.as_ref()) else {
+        info!("compositor lacks ext-idle-notify-v1; pets will not notice you are away");
+        return None;
+    };
+    let timeout = u32::try_from(after.as_millis()).unwrap_or(u32::MAX);
+    Some(notifier.get_idle_notification(timeout, seat, qh, ()))
+}
+
 /// Assigns the offscreen render target to the main camera.
 ///
 /// Ordered after [`crate::camera::spawn_camera`] because the camera does not
@@ -238,11 +291,18 @@ fn assign_camera_target(
     }
 }
 
-/// Drains queued Wayland events into the frame's pointer state, and turns a
-/// compositor-requested close into the app's one shutdown path.
+/// Drains queued Wayland events into the frame's pointer state and touch
+/// samples, and turns a compositor-requested close into the app's one
+/// shutdown path.
+///
+/// Touch events are published as they come rather than folded into one
+/// sample: every finger's `Down` and `Up` matters, not just the latest.
 fn pump_wayland_events(
+    time: Res<Time>,
     mut connection: NonSendMut<WaylandConnection>,
     mut pointer: ResMut<WaylandPointerState>,
+    mut touches: MessageWriter<TouchSample>,
+    mut activity: MessageWriter<UserActivity>,
     mut shutdown: MessageWriter<AppShutdown>,
 ) {
     let connection = &mut *connection;
@@ -267,9 +327,30 @@ fn pump_wayland_events(
                     pointer.buttons.set(button, pressed);
                 }
             }
+            PointerEvent::Scroll(value) => pointer.scroll -= value / AXIS_PER_LINE,
         }
     }
 
+    let at_time = time.elapsed();
+    touches.write_batch(connection.state.touch_events.drain(..).map(|event| {
+        let (id, phase, at) = match event {
+            TouchEvent::Down { id, at } => (id, TouchPhase::Down, Some(at)),
+            TouchEvent::Motion { id, at } => (id, TouchPhase::Moved, Some(at)),
+            TouchEvent::Up { id } => (id, TouchPhase::Up, None),
+            TouchEvent::Cancel { id } => (id, TouchPhase::Cancelled, None),
+        };
+        TouchSample {
+            id,
+            phase,
+            at: at.map_or(PointerAt::Absent, |at| {
+                PointerAt::Surface(SurfaceLogical(at))
+            }),
+            at_time,
+        }
+    }));
+
+    activity.write_batch(connection.state.idle_events.drain(..));
+
     if connection.state.closed {
         shutdown.write(AppShutdown);
     }
@@ -279,7 +360,7 @@ fn pump_wayland_events(
 /// already the space `wl_pointer` reports in, so no conversion is needed.
 fn sample_pointer(
     time: Res<Time>,
-    pointer: Res<WaylandPointerState>,
+    mut pointer: ResMut<WaylandPointerState>,
     mut samples: MessageWriter<PointerSample>,
 ) {
     let at = match pointer.position {
@@ -289,6 +370,7 @@ fn sample_pointer(
     samples.write(PointerSample {
         at,
         buttons: pointer.buttons,
+        scroll: std::mem::take(&mut pointer.scroll),
         at_time: time.elapsed(),
     });
 }
@@ -331,6 +413,9 @@ fn apply_input_region(
 
 impl Drop for WaylandConnection {
     fn drop(&mut self) {
+        if let Some(notification) = &self.idle_notification {
+            notification.destroy();
+        }
         self.layer_surface.destroy();
         self.surface.destroy();
     }
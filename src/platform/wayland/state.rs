@@ -8,18 +8,36 @@
 //! are actually available.
 
 use bevy::math::Vec2;
+use std::collections::HashMap;
 use wayland_client::protocol::{
-    wl_compositor, wl_output, wl_pointer, wl_registry, wl_seat, wl_surface,
+    wl_compositor, wl_output, wl_pointer, wl_registry, wl_seat, wl_surface, wl_touch,
+};
+use wayland_client::{
+    Connection, Dispatch, Proxy, QueueHandle, delegate_noop, event_created_child,
+};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1, ext_idle_notifier_v1,
+};
+use wayland_protocols::wp::tablet::zv2::client::{
+    zwp_tablet_manager_v2, zwp_tablet_pad_group_v2, zwp_tablet_pad_ring_v2,
+    zwp_tablet_pad_strip_v2, zwp_tablet_pad_v2, zwp_tablet_seat_v2, zwp_tablet_tool_v2,
+    zwp_tablet_v2,
 };
-use wayland_client::{Connection, Dispatch, QueueHandle, delegate_noop};
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 
 use crate::core::coords::MonitorGeometry;
+use crate::core::idle::UserActivity;
 
 const COMPOSITOR: &str = "wl_compositor";
 const LAYER_SHELL: &str = "zwlr_layer_shell_v1";
 const SEAT: &str = "wl_seat";
 const OUTPUT: &str = "wl_output";
+const IDLE_NOTIFIER: &str = "ext_idle_notifier_v1";
+const TABLET_MANAGER: &str = "zwp_tablet_manager_v2";
+
+/// Added to a pen's object id to make its touch id, so pens and fingers,
+/// whose ids count up from zero, never share one.
+const PEN_IDS: u64 = 1 << 32;
 
 /// A pointer event queued for the next frame's [`crate::core::input::PointerSample`].
 ///
@@ -30,7 +48,30 @@ pub enum PointerEvent {
     Enter(Vec2),
     Motion(Vec2),
     Leave,
-    Button { code: u32, pressed: bool },
+    Button {
+        code: u32,
+        pressed: bool,
+    },
+    /// Vertical scrolling, in `wl_pointer.axis` units: positive is down.
+    Scroll(f32),
+}
+
+/// A finger or pen event queued for the next frame's
+/// [`crate::core::touch::TouchSample`]s, in surface-local logical pixels.
+#[derive(Debug, Clone, Copy)]
+pub enum TouchEvent {
+    Down { id: u64, at: Vec2 },
+    Motion { id: u64, at: Vec2 },
+    Up { id: u64 },
+    Cancel { id: u64 },
+}
+
+/// A pen in range of a tablet. Its position arrives whether or not it
+/// touches, so it is kept for the moment it does.
+#[derive(Debug, Clone, Copy, Default)]
+struct Pen {
+    at: Vec2,
+    down: bool,
 }
 
 /// In-progress `wl_output` geometry, assembled across several events and only
@@ -48,7 +89,13 @@ pub struct WaylandState {
     pub layer_shell: Option<zwlr_layer_shell_v1::ZwlrLayerShellV1>,
     pub seat: Option<wl_seat::WlSeat>,
     pub pointer: Option<wl_pointer::WlPointer>,
+    pub touch: Option<wl_touch::WlTouch>,
+    /// Optional: without it a pen on a tablet does nothing.
+    pub tablet_manager: Option<zwp_tablet_manager_v2::ZwpTabletManagerV2>,
+    pub tablet_seat: Option<zwp_tablet_seat_v2::ZwpTabletSeatV2>,
     pub output: Option<wl_output::WlOutput>,
+    /// Optional: without it pets simply never notice the user is away.
+    pub idle_notifier: Option<ext_idle_notifier_v1::ExtIdleNotifierV1>,
     output_draft: OutputDraft,
     /// Finalized on the output's `Done` event. `None` beforehand: a v1
     /// backend supports exactly one output, so there is nothing to fall back
@@ -60,6 +107,12 @@ pub struct WaylandState {
     /// The compositor asked us to close.
     pub closed: bool,
     pub pointer_events: Vec<PointerEvent>,
+    pub touch_events: Vec<TouchEvent>,
+    /// Fingers down, so a cancel, which names none, can lift each of them.
+    fingers: Vec<u64>,
+    pens: HashMap<u64, Pen>,
+    /// `Idled` and `Resumed` only; the compositor applies the threshold.
+    pub idle_events: Vec<UserActivity>,
 }
 
 impl WaylandState {
@@ -69,12 +122,20 @@ impl WaylandState {
             layer_shell: None,
             seat: None,
             pointer: None,
+            touch: None,
+            tablet_manager: None,
+            tablet_seat: None,
             output: None,
+            idle_notifier: None,
             output_draft: OutputDraft::default(),
             monitor: None,
             configured_size: None,
             closed: false,
             pointer_events: Vec::new(),
+            touch_events: Vec::new(),
+            fingers: Vec::new(),
+            pens: HashMap::new(),
+            idle_events: Vec::new(),
         }
     }
 }
@@ -100,6 +161,12 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
             COMPOSITOR => state.compositor = Some(registry.bind(name, version.min(4), qh, ())),
             LAYER_SHELL => state.layer_shell = Some(registry.bind(name, version.min(4), qh, ())),
             SEAT => state.seat = Some(registry.bind(name, version.min(7), qh, ())),
+            IDLE_NOTIFIER => {
+                state.idle_notifier = Some(registry.bind(name, version.min(1), qh, ()));
+            }
+            TABLET_MANAGER => {
+                state.tablet_manager = Some(registry.bind(name, version.min(1), qh, ()));
+            }
             // A v1 backend supports a single output; later globals are ignored.
             OUTPUT if state.output.is_none() => {
                 state.output = Some(registry.bind(name, version.min(3), qh, ()));
@@ -154,13 +221,15 @@ impl Dispatch<wl_seat::WlSeat, ()> for WaylandState {
         let wl_seat::Event::Capabilities { capabilities } = event else {
             return;
         };
-        let has_pointer = capabilities
+        let capabilities = capabilities
             .into_result()
-            .map(|caps| caps.contains(wl_seat::Capability::Pointer))
-            .unwrap_or(false);
-        if has_pointer && state.pointer.is_none() {
+            .unwrap_or(wl_seat::Capability::empty());
+        if capabilities.contains(wl_seat::Capability::Pointer) && state.pointer.is_none() {
             state.pointer = Some(seat.get_pointer(qh, ()));
         }
+        if capabilities.contains(wl_seat::Capability::Touch) && state.touch.is_none() {
+            state.touch = Some(seat.get_touch(qh, ()));
+        }
     }
 }
 
@@ -201,6 +270,13 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandState {
                     pressed: btn == wl_pointer::ButtonState::Pressed,
                 });
             }
+            wl_pointer::Event::Axis {
+                axis: wayland_client::WEnum::Value(wl_pointer::Axis::VerticalScroll),
+                value,
+                ..
+            } => state
+                .pointer_events
+                .push(PointerEvent::Scroll(value as f32)),
             _ => {}
         }
     }
@@ -230,7 +306,165 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for WaylandState {
     }
 }
 
+impl Dispatch<ext_idle_notification_v1::ExtIdleNotificationV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &ext_idle_notification_v1::ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => state.idle_events.push(UserActivity::Idled),
+            ext_idle_notification_v1::Event::Resumed => {
+                state.idle_events.push(UserActivity::Resumed);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_touch::WlTouch, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &wl_touch::WlTouch,
+        event: wl_touch::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_touch::Event::Down { id, x, y, .. } => {
+                let id = u64::from(id as u32);
+                state.fingers.push(id);
+                state.touch_events.push(TouchEvent::Down {
+                    id,
+                    at: Vec2::new(x as f32, y as f32),
+                });
+            }
+            wl_touch::Event::Motion { id, x, y, .. } => {
+                state.touch_events.push(TouchEvent::Motion {
+                    id: u64::from(id as u32),
+                    at: Vec2::new(x as f32, y as f32),
+                });
+            }
+            wl_touch::Event::Up { id, .. } => {
+                let id = u64::from(id as u32);
+                state.fingers.retain(|&finger| finger != id);
+                state.touch_events.push(TouchEvent::Up { id });
+            }
+            wl_touch::Event::Cancel => {
+                for id in state.fingers.drain(..) {
+                    state.touch_events.push(TouchEvent::Cancel { id });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_tablet_seat_v2::ZwpTabletSeatV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &zwp_tablet_seat_v2::ZwpTabletSeatV2,
+        _: zwp_tablet_seat_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Tablets, pens and pads each arrive as an object of their own; only
+        // a pen's events mean anything here.
+    }
+
+    event_created_child!(WaylandState, zwp_tablet_seat_v2::ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (zwp_tablet_v2::ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (zwp_tablet_tool_v2::ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (zwp_tablet_pad_v2::ZwpTabletPadV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_tool_v2::ZwpTabletToolV2, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        tool: &zwp_tablet_tool_v2::ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let id = PEN_IDS + u64::from(tool.id().protocol_id());
+        if let zwp_tablet_tool_v2::Event::Removed = event {
+            if state.pens.remove(&id).is_some_and(|pen| pen.down) {
+                state.touch_events.push(TouchEvent::Cancel { id });
+            }
+            return;
+        }
+        let pen = state.pens.entry(id).or_default();
+        match event {
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                pen.at = Vec2::new(x as f32, y as f32);
+                if pen.down {
+                    state
+                        .touch_events
+                        .push(TouchEvent::Motion { id, at: pen.at });
+                }
+            }
+            zwp_tablet_tool_v2::Event::Down { .. } => {
+                pen.down = true;
+                state.touch_events.push(TouchEvent::Down { id, at: pen.at });
+            }
+            // Leaving range while touching is lifting, just without an `Up`.
+            zwp_tablet_tool_v2::Event::Up | zwp_tablet_tool_v2::Event::ProximityOut if pen.down => {
+                pen.down = false;
+                state.touch_events.push(TouchEvent::Up { id });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_tablet_pad_v2::ZwpTabletPadV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &zwp_tablet_pad_v2::ZwpTabletPadV2,
+        _: zwp_tablet_pad_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Pads are buttons and rings beside the tablet, not a place on the
+        // screen. Their objects must still be accepted as they arrive.
+    }
+
+    event_created_child!(WaylandState, zwp_tablet_pad_v2::ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, ()> for WaylandState {
+    fn event(
+        _: &mut Self,
+        _: &zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2,
+        _: zwp_tablet_pad_group_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+
+    event_created_child!(WaylandState, zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2, ()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2, ()),
+    ]);
+}
+
 delegate_noop!(WaylandState: ignore wl_compositor::WlCompositor);
 delegate_noop!(WaylandState: ignore wl_surface::WlSurface);
 delegate_noop!(WaylandState: ignore zwlr_layer_shell_v1::ZwlrLayerShellV1);
+delegate_noop!(WaylandState: ignore ext_idle_notifier_v1::ExtIdleNotifierV1);
+delegate_noop!(WaylandState: ignore zwp_tablet_manager_v2::ZwpTabletManagerV2);
+delegate_noop!(WaylandState: ignore zwp_tablet_v2::ZwpTabletV2);
+delegate_noop!(WaylandState: ignore zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2);
+delegate_noop!(WaylandState: ignore zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2);
 delegate_noop!(WaylandState: ignore wayland_client::protocol::wl_region::WlRegion);
@@ -0,0 +1,187 @@
+//! ECS glue for skin scripts: calling their hooks, and doing what they ask.
+//!
+//! The script itself, and what it may do, is `skin::script`. Nothing here runs
+//! for a skin without one.
+
+use bevy::prelude::*;
+
+use crate::core::PetSystems;
+use crate::core::brain::{PetBrain, PetState};
+use crate::core::input::Intent;
+use crate::core::menu::PetAction;
+use crate::core::rng::PetRng;
+use crate::pet::{MoveTarget, Paused, PendingInterrupt, Pet, StateEntered};
+use crate::skin::Skin;
+use crate::skin::script::{Hook, PetView, ScriptAction, SkinScript};
+use crate::speech::Say;
+
+type ScriptedPet<'a> = (
+    Entity,
+    &'a PetBrain,
+    &'a Transform,
+    &'a mut PendingInterrupt,
+    &'a mut MoveTarget,
+);
+
+pub struct ScriptPlugin;
+
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            // After the app's own handling of intents, so a script has the
+            // last word.
+            (
+                script_intents,
+                script_ticks.run_if(resource_equals(Paused(false))),
+            )
+                .chain()
+                .run_if(has_script)
+                .after(PetSystems::Normalize)
+                .before(PetSystems::Brain),
+        )
+        .add_systems(
+            Update,
+            script_entries
+                .run_if(has_script)
+                .after(PetSystems::Brain)
+                .before(PetSystems::Enter),
+        );
+    }
+}
+
+fn has_script(skin: Res<Skin>) -> bool {
+    skin.script.is_some()
+}
+
+/// The hook argument for an intent aimed at one pet, if scripts hear of it.
+fn intent_kind(intent: &Intent) -> Option<(Entity, &'static str)> {
+    let named = match *intent {
+        Intent::SummonPet { pet, .. } => (pet, "summon"),
+        Intent::Grab { pet, .. } => (pet, "grab"),
+        Intent::Release { pet } => (pet, "release"),
+        Intent::Pet { pet } => (pet, "pet"),
+        Intent::Poke { pet } => (pet, "poke"),
+        Intent::Act { pet, action } => {
+            let kind = match action {
+                PetAction::Feed => "feed",
+                PetAction::Pet => "pet",
+                PetAction::Play => "play",
+                PetAction::Sleep => "sleep",
+                PetAction::Follow => "follow",
+                PetAction::Remove => return None,
+            };
+            (pet, kind)
+        }
+        _ => return None,
+    };
+    Some(named)
+}
+
+fn script_intents(
+    skin: Res<Skin>,
+    mut intents: MessageReader<Intent>,
+    mut rng: ResMut<PetRng>,
+    mut says: MessageWriter<Say>,
+    mut pets: Query<ScriptedPet, With<Pet>>,
+) {
+    let Some(script) = &skin.script else {
+        return;
+    };
+    for (pet, kind) in intents.read().filter_map(intent_kind) {
+        if let Ok((pet, brain, transform, mut interrupt, mut target)) = pets.get_mut(pet) {
+            let actions = call(script, Hook::Intent(kind), brain, transform, &mut rng);
+            apply(actions, pet, brain, &mut interrupt, &mut target, &mut says);
+        }
+    }
+}
+
+fn script_ticks(
+    skin: Res<Skin>,
+    time: Res<Time>,
+    mut rng: ResMut<PetRng>,
+    mut says: MessageWriter<Say>,
+    mut pets: Query<ScriptedPet, With<Pet>>,
+) {
+    let Some(script) = &skin.script else {
+        return;
+    };
+    let hook = Hook::Tick(time.delta());
+    for (pet, brain, transform, mut interrupt, mut target) in &mut pets {
+        let actions = call(script, hook, brain, transform, &mut rng);
+        apply(actions, pet, brain, &mut interrupt, &mut target, &mut says);
+    }
+}
+
+fn script_entries(
+    skin: Res<Skin>,
+    mut entries: MessageReader<StateEntered>,
+    mut rng: ResMut<PetRng>,
+    mut says: MessageWriter<Say>,
+    mut pets: Query<ScriptedPet, With<Pet>>,
+) {
+    let Some(script) = &skin.script else {
+        return;
+    };
+    for entry in entries.read() {
+        if let Ok((pet, brain, transform, mut interrupt, mut target)) = pets.get_mut(entry.pet) {
+            let actions = call(script, Hook::Enter(entry.state), brain, transform, &mut rng);
+            apply(actions, pet, brain, &mut interrupt, &mut target, &mut says);
+        }
+    }
+}
+
+/// Calls `hook` for one pet. A script that fails is reported once; after
+/// that it asks for nothing.
+fn call(
+    script: &SkinScript,
+    hook: Hook,
+    brain: &PetBrain,
+    transform: &Transform,
+    rng: &mut PetRng,
+) -> Vec<ScriptAction> {
+    let pet = PetView {
+        state: brain.state,
+        position: transform.translation.truncate(),
+    };
+    script.run(hook, pet, rng).unwrap_or_else(|error| {
+        warn!("skin script failed and is switched off: {error}");
+        Vec::new()
+    })
+}
+
+/// Does what a hook asked of `pet`.
+///
+/// A held pet is the user's, and one locked asleep for an absent user stays
+/// put: a script can still have either speak, but cannot move it or change
+/// its state.
+fn apply(
+    actions: Vec<ScriptAction>,
+    pet: Entity,
+    brain: &PetBrain,
+    interrupt: &mut PendingInterrupt,
+    target: &mut MoveTarget,
+    says: &mut MessageWriter<Say>,
+) {
+    // As for an errand, a pending interrupt is the state the pet is about to
+    // be in: a grab this frame counts.
+    let left_alone = brain.locked || interrupt.0.unwrap_or(brain.state) == PetState::Dragged;
+    for action in actions {
+        match action {
+            ScriptAction::Enter(state) if !left_alone => {
+                interrupt.0 = Some(state);
+            }
+            ScriptAction::WalkTo(to) if !left_alone => {
+                target.0 = Some(to);
+                interrupt.0 = Some(PetState::Walking);
+            }
+            ScriptAction::Say(text) => {
+                says.write(Say {
+                    pet: Some(pet),
+                    text,
+                });
+            }
+            ScriptAction::Enter(_) | ScriptAction::WalkTo(_) => {}
+        }
+    }
+}
@@ -1,13 +1,15 @@
-//! Single instance, and `batates --quit`.
+//! Single instance, and controlling it from the command line.
 //!
 //! A local socket serves both purposes: if connecting succeeds, an instance is
 //! already running, which is what makes a second launch refuse to start and
-//! what lets `--quit` ask the first one to exit.
+//! what lets `--quit`, `--drop`, `--say`, `--pomodoro` or `--shortcut` reach
+//! the first one.
 //!
 //! This is the quit path that always works. The tray needs a StatusNotifierItem
 //! host, which not every Linux session runs, and a global hotkey has no Wayland
 //! equivalent at all; a socket has neither problem, so it is what the
-//! documentation points people at for binding a key in their compositor.
+//! documentation points people at for binding a key in their compositor, to
+//! quit or to act on a pet with `--shortcut`.
 
 use bevy::prelude::*;
 use interprocess::local_socket::traits::Stream;
@@ -17,11 +19,115 @@ use interprocess::local_socket::{
 use std::io::{Read, Write};
 use std::sync::Mutex;
 use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::time::Duration;
 
+use crate::core::items::ItemKind;
+use crate::core::shortcuts::{Shortcut, ShortcutPressed};
+use crate::core::timers::{self, TimerKind, TimerSpec};
+use crate::items::DropItem;
 use crate::shell::shutdown::AppShutdown;
+use crate::speech::Say;
+use crate::timers::AddTimer;
 
-/// What a client may ask of a running instance.
-const QUIT_COMMAND: &[u8] = b"quit\n";
+/// What a client may ask of a running instance, one per connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcCommand {
+    Quit,
+    /// Drop an item somewhere visible.
+    Drop(ItemKind),
+    /// Show a message in a pet's speech bubble, such as "build finished".
+    Say(String),
+    /// Start a timer. Only whole minutes cross the wire, and the pet plays
+    /// the default state.
+    Timer(TimerSpec),
+    /// Act as if a keyboard shortcut had been pressed.
+    Shortcut(Shortcut),
+}
+
+impl IpcCommand {
+    /// The line sent over the socket. Plain text, so `socat` can drive it too.
+    pub fn encode(&self) -> String {
+        match self {
+            IpcCommand::Quit => "quit\n".to_string(),
+            IpcCommand::Drop(kind) => format!("drop {}\n", kind.label().to_lowercase()),
+            IpcCommand::Say(text) => format!("say {}\n", one_line(text)),
+            IpcCommand::Timer(spec) => match spec.kind {
+                TimerKind::Once { after } => format!(
+                    "timer once {} {}\n",
+                    minutes(after),
+                    one_line(&spec.message)
+                ),
+                TimerKind::Every { period } => format!(
+                    "timer every {} {}\n",
+                    minutes(period),
+                    one_line(&spec.message)
+                ),
+                TimerKind::Pomodoro { work, rest } => {
+                    format!("timer pomodoro {} {}\n", minutes(work), minutes(rest))
+                }
+            },
+            IpcCommand::Shortcut(shortcut) => format!("shortcut {}\n", shortcut.label()),
+        }
+    }
+
+    /// Reads a received line. Anything unrecognised is `None` and ignored, so
+    /// an old instance is not confused by a newer client.
+    pub fn parse(line: &str) -> Option<Self> {
+        // The message is the rest of the line, spacing and all.
+        if let Some(text) = line.trim().strip_prefix("say ") {
+            return Some(IpcCommand::Say(text.trim().to_string()));
+        }
+        if let Some(rest) = line.trim().strip_prefix("timer ") {
+            return parse_timer(rest).map(IpcCommand::Timer);
+        }
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["quit"] => Some(IpcCommand::Quit),
+            ["drop", item] => item.parse().ok().map(IpcCommand::Drop),
+            ["shortcut", name] => name.parse().ok().map(IpcCommand::Shortcut),
+            _ => None,
+        }
+    }
+}
+
+/// A message as one line, whatever it held.
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn minutes(duration: Duration) -> u64 {
+    duration.as_secs() / 60
+}
+
+/// Reads what follows `timer `: `once` or `every`, minutes and a message, or
+/// `pomodoro` and the minutes of work and of rest.
+fn parse_timer(rest: &str) -> Option<TimerSpec> {
+    let positive = |word: &str| {
+        word.parse::<u64>()
+            .ok()
+            .filter(|&minutes| minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes.saturating_mul(60)))
+    };
+    let mut words = rest.trim().splitn(3, ' ');
+    let (kind, first) = (words.next()?, positive(words.next()?)?);
+    let tail = words.next().unwrap_or("").trim();
+
+    let kind = match kind {
+        "once" => TimerKind::Once { after: first },
+        "every" => TimerKind::Every { period: first },
+        "pomodoro" => return Some(TimerSpec::pomodoro(first, positive(tail)?)),
+        _ => return None,
+    };
+    let message = if tail.is_empty() {
+        timers::DEFAULT_MESSAGE
+    } else {
+        tail
+    };
+    Some(TimerSpec {
+        kind,
+        message: message.to_string(),
+        state: timers::DEFAULT_STATE,
+    })
+}
 
 /// The socket name. Namespaced rather than a filesystem path so the same code
 /// works against a Windows named pipe.
@@ -29,15 +135,15 @@ fn socket_name() -> std::io::Result<interprocess::local_socket::Name<'static>> {
     "batates.sock".to_ns_name::<GenericNamespaced>()
 }
 
-/// Asks a running instance to quit.
+/// Sends `command` to a running instance.
 ///
-/// `Ok(false)` means nothing was listening, which is not an error: asking a
-/// stopped app to stop has already succeeded.
-pub fn request_quit() -> std::io::Result<bool> {
+/// `Ok(false)` means nothing was listening. For `quit` that is not an error:
+/// asking a stopped app to stop has already succeeded.
+pub fn send_command(command: &IpcCommand) -> std::io::Result<bool> {
     let name = socket_name()?;
     match LocalStream::connect(name) {
         Ok(mut stream) => {
-            stream.write_all(QUIT_COMMAND)?;
+            stream.write_all(command.encode().as_bytes())?;
             Ok(true)
         }
         Err(_) => Ok(false),
@@ -57,9 +163,9 @@ pub fn instance_running() -> bool {
 /// The receiver is `Send` but not `Sync`, so it needs a mutex to live in a
 /// resource. There is exactly one reader, so the lock is never contended.
 #[derive(Resource)]
-pub struct IpcCommands(Mutex<Receiver<()>>);
+pub struct IpcCommands(Mutex<Receiver<IpcCommand>>);
 
-/// Starts listening for `--quit` requests.
+/// Starts listening for commands from other processes.
 ///
 /// The listener blocks, so it lives on its own thread and reports through a
 /// channel the app polls. A failure here costs the `--quit` path but nothing
@@ -97,8 +203,8 @@ pub fn start_listener() -> Option<IpcCommands> {
             let Ok(mut stream) = connection else { continue };
             let mut request = String::new();
             if stream.read_to_string(&mut request).is_ok()
-                && request.trim() == "quit"
-                && sender.send(()).is_err()
+                && let Some(command) = IpcCommand::parse(&request)
+                && sender.send(command).is_err()
             {
                 // The app is gone; nothing left to serve.
                 break;
@@ -109,18 +215,91 @@ pub fn start_listener() -> Option<IpcCommands> {
     Some(IpcCommands(Mutex::new(receiver)))
 }
 
-/// Turns a received command into a shutdown request.
-pub fn poll_ipc(commands: Option<Res<IpcCommands>>, mut shutdown: MessageWriter<AppShutdown>) {
+/// Turns received commands into app messages.
+pub fn poll_ipc(
+    commands: Option<Res<IpcCommands>>,
+    mut drops: MessageWriter<DropItem>,
+    mut says: MessageWriter<Say>,
+    mut timers: MessageWriter<AddTimer>,
+    mut shortcuts: MessageWriter<ShortcutPressed>,
+    mut shutdown: MessageWriter<AppShutdown>,
+) {
     let Some(commands) = commands else { return };
     let Ok(receiver) = commands.0.lock() else {
         return;
     };
     loop {
         match receiver.try_recv() {
-            Ok(()) => {
+            Ok(IpcCommand::Quit) => {
                 shutdown.write(AppShutdown);
             }
+            Ok(IpcCommand::Drop(kind)) => {
+                drops.write(DropItem {
+                    kind,
+                    at: None,
+                    for_pet: None,
+                });
+            }
+            Ok(IpcCommand::Say(text)) => {
+                says.write(Say { pet: None, text });
+            }
+            Ok(IpcCommand::Timer(spec)) => {
+                timers.write(AddTimer(spec));
+            }
+            Ok(IpcCommand::Shortcut(shortcut)) => {
+                shortcuts.write(ShortcutPressed(shortcut));
+            }
             Err(TryRecvError::Empty | TryRecvError::Disconnected) => return,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_command_survives_the_wire() {
+        let commands = [
+            IpcCommand::Quit,
+            IpcCommand::Say("build finished: 0 warnings".to_string()),
+            IpcCommand::Timer(TimerSpec::pomodoro(
+                Duration::from_secs(50 * 60),
+                Duration::from_secs(10 * 60),
+            )),
+            IpcCommand::Timer(TimerSpec {
+                kind: TimerKind::Every {
+                    period: Duration::from_secs(30 * 60),
+                },
+                message: "drink some water".to_string(),
+                state: timers::DEFAULT_STATE,
+            }),
+        ]
+        .into_iter()
+        .chain(ItemKind::ALL.into_iter().map(IpcCommand::Drop))
+        .chain(Shortcut::ALL.into_iter().map(IpcCommand::Shortcut));
+        for command in commands {
+            assert_eq!(IpcCommand::parse(&command.encode()), Some(command));
+        }
+    }
+
+    #[test]
+    fn unknown_commands_are_ignored() {
+        assert_eq!(IpcCommand::parse("dance\n"), None);
+        assert_eq!(IpcCommand::parse("drop cake\n"), None);
+        assert_eq!(IpcCommand::parse("shortcut dance\n"), None);
+        assert_eq!(IpcCommand::parse("quit now\n"), None);
+        assert_eq!(IpcCommand::parse("timer once 0 tea\n"), None);
+        assert_eq!(IpcCommand::parse("timer daily 5 tea\n"), None);
+        assert_eq!(IpcCommand::parse("timer pomodoro 25\n"), None);
+        assert_eq!(IpcCommand::parse(""), None);
+    }
+
+    /// A message is one line on the wire, however it was written.
+    #[test]
+    fn said_text_is_sent_on_one_line() {
+        let sent = IpcCommand::Say("build\nfinished".to_string()).encode();
+        assert_eq!(sent, "say build finished\n");
+        assert_eq!(IpcCommand::parse("say"), None);
+    }
+}
@@ -6,6 +6,7 @@ pub mod tray;
 
 use bevy::prelude::*;
 
+use crate::config::Config;
 use shutdown::AppShutdown;
 
 /// Tray, signals, and the control socket.
@@ -31,7 +32,9 @@ impl Plugin for ShellPlugin {
         // Both are created here rather than in a startup system: the tray
         // handle is not thread-safe and must be owned by the main thread, which
         // is where plugin construction runs.
-        if let Some(tray) = tray::build_tray() {
+        // Config is inserted before any plugin is added, so it is there to read.
+        let config = app.world().resource::<Config>();
+        if let Some(tray) = tray::build_tray(config) {
             app.insert_non_send(tray);
         }
 
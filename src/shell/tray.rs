@@ -5,13 +5,37 @@
 //!
 //! Menu events arrive on a global channel from the platform's own event loop
 //! rather than through Bevy, so they are polled once per frame.
+//!
+//! The menu lists each pet and the current settings, so it is rebuilt whenever
+//! those change. Each build hands out fresh item ids, and a map from id to
+//! [`TrayAction`] is kept alongside it.
 
 use bevy::prelude::*;
-use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tray_icon::menu::{
+    CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu,
+};
 use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
 
-use crate::pet::{DespawnPet, Pet, SpawnPet};
+use crate::config::edit::{Setting, save_setting};
+use crate::config::{Config, PetScale, config_path, paths, skin_names};
+use crate::core::coords::World2d;
+use crate::core::input::{Intent, InteractionTier};
+use crate::core::items::ItemKind;
+use crate::core::menu::PetAction;
+use crate::core::movement::Following;
+use crate::items::DropItem;
+use crate::pet::{DespawnPet, Paused, Pet, SpawnPet};
 use crate::shell::shutdown::AppShutdown;
+use crate::skin::Skin;
+
+/// Scales offered in the menu. A configured value outside this list still
+/// works; it just has no tick next to it.
+const SCALES: [f32; 5] = [1.0, 1.5, 2.0, 3.0, 4.0];
+
+/// Volumes offered in the menu, likewise.
+const VOLUMES: [f32; 4] = [0.25, 0.5, 0.75, 1.0];
 
 /// The tray icon, kept alive for the process's lifetime.
 ///
@@ -19,10 +43,218 @@ use crate::shell::shutdown::AppShutdown;
 /// reads it. Held as a non-send resource because the platform handle is not
 /// thread-safe and must stay on the thread that created it.
 pub struct Tray {
-    _icon: TrayIcon,
-    add_pet: String,
-    remove_pet: String,
-    quit: String,
+    icon: TrayIcon,
+    /// What the current menu was built from.
+    shown: MenuModel,
+    /// What each item of the current menu does, by menu id.
+    actions: HashMap<String, TrayAction>,
+    /// Where settings chosen from the menu are written back to.
+    config_path: PathBuf,
+}
+
+/// Everything the menu displays. When this differs from what is shown, the
+/// menu is rebuilt.
+#[derive(Debug, Clone, PartialEq)]
+struct MenuModel {
+    skin: String,
+    scale: f32,
+    volume: f32,
+    muted: bool,
+    click_to_summon: bool,
+    debug_overlay: bool,
+    paused: bool,
+    /// Each pet and whether it is following the cursor, in spawn order, so
+    /// "Pet 1" is the oldest.
+    pets: Vec<(Entity, bool)>,
+    /// Whether the cursor is tracked everywhere, which following needs.
+    can_follow: bool,
+    /// What the current skin has to drop.
+    items: Vec<ItemKind>,
+}
+
+impl MenuModel {
+    fn new(
+        config: &Config,
+        paused: Paused,
+        tier: InteractionTier,
+        pets: Vec<(Entity, bool)>,
+        items: Vec<ItemKind>,
+    ) -> Self {
+        Self {
+            skin: config.skin.clone(),
+            scale: config.scale.0,
+            volume: config.sound.volume,
+            muted: config.sound.muted,
+            click_to_summon: config.click_to_summon,
+            debug_overlay: config.debug_overlay,
+            paused: paused.0,
+            pets,
+            can_follow: tier == InteractionTier::ClickToSummon,
+            items,
+        }
+    }
+}
+
+/// What a menu item does when clicked.
+#[derive(Debug, Clone, PartialEq)]
+enum TrayAction {
+    AddPet,
+    Drop(ItemKind),
+    SummonToCentre(Entity),
+    ToggleFollow(Entity),
+    RemovePet(Entity),
+    Skin(String),
+    Scale(f32),
+    Volume(f32),
+    ToggleMute,
+    ToggleClickToSummon,
+    ToggleDebugOverlay,
+    TogglePause,
+    Quit,
+}
+
+/// Creates menu items and records the action behind each.
+#[derive(Default)]
+struct ActionMap(HashMap<String, TrayAction>);
+
+impl ActionMap {
+    fn item(&mut self, text: &str, enabled: bool, action: TrayAction) -> MenuItem {
+        let item = MenuItem::new(text, enabled, None);
+        self.0.insert(item.id().0.clone(), action);
+        item
+    }
+
+    fn check(
+        &mut self,
+        text: &str,
+        enabled: bool,
+        checked: bool,
+        action: TrayAction,
+    ) -> CheckMenuItem {
+        let item = CheckMenuItem::new(text, enabled, checked, None);
+        self.0.insert(item.id().0.clone(), action);
+        item
+    }
+}
+
+/// Builds the menu for `model`, offering `skins` in the skin picker.
+fn build_menu(
+    model: &MenuModel,
+    skins: &[String],
+) -> tray_icon::menu::Result<(Menu, HashMap<String, TrayAction>)> {
+    let mut actions = ActionMap::default();
+
+    let pets = Submenu::new("Pets", true);
+    // Removing the last pet would leave nothing to interact with, so keep one
+    // alive.
+    let removable = model.pets.len() > 1;
+    // Like click-to-summon, shown but disabled when the cursor is only seen
+    // over the pet, and labelled so it is clear why.
+    let follow_label = if model.can_follow {
+        "Follow the cursor"
+    } else {
+        "Follow the cursor (needs click to summon)"
+    };
+    for (n, &(pet, following)) in model.pets.iter().enumerate() {
+        let summon = actions.item("Summon to centre", true, TrayAction::SummonToCentre(pet));
+        let follow = actions.check(
+            follow_label,
+            model.can_follow,
+            model.can_follow && following,
+            TrayAction::ToggleFollow(pet),
+        );
+        let remove = actions.item("Remove this one", removable, TrayAction::RemovePet(pet));
+        pets.append(&Submenu::with_items(
+            format!("Pet {}", n + 1),
+            true,
+            &[&summon, &follow, &remove],
+        )?)?;
+    }
+    pets.append_items(&[
+        &PredefinedMenuItem::separator(),
+        &actions.item("Add pet", true, TrayAction::AddPet),
+    ])?;
+
+    // Disabled rather than hidden when the skin has nothing, so it is clear
+    // the option exists.
+    let drop = Submenu::new("Drop", !model.items.is_empty());
+    for &kind in &model.items {
+        drop.append(&actions.item(kind.label(), true, TrayAction::Drop(kind)))?;
+    }
+
+    let skin = Submenu::new("Skin", true);
+    for name in skins {
+        skin.append(&actions.check(
+            name,
+            true,
+            *name == model.skin,
+            TrayAction::Skin(name.clone()),
+        ))?;
+    }
+
+    let scale = Submenu::new("Scale", true);
+    for factor in SCALES {
+        scale.append(&actions.check(
+            &format!("{factor}x"),
+            true,
+            factor == model.scale,
+            TrayAction::Scale(factor),
+        ))?;
+    }
+
+    let sound = Submenu::new("Sound", true);
+    sound.append_items(&[
+        &actions.check("Mute", true, model.muted, TrayAction::ToggleMute),
+        &PredefinedMenuItem::separator(),
+    ])?;
+    for volume in VOLUMES {
+        sound.append(&actions.check(
+            &format!("{}%", (volume * 100.0).round()),
+            true,
+            volume == model.volume,
+            TrayAction::Volume(volume),
+        ))?;
+    }
+
+    // Shown but disabled where the backend cannot see the pointer outside the
+    // pet, so the option does not just silently do nothing.
+    let summon_available = cfg!(not(target_os = "linux"));
+    let click_to_summon = actions.check(
+        "Click desktop to summon",
+        summon_available,
+        summon_available && model.click_to_summon,
+        TrayAction::ToggleClickToSummon,
+    );
+    let debug_overlay = actions.check(
+        "Debug overlay",
+        true,
+        model.debug_overlay,
+        TrayAction::ToggleDebugOverlay,
+    );
+    let pause = actions.item(
+        if model.paused { "Resume" } else { "Pause" },
+        true,
+        TrayAction::TogglePause,
+    );
+    let quit = actions.item("Quit Batates", true, TrayAction::Quit);
+
+    let menu = Menu::new();
+    let items: [&dyn IsMenuItem; 12] = [
+        &pets,
+        &drop,
+        &PredefinedMenuItem::separator(),
+        &skin,
+        &scale,
+        &sound,
+        &click_to_summon,
+        &debug_overlay,
+        &PredefinedMenuItem::separator(),
+        &pause,
+        &PredefinedMenuItem::separator(),
+        &quit,
+    ];
+    menu.append_items(&items)?;
+    Ok((menu, actions.0))
 }
 
 /// Builds the tray icon.
@@ -30,7 +262,7 @@ pub struct Tray {
 /// Errors are reported rather than fatal: a missing tray is a degraded
 /// experience, not a broken app, and on Linux it depends on the user running a
 /// StatusNotifierItem host at all.
-pub fn build_tray() -> Option<Tray> {
+pub fn build_tray(config: &Config) -> Option<Tray> {
     // The Linux backend is built on gtk, but neither initializes it nor runs
     // its event loop; both are this app's responsibility. Without this, gtk
     // panics as soon as the menu is built.
@@ -40,27 +272,21 @@ pub fn build_tray() -> Option<Tray> {
         return None;
     }
 
-    let add_pet = MenuItem::new("Add pet", true, None);
-    let remove_pet = MenuItem::new("Remove pet", true, None);
-    let quit = MenuItem::new("Quit Batates", true, None);
-
-    let menu = Menu::new();
-    let items: [&dyn tray_icon::menu::IsMenuItem; 4] = [
-        &add_pet,
-        &remove_pet,
-        &PredefinedMenuItem::separator(),
-        &quit,
-    ];
-    if let Err(error) = menu.append_items(&items) {
-        warn!("could not build the tray menu: {error}");
-        return None;
-    }
-
-    let ids = (
-        add_pet.id().0.clone(),
-        remove_pet.id().0.clone(),
-        quit.id().0.clone(),
+    // No pets or skin exist yet; the first poll rebuilds the menu once they do.
+    let model = MenuModel::new(
+        config,
+        Paused::default(),
+        InteractionTier::PetOnly,
+        Vec::new(),
+        Vec::new(),
     );
+    let (menu, actions) = match build_menu(&model, &skin_names(&paths::skins_dir())) {
+        Ok(built) => built,
+        Err(error) => {
+            warn!("could not build the tray menu: {error}");
+            return None;
+        }
+    };
 
     match TrayIconBuilder::new()
         .with_menu(Box::new(menu))
@@ -69,10 +295,10 @@ pub fn build_tray() -> Option<Tray> {
         .build()
     {
         Ok(icon) => Some(Tray {
-            _icon: icon,
-            add_pet: ids.0,
-            remove_pet: ids.1,
-            quit: ids.2,
+            icon,
+            shown: model,
+            actions,
+            config_path: config_path(),
         }),
         Err(error) => {
             warn!("could not create the tray icon: {error}; use --quit to exit");
@@ -118,14 +344,23 @@ fn image_from_png(bytes: &[u8]) -> Result<(Vec<u8>, u32, u32), ()> {
 }
 
 /// Polls the tray's menu channel and turns clicks into app messages.
+// Bevy systems declare their dependencies as parameters; splitting this into a
+// SystemParam struct would hide them without reducing the coupling.
+#[allow(clippy::too_many_arguments)]
 pub fn poll_tray(
-    tray: Option<NonSend<Tray>>,
-    pets: Query<Entity, With<Pet>>,
+    tray: Option<NonSendMut<Tray>>,
+    mut config: ResMut<Config>,
+    mut paused: ResMut<Paused>,
+    skin: Res<Skin>,
+    tier: Res<InteractionTier>,
+    pets: Query<(Entity, &Transform, Has<Following>), With<Pet>>,
     mut spawns: MessageWriter<SpawnPet>,
     mut despawns: MessageWriter<DespawnPet>,
+    mut drops: MessageWriter<DropItem>,
+    mut intents: MessageWriter<Intent>,
     mut shutdown: MessageWriter<AppShutdown>,
 ) {
-    let Some(tray) = tray else { return };
+    let Some(mut tray) = tray else { return };
 
     // Bevy's event loop is winit's, not gtk's, so gtk's own loop must be
     // pumped manually or the tray menu never redraws or dispatches clicks.
@@ -135,19 +370,113 @@ pub fn poll_tray(
     }
 
     while let Ok(event) = MenuEvent::receiver().try_recv() {
-        let id = &event.id().0;
-        if id == &tray.quit {
-            shutdown.write(AppShutdown);
-        } else if id == &tray.add_pet {
-            spawns.write(SpawnPet { at: Vec2::ZERO });
-        } else if id == &tray.remove_pet {
-            // Removing the last pet would leave nothing to interact with, so
-            // keep one alive.
-            if pets.iter().count() > 1
-                && let Some(pet) = pets.iter().next()
-            {
-                despawns.write(DespawnPet { pet });
+        // An id from a menu since replaced matches nothing and is dropped.
+        let Some(action) = tray.actions.get(&event.id().0).cloned() else {
+            continue;
+        };
+        match action {
+            TrayAction::AddPet => {
+                spawns.write(SpawnPet { at: Vec2::ZERO });
+            }
+            TrayAction::Drop(kind) => {
+                drops.write(DropItem {
+                    kind,
+                    at: None,
+                    for_pet: None,
+                });
+            }
+            TrayAction::SummonToCentre(pet) => {
+                intents.write(Intent::SummonPet {
+                    pet,
+                    to: World2d(Vec2::ZERO),
+                });
+            }
+            TrayAction::ToggleFollow(pet) => {
+                intents.write(Intent::Act {
+                    pet,
+                    action: PetAction::Follow,
+                });
+            }
+            TrayAction::RemovePet(pet) => {
+                // The menu can lag the world by a frame, so the pet may already
+                // be gone, and it may have become the last one.
+                if pets.contains(pet) && pets.iter().count() > 1 {
+                    despawns.write(DespawnPet { pet });
+                }
+            }
+            TrayAction::Skin(name) => {
+                change_setting(&mut config, &tray.config_path, Setting::Skin(name));
+            }
+            TrayAction::Scale(factor) => {
+                change_setting(&mut config, &tray.config_path, Setting::Scale(factor));
+            }
+            TrayAction::Volume(volume) => {
+                change_setting(&mut config, &tray.config_path, Setting::Volume(volume));
+            }
+            TrayAction::ToggleMute => {
+                let on = !config.sound.muted;
+                change_setting(&mut config, &tray.config_path, Setting::Muted(on));
+            }
+            TrayAction::ToggleClickToSummon => {
+                let on = !config.click_to_summon;
+                change_setting(&mut config, &tray.config_path, Setting::ClickToSummon(on));
+            }
+            TrayAction::ToggleDebugOverlay => {
+                let on = !config.debug_overlay;
+                change_setting(&mut config, &tray.config_path, Setting::DebugOverlay(on));
+            }
+            TrayAction::TogglePause => paused.0 = !paused.0,
+            TrayAction::Quit => {
+                shutdown.write(AppShutdown);
             }
         }
     }
+
+    let mut by_order: Vec<(Entity, f32, bool)> = pets
+        .iter()
+        .map(|(pet, transform, following)| (pet, transform.translation.z, following))
+        .collect();
+    // Draw order is spawn order, so sorting by it keeps each pet's number
+    // stable as others come and go.
+    by_order.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let model = MenuModel::new(
+        &config,
+        *paused,
+        *tier,
+        by_order
+            .into_iter()
+            .map(|(pet, _, following)| (pet, following))
+            .collect(),
+        skin.items.iter().map(|item| item.kind).collect(),
+    );
+
+    if model != tray.shown {
+        match build_menu(&model, &skin_names(&paths::skins_dir())) {
+            Ok((menu, actions)) => {
+                tray.icon.set_menu(Some(Box::new(menu)));
+                tray.actions = actions;
+            }
+            Err(error) => warn!("could not rebuild the tray menu: {error}"),
+        }
+        // Recorded even on failure, so a broken menu is not rebuilt every frame.
+        tray.shown = model;
+    }
+}
+
+/// Applies a setting chosen from the menu and writes it to the config file.
+///
+/// The change takes effect either way; a failed write only means it does not
+/// survive a restart.
+fn change_setting(config: &mut Config, path: &Path, setting: Setting) {
+    match &setting {
+        Setting::Skin(name) => config.skin = name.clone(),
+        Setting::Scale(factor) => config.scale = PetScale(*factor),
+        Setting::Volume(volume) => config.sound.volume = *volume,
+        Setting::Muted(on) => config.sound.muted = *on,
+        Setting::ClickToSummon(on) => config.click_to_summon = *on,
+        Setting::DebugOverlay(on) => config.debug_overlay = *on,
+    }
+    if let Err(error) = save_setting(path, &setting) {
+        warn!("{error}; the change will not survive a restart");
+    }
 }
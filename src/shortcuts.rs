@@ -0,0 +1,111 @@
+//! ECS glue for keyboard shortcuts: selecting a pet and acting on it without
+//! a pointer.
+//!
+//! What a shortcut does to the selection is `core::shortcuts`; hearing one is
+//! the platform's, or the control socket's. The platform's shortcuts are
+//! opt-in, but `batates --shortcut` always works, which is the only way on
+//! Wayland.
+
+use bevy::prelude::*;
+
+use crate::config::Config;
+use crate::core::PetSystems;
+use crate::core::hitbox::pet_rect_world;
+use crate::core::input::{GestureState, Intent};
+use crate::core::shortcuts::{Selection, ShortcutPressed, ShortcutSource};
+use crate::pet::Pet;
+use crate::platform::shortcuts;
+use crate::skin::Skin;
+
+/// How much bigger than the pet's frame the selection ring is.
+const RING_MARGIN: f32 = 1.15;
+
+/// The platform's registered shortcuts, for as long as they are registered.
+#[derive(Resource)]
+struct Registered(Box<dyn ShortcutSource>);
+
+pub struct ShortcutPlugin;
+
+impl Plugin for ShortcutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selection>()
+            .add_message::<ShortcutPressed>()
+            .add_systems(
+                Update,
+                // Before `Normalize`, so the intents are applied this frame.
+                (
+                    poll_shortcuts.run_if(resource_exists::<Registered>),
+                    act_on_shortcuts,
+                )
+                    .chain()
+                    .after(PetSystems::Sample)
+                    .before(PetSystems::Normalize),
+            )
+            .add_systems(PostUpdate, draw_selection_ring);
+
+        // Registered here rather than in a startup system, as for the tray:
+        // macOS delivers hotkeys to the main thread's event target, and plugin
+        // construction runs on the main thread.
+        let config = &app.world().resource::<Config>().shortcuts;
+        if config.enabled
+            && let Some(source) = shortcuts::listen(&config.bindings)
+        {
+            app.insert_resource(Registered(source));
+        }
+    }
+}
+
+fn poll_shortcuts(mut source: ResMut<Registered>, mut pressed: MessageWriter<ShortcutPressed>) {
+    pressed.write_batch(source.0.poll().into_iter().map(ShortcutPressed));
+}
+
+/// Moves the selection, or turns a shortcut into an intent for the selected
+/// pet.
+fn act_on_shortcuts(
+    mut pressed: MessageReader<ShortcutPressed>,
+    mut selection: ResMut<Selection>,
+    time: Res<Time>,
+    gesture: Res<GestureState>,
+    pets: Query<(Entity, &Transform), With<Pet>>,
+    mut intents: MessageWriter<Intent>,
+) {
+    if pressed.is_empty() {
+        return;
+    }
+    let pets: Vec<_> = pets
+        .iter()
+        .map(|(pet, transform)| (pet, transform.translation.x))
+        .collect();
+    for &ShortcutPressed(shortcut) in pressed.read() {
+        if let Some(intent) = selection.press(shortcut, &pets, gesture.cursor, time.elapsed()) {
+            intents.write(intent);
+        }
+    }
+}
+
+/// Circles the selected pet for a few seconds after each shortcut, so it is
+/// clear which one the next will act on.
+fn draw_selection_ring(
+    mut gizmos: Gizmos,
+    selection: Res<Selection>,
+    time: Res<Time>,
+    skin: Res<Skin>,
+    pets: Query<&Transform, With<Pet>>,
+) {
+    let Some(transform) = selection
+        .ring(time.elapsed())
+        .and_then(|pet| pets.get(pet).ok())
+    else {
+        return;
+    };
+    let rect = pet_rect_world(
+        transform.translation.truncate(),
+        skin.frame_size(),
+        transform.scale.x,
+    );
+    gizmos.circle_2d(
+        Isometry2d::from_translation(rect.center()),
+        rect.size().max_element() * 0.5 * RING_MARGIN,
+        Color::srgb(1.0, 0.8, 0.1),
+    );
+}
@@ -0,0 +1,1033 @@
+//! Importing a skin straight from an Aseprite file.
+//!
+//! Artists keep their animation in a `.aseprite` file with one tag per state,
+//! so rather than exporting frames and running `batates skin build`, the file is
+//! read directly. Each tag's frames become that state's row of a sheet built
+//! in memory, and the tag's frame durations set its timing: a frame rate when
+//! they are even, each frame's own duration when they are not.
+//!
+//! Aseprite has no notion of walk speed or transition weights. A tag's user
+//! data may carry them as a RON struct, for example
+//! `(duration: (3.0, 10.0), transitions: [(to: Walking, weight: 2)])`, and
+//! anything left out is taken from the built-in koala. The result is an
+//! ordinary [`SkinManifest`], so it is validated by exactly the same
+//! [`SkinManifest::into_parts`] as a hand-written one.
+//!
+//! Only what a sprite sheet can express is composited: visible layers, cel
+//! and layer opacity, and the normal blend mode. Other blend modes are drawn
+//! as normal, and tilemap layers are rejected.
+//!
+//! Format reference: <https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md>
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::manifest::{LocomotionSpec, PlaybackSpec, SkinManifest, StateSpec, TransitionSpec};
+use crate::core::brain::PetState;
+
+const FILE_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+const HEADER_SIZE: usize = 128;
+
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+const CHUNK_USER_DATA: u16 = 0x2020;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_REFERENCE: u16 = 64;
+const LAYER_GROUP: u16 = 1;
+/// Header flag: layer opacity is meaningful. Older files leave it unset and
+/// every layer opaque.
+const LAYER_OPACITY_VALID: u32 = 1;
+
+const CEL_RAW: u16 = 0;
+const CEL_LINKED: u16 = 1;
+const CEL_COMPRESSED: u16 = 2;
+
+/// The widest or tallest canvas read. Far bigger than any pet, and small
+/// enough that a file cannot ask for gigabytes of frames.
+const MAX_FRAME_SIDE: u32 = 1024;
+/// The widest sheet built: wgpu's default limit on a texture's side.
+const MAX_SHEET_SIDE: u32 = 8192;
+/// An indexed pixel is one byte.
+const MAX_PALETTE: usize = 256;
+
+#[derive(Debug, Error)]
+pub enum AsepriteError {
+    #[error("the file ends early")]
+    Truncated,
+    #[error("not an Aseprite file")]
+    NotAseprite,
+    #[error("frame {frame} is corrupt")]
+    BadFrame { frame: usize },
+    #[error("colour depth {0} is not one Aseprite writes")]
+    ColourDepth(u16),
+    #[error(
+        "the canvas is {width}x{height}, but a frame is at most {MAX_FRAME_SIDE} pixels a side"
+    )]
+    Canvas { width: u32, height: u32 },
+    #[error("the palette has {len} colours, but an indexed pixel picks from {MAX_PALETTE}")]
+    Palette { len: usize },
+    #[error("the longest tag has {columns} frames, too many for a row of the sheet")]
+    Columns { columns: u32 },
+    #[error("a cel in frame {frame} is {width}x{height} at {at}, which is not on the canvas")]
+    CelBounds {
+        frame: usize,
+        at: IVec2,
+        width: u32,
+        height: u32,
+    },
+    #[error("a cel in frame {frame} could not be decompressed: {reason}")]
+    Inflate { frame: usize, reason: String },
+    #[error("a cel in frame {frame} has {got} bytes of pixels but needs {want}")]
+    CelSize {
+        frame: usize,
+        got: usize,
+        want: usize,
+    },
+    #[error("a cel in frame {frame} links to frame {to}, which has no cel on that layer")]
+    BadLink { frame: usize, to: usize },
+    #[error("frame {frame} uses a tilemap layer, which is not supported; flatten it first")]
+    Tilemap { frame: usize },
+    #[error("tag {tag:?} covers frames {from}..={to}, but the file has {frames}")]
+    TagRange {
+        tag: String,
+        from: usize,
+        to: usize,
+        frames: usize,
+    },
+    #[error("no tag names the {state:?} state")]
+    MissingTag { state: PetState },
+    #[error("more than one tag names the {state:?} state")]
+    DuplicateTag { state: PetState },
+    #[error("user data on tag {tag:?} is not valid behaviour: {source}")]
+    Behaviour {
+        tag: String,
+        #[source]
+        source: Box<ron::error::SpannedError>,
+    },
+}
+
+/// A skin read from an Aseprite file: the manifest it stands for, and the
+/// sheet that manifest describes.
+#[derive(Debug, Clone)]
+pub struct AsepriteSkin {
+    pub manifest: SkinManifest,
+    pub sheet: SheetPixels,
+}
+
+/// An RGBA8 sheet, row-major from the top-left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetPixels {
+    pub size: UVec2,
+    pub rgba: Vec<u8>,
+}
+
+/// Behaviour a tag's user data may set. Omitted fields come from the fallback.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TagBehaviour {
+    playback: Option<PlaybackSpec>,
+    duration: Option<(f32, f32)>,
+    locomotion: Option<LocomotionSpec>,
+    transitions: Option<Vec<TransitionSpec>>,
+    phrases: Option<Vec<String>>,
+}
+
+/// Reads an Aseprite file into a manifest and sheet.
+///
+/// `name` becomes the skin's name and `sheet`, and `fallback` supplies the
+/// behaviour a tag does not specify. The caller still runs
+/// [`SkinManifest::into_parts`] on the result.
+pub fn import(
+    bytes: &[u8],
+    name: &str,
+    fallback: &SkinManifest,
+) -> Result<AsepriteSkin, AsepriteError> {
+    let file = parse(bytes)?;
+    let frames = file.composite();
+
+    let mut rows = Vec::with_capacity(PetState::ALL.len());
+    for state in PetState::ALL {
+        let mut tags = file.tags.iter().filter(|tag| names_state(&tag.name, state));
+        let tag = tags.next().ok_or(AsepriteError::MissingTag { state })?;
+        if tags.next().is_some() {
+            return Err(AsepriteError::DuplicateTag { state });
+        }
+        if tag.to < tag.from || tag.to >= frames.len() {
+            return Err(AsepriteError::TagRange {
+                tag: tag.name.clone(),
+                from: tag.from,
+                to: tag.to,
+                frames: frames.len(),
+            });
+        }
+        rows.push((state, tag, tag.sequence()));
+    }
+
+    let columns = rows
+        .iter()
+        .map(|(_, _, sequence)| sequence.len() as u32)
+        .max()
+        .unwrap_or(1);
+    let frame_size = file.size;
+    // The canvas is at most `MAX_FRAME_SIDE` tall, so only a row can be too
+    // long.
+    let width = columns
+        .checked_mul(frame_size.x)
+        .filter(|&width| width <= MAX_SHEET_SIDE)
+        .ok_or(AsepriteError::Columns { columns })?;
+    let sheet_size = UVec2::new(width, PetState::ALL.len() as u32 * frame_size.y);
+    let mut sheet = SheetPixels {
+        size: sheet_size,
+        rgba: vec![0; image_bytes(sheet_size, 4).ok_or(AsepriteError::Columns { columns })?],
+    };
+
+    let mut states = Vec::with_capacity(rows.len());
+    for (row, (state, tag, sequence)) in rows.into_iter().enumerate() {
+        for (column, &frame) in sequence.iter().enumerate() {
+            let origin = UVec2::new(column as u32, row as u32) * frame_size;
+            blit(&mut sheet, origin, frame_size, &frames[frame]);
+        }
+
+        let behaviour = match &tag.user_data {
+            Some(text) if !text.trim().is_empty() => ron::Options::default()
+                .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+                .from_str::<TagBehaviour>(text)
+                .map_err(|source| AsepriteError::Behaviour {
+                    tag: tag.name.clone(),
+                    source: Box::new(source),
+                })?,
+            _ => TagBehaviour::default(),
+        };
+        let base = fallback.states.iter().find(|spec| spec.state == state);
+        let durations_ms: Vec<u16> = sequence
+            .iter()
+            .map(|&frame| file.durations[frame])
+            .collect();
+        let total_ms: u32 = durations_ms.iter().copied().map(u32::from).sum();
+
+        states.push(StateSpec {
+            state,
+            frames: sequence.len() as u32,
+            fps: Some(fps_for(sequence.len(), total_ms, fallback.default_fps)),
+            frame_durations: uneven_durations(&durations_ms),
+            // Aseprite's own "repeat" says it too: a tag played a set number
+            // of times is a one-shot.
+            playback: behaviour
+                .playback
+                .or((tag.repeat > 0).then_some(PlaybackSpec::Once))
+                .or(base.map(|spec| spec.playback))
+                .unwrap_or(PlaybackSpec::Loop),
+            duration: behaviour
+                .duration
+                .or(base.map(|spec| spec.duration))
+                .unwrap_or((1.0, 1.0)),
+            locomotion: behaviour
+                .locomotion
+                .or(base.map(|spec| spec.locomotion))
+                .unwrap_or(LocomotionSpec::Still),
+            transitions: behaviour
+                .transitions
+                .or(base.map(|spec| spec.transitions.clone()))
+                .unwrap_or_default(),
+            // One tag per state is one row per state.
+            directions: Vec::new(),
+            phrases: behaviour
+                .phrases
+                .or(base.map(|spec| spec.phrases.clone()))
+                .unwrap_or_default(),
+        });
+    }
+
+    Ok(AsepriteSkin {
+        manifest: SkinManifest {
+            name: name.to_string(),
+            sheet: Some(name.to_string()),
+            atlases: Vec::new(),
+            frame_size: (frame_size.x, frame_size.y),
+            columns: Some(columns),
+            default_fps: fallback.default_fps,
+            states,
+            clips: Vec::new(),
+            items: Vec::new(),
+            sounds: Vec::new(),
+            load: Vec::new(),
+            script: None,
+            behaviour: None,
+        },
+        sheet,
+    })
+}
+
+/// Whether a tag is named after `state`, ignoring case, spaces and
+/// underscores, so `SendingLove`, `sending love` and `sending_love` all work.
+fn names_state(tag: &str, state: PetState) -> bool {
+    let simple = |text: &str| -> String {
+        text.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    simple(tag) == simple(&format!("{state:?}"))
+}
+
+/// The frame rate that plays `frames` frames in `total_ms`.
+///
+/// Frames of uneven length are averaged; they also keep their own durations
+/// through [`uneven_durations`], which take precedence.
+fn fps_for(frames: usize, total_ms: u32, fallback: u8) -> u8 {
+    if total_ms == 0 {
+        return fallback;
+    }
+    let fps = (frames as f32 * 1000.0 / total_ms as f32).round();
+    fps.clamp(1.0, f32::from(u8::MAX)) as u8
+}
+
+/// Each frame's duration in seconds, when they are not all the same.
+///
+/// Even timing stays a plain frame rate, which is what a hand-written
+/// manifest would say. A zero-length frame, which Aseprite allows but the
+/// stepper cannot show, is left to the average rate as well.
+fn uneven_durations(durations_ms: &[u16]) -> Option<Vec<f32>> {
+    let first = *durations_ms.first()?;
+    if durations_ms.iter().all(|&ms| ms == first) || durations_ms.contains(&0) {
+        return None;
+    }
+    Some(
+        durations_ms
+            .iter()
+            .map(|&ms| f32::from(ms) / 1000.0)
+            .collect(),
+    )
+}
+
+/// Copies one composited frame into the sheet at `origin`.
+fn blit(sheet: &mut SheetPixels, origin: UVec2, size: UVec2, frame: &[u8]) {
+    let row_bytes = size.x as usize * 4;
+    for y in 0..size.y as usize {
+        let from = y * row_bytes;
+        let to = ((origin.y as usize + y) * sheet.size.x as usize + origin.x as usize) * 4;
+        sheet.rgba[to..to + row_bytes].copy_from_slice(&frame[from..from + row_bytes]);
+    }
+}
+
+/// Bytes in an image of `size` at `depth` bytes a pixel, if that fits in
+/// memory at all.
+fn image_bytes(size: UVec2, depth: usize) -> Option<usize> {
+    (size.x as usize)
+        .checked_mul(size.y as usize)?
+        .checked_mul(depth)
+}
+
+/// Whether a cel of `size` at `at` is one a frame can hold: no bigger than a
+/// frame may be, and overlapping the canvas.
+fn cel_fits(at: IVec2, size: UVec2, canvas: UVec2) -> bool {
+    // Positions are `i16` and sides `u16`, so none of this can overflow.
+    size.max_element() <= MAX_FRAME_SIDE
+        && (at + size.as_ivec2()).cmpgt(IVec2::ZERO).all()
+        && at.cmplt(canvas.as_ivec2()).all()
+}
+
+/// The parts of an Aseprite file a skin needs.
+struct AseFile {
+    size: UVec2,
+    /// Of one composited RGBA frame.
+    frame_bytes: usize,
+    depth: u16,
+    transparent_index: u8,
+    layers: Vec<Layer>,
+    palette: Vec<[u8; 4]>,
+    /// Per frame, in milliseconds.
+    durations: Vec<u16>,
+    /// Per frame.
+    cels: Vec<Vec<Cel>>,
+    tags: Vec<Tag>,
+}
+
+struct Layer {
+    /// Visible itself and inside visible groups.
+    shown: bool,
+    opacity: u8,
+}
+
+#[derive(Clone)]
+struct Cel {
+    layer: usize,
+    at: IVec2,
+    opacity: u8,
+    z_index: i16,
+    size: UVec2,
+    /// In the file's colour depth.
+    pixels: Vec<u8>,
+}
+
+struct Tag {
+    name: String,
+    from: usize,
+    to: usize,
+    direction: u8,
+    repeat: u16,
+    user_data: Option<String>,
+}
+
+impl Tag {
+    /// Frame indices in the order the tag plays them once through.
+    fn sequence(&self) -> Vec<usize> {
+        let forward: Vec<usize> = (self.from..=self.to).collect();
+        let backward: Vec<usize> = forward.iter().rev().copied().collect();
+        // A ping-pong does not repeat the frame it turns on.
+        let inner = |run: &[usize]| run[1..run.len().saturating_sub(1).max(1)].to_vec();
+        match self.direction {
+            1 => backward,
+            2 => [forward.clone(), inner(&backward)].concat(),
+            3 => [backward.clone(), inner(&forward)].concat(),
+            _ => forward,
+        }
+    }
+}
+
+/// Little-endian reads over a byte slice, failing on truncation.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, at: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], AsepriteError> {
+        let end = self.at.checked_add(n).ok_or(AsepriteError::Truncated)?;
+        let slice = self
+            .bytes
+            .get(self.at..end)
+            .ok_or(AsepriteError::Truncated)?;
+        self.at = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, n: usize) -> Result<(), AsepriteError> {
+        self.take(n).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, AsepriteError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, AsepriteError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, AsepriteError> {
+        self.u16().map(|v| v as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, AsepriteError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, AsepriteError> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.bytes[self.at..];
+        self.at = self.bytes.len();
+        rest
+    }
+}
+
+fn parse(bytes: &[u8]) -> Result<AseFile, AsepriteError> {
+    let mut header = Reader::new(bytes.get(..HEADER_SIZE).ok_or(AsepriteError::Truncated)?);
+    header.skip(4)?;
+    if header.u16()? != FILE_MAGIC {
+        return Err(AsepriteError::NotAseprite);
+    }
+    let frame_count = header.u16()? as usize;
+    let size = UVec2::new(u32::from(header.u16()?), u32::from(header.u16()?));
+    let depth = header.u16()?;
+    if !matches!(depth, 8 | 16 | 32) {
+        return Err(AsepriteError::ColourDepth(depth));
+    }
+    let frame_bytes = image_bytes(size, 4)
+        .filter(|_| size.max_element() <= MAX_FRAME_SIDE)
+        .ok_or(AsepriteError::Canvas {
+            width: size.x,
+            height: size.y,
+        })?;
+    let flags = header.u32()?;
+    header.skip(2 + 8)?;
+    let transparent_index = header.u8()?;
+
+    let mut file = AseFile {
+        size,
+        frame_bytes,
+        depth,
+        transparent_index,
+        layers: Vec::new(),
+        palette: Vec::new(),
+        durations: Vec::with_capacity(frame_count),
+        cels: Vec::with_capacity(frame_count),
+        tags: Vec::new(),
+    };
+    // Visibility of the enclosing group at each nesting level.
+    let mut groups_shown: Vec<bool> = Vec::new();
+
+    let mut body = Reader::new(&bytes[HEADER_SIZE..]);
+    for frame in 0..frame_count {
+        let frame_bytes = body.u32()? as usize;
+        let mut reader = Reader::new(body.take(frame_bytes.saturating_sub(4))?);
+        if reader.u16()? != FRAME_MAGIC {
+            return Err(AsepriteError::BadFrame { frame });
+        }
+        let old_chunks = reader.u16()?;
+        file.durations.push(reader.u16()?);
+        reader.skip(2)?;
+        let chunks = match reader.u32()? {
+            0 => u32::from(old_chunks),
+            n => n,
+        };
+
+        let mut cels = Vec::new();
+        // User data chunks right after a tags chunk belong to its tags, in
+        // order.
+        let mut next_tag_data: Option<usize> = None;
+        for _ in 0..chunks {
+            let chunk_size = reader.u32()? as usize;
+            let kind = reader.u16()?;
+            let mut chunk = Reader::new(reader.take(chunk_size.saturating_sub(6))?);
+            if kind != CHUNK_USER_DATA {
+                next_tag_data = None;
+            }
+            match kind {
+                CHUNK_LAYER => {
+                    let layer_flags = chunk.u16()?;
+                    let layer_type = chunk.u16()?;
+                    let level = chunk.u16()? as usize;
+                    chunk.skip(2 + 2 + 2)?;
+                    let opacity = chunk.u8()?;
+
+                    groups_shown.truncate(level);
+                    let parent_shown = groups_shown.last().copied().unwrap_or(true);
+                    let shown = parent_shown
+                        && layer_flags & LAYER_VISIBLE != 0
+                        && layer_flags & LAYER_REFERENCE == 0;
+                    if layer_type == LAYER_GROUP {
+                        groups_shown.push(shown);
+                    }
+                    file.layers.push(Layer {
+                        shown,
+                        opacity: if flags & LAYER_OPACITY_VALID != 0 {
+                            opacity
+                        } else {
+                            u8::MAX
+                        },
+                    });
+                }
+                CHUNK_CEL => {
+                    let layer = chunk.u16()? as usize;
+                    let at = IVec2::new(i32::from(chunk.i16()?), i32::from(chunk.i16()?));
+                    let opacity = chunk.u8()?;
+                    let cel_type = chunk.u16()?;
+                    let z_index = chunk.i16()?;
+                    chunk.skip(5)?;
+                    let (size, pixels) = match cel_type {
+                        CEL_RAW | CEL_COMPRESSED => {
+                            let size = UVec2::new(u32::from(chunk.u16()?), u32::from(chunk.u16()?));
+                            // Checked before inflating, since the size is what
+                            // bounds how much a few bytes of zlib may become.
+                            if !cel_fits(at, size, file.size) {
+                                return Err(AsepriteError::CelBounds {
+                                    frame,
+                                    at,
+                                    width: size.x,
+                                    height: size.y,
+                                });
+                            }
+                            let want = image_bytes(size, usize::from(depth / 8))
+                                .ok_or(AsepriteError::BadFrame { frame })?;
+                            let data = chunk.rest();
+                            let pixels = if cel_type == CEL_RAW {
+                                data.to_vec()
+                            } else {
+                                miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, want)
+                                    .map_err(|error| AsepriteError::Inflate {
+                                        frame,
+                                        reason: error.to_string(),
+                                    })?
+                            };
+                            if pixels.len() < want {
+                                return Err(AsepriteError::CelSize {
+                                    frame,
+                                    got: pixels.len(),
+                                    want,
+                                });
+                            }
+                            (size, pixels)
+                        }
+                        CEL_LINKED => {
+                            let to = chunk.u16()? as usize;
+                            let linked = file
+                                .cels
+                                .get(to)
+                                .and_then(|cels| cels.iter().find(|cel| cel.layer == layer))
+                                .ok_or(AsepriteError::BadLink { frame, to })?;
+                            (linked.size, linked.pixels.clone())
+                        }
+                        _ => return Err(AsepriteError::Tilemap { frame }),
+                    };
+                    cels.push(Cel {
+                        layer,
+                        at,
+                        opacity,
+                        z_index,
+                        size,
+                        pixels,
+                    });
+                }
+                CHUNK_TAGS => {
+                    let count = chunk.u16()?;
+                    chunk.skip(8)?;
+                    next_tag_data = Some(file.tags.len());
+                    for _ in 0..count {
+                        let from = chunk.u16()? as usize;
+                        let to = chunk.u16()? as usize;
+                        let direction = chunk.u8()?;
+                        let repeat = chunk.u16()?;
+                        chunk.skip(6 + 3 + 1)?;
+                        file.tags.push(Tag {
+                            name: chunk.string()?,
+                            from,
+                            to,
+                            direction,
+                            repeat,
+                            user_data: None,
+                        });
+                    }
+                }
+                CHUNK_USER_DATA => {
+                    if let Some(index) = next_tag_data
+                        && let Some(tag) = file.tags.get_mut(index)
+                    {
+                        let data_flags = chunk.u32()?;
+                        if data_flags & 1 != 0 {
+                            tag.user_data = Some(chunk.string()?);
+                        }
+                        next_tag_data = Some(index + 1);
+                    }
+                }
+                CHUNK_PALETTE => {
+                    let len = chunk.u32()? as usize;
+                    if len > MAX_PALETTE {
+                        return Err(AsepriteError::Palette { len });
+                    }
+                    let first = chunk.u32()? as usize;
+                    let last = chunk.u32()? as usize;
+                    chunk.skip(8)?;
+                    file.palette.resize(len.max(file.palette.len()), [0; 4]);
+                    for index in first..=last {
+                        let entry_flags = chunk.u16()?;
+                        let rgba = [chunk.u8()?, chunk.u8()?, chunk.u8()?, chunk.u8()?];
+                        if entry_flags & 1 != 0 {
+                            chunk.string()?;
+                        }
+                        if let Some(slot) = file.palette.get_mut(index) {
+                            *slot = rgba;
+                        }
+                    }
+                }
+                // Old palettes, slices, colour profiles and the like say
+                // nothing a sheet needs.
+                _ => {}
+            }
+        }
+        file.cels.push(cels);
+    }
+
+    Ok(file)
+}
+
+impl AseFile {
+    /// Flattens each frame's visible cels into one RGBA8 image.
+    fn composite(&self) -> Vec<Vec<u8>> {
+        let mut frames = Vec::with_capacity(self.cels.len());
+        for cels in &self.cels {
+            let mut canvas = vec![0u8; self.frame_bytes];
+
+            // Aseprite's draw order: by layer, shifted by each cel's z-index,
+            // with the z-index breaking ties.
+            let mut ordered: Vec<&Cel> = cels.iter().collect();
+            ordered.sort_by_key(|cel| (cel.layer as i64 + i64::from(cel.z_index), cel.z_index));
+
+            for cel in ordered {
+                let Some(layer) = self.layers.get(cel.layer) else {
+                    continue;
+                };
+                if !layer.shown {
+                    continue;
+                }
+                let opacity = f32::from(cel.opacity) / 255.0 * f32::from(layer.opacity) / 255.0;
+                for y in 0..cel.size.y {
+                    for x in 0..cel.size.x {
+                        let at = cel.at + IVec2::new(x as i32, y as i32);
+                        if at.x < 0
+                            || at.y < 0
+                            || at.x as u32 >= self.size.x
+                            || at.y as u32 >= self.size.y
+                        {
+                            continue;
+                        }
+                        let src = self.rgba(cel, y as usize * cel.size.x as usize + x as usize);
+                        let dst = (at.y as usize * self.size.x as usize + at.x as usize) * 4;
+                        blend(&mut canvas[dst..dst + 4], src, opacity);
+                    }
+                }
+            }
+            frames.push(canvas);
+        }
+        frames
+    }
+
+    /// The cel's pixel at `index`, as RGBA.
+    fn rgba(&self, cel: &Cel, index: usize) -> [u8; 4] {
+        match self.depth {
+            32 => {
+                let p = &cel.pixels[index * 4..index * 4 + 4];
+                [p[0], p[1], p[2], p[3]]
+            }
+            16 => {
+                let p = &cel.pixels[index * 2..index * 2 + 2];
+                [p[0], p[0], p[0], p[1]]
+            }
+            _ => {
+                let entry = cel.pixels[index];
+                if entry == self.transparent_index {
+                    return [0; 4];
+                }
+                self.palette
+                    .get(usize::from(entry))
+                    .copied()
+                    .unwrap_or([0; 4])
+            }
+        }
+    }
+}
+
+/// Draws `src` over `dst` at `opacity`, in straight (not premultiplied) alpha.
+fn blend(dst: &mut [u8], src: [u8; 4], opacity: f32) {
+    let sa = f32::from(src[3]) / 255.0 * opacity;
+    if sa <= 0.0 {
+        return;
+    }
+    let da = f32::from(dst[3]) / 255.0;
+    let out_a = sa + da * (1.0 - sa);
+    for (d, s) in dst[..3].iter_mut().zip(src) {
+        let mixed = f32::from(s) * sa + f32::from(*d) * da * (1.0 - sa);
+        *d = (mixed / out_a).round() as u8;
+    }
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a minimal RGBA Aseprite file: one layer, one cel per frame, and
+    /// the given tags as `(name, from, to, user data)`.
+    fn ase_file(frames: &[(u16, [u8; 4])], tags: &[(&str, u16, u16, &str)]) -> Vec<u8> {
+        // A 1x1 cel at the frame's top-left.
+        ase_file_with_cels(frames, tags, |colour| {
+            (1, 1, miniz_oxide::deflate::compress_to_vec_zlib(&colour, 6))
+        })
+    }
+
+    /// As [`ase_file`], with each frame's compressed cel at the top-left made
+    /// by `cel` from the frame's colour, as `(width, height, zlib data)`.
+    fn ase_file_with_cels(
+        frames: &[(u16, [u8; 4])],
+        tags: &[(&str, u16, u16, &str)],
+        cel: impl Fn([u8; 4]) -> (u16, u16, Vec<u8>),
+    ) -> Vec<u8> {
+        fn chunk(kind: u16, data: &[u8]) -> Vec<u8> {
+            let mut out = ((data.len() + 6) as u32).to_le_bytes().to_vec();
+            out.extend(kind.to_le_bytes());
+            out.extend(data);
+            out
+        }
+        fn string(text: &str) -> Vec<u8> {
+            let mut out = (text.len() as u16).to_le_bytes().to_vec();
+            out.extend(text.as_bytes());
+            out
+        }
+
+        let mut body = Vec::new();
+        for (index, &(duration, colour)) in frames.iter().enumerate() {
+            let mut chunks = Vec::new();
+            if index == 0 {
+                let mut layer = vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0];
+                layer.extend(string("art"));
+                chunks.push(chunk(CHUNK_LAYER, &layer));
+
+                let mut tag_data = (tags.len() as u16).to_le_bytes().to_vec();
+                tag_data.extend([0; 8]);
+                for (name, from, to, _) in tags {
+                    tag_data.extend(from.to_le_bytes());
+                    tag_data.extend(to.to_le_bytes());
+                    tag_data.extend([0, 0, 0]);
+                    tag_data.extend([0; 10]);
+                    tag_data.extend(string(name));
+                }
+                chunks.push(chunk(CHUNK_TAGS, &tag_data));
+                for (.., user_data) in tags {
+                    let mut data = 1u32.to_le_bytes().to_vec();
+                    data.extend(string(user_data));
+                    chunks.push(chunk(CHUNK_USER_DATA, &data));
+                }
+            }
+
+            let (width, height, pixels) = cel(colour);
+            let mut cel = vec![0, 0, 0, 0, 0, 0, 255];
+            cel.extend(CEL_COMPRESSED.to_le_bytes());
+            cel.extend([0; 7]);
+            cel.extend(width.to_le_bytes());
+            cel.extend(height.to_le_bytes());
+            cel.extend(pixels);
+            chunks.push(chunk(CHUNK_CEL, &cel));
+
+            let data: Vec<u8> = chunks.concat();
+            body.extend(((data.len() + 16) as u32).to_le_bytes());
+            body.extend(FRAME_MAGIC.to_le_bytes());
+            body.extend((chunks.len() as u16).to_le_bytes());
+            body.extend(duration.to_le_bytes());
+            body.extend([0; 6]);
+            body.extend(data);
+        }
+
+        let mut header = vec![0u8; HEADER_SIZE];
+        header[0..4].copy_from_slice(&((HEADER_SIZE + body.len()) as u32).to_le_bytes());
+        header[4..6].copy_from_slice(&FILE_MAGIC.to_le_bytes());
+        header[6..8].copy_from_slice(&(frames.len() as u16).to_le_bytes());
+        header[8..10].copy_from_slice(&2u16.to_le_bytes());
+        header[10..12].copy_from_slice(&2u16.to_le_bytes());
+        header[12..14].copy_from_slice(&32u16.to_le_bytes());
+        header[14..18].copy_from_slice(&LAYER_OPACITY_VALID.to_le_bytes());
+        [header, body].concat()
+    }
+
+    fn koala() -> SkinManifest {
+        SkinManifest::parse(super::super::BUILTIN_MANIFEST, "<builtin>").expect("valid")
+    }
+
+    /// One frame per state, except Walking, which gets two at 100ms each.
+    fn every_state() -> Vec<u8> {
+        let mut frames = Vec::new();
+        let mut tags = Vec::new();
+        for (index, state) in PetState::ALL.iter().enumerate() {
+            let name = format!("{state:?}");
+            let first = frames.len() as u16;
+            frames.push((250, [index as u8 * 10, 0, 0, 255]));
+            if *state == PetState::Walking {
+                frames[first as usize].0 = 100;
+                frames.push((100, [0, 200, 0, 255]));
+            }
+            let user_data = if *state == PetState::Eating {
+                "(duration: (1.0, 1.5))"
+            } else {
+                ""
+            };
+            tags.push((name, first, frames.len() as u16 - 1, user_data));
+        }
+        let tags: Vec<(&str, u16, u16, &str)> = tags
+            .iter()
+            .map(|(name, from, to, data)| (name.as_str(), *from, *to, *data))
+            .collect();
+        ase_file(&frames, &tags)
+    }
+
+    #[test]
+    fn tags_become_validated_states() {
+        let skin = import(&every_state(), "test.aseprite", &koala()).expect("imports");
+        assert_eq!(skin.manifest.columns, Some(2));
+        assert_eq!(skin.sheet.size, UVec2::new(4, 16));
+
+        let (geometry, table) = skin.manifest.into_parts().expect("valid manifest");
+        assert_eq!(geometry.frame_size, UVec2::splat(2));
+        geometry
+            .verify_sheet(skin.sheet.size)
+            .expect("sheet matches");
+        assert_eq!(table.get(PetState::Walking).frames, 2);
+        assert_eq!(table.get(PetState::Walking).fps, 10);
+        assert_eq!(table.get(PetState::Sitting).fps, 4);
+        assert!(table.get(PetState::Walking).frame_durations.is_none());
+    }
+
+    #[test]
+    fn uneven_frames_keep_their_own_durations() {
+        assert_eq!(uneven_durations(&[100, 100, 100]), None);
+        assert_eq!(
+            uneven_durations(&[100, 600, 100]),
+            Some(vec![0.1, 0.6, 0.1])
+        );
+        assert_eq!(uneven_durations(&[100, 0]), None);
+    }
+
+    #[test]
+    fn frames_land_in_their_state_row() {
+        let skin = import(&every_state(), "test.aseprite", &koala()).expect("imports");
+        let pixel = |x: u32, y: u32| {
+            let at = ((y * skin.sheet.size.x + x) * 4) as usize;
+            &skin.sheet.rgba[at..at + 4]
+        };
+        let walking = PetState::ALL
+            .iter()
+            .position(|&s| s == PetState::Walking)
+            .unwrap() as u32;
+        // The cel covers each frame's top-left pixel only.
+        assert_eq!(pixel(2, walking * 2), [0, 200, 0, 255]);
+        assert_eq!(pixel(1, walking * 2), [0, 0, 0, 0]);
+        // Sitting has one frame, so its second column is empty.
+        assert_eq!(pixel(2, 6 * 2), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn user_data_overrides_the_fallback_behaviour() {
+        let skin = import(&every_state(), "test.aseprite", &koala()).expect("imports");
+        let spec = |state| {
+            skin.manifest
+                .states
+                .iter()
+                .find(|spec| spec.state == state)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(spec(PetState::Eating).duration, (1.0, 1.5));
+        let koala_idle = koala().states[3].clone();
+        assert_eq!(spec(PetState::Idle).duration, koala_idle.duration);
+        assert_eq!(
+            spec(PetState::Walking).locomotion,
+            koala().states[7].locomotion
+        );
+    }
+
+    #[test]
+    fn a_missing_state_tag_is_an_error() {
+        let file = ase_file(&[(100, [0; 4])], &[("Walking", 0, 0, "")]);
+        assert!(matches!(
+            import(&file, "t", &koala()),
+            Err(AsepriteError::MissingTag {
+                state: PetState::Chilling
+            })
+        ));
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        assert!(matches!(
+            import(b"\x89PNG", "t", &koala()),
+            Err(AsepriteError::Truncated)
+        ));
+        assert!(matches!(
+            import(&[0; HEADER_SIZE], "t", &koala()),
+            Err(AsepriteError::NotAseprite)
+        ));
+    }
+
+    #[test]
+    fn oversized_canvases_and_palettes_are_rejected() {
+        let mut wide = ase_file(&[(100, [0; 4])], &[]);
+        wide[8..10].copy_from_slice(&2000u16.to_le_bytes());
+        assert!(matches!(
+            parse(&wide),
+            Err(AsepriteError::Canvas {
+                width: 2000,
+                height: 2
+            })
+        ));
+
+        // A palette chunk appended to the only frame, which ends the file.
+        let mut palette = 300u32.to_le_bytes().to_vec();
+        palette.extend([0; 16]);
+        let mut chunk = ((palette.len() + 6) as u32).to_le_bytes().to_vec();
+        chunk.extend(CHUNK_PALETTE.to_le_bytes());
+        chunk.extend(palette);
+        let mut file = ase_file(&[(100, [0; 4])], &[]);
+        let frame = HEADER_SIZE;
+        let frame_bytes = u32::from_le_bytes(file[frame..frame + 4].try_into().unwrap());
+        file[frame..frame + 4].copy_from_slice(&(frame_bytes + chunk.len() as u32).to_le_bytes());
+        let chunks = u16::from_le_bytes([file[frame + 6], file[frame + 7]]);
+        file[frame + 6..frame + 8].copy_from_slice(&(chunks + 1).to_le_bytes());
+        file.extend(chunk);
+        assert!(matches!(
+            parse(&file),
+            Err(AsepriteError::Palette { len: 300 })
+        ));
+    }
+
+    /// A few kilobytes of zlib can inflate to gigabytes, so a cel is only
+    /// inflated to the size it claims, and only claims what a frame can hold.
+    #[test]
+    fn cels_inflate_no_further_than_a_frame() {
+        let bomb = miniz_oxide::deflate::compress_to_vec_zlib(&vec![0; 64 << 20], 10);
+        assert!(bomb.len() < 128 << 10);
+        let file = ase_file_with_cels(&[(100, [0; 4])], &[], |_| (2, 2, bomb.clone()));
+        assert!(matches!(
+            parse(&file),
+            Err(AsepriteError::Inflate { frame: 0, .. })
+        ));
+
+        let huge = ase_file_with_cels(&[(100, [0; 4])], &[], |_| {
+            (u16::MAX, u16::MAX, bomb.clone())
+        });
+        assert!(matches!(
+            parse(&huge),
+            Err(AsepriteError::CelBounds {
+                frame: 0,
+                width: 65535,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn cels_off_the_canvas_are_rejected() {
+        let mut file = ase_file(&[(100, [0; 4])], &[]);
+        // The cel's x, which comes just before its opacity and type.
+        let cel = file
+            .windows(9)
+            .position(|w| w[6..9] == [255, 2, 0])
+            .expect("the cel's opacity and type");
+        file[cel + 2..cel + 4].copy_from_slice(&5i16.to_le_bytes());
+        assert!(matches!(
+            parse(&file),
+            Err(AsepriteError::CelBounds { frame: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn tag_names_are_matched_loosely() {
+        assert!(names_state("sending_love", PetState::SendingLove));
+        assert!(names_state("Sending Love", PetState::SendingLove));
+        assert!(!names_state("Sending", PetState::SendingLove));
+    }
+
+    #[test]
+    fn ping_pong_does_not_repeat_the_turning_frames() {
+        let tag = Tag {
+            name: String::new(),
+            from: 2,
+            to: 5,
+            direction: 2,
+            repeat: 0,
+            user_data: None,
+        };
+        assert_eq!(tag.sequence(), [2, 3, 4, 5, 4, 3]);
+    }
+}
@@ -0,0 +1,513 @@
+//! Packed atlases: frames trimmed of their transparent border and packed
+//! tightly, over one sheet or several, as TexturePacker writes them.
+//!
+//! A grid sheet is as wide as the longest animation, so a state with one
+//! frame pays for a row of empty cells. A packed atlas stores only the pixels
+//! that are drawn, and says for each frame where its rect is on which sheet
+//! and where that rect sat inside the untrimmed frame. Both of
+//! TexturePacker's JSON layouts, "hash" and "array", are read, as is
+//! Aseprite's JSON export, which uses the same fields. Rotated frames are
+//! not supported.
+//!
+//! Frames are named `<row>_<number>`, as `batates skin build` reads them:
+//! `walk_03` is a `Walking` frame, `walk_up_03` one of its `Up` row, and a
+//! clip's frames are named after the clip. A directory or extension in the
+//! name is ignored. Within a row, frames play in number order.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use super::manifest::{SkinError, SkinGeometry, in_skin};
+
+#[derive(Debug, Error)]
+pub enum AtlasError {
+    #[error("not a TexturePacker JSON atlas: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("frame {frame:?} is rotated; export with rotation turned off")]
+    Rotated { frame: String },
+    #[error("frame {frame:?} is not named <row>_<number>")]
+    BadName { frame: String },
+    #[error("frame {frame:?} belongs to no row this skin has")]
+    UnknownRow { frame: String },
+    #[error("frame {frame:?} appears more than once")]
+    DuplicateFrame { frame: String },
+    #[error(
+        "frame {frame:?} was cut from a {}x{} frame, but the skin's frames are {}x{}",
+        .got.x, .got.y, .want.x, .want.y
+    )]
+    SourceSize {
+        frame: String,
+        got: UVec2,
+        want: UVec2,
+    },
+    #[error("frame {frame:?} has no pixels")]
+    Empty { frame: String },
+    #[error("frame {frame:?} lies outside its sheet")]
+    OffSheet { frame: String },
+    #[error("frame {frame:?} is trimmed to a rect that overhangs its frame")]
+    Overhang { frame: String },
+    #[error("its image {image:?} is outside the skin")]
+    OutsideSkin { image: String },
+}
+
+/// Where one frame is drawn from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRect {
+    /// Into [`PackedAtlas::sheets`].
+    pub sheet: usize,
+    /// The trimmed pixels, on the sheet.
+    pub rect: URect,
+    /// Where `rect`'s top-left corner sat inside the untrimmed frame.
+    pub offset: UVec2,
+}
+
+/// One sheet of a packed atlas, as its file describes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtlasSheet {
+    /// Relative to the atlas file.
+    pub image: String,
+    pub size: UVec2,
+}
+
+/// Every frame of a skin, placed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedAtlas {
+    pub sheets: Vec<AtlasSheet>,
+    /// Indexed like [`atlas_index`](crate::core::animation::atlas_index);
+    /// `None` past the end of a row.
+    pub frames: Vec<Option<FrameRect>>,
+}
+
+impl PackedAtlas {
+    /// Checks a decoded sheet is the size its atlas file said it would be.
+    ///
+    /// The rects were only checked against that promise, so a sheet exported
+    /// again without its atlas would otherwise draw whatever lands there.
+    pub fn verify_sheet(&self, sheet: usize, actual: UVec2) -> Result<(), SkinError> {
+        let want = self.sheets[sheet].size;
+        if actual != want {
+            return Err(SkinError::AtlasSheetSize {
+                image: self.sheets[sheet].image.clone(),
+                want,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// One atlas file, parsed but not yet matched to a skin.
+#[derive(Debug, Clone)]
+pub struct AtlasFile {
+    sheet: AtlasSheet,
+    frames: Vec<(String, FrameSpec)>,
+}
+
+impl AtlasFile {
+    pub fn parse(json: &[u8]) -> Result<Self, AtlasError> {
+        let raw: RawAtlas = serde_json::from_slice(json)?;
+        // Written by a tool, but shipped with a skin like any other file.
+        if !in_skin(&raw.meta.image) {
+            return Err(AtlasError::OutsideSkin {
+                image: raw.meta.image,
+            });
+        }
+        let frames = match raw.frames {
+            FrameList::Hash(frames) => frames.into_iter().collect(),
+            FrameList::Array(frames) => frames
+                .into_iter()
+                .map(|named| (named.filename, named.frame))
+                .collect(),
+        };
+        Ok(Self {
+            sheet: AtlasSheet {
+                image: raw.meta.image,
+                size: raw.meta.size.into(),
+            },
+            frames,
+        })
+    }
+
+    /// The image this file places frames on, relative to the file.
+    pub fn image(&self) -> &str {
+        &self.sheet.image
+    }
+}
+
+/// Places every frame `geometry` needs from `files`, each named by its path
+/// for errors. The files' order is the sheets' order.
+pub fn place(
+    files: &[(String, AtlasFile)],
+    geometry: &SkinGeometry,
+) -> Result<PackedAtlas, SkinError> {
+    let mut found: Vec<Vec<(u32, FrameRect)>> = vec![Vec::new(); geometry.sheet_rows.len()];
+    for (sheet, (path, file)) in files.iter().enumerate() {
+        let error = |source| SkinError::Atlas {
+            path: path.clone(),
+            source,
+        };
+        for (name, spec) in &file.frames {
+            let (row_name, number) = frame_key(name).ok_or_else(|| {
+                error(AtlasError::BadName {
+                    frame: name.clone(),
+                })
+            })?;
+            let row = geometry
+                .sheet_rows
+                .iter()
+                .position(|row| row.name == row_name)
+                .ok_or_else(|| {
+                    error(AtlasError::UnknownRow {
+                        frame: name.clone(),
+                    })
+                })?;
+            if found[row].iter().any(|(seen, _)| *seen == number) {
+                return Err(error(AtlasError::DuplicateFrame {
+                    frame: name.clone(),
+                }));
+            }
+            let (rect, offset) = spec
+                .placement(name, file.sheet.size, geometry.frame_size)
+                .map_err(error)?;
+            found[row].push((
+                number,
+                FrameRect {
+                    sheet,
+                    rect,
+                    offset,
+                },
+            ));
+        }
+    }
+
+    let columns = geometry.columns as usize;
+    let mut frames = vec![None; geometry.sheet_rows.len() * columns];
+    for (row, (spec, mut placed)) in geometry.sheet_rows.iter().zip(found).enumerate() {
+        if placed.len() != spec.frames as usize {
+            return Err(SkinError::AtlasFrameCount {
+                row: spec.name.clone(),
+                got: placed.len(),
+                want: spec.frames,
+            });
+        }
+        placed.sort_by_key(|(number, _)| *number);
+        for (column, (_, frame)) in placed.into_iter().enumerate() {
+            frames[row * columns + column] = Some(frame);
+        }
+    }
+
+    Ok(PackedAtlas {
+        sheets: files.iter().map(|(_, file)| file.sheet.clone()).collect(),
+        frames,
+    })
+}
+
+/// Splits `walk_03`, `walk_03.png` or `koala/walk_03.png` into `("walk", 3)`.
+fn frame_key(name: &str) -> Option<(&str, u32)> {
+    let file = name.rsplit('/').next()?;
+    let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+    let (row, number) = stem.rsplit_once('_')?;
+    if row.is_empty() {
+        return None;
+    }
+    Some((row, number.parse().ok()?))
+}
+
+#[derive(Deserialize)]
+struct RawAtlas {
+    frames: FrameList,
+    meta: Meta,
+}
+
+/// TexturePacker's "hash" layout keys frames by name; "array" lists them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FrameList {
+    Hash(BTreeMap<String, FrameSpec>),
+    Array(Vec<NamedFrame>),
+}
+
+#[derive(Deserialize)]
+struct NamedFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: FrameSpec,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FrameSpec {
+    frame: JsonRect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: JsonRect,
+    source_size: JsonSize,
+}
+
+impl FrameSpec {
+    /// The frame's rect on a `sheet`-sized sheet, and where it sat inside a
+    /// `frame_size` frame.
+    fn placement(
+        &self,
+        name: &str,
+        sheet: UVec2,
+        frame_size: UVec2,
+    ) -> Result<(URect, UVec2), AtlasError> {
+        let frame = || name.to_string();
+        if self.rotated {
+            return Err(AtlasError::Rotated { frame: frame() });
+        }
+        let source = UVec2::from(self.source_size);
+        if source != frame_size {
+            return Err(AtlasError::SourceSize {
+                frame: frame(),
+                got: source,
+                want: frame_size,
+            });
+        }
+        let min = UVec2::new(self.frame.x, self.frame.y);
+        let size = UVec2::new(self.frame.w, self.frame.h);
+        // A zero-sized rect has nowhere to anchor, and no pixels to show.
+        if size.cmpeq(UVec2::ZERO).any() {
+            return Err(AtlasError::Empty { frame: frame() });
+        }
+        // The sums are checked: a corner past `u32::MAX` is off any sheet.
+        let max = checked_add(min, size)
+            .filter(|max| !max.cmpgt(sheet).any())
+            .ok_or_else(|| AtlasError::OffSheet { frame: frame() })?;
+        let offset = UVec2::new(self.sprite_source_size.x, self.sprite_source_size.y);
+        if checked_add(offset, size).is_none_or(|end| end.cmpgt(frame_size).any()) {
+            return Err(AtlasError::Overhang { frame: frame() });
+        }
+        Ok((URect::from_corners(min, max), offset))
+    }
+}
+
+/// `a + b`, unless either side overflows.
+fn checked_add(a: UVec2, b: UVec2) -> Option<UVec2> {
+    Some(UVec2::new(a.x.checked_add(b.x)?, a.y.checked_add(b.y)?))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct JsonRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct JsonSize {
+    w: u32,
+    h: u32,
+}
+
+impl From<JsonSize> for UVec2 {
+    fn from(size: JsonSize) -> Self {
+        UVec2::new(size.w, size.h)
+    }
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    image: String,
+    size: JsonSize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::brain::PetState;
+    use crate::skin::manifest::SkinManifest;
+
+    /// A skin whose states all have one frame, except Walking with two.
+    fn geometry() -> SkinGeometry {
+        let mut states = String::new();
+        for state in PetState::ALL {
+            let frames = if state == PetState::Walking { 2 } else { 1 };
+            states.push_str(&format!(
+                "(state: {state:?}, frames: {frames}, playback: Loop, duration: (1.0, 2.0), \
+                 locomotion: Still, transitions: [(to: Idle, weight: 1)]),\n"
+            ));
+        }
+        let text = format!(
+            "SkinManifest(name: \"t\", atlases: [\"a.json\"], frame_size: (10, 10), \
+             default_fps: 12, states: [{states}])"
+        );
+        let (geometry, _) = SkinManifest::parse(&text, "test")
+            .and_then(SkinManifest::into_parts)
+            .expect("valid");
+        geometry
+    }
+
+    /// One entry per frame, trimmed to the 4x6 rect at (3, 2) in its frame.
+    fn frame_json(name: &str, at: u32) -> String {
+        format!(
+            "\"{name}\": {{\"frame\": {{\"x\": {at}, \"y\": 0, \"w\": 4, \"h\": 6}}, \
+             \"rotated\": false, \"trimmed\": true, \
+             \"spriteSourceSize\": {{\"x\": 3, \"y\": 2, \"w\": 4, \"h\": 6}}, \
+             \"sourceSize\": {{\"w\": 10, \"h\": 10}}}}"
+        )
+    }
+
+    fn hash_atlas(names: &[&str]) -> String {
+        let frames: Vec<String> = names
+            .iter()
+            .zip((0..).step_by(4))
+            .map(|(name, at)| frame_json(name, at))
+            .collect();
+        format!(
+            "{{\"frames\": {{{}}}, \"meta\": {{\"image\": \"a.png\", \"size\": {{\"w\": 64, \"h\": 8}}}}}}",
+            frames.join(", ")
+        )
+    }
+
+    const EVERY_FRAME: [&str; 9] = [
+        "chill_0.png",
+        "drag_0.png",
+        "eat_0.png",
+        "idle_0.png",
+        "jump_0.png",
+        "love_0.png",
+        "sit_0.png",
+        "walk_1.png",
+        "walk_0.png",
+    ];
+
+    fn place_one(json: &str) -> Result<PackedAtlas, SkinError> {
+        let file = AtlasFile::parse(json.as_bytes()).expect("parses");
+        place(&[("a.json".to_string(), file)], &geometry())
+    }
+
+    #[test]
+    fn frames_land_in_their_row_in_number_order() {
+        let atlas = place_one(&hash_atlas(&EVERY_FRAME)).expect("places");
+        let geometry = geometry();
+        assert_eq!(geometry.columns, 2, "the widest row");
+        assert_eq!(atlas.frames.len(), 16);
+        assert_eq!(
+            atlas.sheets,
+            [AtlasSheet {
+                image: "a.png".to_string(),
+                size: UVec2::new(64, 8)
+            }]
+        );
+
+        let walking = PetState::Walking.row() as usize * 2;
+        // walk_0 was listed last, at x = 32.
+        assert_eq!(
+            atlas.frames[walking],
+            Some(FrameRect {
+                sheet: 0,
+                rect: URect::new(32, 0, 36, 6),
+                offset: UVec2::new(3, 2),
+            })
+        );
+        assert_eq!(atlas.frames[walking + 1].unwrap().rect.min.x, 28);
+        // Sitting has one frame; its second cell is padding.
+        assert!(atlas.frames[PetState::Sitting.row() as usize * 2 + 1].is_none());
+    }
+
+    #[test]
+    fn the_array_layout_reads_the_same() {
+        let hash = place_one(&hash_atlas(&EVERY_FRAME)).expect("places");
+        let array = hash_atlas(&EVERY_FRAME)
+            .replace("\"frames\": {", "\"frames\": [")
+            .replace("}}}, \"meta\"", "}}], \"meta\"");
+        let array = EVERY_FRAME.iter().fold(array, |json, name| {
+            json.replace(
+                &format!("\"{name}\": {{"),
+                &format!("{{\"filename\": \"{name}\", "),
+            )
+        });
+        assert_eq!(place_one(&array).expect("places"), hash);
+    }
+
+    #[test]
+    fn frames_are_checked_against_the_skin() {
+        let mut names = EVERY_FRAME.to_vec();
+        names.pop();
+        assert!(matches!(
+            place_one(&hash_atlas(&names)),
+            Err(SkinError::AtlasFrameCount { row, got: 1, want: 2 }) if row == "walk"
+        ));
+
+        names.push("tail_0.png");
+        assert!(matches!(
+            place_one(&hash_atlas(&names)),
+            Err(SkinError::Atlas {
+                source: AtlasError::UnknownRow { .. },
+                ..
+            })
+        ));
+
+        let rotated =
+            hash_atlas(&EVERY_FRAME).replacen("\"rotated\": false", "\"rotated\": true", 1);
+        assert!(matches!(
+            place_one(&rotated),
+            Err(SkinError::Atlas {
+                source: AtlasError::Rotated { .. },
+                ..
+            })
+        ));
+
+        let bigger =
+            hash_atlas(&EVERY_FRAME).replacen("\"w\": 10, \"h\": 10", "\"w\": 20, \"h\": 20", 1);
+        assert!(matches!(
+            place_one(&bigger),
+            Err(SkinError::Atlas {
+                source: AtlasError::SourceSize { .. },
+                ..
+            })
+        ));
+
+        let far = hash_atlas(&EVERY_FRAME).replacen(
+            "\"frame\": {\"x\": 0,",
+            "\"frame\": {\"x\": 4294967295,",
+            1,
+        );
+        assert!(matches!(
+            place_one(&far),
+            Err(SkinError::Atlas {
+                source: AtlasError::OffSheet { .. },
+                ..
+            })
+        ));
+
+        let shifted = hash_atlas(&EVERY_FRAME).replacen(
+            "\"spriteSourceSize\": {\"x\": 3,",
+            "\"spriteSourceSize\": {\"x\": 4294967295,",
+            1,
+        );
+        assert!(matches!(
+            place_one(&shifted),
+            Err(SkinError::Atlas {
+                source: AtlasError::Overhang { .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn an_image_outside_the_skin_is_rejected() {
+        for image in ["../a.png", "/home/me/a.png"] {
+            let json = hash_atlas(&EVERY_FRAME).replace("\"a.png\"", &format!("{image:?}"));
+            assert!(matches!(
+                AtlasFile::parse(json.as_bytes()),
+                Err(AtlasError::OutsideSkin { .. })
+            ));
+        }
+        let nested = hash_atlas(&EVERY_FRAME).replace("\"a.png\"", "\"sheets/a.png\"");
+        assert!(AtlasFile::parse(nested.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn frame_names_ignore_directories_and_extensions() {
+        assert_eq!(frame_key("walk_03"), Some(("walk", 3)));
+        assert_eq!(frame_key("koala/walk_up_03.png"), Some(("walk_up", 3)));
+        assert_eq!(frame_key("walk.png"), None);
+        assert_eq!(frame_key("_03.png"), None);
+    }
+}
@@ -0,0 +1,376 @@
+//! `batates skin build`: packs a folder of per-frame PNGs into a skin.
+//!
+//! Input files are named `<state>_<frame>.png`, e.g. `walk_03.png`. Each
+//! state's frames fill one row of the sheet, in [`PetState::ALL`] order, and a
+//! `skin.ron` describing the grid is written beside it. Behaviour starts as
+//! the built-in koala's; the manifest is meant to be hand-edited afterwards.
+
+use bevy::prelude::*;
+use std::path::Path;
+use thiserror::Error;
+
+use super::manifest::{LocomotionSpec, SkinManifest, StateSpec};
+use crate::core::brain::PetState;
+
+/// Filename prefix for each state. Short, because they are typed a lot.
+pub const FRAME_PREFIXES: [(&str, PetState); 8] = [
+    ("chill", PetState::Chilling),
+    ("drag", PetState::Dragged),
+    ("eat", PetState::Eating),
+    ("idle", PetState::Idle),
+    ("jump", PetState::Jumping),
+    ("love", PetState::SendingLove),
+    ("sit", PetState::Sitting),
+    ("walk", PetState::Walking),
+];
+
+#[derive(Debug, Error)]
+pub enum BuildError {
+    #[error("could not read {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("could not decode {path}: {reason}")]
+    Decode { path: String, reason: String },
+    #[error("no frames for {states:?}; a skin must implement all eight states")]
+    MissingStates { states: Vec<PetState> },
+    #[error("could not write {path}: {source}")]
+    Write {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("could not encode {path}: {source}")]
+    Encode {
+        path: String,
+        #[source]
+        source: png::EncodingError,
+    },
+}
+
+/// What `build` was asked for.
+#[derive(Debug, Clone, Copy)]
+pub struct BuildOptions {
+    /// Every frame is scaled to this square size.
+    pub frame_size: u32,
+    pub fps: u8,
+}
+
+/// What `build` wrote.
+#[derive(Debug, Clone)]
+pub struct Built {
+    pub columns: u32,
+    /// Frames found per state, in row order.
+    pub frames: Vec<(PetState, usize)>,
+    /// PNGs that matched the naming pattern but no state.
+    pub ignored: Vec<String>,
+}
+
+/// Packs the frames in `input` into `out/sheet.png` and `out/skin.ron`.
+pub fn build(input: &Path, out: &Path, options: BuildOptions) -> Result<Built, BuildError> {
+    let entries = std::fs::read_dir(input).map_err(|source| BuildError::Read {
+        path: input.display().to_string(),
+        source,
+    })?;
+
+    let mut found: Vec<Vec<(u32, std::path::PathBuf)>> = vec![Vec::new(); FRAME_PREFIXES.len()];
+    let mut ignored = Vec::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some((prefix, number)) = parse_frame_name(&file_name) else {
+            continue;
+        };
+        match FRAME_PREFIXES
+            .iter()
+            .position(|(known, _)| *known == prefix)
+        {
+            Some(row) => found[row].push((number, entry.path())),
+            None => ignored.push(file_name),
+        }
+    }
+    ignored.sort();
+
+    let missing: Vec<PetState> = FRAME_PREFIXES
+        .iter()
+        .zip(&found)
+        .filter(|(_, frames)| frames.is_empty())
+        .map(|((_, state), _)| *state)
+        .collect();
+    if !missing.is_empty() {
+        return Err(BuildError::MissingStates { states: missing });
+    }
+
+    let size = UVec2::splat(options.frame_size);
+    let columns = found.iter().map(Vec::len).max().unwrap_or(1) as u32;
+    let sheet_size = UVec2::new(columns, FRAME_PREFIXES.len() as u32) * size;
+    let mut sheet = vec![0u8; (sheet_size.x * sheet_size.y * 4) as usize];
+
+    for (row, frames) in found.iter_mut().enumerate() {
+        frames.sort();
+        for (column, (_, path)) in frames.iter().enumerate() {
+            let bytes = std::fs::read(path).map_err(|source| BuildError::Read {
+                path: path.display().to_string(),
+                source,
+            })?;
+            let image =
+                super::decode_png(&bytes, &path.display().to_string()).map_err(|error| {
+                    BuildError::Decode {
+                        path: path.display().to_string(),
+                        reason: error.to_string(),
+                    }
+                })?;
+            let origin = UVec2::new(column as u32, row as u32) * size;
+            paste_scaled(&mut sheet, sheet_size.x, origin, size, &image);
+        }
+    }
+
+    std::fs::create_dir_all(out).map_err(|source| BuildError::Write {
+        path: out.display().to_string(),
+        source,
+    })?;
+    write_png(&out.join("sheet.png"), sheet_size, &sheet)?;
+
+    let name = out
+        .file_name()
+        .map_or_else(|| "skin".to_string(), |n| n.to_string_lossy().into_owned());
+    let counts: Vec<(PetState, usize)> = FRAME_PREFIXES
+        .iter()
+        .zip(&found)
+        .map(|((_, state), frames)| (*state, frames.len()))
+        .collect();
+    let manifest = manifest_for(&name, &counts, columns, options);
+    let manifest_path = out.join("skin.ron");
+    std::fs::write(&manifest_path, manifest_text(&manifest)).map_err(|source| {
+        BuildError::Write {
+            path: manifest_path.display().to_string(),
+            source,
+        }
+    })?;
+
+    Ok(Built {
+        columns,
+        frames: counts,
+        ignored,
+    })
+}
+
+/// Splits `walk_03.png` into `("walk", 3)`.
+fn parse_frame_name(file_name: &str) -> Option<(&str, u32)> {
+    let stem = file_name.strip_suffix(".png")?;
+    let (prefix, number) = stem.rsplit_once('_')?;
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some((prefix, number.parse().ok()?))
+}
+
+/// The manifest for a freshly packed sheet, with the koala's behaviour.
+fn manifest_for(
+    name: &str,
+    counts: &[(PetState, usize)],
+    columns: u32,
+    options: BuildOptions,
+) -> SkinManifest {
+    let koala =
+        SkinManifest::parse(super::BUILTIN_MANIFEST, "<builtin>").expect("built-in skin is valid");
+    let states = counts
+        .iter()
+        .map(|&(state, frames)| {
+            let base = koala
+                .states
+                .iter()
+                .find(|spec| spec.state == state)
+                .expect("the built-in skin has every state");
+            StateSpec {
+                state,
+                frames: frames as u32,
+                // A single frame has nothing to animate; a slow clock saves
+                // spinning the frame timer needlessly.
+                fps: (frames == 1).then_some(1),
+                // The koala's timings are for the koala's frame counts, and
+                // its direction rows are not in this sheet.
+                frame_durations: None,
+                directions: Vec::new(),
+                ..base.clone()
+            }
+        })
+        .collect();
+
+    SkinManifest {
+        name: name.to_string(),
+        sheet: Some("sheet.png".to_string()),
+        atlases: Vec::new(),
+        frame_size: (options.frame_size, options.frame_size),
+        columns: Some(columns),
+        default_fps: options.fps,
+        states,
+        clips: Vec::new(),
+        items: Vec::new(),
+        sounds: Vec::new(),
+        load: Vec::new(),
+        script: None,
+        behaviour: None,
+    }
+}
+
+/// Writes `manifest` as RON, laid out like the shipped skins.
+fn manifest_text(manifest: &SkinManifest) -> String {
+    let mut text = String::new();
+    let mut line = |s: String| {
+        text.push_str(&s);
+        text.push('\n');
+    };
+    line("// Generated by `batates skin build`. Row order is the state order below;".into());
+    line("// the sheet is a strict rows x columns grid.".into());
+    line("SkinManifest(".into());
+    line(format!("    name: {:?},", manifest.name));
+    if let Some(sheet) = &manifest.sheet {
+        line(format!("    sheet: {sheet:?},"));
+    }
+    line(format!(
+        "    frame_size: ({}, {}),",
+        manifest.frame_size.0, manifest.frame_size.1
+    ));
+    if let Some(columns) = manifest.columns {
+        line(format!("    columns: {columns},"));
+    }
+    line(format!("    default_fps: {},", manifest.default_fps));
+    line("    states: [".into());
+    for spec in &manifest.states {
+        let transitions = spec
+            .transitions
+            .iter()
+            .map(|t| format!("(to: {:?}, weight: {})", t.to, t.weight))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let locomotion = match spec.locomotion {
+            LocomotionSpec::Still => "Still".to_string(),
+            LocomotionSpec::Held => "Held".to_string(),
+            LocomotionSpec::Walk { speed } => format!("Walk(speed: {speed:?})"),
+        };
+        line("        (".into());
+        line(format!("            state: {:?},", spec.state));
+        line(format!("            frames: {},", spec.frames));
+        if let Some(fps) = spec.fps {
+            line(format!("            fps: Some({fps}),"));
+        }
+        if let Some(durations) = &spec.frame_durations {
+            let durations = durations
+                .iter()
+                .map(|seconds| format!("{seconds:?}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            line(format!("            frame_durations: Some([{durations}]),"));
+        }
+        line(format!("            playback: {:?},", spec.playback));
+        line(format!(
+            "            duration: ({:?}, {:?}),",
+            spec.duration.0, spec.duration.1
+        ));
+        line(format!("            locomotion: {locomotion},"));
+        line(format!("            transitions: [{transitions}],"));
+        if !spec.directions.is_empty() {
+            let directions = spec
+                .directions
+                .iter()
+                .map(|d| format!("{d:?}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            line(format!("            directions: [{directions}],"));
+        }
+        if !spec.phrases.is_empty() {
+            let phrases = spec
+                .phrases
+                .iter()
+                .map(|phrase| format!("{phrase:?}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            line(format!("            phrases: [{phrases}],"));
+        }
+        line("        ),".into());
+    }
+    line("    ],".into());
+    line(")".into());
+    text
+}
+
+/// Copies `image` into the sheet at `origin`, scaled to `size` by nearest
+/// neighbour, which keeps pixel art crisp.
+fn paste_scaled(sheet: &mut [u8], sheet_width: u32, origin: UVec2, size: UVec2, image: &Image) {
+    let source = image.size();
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let from = UVec2::new(x, y) * source / size;
+            let Ok(colour) = image.get_color_at(from.x, from.y) else {
+                continue;
+            };
+            let at = (((origin.y + y) * sheet_width + origin.x + x) * 4) as usize;
+            sheet[at..at + 4].copy_from_slice(&colour.to_srgba().to_u8_array());
+        }
+    }
+}
+
+fn write_png(path: &Path, size: UVec2, rgba: &[u8]) -> Result<(), BuildError> {
+    let file = std::fs::File::create(path).map_err(|source| BuildError::Write {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let encode_error = |source| BuildError::Encode {
+        path: path.display().to_string(),
+        source,
+    };
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), size.x, size.y);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(encode_error)?;
+    writer.write_image_data(rgba).map_err(encode_error)?;
+    writer.finish().map_err(encode_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_names_split_into_prefix_and_number() {
+        assert_eq!(parse_frame_name("walk_03.png"), Some(("walk", 3)));
+        assert_eq!(parse_frame_name("love_10.png"), Some(("love", 10)));
+        assert_eq!(parse_frame_name("walk_03.gif"), None);
+        assert_eq!(parse_frame_name("walk.png"), None);
+        assert_eq!(parse_frame_name("walk_two.png"), None);
+    }
+
+    #[test]
+    fn prefixes_cover_every_state_in_row_order() {
+        let states: Vec<PetState> = FRAME_PREFIXES.iter().map(|(_, state)| *state).collect();
+        assert_eq!(states, PetState::ALL);
+    }
+
+    /// What `build` writes must load: parse it back and validate it.
+    #[test]
+    fn the_written_manifest_is_a_valid_skin() {
+        let counts: Vec<(PetState, usize)> = PetState::ALL
+            .iter()
+            .map(|&state| (state, if state == PetState::Sitting { 1 } else { 6 }))
+            .collect();
+        let options = BuildOptions {
+            frame_size: 32,
+            fps: 10,
+        };
+        let text = manifest_text(&manifest_for("fox", &counts, 6, options));
+
+        let manifest = SkinManifest::parse(&text, "fox").expect("parses");
+        let (geometry, table) = manifest.into_parts().expect("valid");
+        assert_eq!(geometry.name, "fox");
+        assert_eq!(geometry.frame_size, UVec2::splat(32));
+        assert_eq!(geometry.columns, 6);
+        assert_eq!(table.get(PetState::Walking).fps, 10);
+        assert_eq!(table.get(PetState::Sitting).fps, 1);
+        assert!(matches!(
+            table.get(PetState::Walking).locomotion,
+            crate::core::brain::Locomotion::Walk { .. }
+        ));
+    }
+}
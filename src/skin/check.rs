@@ -0,0 +1,389 @@
+//! `batates skin check`: everything wrong with a skin, without starting the app.
+//!
+//! Loading stops at the first error and falls back to the koala, which is
+//! right for a running pet and useless for someone making a skin. This reads
+//! the same files the loader does but keeps going, and points each problem at
+//! the line of `skin.ron` it comes from where there is one.
+
+use std::fmt;
+use std::path::Path;
+
+use super::atlas::{self, AtlasFile};
+use super::manifest::{SkinError, SkinManifest};
+use super::script::SkinScript;
+use super::{
+    BUILTIN_MANIFEST, SkinSource, atlas_image, decode_png, decode_sound, read_builtin, read_bytes,
+};
+
+/// Whether a problem stops the skin loading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// Loads fine, but is probably not what the author meant.
+    Warning,
+}
+
+/// One thing wrong with a skin, and where.
+#[derive(Debug)]
+pub struct Problem {
+    pub file: String,
+    /// 1-based, when the problem belongs to a line of the manifest.
+    pub line: Option<usize>,
+    pub severity: Severity,
+    pub error: SkinError,
+}
+
+impl Problem {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, "{line}:")?;
+        }
+        if self.severity == Severity::Warning {
+            write!(f, " warning:")?;
+        }
+        write!(f, " {}", self.error)
+    }
+}
+
+/// Every problem with the skin at `source`. No errors means it loads as
+/// written.
+pub fn check(source: &SkinSource) -> Vec<Problem> {
+    match source {
+        SkinSource::Builtin => check_manifest(
+            BUILTIN_MANIFEST,
+            "<builtin>/skin.ron",
+            read_builtin,
+            read_builtin,
+        ),
+        SkinSource::Directory(dir) => {
+            let manifest_path = dir.join("skin.ron");
+            let file = manifest_path.display().to_string();
+            match super::read_file(&manifest_path) {
+                Ok(text) => check_manifest(
+                    &text,
+                    &file,
+                    |sheet| read_bytes(&dir.join(sheet)),
+                    |sprite| read_bytes(&dir.join(sprite)),
+                ),
+                Err(error) => vec![Problem {
+                    file,
+                    line: None,
+                    severity: Severity::Error,
+                    error,
+                }],
+            }
+        }
+        SkinSource::Aseprite(path) => check_aseprite(path),
+    }
+}
+
+/// Checks a manifest and the files it names. The readers fetch the sheets and
+/// atlas files, then the item sprites, sounds and script, by the names the
+/// manifest gives, so the built-in skin can answer from memory.
+fn check_manifest(
+    text: &str,
+    file: &str,
+    read_sheet: impl Fn(&str) -> Result<Vec<u8>, SkinError>,
+    read_item: impl Fn(&str) -> Result<Vec<u8>, SkinError>,
+) -> Vec<Problem> {
+    let located = |severity: Severity| {
+        move |error: SkinError| Problem {
+            file: file.to_string(),
+            line: line_of(text, &error),
+            severity,
+            error,
+        }
+    };
+    let unlocated = |error: SkinError| Problem {
+        file: file.to_string(),
+        line: None,
+        severity: Severity::Error,
+        error,
+    };
+    // A parse error already carries its position.
+    let manifest = match SkinManifest::parse(text, file) {
+        Ok(manifest) => manifest,
+        Err(error) => return vec![unlocated(error)],
+    };
+
+    let mut problems: Vec<Problem> = manifest
+        .problems()
+        .into_iter()
+        .map(located(Severity::Error))
+        .collect();
+    let valid = problems.is_empty();
+    problems.extend(
+        manifest
+            .unreachable()
+            .into_iter()
+            .map(located(Severity::Warning)),
+    );
+
+    // The expected sizes are only known for a manifest that validates.
+    let geometry = valid
+        .then(|| manifest.clone().into_parts().ok())
+        .flatten()
+        .map(|(geometry, _)| geometry);
+
+    if let Some(name) = &manifest.sheet {
+        match read_sheet(name).and_then(|bytes| decode_png(&bytes, name)) {
+            Ok(sheet) => {
+                if let Some(geometry) = &geometry
+                    && let Err(error) = geometry.verify_sheet(sheet.size())
+                {
+                    problems.push(unlocated(error));
+                }
+            }
+            Err(error) => problems.push(unlocated(error)),
+        }
+    }
+
+    let mut files = Vec::with_capacity(manifest.atlases.len());
+    for name in &manifest.atlases {
+        let file = read_sheet(name).and_then(|bytes| {
+            AtlasFile::parse(&bytes).map_err(|source| SkinError::Atlas {
+                path: name.clone(),
+                source,
+            })
+        });
+        match file {
+            Ok(file) => files.push((name.clone(), file)),
+            Err(error) => problems.push(unlocated(error)),
+        }
+    }
+    // Placing needs every file; one that failed is already reported.
+    let placed = match &geometry {
+        Some(geometry) if files.len() == manifest.atlases.len() => {
+            match atlas::place(&files, geometry) {
+                Ok(placed) => Some(placed),
+                Err(error) => {
+                    problems.push(unlocated(error));
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    for (index, (name, file)) in files.iter().enumerate() {
+        let image = atlas_image(name, file);
+        match read_sheet(&image).and_then(|bytes| decode_png(&bytes, &image)) {
+            Ok(sheet) => {
+                if let Some(atlas) = &placed
+                    && let Err(error) = atlas.verify_sheet(index, sheet.size())
+                {
+                    problems.push(unlocated(error));
+                }
+            }
+            Err(error) => problems.push(unlocated(error)),
+        }
+    }
+
+    for item in &manifest.items {
+        if let Err(error) =
+            read_item(&item.sprite).and_then(|bytes| decode_png(&bytes, &item.sprite))
+        {
+            problems.push(unlocated(error));
+        }
+    }
+    for sound in &manifest.sounds {
+        if let Err(error) =
+            read_item(&sound.file).and_then(|bytes| decode_sound(bytes, &sound.file))
+        {
+            problems.push(unlocated(error));
+        }
+    }
+    if let Some(name) = &manifest.script
+        && let Err(error) = read_item(name).and_then(|bytes| SkinScript::load(&bytes, name))
+    {
+        problems.push(unlocated(error));
+    }
+    problems
+}
+
+fn check_aseprite(path: &Path) -> Vec<Problem> {
+    let file = path.display().to_string();
+    let file = file.as_str();
+    let problem = |severity: Severity| {
+        move |error: SkinError| Problem {
+            file: file.to_string(),
+            line: None,
+            severity,
+            error,
+        }
+    };
+
+    let bytes = match read_bytes(path) {
+        Ok(bytes) => bytes,
+        Err(error) => return vec![problem(Severity::Error)(error)],
+    };
+    let fallback =
+        SkinManifest::parse(BUILTIN_MANIFEST, "<builtin>").expect("built-in skin is valid");
+    let name = path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    match super::aseprite::import(&bytes, &name, &fallback) {
+        Ok(imported) => {
+            let manifest = imported.manifest;
+            let mut problems: Vec<Problem> = manifest
+                .problems()
+                .into_iter()
+                .map(problem(Severity::Error))
+                .collect();
+            problems.extend(
+                manifest
+                    .unreachable()
+                    .into_iter()
+                    .map(problem(Severity::Warning)),
+            );
+            problems
+        }
+        Err(source) => vec![problem(Severity::Error)(SkinError::Aseprite {
+            path: file.to_string(),
+            source,
+        })],
+    }
+}
+
+/// The manifest line an error is about, if it is about one state, item,
+/// sound or rule, or the behaviour tree.
+fn line_of(text: &str, error: &SkinError) -> Option<usize> {
+    let needle = match error {
+        SkinError::StateOrder { got: state, .. }
+        | SkinError::TooManyFrames { state, .. }
+        | SkinError::NoFrames { state }
+        | SkinError::DuplicateDirection { state, .. }
+        | SkinError::FrameDurationCount { state, .. }
+        | SkinError::BadFrameDuration { state, .. }
+        | SkinError::BadDuration { state, .. }
+        | SkinError::BadTransitions { state, .. }
+        | SkinError::HeldTransition { state }
+        | SkinError::Unreachable { state } => format!("state: {state:?}"),
+        // The cycle's first state, where a fix most likely starts.
+        SkinError::AbsorbingCycle { states } => format!("state: {:?}", states.first()?),
+        SkinError::HeldItemState { kind } => format!("kind: {kind:?}"),
+        SkinError::ClipFrames { name, .. }
+        | SkinError::ClipNeverPlays { name, .. }
+        | SkinError::DuplicateClip { name, .. } => format!("name: {name:?}"),
+        // The second declaration is the duplicate.
+        SkinError::DuplicateItem { kind } => {
+            let needle = format!("kind: {kind:?}");
+            let at = text.rfind(&needle)?;
+            return Some(line_at(text, at));
+        }
+        SkinError::DuplicateSound { cue } => {
+            let needle = format!("on: {cue:?}");
+            let at = text.rfind(&needle)?;
+            return Some(line_at(text, at));
+        }
+        // Rules are numbered from one, in the order they are written.
+        SkinError::LoadRule { index, .. } => {
+            let (at, _) = text.match_indices("when:").nth(index.checked_sub(1)?)?;
+            return Some(line_at(text, at));
+        }
+        SkinError::Behaviour { .. } => "behaviour:".to_string(),
+        SkinError::OutsideSkin { path } => format!("{path:?}"),
+        _ => return None,
+    };
+    text.find(&needle).map(|at| line_at(text, at))
+}
+
+fn line_at(text: &str, byte: usize) -> usize {
+    text[..byte].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::brain::PetState;
+    use crate::skin::{BUILTIN_ITEMS, BUILTIN_SHEET};
+    use std::path::PathBuf;
+
+    fn errors(problems: Vec<Problem>) -> Vec<Problem> {
+        problems.into_iter().filter(Problem::is_error).collect()
+    }
+
+    #[test]
+    fn shipped_skins_check_clean() {
+        let panda = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/skins/panda");
+        for source in [SkinSource::Builtin, SkinSource::Directory(panda)] {
+            let problems = check(&source);
+            assert!(
+                !problems.iter().any(Problem::is_error),
+                "{source:?}: {problems:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn problems_point_at_their_state() {
+        let text = BUILTIN_MANIFEST.replacen("frames: 24", "frames: 0", 1);
+        let problems = check_manifest(
+            &text,
+            "koala/skin.ron",
+            |_| Ok(BUILTIN_SHEET.to_vec()),
+            |_| Ok(BUILTIN_ITEMS[0].1.to_vec()),
+        );
+        let problems = errors(problems);
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        let problem = &problems[0];
+        assert!(matches!(
+            problem.error,
+            SkinError::NoFrames {
+                state: PetState::Eating
+            }
+        ));
+        let line = text.lines().nth(problem.line.unwrap() - 1).unwrap();
+        assert!(line.contains("state: Eating"), "{line}");
+        assert!(problem.to_string().starts_with("koala/skin.ron:"));
+    }
+
+    #[test]
+    fn a_missing_sheet_is_reported_alongside_manifest_problems() {
+        let text = BUILTIN_MANIFEST.replacen("frames: 24", "frames: 0", 1);
+        let problems = check_manifest(
+            &text,
+            "koala/skin.ron",
+            |sheet| {
+                Err(SkinError::Read {
+                    path: sheet.to_string(),
+                    source: std::io::ErrorKind::NotFound.into(),
+                })
+            },
+            |_| Ok(BUILTIN_ITEMS[0].1.to_vec()),
+        );
+        let problems = errors(problems);
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(matches!(problems[1].error, SkinError::Read { .. }));
+    }
+
+    /// The koala only jumps and sends love when asked to. That is worth
+    /// mentioning but does not stop it loading.
+    #[test]
+    fn interrupt_only_states_are_warnings() {
+        let problems = check(&SkinSource::Builtin);
+        let warned: Vec<&SkinError> = problems
+            .iter()
+            .filter(|problem| problem.severity == Severity::Warning)
+            .map(|problem| &problem.error)
+            .collect();
+        assert!(matches!(
+            warned.as_slice(),
+            [
+                SkinError::Unreachable {
+                    state: PetState::Jumping
+                },
+                SkinError::Unreachable {
+                    state: PetState::SendingLove
+                },
+            ]
+        ));
+        assert!(problems[0].to_string().contains(": warning: "));
+    }
+}
@@ -1,9 +1,15 @@
 //! Skin manifests: the on-disk description of a sprite sheet and its behaviour.
 //!
-//! The sheet is a strict `rows x columns` grid. A state's row is its position in
-//! the `states` list, which is why the list must be complete and in
-//! [`PetState::ALL`] order. That is what turns the old hand-maintained table of
-//! absolute frame indices into arithmetic.
+//! Frames are laid out in rows. A state's row is its position in the `states`
+//! list, which is why the list must be complete and in [`PetState::ALL`]
+//! order. That is what turns the old hand-maintained table of absolute frame
+//! indices into arithmetic. Rows for a state's other `directions` follow the
+//! eight, in the order they are declared, and then one row per transition
+//! clip.
+//!
+//! A `sheet` draws those rows as a strict `rows x columns` grid. `atlases`
+//! instead name packed atlas files, which place each frame by name wherever
+//! the packer put it; see [`super::atlas`].
 //!
 //! Serde types live here rather than in `core` so the gameplay logic stays free
 //! of serialisation concerns; [`SkinManifest::into_parts`] is the boundary where
@@ -11,15 +17,24 @@
 
 use bevy::prelude::*;
 use serde::Deserialize;
+use std::path::{Component, Path};
 use std::time::Duration;
 use thiserror::Error;
 
+use crate::core::animation::DirectionRows;
+use crate::core::behaviour::{BehaviourTree, Goal, Node, TreeError};
 use crate::core::brain::{
-    Locomotion, PetState, Playback, StateDef, StateTable, TableError, WeightedTable,
+    Clip, Locomotion, PetState, Playback, StateDef, StateTable, TableError, WeightedTable,
 };
+use crate::core::items::ItemKind;
+use crate::core::load::{LoadCondition, LoadRule};
+use crate::core::movement::Heading;
+use crate::core::sound::SoundCue;
 
 #[derive(Debug, Error)]
 pub enum SkinError {
+    #[error("skin layout: {reason}")]
+    Layout { reason: &'static str },
     #[error("could not read skin manifest at {path}: {source}")]
     Read {
         path: String,
@@ -52,6 +67,23 @@ pub enum SkinError {
     },
     #[error("state {state:?} must declare at least one frame")]
     NoFrames { state: PetState },
+    #[error("state {state:?} lists {got} frame durations for its {frames} frames")]
+    FrameDurationCount {
+        state: PetState,
+        got: usize,
+        frames: u32,
+    },
+    #[error("state {state:?} frame {frame} lasts {seconds}s; frames must last a positive time")]
+    BadFrameDuration {
+        state: PetState,
+        frame: usize,
+        seconds: f32,
+    },
+    #[error("state {state:?} declares its {direction:?} row more than once")]
+    DuplicateDirection {
+        state: PetState,
+        direction: DirectionSpec,
+    },
     #[error("state {state:?} has duration min {min}s greater than max {max}s")]
     BadDuration { state: PetState, min: f32, max: f32 },
     #[error("state {state:?} has an unusable transition table: {source}")]
@@ -72,6 +104,75 @@ pub enum SkinError {
         "skin declares {rows} rows and frame height {height}, but the sheet is {actual}px tall"
     )]
     SheetHeight { rows: u32, height: u32, actual: u32 },
+    #[error(
+        "atlas sheet {image} is {}x{}px, but its atlas describes {}x{}px",
+        .actual.x, .actual.y, .want.x, .want.y
+    )]
+    AtlasSheetSize {
+        image: String,
+        want: UVec2,
+        actual: UVec2,
+    },
+    #[error("atlas row {row:?} has {got} frames, but the manifest declares {want}")]
+    AtlasFrameCount { row: String, got: usize, want: u32 },
+    #[error("could not read atlas {path}: {source}")]
+    Atlas {
+        path: String,
+        #[source]
+        source: super::atlas::AtlasError,
+    },
+    #[error("clip {name:?} declares {frames} frames; it needs 1 to {columns}, the sheet's columns")]
+    ClipFrames {
+        name: String,
+        frames: u32,
+        columns: u32,
+    },
+    #[error("clip {name:?} bridges {from:?} to {to:?}, but {from:?} never transitions to {to:?}")]
+    ClipNeverPlays {
+        name: String,
+        from: PetState,
+        to: PetState,
+    },
+    #[error("clip {name:?} is the second clip from {from:?} to {to:?}")]
+    DuplicateClip {
+        name: String,
+        from: PetState,
+        to: PetState,
+    },
+    #[error("item {kind:?} is declared more than once")]
+    DuplicateItem { kind: ItemKind },
+    #[error("sound for {cue:?} is declared more than once")]
+    DuplicateSound { cue: SoundCue },
+    #[error("could not play sound {path}: {reason}")]
+    Sound { path: String, reason: &'static str },
+    #[error("{path:?} is outside the skin: a skin's files are beside its manifest or below it")]
+    OutsideSkin { path: String },
+    #[error("could not compile script {path}: {message}")]
+    Script { path: String, message: String },
+    #[error("skin behaviour tree is unusable: {source}")]
+    Behaviour {
+        #[source]
+        source: TreeError,
+    },
+    /// `index` counts the `load` rules from 1.
+    #[error("load rule {index}: {reason}")]
+    LoadRule { index: usize, reason: &'static str },
+    #[error("item {kind:?} cannot trigger Dragged: that state is owned by the pointer")]
+    HeldItemState { kind: ItemKind },
+    #[error("state {state:?} transitions to Dragged, which only the pointer may enter")]
+    HeldTransition { state: PetState },
+    #[error(
+        "states {states:?} only lead to each other, so a pet that enters them never idles or walks again"
+    )]
+    AbsorbingCycle { states: Vec<PetState> },
+    #[error("state {state:?} is only entered by interrupts: no other state transitions to it")]
+    Unreachable { state: PetState },
+    #[error("could not import Aseprite file {path}: {source}")]
+    Aseprite {
+        path: String,
+        #[source]
+        source: super::aseprite::AsepriteError,
+    },
 }
 
 /// How a state moves, as written in the manifest.
@@ -107,6 +208,24 @@ impl From<PlaybackSpec> for Playback {
     }
 }
 
+/// A way a state can face besides right, which its own row draws.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+pub enum DirectionSpec {
+    Left,
+    Up,
+    Down,
+}
+
+impl From<DirectionSpec> for Heading {
+    fn from(spec: DirectionSpec) -> Self {
+        match spec {
+            DirectionSpec::Left => Heading::Left,
+            DirectionSpec::Up => Heading::Up,
+            DirectionSpec::Down => Heading::Down,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize)]
 pub struct TransitionSpec {
     pub to: PetState,
@@ -120,11 +239,112 @@ pub struct StateSpec {
     /// Overrides the skin-wide default when a state needs its own pace.
     #[serde(default)]
     pub fps: Option<u8>,
+    /// Seconds each frame is shown, one per frame, for holds on key poses.
+    /// Overrides `fps` entirely.
+    #[serde(default)]
+    pub frame_durations: Option<Vec<f32>>,
     pub playback: PlaybackSpec,
     /// Seconds, inclusive range the state's duration is drawn from.
     pub duration: (f32, f32),
     pub locomotion: LocomotionSpec,
     pub transitions: Vec<TransitionSpec>,
+    /// Extra rows drawing this state facing other ways, with the same frames
+    /// and timing. Without a `Left` row the right-facing one is mirrored.
+    #[serde(default)]
+    pub directions: Vec<DirectionSpec>,
+    /// Things a pet may say on entering this state, one picked at random.
+    #[serde(default)]
+    pub phrases: Vec<String>,
+}
+
+/// A one-shot animation played between two states, instead of snapping from
+/// one to the other. Only transitions the state machine takes on its own play
+/// it; an interrupt still enters its state at once.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipSpec {
+    /// For error messages, such as `sit_to_stand`.
+    pub name: String,
+    pub from: PetState,
+    pub to: PetState,
+    pub frames: u32,
+    #[serde(default)]
+    pub fps: Option<u8>,
+}
+
+/// A droppable item this skin can draw, and how its pets react to reaching it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemSpec {
+    pub kind: ItemKind,
+    /// Image filename, resolved relative to the manifest's own directory.
+    pub sprite: String,
+    pub state: PetState,
+}
+
+/// A clip this skin plays on a cue.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SoundSpec {
+    pub on: SoundCue,
+    /// Ogg Vorbis filename, resolved relative to the manifest's own directory.
+    pub file: String,
+}
+
+/// How this skin's pets react to one condition of the machine they run on.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoadSpec {
+    pub when: LoadCondition,
+    /// Transition weight multipliers while `when` holds, such as
+    /// `[(Walking, 3.0)]` for pets that pace while the CPU is busy.
+    #[serde(default)]
+    pub bias: Vec<(PetState, f32)>,
+    /// A state every pet enters as `when` starts to hold.
+    #[serde(default)]
+    pub enter: Option<PetState>,
+}
+
+/// A node of a skin's behaviour tree, as written in the manifest.
+#[derive(Debug, Clone, Deserialize)]
+pub enum BehaviourSpec {
+    Sequence(Vec<BehaviourSpec>),
+    Selector(Vec<BehaviourSpec>),
+    /// Seconds, an inclusive range, left to the state's own transitions.
+    Wait(f32, f32),
+    WalkTo(GoalSpec),
+    Play(PetState),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum GoalSpec {
+    Anywhere,
+    /// Fractions of the screen from its middle, -1 to 1 on each axis.
+    Spot(f32, f32),
+}
+
+impl BehaviourSpec {
+    fn node(&self) -> Result<Node, TreeError> {
+        let children = |specs: &[BehaviourSpec]| -> Result<Vec<Node>, TreeError> {
+            specs.iter().map(Self::node).collect()
+        };
+        Ok(match *self {
+            Self::Sequence(ref specs) => Node::Sequence(children(specs)?),
+            Self::Selector(ref specs) => Node::Selector(children(specs)?),
+            Self::Wait(min, max) => {
+                // `Duration` cannot hold a negative, NaN or huge wait, so it
+                // is caught here rather than by the tree.
+                let seconds = |s| Duration::try_from_secs_f32(s).map_err(|_| TreeError::BadWait);
+                Node::Wait(seconds(min)?, seconds(max)?)
+            }
+            Self::WalkTo(GoalSpec::Anywhere) => Node::WalkTo(Goal::Anywhere),
+            Self::WalkTo(GoalSpec::Spot(x, y)) => Node::WalkTo(Goal::Spot(Vec2::new(x, y))),
+            Self::Play(state) => Node::Play(state),
+        })
+    }
+}
+
+/// Validates the behaviour tree.
+fn behaviour_tree(spec: &BehaviourSpec) -> Result<BehaviourTree, SkinError> {
+    spec.node()
+        .and_then(BehaviourTree::new)
+        .map_err(|source| SkinError::Behaviour { source })
 }
 
 /// A skin as written on disk.
@@ -132,30 +352,105 @@ pub struct StateSpec {
 #[serde(deny_unknown_fields)]
 pub struct SkinManifest {
     pub name: String,
-    /// Sheet filename, resolved relative to the manifest's own directory.
-    pub sheet: String,
+    /// Sheet filename, resolved relative to the manifest's own directory, for
+    /// a skin drawn as a grid.
+    #[serde(default)]
+    pub sheet: Option<String>,
+    /// Packed atlas files instead of a `sheet`, one per sheet they pack, and
+    /// resolved like it.
+    #[serde(default)]
+    pub atlases: Vec<String>,
+    /// The size of every frame before any trimming.
     pub frame_size: (u32, u32),
-    pub columns: u32,
+    /// The grid's width in frames. Only a `sheet` has one.
+    #[serde(default)]
+    pub columns: Option<u32>,
     pub default_fps: u8,
     pub states: Vec<StateSpec>,
+    #[serde(default)]
+    pub clips: Vec<ClipSpec>,
+    /// Optional: a skin without items simply has nothing to drop.
+    #[serde(default)]
+    pub items: Vec<ItemSpec>,
+    /// Optional as well: a skin without sounds is silent.
+    #[serde(default)]
+    pub sounds: Vec<SoundSpec>,
+    /// And a skin without load rules ignores the machine.
+    #[serde(default)]
+    pub load: Vec<LoadSpec>,
+    /// A Rhai script beside the manifest, for behaviour the tables cannot
+    /// express.
+    #[serde(default)]
+    pub script: Option<String>,
+    /// A behaviour tree over the states, for plans longer than one
+    /// transition.
+    #[serde(default)]
+    pub behaviour: Option<BehaviourSpec>,
 }
 
 /// The validated visual half of a skin.
 #[derive(Debug, Clone, PartialEq)]
 pub struct SkinGeometry {
     pub name: String,
-    pub sheet: String,
+    pub layout: SheetLayout,
     pub frame_size: UVec2,
+    /// Frames per row as [`atlas_index`](crate::core::animation::atlas_index)
+    /// counts them: the grid's width, or a packed atlas's longest row.
     pub columns: u32,
     pub rows: u32,
+    /// One per row, in row order.
+    pub sheet_rows: Vec<SheetRow>,
+    pub directions: DirectionRows,
+    pub items: Vec<ItemDef>,
+    pub sounds: Vec<SoundDef>,
+    pub load: Vec<LoadRule>,
+    pub script: Option<String>,
+}
+
+/// Where a skin's frames are drawn from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SheetLayout {
+    /// One sheet, a strict `rows x columns` grid.
+    Grid { sheet: String },
+    /// Trimmed frames packed over one or more sheets, as these files place
+    /// them.
+    Packed { atlases: Vec<String> },
+}
+
+/// A row of frames, by the name a packed atlas files its frames under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetRow {
+    /// `walk` for the Walking row, `walk_up` for its Up row, or a clip's name.
+    pub name: String,
+    pub frames: u32,
+}
+
+/// A validated item: at most one per kind, never reacting with a held state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemDef {
+    pub kind: ItemKind,
+    pub sprite: String,
+    pub reaction: PetState,
+}
+
+/// A validated sound: at most one per cue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundDef {
+    pub cue: SoundCue,
+    pub file: String,
 }
 
 impl SkinManifest {
+    /// Parses a manifest. `sheet` and `columns` are optional, but written
+    /// bare, so `Some(..)` may be left out.
     pub fn parse(text: &str, path: &str) -> Result<Self, SkinError> {
-        ron::from_str(text).map_err(|source| SkinError::Parse {
-            path: path.to_string(),
-            source: Box::new(source),
-        })
+        ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_str(text)
+            .map_err(|source| SkinError::Parse {
+                path: path.to_string(),
+                source: Box::new(source),
+            })
     }
 
     /// Validates the manifest and splits it into geometry and a behaviour table.
@@ -163,80 +458,529 @@ impl SkinManifest {
     /// Every failure mode is checked here, at the boundary, so nothing
     /// downstream has to defend against a malformed skin.
     pub fn into_parts(self) -> Result<(SkinGeometry, StateTable), SkinError> {
-        if self.states.len() != PetState::ALL.len() {
-            return Err(SkinError::StateCount {
-                got: self.states.len(),
-                want: PetState::ALL.len(),
+        let layout = self.layout()?;
+        if let Some(outside) = self.files_outside().next() {
+            return Err(outside);
+        }
+        self.check_state_count()?;
+        let mut defs = self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(index, spec)| self.state_def(index, spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(cycle) = self.absorbing_cycles().into_iter().next() {
+            return Err(cycle);
+        }
+
+        let mut items: Vec<ItemDef> = Vec::with_capacity(self.items.len());
+        for spec in &self.items {
+            items.push(item_def(&items, spec)?);
+        }
+        let mut sounds: Vec<SoundDef> = Vec::with_capacity(self.sounds.len());
+        for spec in &self.sounds {
+            sounds.push(sound_def(&sounds, spec)?);
+        }
+        let load = self
+            .load
+            .iter()
+            .enumerate()
+            .map(|(index, spec)| load_rule(index + 1, spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let tree = self.behaviour.as_ref().map(behaviour_tree).transpose()?;
+
+        let base_rows = PetState::ALL.len() as u32;
+        let extra: Vec<(PetState, Heading, u32)> = self
+            .states
+            .iter()
+            .flat_map(|spec| spec.directions.iter().map(|&d| (spec.state, d.into())))
+            .zip(base_rows..)
+            .map(|((state, heading), row)| (state, heading, row))
+            .collect();
+
+        let mut sheet_rows: Vec<SheetRow> = self
+            .states
+            .iter()
+            .map(|spec| SheetRow {
+                name: row_prefix(spec.state).to_string(),
+                frames: spec.frames,
+            })
+            .collect();
+        sheet_rows.extend(self.states.iter().flat_map(|spec| {
+            spec.directions.iter().map(|direction| SheetRow {
+                name: format!("{}_{direction:?}", row_prefix(spec.state)).to_lowercase(),
+                frames: spec.frames,
+            })
+        }));
+        sheet_rows.extend(self.clips.iter().map(|spec| SheetRow {
+            name: spec.name.clone(),
+            frames: spec.frames,
+        }));
+
+        let first_clip_row = base_rows + extra.len() as u32;
+        for (index, spec) in self.clips.iter().enumerate() {
+            self.check_clip(&self.clips[..index], spec)?;
+            defs[spec.from.row() as usize].clips.push(Clip {
+                to: spec.to,
+                row: first_clip_row + index as u32,
+                frames: spec.frames,
+                fps: spec.fps.unwrap_or(self.default_fps),
             });
         }
 
-        let mut defs = Vec::with_capacity(self.states.len());
+        let columns = self.columns();
+        let geometry = SkinGeometry {
+            name: self.name,
+            layout,
+            frame_size: UVec2::new(self.frame_size.0, self.frame_size.1),
+            columns,
+            rows: sheet_rows.len() as u32,
+            sheet_rows,
+            directions: DirectionRows::new(extra),
+            items,
+            sounds,
+            load,
+            script: self.script,
+        };
+
+        Ok((geometry, StateTable::new(defs).with_tree(tree)))
+    }
+
+    /// Everything [`into_parts`](Self::into_parts) would reject, rather than
+    /// only the first, for `batates skin check`.
+    pub fn problems(&self) -> Vec<SkinError> {
+        let mut problems: Vec<SkinError> = self.layout().err().into_iter().collect();
+        problems.extend(self.files_outside());
+        problems.extend(self.check_state_count().err());
         for (index, spec) in self.states.iter().enumerate() {
-            let want = PetState::ALL[index];
-            if spec.state != want {
-                return Err(SkinError::StateOrder {
-                    index,
-                    got: spec.state,
-                    want,
-                });
+            if let Err(error) = self.state_def(index, spec) {
+                problems.push(error);
+            }
+        }
+        problems.extend(self.absorbing_cycles());
+        for (index, spec) in self.clips.iter().enumerate() {
+            if let Err(error) = self.check_clip(&self.clips[..index], spec) {
+                problems.push(error);
             }
-            if spec.frames == 0 {
-                return Err(SkinError::NoFrames { state: spec.state });
+        }
+        let mut items: Vec<ItemDef> = Vec::with_capacity(self.items.len());
+        for spec in &self.items {
+            match item_def(&items, spec) {
+                Ok(item) => items.push(item),
+                Err(error) => problems.push(error),
             }
-            if spec.frames > self.columns {
-                return Err(SkinError::TooManyFrames {
-                    state: spec.state,
-                    frames: spec.frames,
-                    columns: self.columns,
+        }
+        let mut sounds: Vec<SoundDef> = Vec::with_capacity(self.sounds.len());
+        for spec in &self.sounds {
+            match sound_def(&sounds, spec) {
+                Ok(sound) => sounds.push(sound),
+                Err(error) => problems.push(error),
+            }
+        }
+        for (index, spec) in self.load.iter().enumerate() {
+            problems.extend(load_rule(index + 1, spec).err());
+        }
+        problems.extend(
+            self.behaviour
+                .as_ref()
+                .and_then(|spec| behaviour_tree(spec).err()),
+        );
+        problems
+    }
+
+    /// Groups of states the chain can enter but never leave, other than the
+    /// ones where the pet idles or walks.
+    ///
+    /// A non-empty transition list is not enough on its own: `Eating` and
+    /// `Sitting` leading only to each other would pass, and trap the pet. Each
+    /// strongly connected component of the transition graph is a set of
+    /// states that all reach one another; one with no edge out is where the
+    /// chain ends up for good, so it must hold `Idle` or `Walking`.
+    pub fn absorbing_cycles(&self) -> Vec<SkinError> {
+        const ROAMING: [PetState; 2] = [PetState::Idle, PetState::Walking];
+
+        let reach = self.reach();
+        let reaches = |from: PetState, to: PetState| {
+            reach
+                .iter()
+                .any(|(state, set)| *state == from && set.contains(&to))
+        };
+
+        let mut cycles = Vec::new();
+        let mut seen: Vec<PetState> = Vec::new();
+        for (state, set) in &reach {
+            if seen.contains(state) {
+                continue;
+            }
+            let component: Vec<PetState> = set
+                .iter()
+                .copied()
+                .filter(|&other| reaches(other, *state))
+                .collect();
+            seen.extend(&component);
+            // Closed: everything reachable from here leads back here. A lone
+            // state with no exits at all is already a `BadTransitions`.
+            let closed = component.len() == set.len();
+            let has_exits = self
+                .states
+                .iter()
+                .any(|spec| spec.state == *state && spec.transitions.iter().any(|t| t.weight > 0));
+            if closed && has_exits && !component.iter().any(|state| ROAMING.contains(state)) {
+                cycles.push(SkinError::AbsorbingCycle { states: component });
+            }
+        }
+        cycles
+    }
+
+    /// States no other state transitions to, so the pet only ever shows them
+    /// when an interrupt (a click, an item, the menu) asks for them.
+    ///
+    /// Not an error: `Jumping` and `SendingLove` are reactions by design. But
+    /// a skin author expecting a state to turn up on its own wants to know.
+    /// `Dragged` is left out, since only the pointer may enter it, and so is
+    /// any state the behaviour tree plays.
+    pub fn unreachable(&self) -> Vec<SkinError> {
+        let planned = self
+            .behaviour
+            .as_ref()
+            .and_then(|spec| behaviour_tree(spec).ok())
+            .map_or_else(Vec::new, |tree| tree.plays());
+        self.states
+            .iter()
+            .map(|spec| spec.state)
+            .filter(|&state| state != PetState::Dragged && !planned.contains(&state))
+            .filter(|&state| {
+                !self.states.iter().any(|spec| {
+                    spec.state != state
+                        && spec
+                            .transitions
+                            .iter()
+                            .any(|t| t.to == state && t.weight > 0)
+                })
+            })
+            .map(|state| SkinError::Unreachable { state })
+            .collect()
+    }
+
+    /// Every declared state with the states it can lead to, itself included,
+    /// in row order. Zero-weight transitions are never taken, so they do not
+    /// count.
+    fn reach(&self) -> Vec<(PetState, Vec<PetState>)> {
+        let exits = |state: PetState| {
+            self.states
+                .iter()
+                .filter(move |spec| spec.state == state)
+                .flat_map(|spec| &spec.transitions)
+                .filter(|t| t.weight > 0)
+                .map(|t| t.to)
+        };
+
+        let mut starts: Vec<PetState> = self.states.iter().map(|spec| spec.state).collect();
+        starts.sort_by_key(|state| state.row());
+        starts.dedup();
+        starts
+            .into_iter()
+            .map(|start| {
+                let mut set = vec![start];
+                let mut next = 0;
+                while let Some(&state) = set.get(next) {
+                    next += 1;
+                    for to in exits(state) {
+                        if !set.contains(&to) {
+                            set.push(to);
+                        }
+                    }
+                }
+                set.sort_by_key(|state| state.row());
+                (start, set)
+            })
+            .collect()
+    }
+
+    /// Where the frames are drawn from: exactly one of `sheet` and `atlases`.
+    fn layout(&self) -> Result<SheetLayout, SkinError> {
+        let reason = match (&self.sheet, self.atlases.is_empty(), self.columns) {
+            (Some(sheet), true, Some(_)) => {
+                return Ok(SheetLayout::Grid {
+                    sheet: sheet.clone(),
                 });
             }
-            let (min, max) = spec.duration;
-            if min > max {
-                return Err(SkinError::BadDuration {
-                    state: spec.state,
-                    min,
-                    max,
+            (None, false, None) => {
+                return Ok(SheetLayout::Packed {
+                    atlases: self.atlases.clone(),
                 });
             }
+            (Some(_), false, _) => "name a `sheet` or `atlases`, not both",
+            (None, true, _) => "name a `sheet`, or `atlases` for a packed atlas",
+            (Some(_), true, None) => "a `sheet` needs `columns`",
+            (None, false, Some(_)) => {
+                "`atlases` place every frame themselves and take no `columns`"
+            }
+        };
+        Err(SkinError::Layout { reason })
+    }
+
+    /// Every file the manifest names that is not inside the skin's directory.
+    fn files_outside(&self) -> impl Iterator<Item = SkinError> + '_ {
+        self.sheet
+            .iter()
+            .chain(&self.atlases)
+            .chain(self.items.iter().map(|spec| &spec.sprite))
+            .chain(self.sounds.iter().map(|spec| &spec.file))
+            .chain(&self.script)
+            .filter(|path| !in_skin(path))
+            .map(|path| SkinError::OutsideSkin { path: path.clone() })
+    }
 
-            let entries = spec
-                .transitions
+    /// Frames per row: the grid's width, or for a packed atlas, which has
+    /// none, the longest row.
+    fn columns(&self) -> u32 {
+        self.columns.unwrap_or_else(|| {
+            self.states
                 .iter()
-                .map(|t| (t.to, t.weight))
-                .collect::<Vec<_>>();
-            let transitions =
-                WeightedTable::new(entries).map_err(|source| SkinError::BadTransitions {
-                    state: spec.state,
-                    source,
-                })?;
+                .map(|spec| spec.frames)
+                .chain(self.clips.iter().map(|spec| spec.frames))
+                .max()
+                .unwrap_or(1)
+        })
+    }
 
-            defs.push(StateDef {
+    /// Validates a clip against the states and the clips before it.
+    fn check_clip(&self, earlier: &[ClipSpec], spec: &ClipSpec) -> Result<(), SkinError> {
+        // A packed atlas has room for any length, but a clip still needs a
+        // frame to show.
+        if spec.frames == 0 || self.columns.is_some_and(|columns| spec.frames > columns) {
+            return Err(SkinError::ClipFrames {
+                name: spec.name.clone(),
                 frames: spec.frames,
-                fps: spec.fps.unwrap_or(self.default_fps),
-                playback: spec.playback.into(),
-                duration: (
-                    Duration::from_secs_f32(min.max(0.0)),
-                    Duration::from_secs_f32(max.max(0.0)),
-                ),
-                locomotion: spec.locomotion.into(),
-                transitions,
+                columns: self.columns(),
+            });
+        }
+        // A clip for a transition the chain never takes would be dead art,
+        // most likely a typo for one it does.
+        let taken = self
+            .states
+            .iter()
+            .filter(|state| state.state == spec.from)
+            .flat_map(|state| &state.transitions)
+            .any(|t| t.to == spec.to && t.weight > 0);
+        if !taken {
+            return Err(SkinError::ClipNeverPlays {
+                name: spec.name.clone(),
+                from: spec.from,
+                to: spec.to,
+            });
+        }
+        if earlier
+            .iter()
+            .any(|clip| clip.from == spec.from && clip.to == spec.to)
+        {
+            return Err(SkinError::DuplicateClip {
+                name: spec.name.clone(),
+                from: spec.from,
+                to: spec.to,
             });
         }
+        Ok(())
+    }
 
-        let geometry = SkinGeometry {
-            name: self.name,
-            sheet: self.sheet,
-            frame_size: UVec2::new(self.frame_size.0, self.frame_size.1),
-            columns: self.columns,
-            rows: PetState::ALL.len() as u32,
+    fn check_state_count(&self) -> Result<(), SkinError> {
+        if self.states.len() != PetState::ALL.len() {
+            return Err(SkinError::StateCount {
+                got: self.states.len(),
+                want: PetState::ALL.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Validates the state at `index` in the list.
+    fn state_def(&self, index: usize, spec: &StateSpec) -> Result<StateDef, SkinError> {
+        // A surplus state has no row to be in order with; the count check
+        // reports it.
+        if let Some(&want) = PetState::ALL.get(index)
+            && spec.state != want
+        {
+            return Err(SkinError::StateOrder {
+                index,
+                got: spec.state,
+                want,
+            });
+        }
+        if spec.frames == 0 {
+            return Err(SkinError::NoFrames { state: spec.state });
+        }
+        if let Some(columns) = self.columns
+            && spec.frames > columns
+        {
+            return Err(SkinError::TooManyFrames {
+                state: spec.state,
+                frames: spec.frames,
+                columns,
+            });
+        }
+        let frame_durations = match &spec.frame_durations {
+            None => None,
+            Some(durations) => {
+                if durations.len() != spec.frames as usize {
+                    return Err(SkinError::FrameDurationCount {
+                        state: spec.state,
+                        got: durations.len(),
+                        frames: spec.frames,
+                    });
+                }
+                // Zero, or less than a nanosecond, which rounds to it, would
+                // spin the frame stepper; NaN never compares at all, and too
+                // large does not fit a `Duration`.
+                let converted = durations
+                    .iter()
+                    .enumerate()
+                    .map(|(frame, &seconds)| {
+                        Duration::try_from_secs_f32(seconds)
+                            .ok()
+                            .filter(|duration| !duration.is_zero())
+                            .ok_or(SkinError::BadFrameDuration {
+                                state: spec.state,
+                                frame,
+                                seconds,
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Some(converted)
+            }
         };
+        let (min, max) = spec.duration;
+        if min > max {
+            return Err(SkinError::BadDuration {
+                state: spec.state,
+                min,
+                max,
+            });
+        }
+        for (i, &direction) in spec.directions.iter().enumerate() {
+            if spec.directions[..i].contains(&direction) {
+                return Err(SkinError::DuplicateDirection {
+                    state: spec.state,
+                    direction,
+                });
+            }
+        }
+        // Dragged locks the pet until the pointer lets go, so a timer that
+        // wandered into it would hold the pet there with nothing to release it.
+        if spec.transitions.iter().any(|t| t.to == PetState::Dragged) {
+            return Err(SkinError::HeldTransition { state: spec.state });
+        }
 
-        Ok((geometry, StateTable::new(defs)))
+        let entries = spec
+            .transitions
+            .iter()
+            .map(|t| (t.to, t.weight))
+            .collect::<Vec<_>>();
+        let transitions =
+            WeightedTable::new(entries).map_err(|source| SkinError::BadTransitions {
+                state: spec.state,
+                source,
+            })?;
+
+        Ok(StateDef {
+            frames: spec.frames,
+            fps: spec.fps.unwrap_or(self.default_fps),
+            frame_durations,
+            playback: spec.playback.into(),
+            duration: (
+                Duration::from_secs_f32(min.max(0.0)),
+                Duration::from_secs_f32(max.max(0.0)),
+            ),
+            locomotion: spec.locomotion.into(),
+            transitions,
+            // Filled in from `clips` once every state is known.
+            clips: Vec::new(),
+            phrases: spec.phrases.clone(),
+        })
+    }
+}
+
+/// The name a state's row goes by in a packed atlas: the prefix
+/// `batates skin build` reads its frames under.
+fn row_prefix(state: PetState) -> &'static str {
+    super::build::FRAME_PREFIXES
+        .iter()
+        .find(|(_, known)| *known == state)
+        .map(|(prefix, _)| *prefix)
+        .expect("every state has a prefix")
+}
+
+/// Validates an item against those already accepted.
+fn item_def(accepted: &[ItemDef], spec: &ItemSpec) -> Result<ItemDef, SkinError> {
+    if accepted.iter().any(|item| item.kind == spec.kind) {
+        return Err(SkinError::DuplicateItem { kind: spec.kind });
+    }
+    // Entering Dragged with nothing holding the pet would leave it stuck,
+    // exactly as a config reaction state would.
+    if spec.state == PetState::Dragged {
+        return Err(SkinError::HeldItemState { kind: spec.kind });
     }
+    Ok(ItemDef {
+        kind: spec.kind,
+        sprite: spec.sprite.clone(),
+        reaction: spec.state,
+    })
+}
+
+/// Whether `path`, relative to a skin's manifest, stays inside the skin: not
+/// absolute, and never climbing out with `..`. A skin is something downloaded,
+/// and must not read whatever else the user has.
+pub fn in_skin(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Validates a sound against those already accepted. One clip per cue keeps
+/// which one plays obvious.
+fn sound_def(accepted: &[SoundDef], spec: &SoundSpec) -> Result<SoundDef, SkinError> {
+    if accepted.iter().any(|sound| sound.cue == spec.on) {
+        return Err(SkinError::DuplicateSound { cue: spec.on });
+    }
+    Ok(SoundDef {
+        cue: spec.on,
+        file: spec.file.clone(),
+    })
+}
+
+/// Validates the `index`th load rule, counting from 1.
+fn load_rule(index: usize, spec: &LoadSpec) -> Result<LoadRule, SkinError> {
+    let invalid = |reason| Err(SkinError::LoadRule { index, reason });
+    if !(0.0..=1.0).contains(&spec.when.threshold()) {
+        return invalid("thresholds are fractions, from 0 to 1");
+    }
+    if spec.bias.is_empty() && spec.enter.is_none() {
+        return invalid("it does nothing; give it a bias, a state to enter, or both");
+    }
+    // A zero factor would cut an exit the skin's transitions were checked
+    // with, which is how a pet ends up stuck.
+    if spec
+        .bias
+        .iter()
+        .any(|(_, factor)| !(factor.is_finite() && *factor > 0.0))
+    {
+        return invalid("bias factors must be greater than zero");
+    }
+    let mut states = spec.bias.iter().map(|(state, _)| *state).chain(spec.enter);
+    if states.any(|state| state == PetState::Dragged) {
+        return invalid("Dragged is owned by the pointer");
+    }
+    Ok(LoadRule {
+        when: spec.when,
+        bias: spec.bias.clone(),
+        enter: spec.enter,
+    })
 }
 
 impl SkinGeometry {
-    /// Checks the loaded sheet actually matches what the manifest promised.
+    /// Checks a grid's sheet actually matches what the manifest promised.
+    /// A packed atlas is checked against its files instead; see
+    /// [`PackedAtlas::verify_sheet`](super::atlas::PackedAtlas::verify_sheet).
     ///
     /// Without this a wrong `columns` value produces silently misaligned
     /// animation rather than an error.
@@ -315,6 +1059,214 @@ mod tests {
         );
     }
 
+    #[test]
+    fn phrases_belong_to_their_state() {
+        let text = valid_ron().replace(
+            "(state: Eating, frames: 8, playback: Loop",
+            "(state: Eating, phrases: [\"Yum.\"], frames: 8, playback: Loop",
+        );
+        let (_, table) = parse(&text).expect("valid");
+        assert_eq!(table.get(PetState::Eating).phrases, ["Yum."]);
+        assert!(table.get(PetState::Idle).phrases.is_empty());
+    }
+
+    #[test]
+    fn frame_durations_are_converted_and_validated() {
+        let with_durations = |durations: &str| {
+            valid_ron().replace(
+                "(state: Jumping, frames: 8, playback: Loop",
+                &format!(
+                    "(state: Jumping, frames: 3, frame_durations: Some({durations}), playback: Loop"
+                ),
+            )
+        };
+
+        let (_, table) = parse(&with_durations("[0.1, 0.5, 0.1]")).expect("valid");
+        let def = table.get(PetState::Jumping);
+        assert_eq!(
+            def.frame_durations.as_deref(),
+            Some([0.1, 0.5, 0.1].map(Duration::from_secs_f32).as_slice())
+        );
+        assert!(table.get(PetState::Walking).frame_durations.is_none());
+
+        assert!(matches!(
+            parse(&with_durations("[0.1, 0.5]")),
+            Err(SkinError::FrameDurationCount {
+                got: 2,
+                frames: 3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&with_durations("[0.1, 0.0, 0.1]")),
+            Err(SkinError::BadFrameDuration { frame: 1, .. })
+        ));
+        assert!(
+            matches!(
+                parse(&with_durations("[0.1, 0.1, 1e-10]")),
+                Err(SkinError::BadFrameDuration { frame: 2, .. })
+            ),
+            "rounds to zero"
+        );
+        assert!(
+            matches!(
+                parse(&with_durations("[1e30, 0.1, 0.1]")),
+                Err(SkinError::BadFrameDuration { frame: 0, .. })
+            ),
+            "does not fit a Duration"
+        );
+    }
+
+    #[test]
+    fn direction_rows_follow_the_eight_state_rows() {
+        let text = valid_ron()
+            .replace(
+                "(state: Idle, frames: 8, playback: Loop",
+                "(state: Idle, directions: [Up], frames: 8, playback: Loop",
+            )
+            .replace(
+                "(state: Walking, frames: 8, playback: Loop",
+                "(state: Walking, directions: [Left, Up, Down], frames: 8, playback: Loop",
+            );
+        let (geometry, _) = parse(&text).expect("valid");
+        assert_eq!(geometry.rows, 12);
+        assert_eq!(
+            geometry.directions,
+            DirectionRows::new(vec![
+                (PetState::Idle, Heading::Up, 8),
+                (PetState::Walking, Heading::Left, 9),
+                (PetState::Walking, Heading::Up, 10),
+                (PetState::Walking, Heading::Down, 11),
+            ])
+        );
+
+        let twice = valid_ron().replace(
+            "(state: Idle, frames: 8, playback: Loop",
+            "(state: Idle, directions: [Up, Up], frames: 8, playback: Loop",
+        );
+        assert!(matches!(
+            parse(&twice),
+            Err(SkinError::DuplicateDirection {
+                state: PetState::Idle,
+                direction: DirectionSpec::Up,
+            })
+        ));
+    }
+
+    #[test]
+    fn clips_hang_off_their_source_state_after_the_direction_rows() {
+        let with_clips = |clips: &str| {
+            valid_ron()
+                .replace(
+                    "(state: Walking, frames: 8, playback: Loop",
+                    "(state: Walking, directions: [Up], frames: 8, playback: Loop",
+                )
+                .replace("states:", &format!("clips: [{clips}], states:"))
+        };
+
+        let (geometry, table) = parse(&with_clips(
+            "(name: \"sit_to_stand\", from: Sitting, to: Idle, frames: 4), \
+             (name: \"hop\", from: Jumping, to: Idle, frames: 2, fps: Some(6))",
+        ))
+        .expect("valid");
+        assert_eq!(geometry.rows, 11);
+        assert_eq!(
+            table.get(PetState::Sitting).clips,
+            [Clip {
+                to: PetState::Idle,
+                row: 9,
+                frames: 4,
+                fps: 12
+            }]
+        );
+        assert_eq!(table.get(PetState::Jumping).clips[0].row, 10);
+        assert_eq!(table.get(PetState::Jumping).clips[0].fps, 6);
+        assert!(table.get(PetState::Idle).clips.is_empty());
+
+        assert!(matches!(
+            parse(&with_clips(
+                "(name: \"sit_to_walk\", from: Sitting, to: Walking, frames: 4)"
+            )),
+            Err(SkinError::ClipNeverPlays { .. })
+        ));
+        assert!(matches!(
+            parse(&with_clips(
+                "(name: \"a\", from: Sitting, to: Idle, frames: 4), \
+                 (name: \"b\", from: Sitting, to: Idle, frames: 4)"
+            )),
+            Err(SkinError::DuplicateClip { name, .. }) if name == "b"
+        ));
+        assert!(matches!(
+            parse(&with_clips(
+                "(name: \"long\", from: Sitting, to: Idle, frames: 99)"
+            )),
+            Err(SkinError::ClipFrames { frames: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn a_skin_names_either_a_sheet_or_atlases() {
+        let packed = valid_ron()
+            .replace("sheet: \"s.png\"", "atlases: [\"a.json\", \"b.json\"]")
+            .replace("columns: 61, ", "");
+        let (geometry, _) = parse(&packed).expect("valid");
+        assert_eq!(
+            geometry.layout,
+            SheetLayout::Packed {
+                atlases: vec!["a.json".to_string(), "b.json".to_string()]
+            }
+        );
+        assert_eq!(geometry.columns, 8, "the longest row");
+
+        let both = valid_ron().replace(
+            "sheet: \"s.png\"",
+            "sheet: \"s.png\", atlases: [\"a.json\"]",
+        );
+        let no_columns = valid_ron().replace("columns: 61, ", "");
+        let packed_with_columns = valid_ron().replace("sheet: \"s.png\"", "atlases: [\"a.json\"]");
+        for text in [both, no_columns, packed_with_columns] {
+            assert!(
+                matches!(parse(&text), Err(SkinError::Layout { .. })),
+                "{text}"
+            );
+        }
+    }
+
+    /// A packed atlas finds a row's frames by these names.
+    #[test]
+    fn rows_are_named_after_their_frame_prefix() {
+        let text = valid_ron()
+            .replace(
+                "(state: Walking, frames: 8, playback: Loop",
+                "(state: Walking, directions: [Up], frames: 8, playback: Loop",
+            )
+            .replace(
+                "states:",
+                "clips: [(name: \"sit_to_stand\", from: Sitting, to: Idle, frames: 3)], states:",
+            );
+        let (geometry, _) = parse(&text).expect("valid");
+        let names: Vec<(&str, u32)> = geometry
+            .sheet_rows
+            .iter()
+            .map(|row| (row.name.as_str(), row.frames))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("chill", 8),
+                ("drag", 8),
+                ("eat", 8),
+                ("idle", 8),
+                ("jump", 8),
+                ("love", 8),
+                ("sit", 8),
+                ("walk", 8),
+                ("walk_up", 8),
+                ("sit_to_stand", 3),
+            ]
+        );
+    }
+
     #[test]
     fn missing_state_is_rejected() {
         let text = valid_ron().replace(
@@ -400,6 +1352,306 @@ mod tests {
         assert!(matches!(parse(&text), Err(SkinError::Parse { .. })));
     }
 
+    #[test]
+    fn items_are_optional_and_validated() {
+        let (geometry, _) = parse(&valid_ron()).expect("valid");
+        assert!(geometry.items.is_empty());
+
+        let with_items =
+            |items: &str| valid_ron().replace("states:", &format!("items: [{items}], states:"));
+
+        let (geometry, _) = parse(&with_items(
+            "(kind: Food, sprite: \"food.png\", state: Eating), \
+             (kind: Ball, sprite: \"ball.png\", state: Jumping)",
+        ))
+        .expect("valid");
+        assert_eq!(geometry.items.len(), 2);
+        assert_eq!(geometry.items[0].reaction, PetState::Eating);
+
+        assert!(matches!(
+            parse(&with_items(
+                "(kind: Ball, sprite: \"a.png\", state: Jumping), \
+                 (kind: Ball, sprite: \"b.png\", state: Idle)"
+            )),
+            Err(SkinError::DuplicateItem {
+                kind: ItemKind::Ball
+            })
+        ));
+        assert!(matches!(
+            parse(&with_items(
+                "(kind: Yarn, sprite: \"y.png\", state: Dragged)"
+            )),
+            Err(SkinError::HeldItemState {
+                kind: ItemKind::Yarn
+            })
+        ));
+    }
+
+    #[test]
+    fn sounds_are_optional_and_one_per_cue() {
+        let (geometry, _) = parse(&valid_ron()).expect("valid");
+        assert!(geometry.sounds.is_empty());
+
+        let with_sounds =
+            |sounds: &str| valid_ron().replace("states:", &format!("sounds: [{sounds}], states:"));
+
+        let (geometry, _) = parse(&with_sounds(
+            "(on: Enter(Jumping), file: \"boing.ogg\"), (on: Pet, file: \"purr.ogg\")",
+        ))
+        .expect("valid");
+        assert_eq!(
+            geometry.sounds,
+            vec![
+                SoundDef {
+                    cue: SoundCue::Enter(PetState::Jumping),
+                    file: "boing.ogg".to_string(),
+                },
+                SoundDef {
+                    cue: SoundCue::Pet,
+                    file: "purr.ogg".to_string(),
+                },
+            ]
+        );
+
+        assert!(matches!(
+            parse(&with_sounds(
+                "(on: Poke, file: \"a.ogg\"), (on: Poke, file: \"b.ogg\")"
+            )),
+            Err(SkinError::DuplicateSound {
+                cue: SoundCue::Poke
+            })
+        ));
+    }
+
+    #[test]
+    fn files_outside_the_skin_are_rejected() {
+        for sheet in ["../s.png", "/etc/passwd", "art/../../s.png"] {
+            let outside = valid_ron().replace("\"s.png\"", &format!("{sheet:?}"));
+            assert!(
+                matches!(parse(&outside), Err(SkinError::OutsideSkin { path }) if path == sheet),
+                "should reject {sheet}"
+            );
+        }
+
+        let nested = valid_ron().replace("\"s.png\"", "\"./art/s.png\"");
+        assert!(parse(&nested).is_ok());
+
+        let everywhere = valid_ron().replace(
+            "states:",
+            "items: [(kind: Ball, sprite: \"../ball.png\", state: Jumping)], \
+             sounds: [(on: Pet, file: \"/tmp/purr.ogg\")], script: \"../s.rhai\", states:",
+        );
+        let manifest = SkinManifest::parse(&everywhere, "test").expect("parses");
+        let outside = manifest
+            .problems()
+            .into_iter()
+            .filter(|problem| matches!(problem, SkinError::OutsideSkin { .. }))
+            .count();
+        assert_eq!(outside, 3);
+    }
+
+    #[test]
+    fn load_rules_are_optional_and_validated() {
+        let (geometry, _) = parse(&valid_ron()).expect("valid");
+        assert!(geometry.load.is_empty());
+
+        let with_load =
+            |rules: &str| valid_ron().replace("states:", &format!("load: [{rules}], states:"));
+        let (geometry, _) = parse(&with_load(
+            "(when: CpuAbove(0.85), bias: [(Walking, 3.0)]), \
+             (when: BatteryBelow(0.2), enter: Sitting)",
+        ))
+        .expect("valid");
+        assert_eq!(
+            geometry.load,
+            [
+                LoadRule {
+                    when: LoadCondition::CpuAbove(0.85),
+                    bias: vec![(PetState::Walking, 3.0)],
+                    enter: None,
+                },
+                LoadRule {
+                    when: LoadCondition::BatteryBelow(0.2),
+                    bias: Vec::new(),
+                    enter: Some(PetState::Sitting),
+                },
+            ]
+        );
+
+        for bad in [
+            "(when: CpuAbove(85.0), enter: Sitting)",
+            "(when: MemoryAbove(0.9))",
+            "(when: CpuAbove(0.9), bias: [(Idle, 0.0)])",
+            "(when: CpuAbove(0.9), enter: Dragged)",
+        ] {
+            assert!(
+                matches!(
+                    parse(&with_load(bad)),
+                    Err(SkinError::LoadRule { index: 1, .. })
+                ),
+                "should reject {bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn behaviour_trees_are_optional_and_validated() {
+        let (_, table) = parse(&valid_ron()).expect("valid");
+        assert!(table.tree().is_none());
+
+        let with_tree =
+            |tree: &str| valid_ron().replace("states:", &format!("behaviour: {tree}, states:"));
+        let (_, table) = parse(&with_tree(
+            "Selector([Sequence([WalkTo(Anywhere), Play(Sitting), Play(Eating)]), \
+             Wait(5.0, 10.0)])",
+        ))
+        .expect("valid");
+        let tree = table.tree().expect("a tree");
+        assert_eq!(
+            tree.plays(),
+            [PetState::Walking, PetState::Sitting, PetState::Eating]
+        );
+
+        for bad in [
+            "Sequence([])",
+            "Play(Dragged)",
+            "Wait(3.0, 1.0)",
+            "Wait(-1.0, 1.0)",
+            "Wait(1e30, 1e30)",
+            "WalkTo(Spot(0.0, 2.0))",
+        ] {
+            assert!(
+                matches!(parse(&with_tree(bad)), Err(SkinError::Behaviour { .. })),
+                "should reject {bad}"
+            );
+        }
+
+        // What the tree plays is reached, if nothing else leads there.
+        let manifest = SkinManifest::parse(&with_tree("Play(Eating)"), "test").expect("parses");
+        assert!(!manifest.unreachable().iter().any(|warning| matches!(
+            warning,
+            SkinError::Unreachable {
+                state: PetState::Eating
+            }
+        )));
+    }
+
+    #[test]
+    fn problems_lists_every_error_not_just_the_first() {
+        let text = valid_ron()
+            .replacen("frames: 8", "frames: 0", 1)
+            .replace("(state: Idle, frames: 8", "(state: Idle, frames: 99");
+        let manifest = SkinManifest::parse(&text, "test").expect("parses");
+        let problems = manifest.problems();
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(matches!(problems[0], SkinError::NoFrames { .. }));
+        assert!(matches!(problems[1], SkinError::TooManyFrames { .. }));
+        assert!(
+            SkinManifest::parse(&valid_ron(), "test")
+                .expect("parses")
+                .problems()
+                .is_empty()
+        );
+    }
+
+    /// Every state has an exit, but Eating and Sitting only lead to each
+    /// other.
+    #[test]
+    fn closed_loops_away_from_idle_are_rejected() {
+        let text = valid_ron()
+            .replace(
+                "(state: Eating, frames: 8, playback: Loop, duration: (1.0, 2.0), \
+                 locomotion: Still, transitions: [(to: Idle, weight: 1)])",
+                "(state: Eating, frames: 8, playback: Loop, duration: (1.0, 2.0), \
+                 locomotion: Still, transitions: [(to: Sitting, weight: 1)])",
+            )
+            .replace(
+                "(state: Sitting, frames: 8, playback: Loop, duration: (1.0, 2.0), \
+                 locomotion: Still, transitions: [(to: Idle, weight: 1)])",
+                "(state: Sitting, frames: 8, playback: Loop, duration: (1.0, 2.0), \
+                 locomotion: Still, transitions: [(to: Eating, weight: 1)])",
+            );
+        match parse(&text) {
+            Err(SkinError::AbsorbingCycle { states }) => {
+                assert_eq!(states, [PetState::Eating, PetState::Sitting]);
+            }
+            other => panic!("expected an AbsorbingCycle error, got {other:?}"),
+        }
+        let manifest = SkinManifest::parse(&text, "test").expect("parses");
+        assert_eq!(
+            manifest.absorbing_cycles().len(),
+            1,
+            "one cycle, reported once"
+        );
+
+        let koala = SkinManifest::parse(&valid_ron(), "test").expect("parses");
+        assert!(koala.absorbing_cycles().is_empty());
+    }
+
+    /// A state that only loops on itself traps the pet just as surely.
+    #[test]
+    fn self_loops_away_from_idle_are_rejected() {
+        let text = valid_ron().replacen(
+            "(state: Chilling, frames: 8, playback: Loop, duration: (1.0, 2.0), \
+             locomotion: Still, transitions: [(to: Idle, weight: 1)])",
+            "(state: Chilling, frames: 8, playback: Loop, duration: (1.0, 2.0), \
+             locomotion: Still, transitions: [(to: Chilling, weight: 1)])",
+            1,
+        );
+        let manifest = SkinManifest::parse(&text, "test").expect("parses");
+        assert!(matches!(
+            manifest.absorbing_cycles().as_slice(),
+            [SkinError::AbsorbingCycle { states }] if states == &[PetState::Chilling]
+        ));
+    }
+
+    /// The roaming states may form a cycle of their own; that is the pet's
+    /// ordinary life, not a trap.
+    #[test]
+    fn cycles_through_idle_or_walking_are_fine() {
+        let text = valid_ron().replacen(
+            "(state: Idle, frames: 8, playback: Loop, duration: (1.0, 2.0), \
+             locomotion: Still, transitions: [(to: Idle, weight: 1)])",
+            "(state: Idle, frames: 8, playback: Loop, duration: (1.0, 2.0), \
+             locomotion: Still, transitions: [(to: Walking, weight: 1)])",
+            1,
+        );
+        assert!(parse(&text).is_ok());
+    }
+
+    #[test]
+    fn transitions_into_dragged_are_rejected() {
+        let text = valid_ron().replacen(
+            "transitions: [(to: Idle, weight: 1)]",
+            "transitions: [(to: Idle, weight: 1), (to: Dragged, weight: 1)]",
+            1,
+        );
+        assert!(matches!(
+            parse(&text),
+            Err(SkinError::HeldTransition {
+                state: PetState::Chilling
+            })
+        ));
+    }
+
+    /// In the test manifest everything leads to Idle, so only Idle is entered
+    /// by another state.
+    #[test]
+    fn states_nothing_leads_to_are_listed_as_unreachable() {
+        let manifest = SkinManifest::parse(&valid_ron(), "test").expect("parses");
+        let unreachable: Vec<PetState> = manifest
+            .unreachable()
+            .into_iter()
+            .map(|warning| match warning {
+                SkinError::Unreachable { state } => state,
+                other => panic!("expected Unreachable, got {other:?}"),
+            })
+            .collect();
+        let mut want: Vec<PetState> = PetState::ALL.to_vec();
+        want.retain(|&state| state != PetState::Idle && state != PetState::Dragged);
+        assert_eq!(unreachable, want);
+    }
+
     #[test]
     fn sheet_dimensions_are_verified() {
         let (geometry, _) = parse(&valid_ron()).expect("valid");
@@ -7,20 +7,57 @@
 //! referencing paths outside its own root. Reading and validating at one
 //! boundary is both smaller and easier to reason about.
 
+pub mod aseprite;
+pub mod atlas;
+pub mod build;
+pub mod check;
 pub mod manifest;
+pub mod script;
 
 use bevy::asset::RenderAssetUsages;
 use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
 use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::sprite::Anchor;
+use lewton::inside_ogg::OggStreamReader;
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 
-use crate::core::brain::StateTable;
-use manifest::{SkinError, SkinGeometry, SkinManifest};
+use crate::core::brain::{PetState, StateTable};
+use crate::core::hitbox::AlphaMask;
+use crate::core::items::ItemKind;
+use crate::core::sound::SoundCue;
+use aseprite::SheetPixels;
+use atlas::{AtlasFile, FrameRect, PackedAtlas};
+use manifest::{SheetLayout, SkinError, SkinGeometry, SkinManifest};
+use script::SkinScript;
 
 /// The built-in skin, compiled in so the app runs with nothing installed.
 const BUILTIN_MANIFEST: &str = include_str!("../../assets/builtin/koala/skin.ron");
+/// The sheet the built-in manifest names `sheet.png`.
 const BUILTIN_SHEET: &[u8] = include_bytes!("../../assets/builtin/koala/sheet.png");
 
+/// The built-in skin's item sprites, by the filename its manifest uses.
+const BUILTIN_ITEMS: [(&str, &[u8]); 3] = [
+    (
+        "food.png",
+        include_bytes!("../../assets/builtin/koala/food.png"),
+    ),
+    (
+        "ball.png",
+        include_bytes!("../../assets/builtin/koala/ball.png"),
+    ),
+    (
+        "yarn.png",
+        include_bytes!("../../assets/builtin/koala/yarn.png"),
+    ),
+];
+
+/// Pixels fainter than this are see-through for picking. Keeps an
+/// anti-aliased fringe or a faint drop shadow from making the pet larger than
+/// it looks.
+const MIN_SOLID_ALPHA: f32 = 0.1;
+
 /// Which skin to load.
 // Directory is selected by the config file; only tests reach it today.
 #[allow(dead_code)]
@@ -30,14 +67,59 @@ pub enum SkinSource {
     Builtin,
     /// A directory holding `skin.ron` and its sheet.
     Directory(PathBuf),
+    /// An `.aseprite` or `.ase` file with one tag per state.
+    Aseprite(PathBuf),
 }
 
 /// A loaded skin's visual half. The behaviour half becomes [`StateTable`].
 #[derive(Resource, Debug, Clone)]
 pub struct Skin {
     pub geometry: SkinGeometry,
+    pub sheets: Vec<SkinSheet>,
+    /// Indexed by [`atlas_index`](crate::core::animation::atlas_index);
+    /// `None` past the end of a packed atlas's row.
+    pub frames: Vec<Option<SkinFrame>>,
+    pub items: Vec<SkinItem>,
+    pub sounds: Vec<SkinSound>,
+    pub script: Option<SkinScript>,
+}
+
+/// One of a skin's textures and the frames cut from it.
+#[derive(Debug, Clone)]
+pub struct SkinSheet {
     pub image: Handle<Image>,
     pub layout: Handle<TextureAtlasLayout>,
+    /// One per frame on this sheet, indexed like `layout`, each the size of
+    /// the untrimmed frame.
+    pub masks: Vec<AlphaMask>,
+}
+
+/// Where a frame is drawn from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkinFrame {
+    /// Into [`Skin::sheets`].
+    pub sheet: usize,
+    /// Into that sheet's layout.
+    pub index: usize,
+    /// Puts a trimmed frame where it sat in the untrimmed one. Zero on a grid,
+    /// where nothing is trimmed.
+    pub anchor: Vec2,
+}
+
+/// A droppable item as this skin draws it.
+#[derive(Debug, Clone)]
+pub struct SkinItem {
+    pub kind: ItemKind,
+    pub image: Handle<Image>,
+    /// Entered by the pet that reaches it.
+    pub reaction: PetState,
+}
+
+/// A clip this skin plays on a cue.
+#[derive(Debug, Clone)]
+pub struct SkinSound {
+    pub cue: SoundCue,
+    pub source: Handle<AudioSource>,
 }
 
 impl Skin {
@@ -49,42 +131,222 @@ impl Skin {
     pub fn columns(&self) -> u32 {
         self.geometry.columns
     }
+
+    /// Which pixels of the frame `sprite` shows are solid.
+    pub fn mask(&self, sprite: &Sprite) -> Option<&AlphaMask> {
+        let atlas = sprite.texture_atlas.as_ref()?;
+        self.sheets
+            .iter()
+            .find(|sheet| sheet.image == sprite.image)?
+            .masks
+            .get(atlas.index)
+    }
+
+    /// Points `sprite` at frame `index`, on whichever sheet holds it.
+    ///
+    /// Bevy mirrors a flipped sprite about its own centre, not the anchor, so
+    /// a trimmed frame's anchor is mirrored here to keep it in place.
+    pub fn show(&self, index: usize, flip_x: bool, sprite: &mut Sprite, anchor: &mut Anchor) {
+        let Some(frame) = self.frames.get(index).copied().flatten() else {
+            return;
+        };
+        let sheet = &self.sheets[frame.sheet];
+        if sprite.image != sheet.image {
+            sprite.image = sheet.image.clone();
+        }
+        match sprite.texture_atlas.as_mut() {
+            Some(atlas) => {
+                if atlas.layout != sheet.layout {
+                    atlas.layout = sheet.layout.clone();
+                }
+                atlas.index = frame.index;
+            }
+            None => {
+                sprite.texture_atlas = Some(TextureAtlas {
+                    layout: sheet.layout.clone(),
+                    index: frame.index,
+                });
+            }
+        }
+        sprite.flip_x = flip_x;
+        let mirror = if flip_x { -1.0 } else { 1.0 };
+        anchor.0 = frame.anchor * Vec2::new(mirror, 1.0);
+    }
+
+    /// How this skin draws `kind`, if it has that item at all.
+    pub fn item(&self, kind: ItemKind) -> Option<&SkinItem> {
+        self.items.iter().find(|item| item.kind == kind)
+    }
+
+    /// The clip this skin plays on `cue`, if it has one.
+    pub fn sound(&self, cue: SoundCue) -> Option<&Handle<AudioSource>> {
+        self.sounds
+            .iter()
+            .find(|sound| sound.cue == cue)
+            .map(|sound| &sound.source)
+    }
+}
+
+/// A sheet as read, before it becomes an [`Image`].
+enum RawSheet {
+    Png(Vec<u8>),
+    /// Already decoded, as the Aseprite importer builds it.
+    Pixels(SheetPixels),
 }
 
 /// A skin's raw contents, before any Bevy assets exist.
 struct RawSkin {
     geometry: SkinGeometry,
     table: StateTable,
-    sheet_bytes: Vec<u8>,
+    /// A grid's one sheet, or each a packed atlas places frames on, by name.
+    sheets: Vec<(String, RawSheet)>,
+    /// Where a packed atlas put each frame. A grid's frames follow from its
+    /// geometry.
+    atlas: Option<PackedAtlas>,
+    /// One per `geometry.items`, in the same order.
+    item_bytes: Vec<Vec<u8>>,
+    /// One per `geometry.sounds`, likewise.
+    sound_bytes: Vec<Vec<u8>>,
+    /// The file `geometry.script` names, if it names one.
+    script_bytes: Option<Vec<u8>>,
 }
 
 /// Reads and validates a skin. Every failure is reported, never defaulted away.
 fn read_skin(source: &SkinSource) -> Result<RawSkin, SkinError> {
-    let (text, path, sheet_bytes) = match source {
-        SkinSource::Builtin => (
-            BUILTIN_MANIFEST.to_string(),
-            "<builtin>".to_string(),
-            BUILTIN_SHEET.to_vec(),
-        ),
+    let (text, path) = match source {
+        SkinSource::Builtin => (BUILTIN_MANIFEST.to_string(), "<builtin>".to_string()),
+        SkinSource::Aseprite(file) => return read_aseprite(file),
         SkinSource::Directory(dir) => {
             let manifest_path = dir.join("skin.ron");
-            let text = read_file(&manifest_path)?;
-            // The sheet name is read from the manifest below, so this arm needs
-            // a second pass; parse first, then load the sheet it names.
-            let manifest = SkinManifest::parse(&text, &manifest_path.display().to_string())?;
-            let sheet_path = dir.join(&manifest.sheet);
-            let bytes = read_bytes(&sheet_path)?;
-            (text, manifest_path.display().to_string(), bytes)
+            (
+                read_file(&manifest_path)?,
+                manifest_path.display().to_string(),
+            )
         }
     };
 
     let manifest = SkinManifest::parse(&text, &path)?;
+    // The built-in koala's reactions are unreachable by design.
+    if *source != SkinSource::Builtin {
+        warn_unreachable(&manifest, &path);
+    }
     let (geometry, table) = manifest.into_parts()?;
 
+    // Every other file a manifest names is relative to it.
+    let read = |name: &str| match source {
+        SkinSource::Builtin => read_builtin(name),
+        SkinSource::Directory(dir) => read_bytes(&dir.join(name)),
+        SkinSource::Aseprite(_) => unreachable!("Aseprite skins are read above"),
+    };
+
+    let (sheets, atlas) = match &geometry.layout {
+        SheetLayout::Grid { sheet } => (vec![(sheet.clone(), RawSheet::Png(read(sheet)?))], None),
+        SheetLayout::Packed { atlases } => {
+            let files = atlases
+                .iter()
+                .map(|name| {
+                    let file =
+                        AtlasFile::parse(&read(name)?).map_err(|source| SkinError::Atlas {
+                            path: name.clone(),
+                            source,
+                        })?;
+                    Ok((name.clone(), file))
+                })
+                .collect::<Result<Vec<_>, SkinError>>()?;
+            let atlas = atlas::place(&files, &geometry)?;
+            let sheets = files
+                .iter()
+                .map(|(name, file)| {
+                    let image = atlas_image(name, file);
+                    let bytes = read(&image)?;
+                    Ok((image, RawSheet::Png(bytes)))
+                })
+                .collect::<Result<_, SkinError>>()?;
+            (sheets, Some(atlas))
+        }
+    };
+
+    let item_bytes = geometry
+        .items
+        .iter()
+        .map(|item| read(&item.sprite))
+        .collect::<Result<_, _>>()?;
+    let sound_bytes = geometry
+        .sounds
+        .iter()
+        .map(|sound| read(&sound.file))
+        .collect::<Result<_, _>>()?;
+    let script_bytes = geometry.script.as_deref().map(read).transpose()?;
+
     Ok(RawSkin {
         geometry,
         table,
-        sheet_bytes,
+        sheets,
+        atlas,
+        item_bytes,
+        sound_bytes,
+        script_bytes,
+    })
+}
+
+/// The sheet an atlas file places frames on, relative to the manifest, as the
+/// file names it relative to itself.
+fn atlas_image(atlas: &str, file: &AtlasFile) -> String {
+    Path::new(atlas)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(file.image())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// One of the built-in skin's files, by the name its manifest uses.
+fn read_builtin(name: &str) -> Result<Vec<u8>, SkinError> {
+    std::iter::once(("sheet.png", BUILTIN_SHEET))
+        .chain(BUILTIN_ITEMS)
+        .find(|(known, _)| *known == name)
+        .map(|(_, bytes)| bytes.to_vec())
+        .ok_or_else(|| SkinError::Read {
+            path: name.to_string(),
+            source: std::io::ErrorKind::NotFound.into(),
+        })
+}
+
+/// Names the states a skin only shows when an interrupt asks for them, as
+/// `batates skin check` does. Worth knowing, but no reason to refuse it.
+fn warn_unreachable(manifest: &SkinManifest, path: &str) {
+    for warning in manifest.unreachable() {
+        warn!("{path}: {warning}");
+    }
+}
+
+/// Reads a skin from an Aseprite file, taking any behaviour its tags leave
+/// out from the built-in koala.
+fn read_aseprite(file: &Path) -> Result<RawSkin, SkinError> {
+    let path = file.display().to_string();
+    let bytes = read_bytes(file)?;
+    let fallback = SkinManifest::parse(BUILTIN_MANIFEST, "<builtin>")?;
+    let name = file
+        .file_stem()
+        .map_or_else(|| path.clone(), |stem| stem.to_string_lossy().into_owned());
+
+    let imported =
+        aseprite::import(&bytes, &name, &fallback).map_err(|source| SkinError::Aseprite {
+            path: path.clone(),
+            source,
+        })?;
+    warn_unreachable(&imported.manifest, &path);
+    let (geometry, table) = imported.manifest.into_parts()?;
+    let sheet = geometry.name.clone();
+    Ok(RawSkin {
+        geometry,
+        table,
+        sheets: vec![(sheet, RawSheet::Pixels(imported.sheet))],
+        atlas: None,
+        // Aseprite files carry no item art, nor sounds, nor scripts.
+        item_bytes: Vec::new(),
+        sound_bytes: Vec::new(),
+        script_bytes: None,
     })
 }
 
@@ -110,40 +372,202 @@ fn build_skin(
     raw: RawSkin,
     images: &mut Assets<Image>,
     layouts: &mut Assets<TextureAtlasLayout>,
+    audio: &mut Assets<AudioSource>,
 ) -> Result<(Skin, StateTable), SkinError> {
-    let image = Image::from_buffer(
-        &raw.sheet_bytes,
-        ImageType::Extension("png"),
-        CompressedImageFormats::NONE,
-        true,
-        ImageSampler::nearest(),
-        RenderAssetUsages::default(),
-    )
-    .map_err(|e| SkinError::Read {
-        path: raw.geometry.sheet.clone(),
-        source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
-    })?;
+    let geometry = &raw.geometry;
+    let rects = match &raw.atlas {
+        Some(atlas) => atlas.frames.clone(),
+        None => grid_frames(geometry),
+    };
+
+    let mut sheets = Vec::with_capacity(raw.sheets.len());
+    let mut frames = vec![None; rects.len()];
+    for (index, (name, sheet)) in raw.sheets.into_iter().enumerate() {
+        let image = match sheet {
+            RawSheet::Png(bytes) => decode_png(&bytes, &name)?,
+            RawSheet::Pixels(pixels) => sheet_image(pixels),
+        };
+        match &raw.atlas {
+            Some(atlas) => atlas.verify_sheet(index, image.size())?,
+            None => geometry.verify_sheet(image.size())?,
+        }
 
-    raw.geometry.verify_sheet(image.size())?;
+        // Only the frames a sheet holds go in its layout, so a packed sheet
+        // costs what its art does, not what its longest row does.
+        let mut layout = TextureAtlasLayout::new_empty(image.size());
+        let mut masks = Vec::new();
+        for (frame, rect) in frames.iter_mut().zip(&rects) {
+            let Some(rect) = rect.filter(|rect| rect.sheet == index) else {
+                continue;
+            };
+            masks.push(frame_mask(&image, &rect, geometry.frame_size));
+            *frame = Some(SkinFrame {
+                sheet: index,
+                index: layout.add_texture(rect.rect),
+                anchor: frame_anchor(&rect, geometry.frame_size),
+            });
+        }
+        sheets.push(SkinSheet {
+            image: images.add(image),
+            layout: layouts.add(layout),
+            masks,
+        });
+    }
+
+    let mut items = Vec::with_capacity(raw.item_bytes.len());
+    for (def, bytes) in geometry.items.iter().zip(&raw.item_bytes) {
+        items.push(SkinItem {
+            kind: def.kind,
+            image: images.add(decode_png(bytes, &def.sprite)?),
+            reaction: def.reaction,
+        });
+    }
+
+    let mut sounds = Vec::with_capacity(raw.sound_bytes.len());
+    for (def, bytes) in geometry.sounds.iter().zip(raw.sound_bytes) {
+        sounds.push(SkinSound {
+            cue: def.cue,
+            source: audio.add(decode_sound(bytes, &def.file)?),
+        });
+    }
 
-    let layout = layouts.add(TextureAtlasLayout::from_grid(
-        raw.geometry.frame_size,
-        raw.geometry.columns,
-        raw.geometry.rows,
-        None,
-        None,
-    ));
+    let script = match (&geometry.script, &raw.script_bytes) {
+        (Some(name), Some(bytes)) => Some(SkinScript::load(bytes, name)?),
+        _ => None,
+    };
 
     Ok((
         Skin {
             geometry: raw.geometry,
-            image: images.add(image),
-            layout,
+            sheets,
+            frames,
+            items,
+            sounds,
+            script,
         },
         raw.table,
     ))
 }
 
+/// Every cell of a grid, as a packed atlas would place it: one sheet, nothing
+/// trimmed.
+fn grid_frames(geometry: &SkinGeometry) -> Vec<Option<FrameRect>> {
+    let size = geometry.frame_size;
+    (0..geometry.rows)
+        .flat_map(|row| (0..geometry.columns).map(move |column| UVec2::new(column, row)))
+        .map(|cell| {
+            let min = cell * size;
+            Some(FrameRect {
+                sheet: 0,
+                rect: URect::from_corners(min, min + size),
+                offset: UVec2::ZERO,
+            })
+        })
+        .collect()
+}
+
+/// Reads a frame's alpha once, so picking never touches the image again.
+///
+/// The mask covers the untrimmed frame, as the hitbox does; whatever was
+/// trimmed away is see-through.
+fn frame_mask(sheet: &Image, frame: &FrameRect, frame_size: UVec2) -> AlphaMask {
+    let size = frame.rect.size();
+    AlphaMask::from_fn(frame_size, |px| {
+        if px.cmplt(frame.offset).any() || (px - frame.offset).cmpge(size).any() {
+            return false;
+        }
+        let at = frame.rect.min + px - frame.offset;
+        // A format `get_color_at` cannot read has no alpha to go by,
+        // so the whole frame counts, as it did before masks.
+        sheet
+            .get_color_at(at.x, at.y)
+            .map_or(true, |color| color.alpha() >= MIN_SOLID_ALPHA)
+    })
+}
+
+/// The anchor that draws a trimmed frame where it sat in the untrimmed one,
+/// whose centre is the pet's position.
+///
+/// An anchor is measured in the sprite's own size from its centre, y up, so
+/// this is the frame's centre as seen from the trimmed rect.
+fn frame_anchor(frame: &FrameRect, frame_size: UVec2) -> Vec2 {
+    let size = frame.rect.size().as_vec2();
+    let centre = frame_size.as_vec2() / 2.0 - frame.offset.as_vec2();
+    Vec2::new(centre.x / size.x - 0.5, 0.5 - centre.y / size.y)
+}
+
+/// Wraps an already-decoded sheet, sampled like a decoded PNG.
+fn sheet_image(pixels: SheetPixels) -> Image {
+    let mut image = Image::new(
+        Extent3d {
+            width: pixels.size.x,
+            height: pixels.size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        pixels.rgba,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+/// Decodes one of a skin's PNGs, naming it in the error.
+fn decode_png(bytes: &[u8], name: &str) -> Result<Image, SkinError> {
+    Image::from_buffer(
+        bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::nearest(),
+        RenderAssetUsages::default(),
+    )
+    .map_err(|e| SkinError::Read {
+        path: name.to_string(),
+        source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
+    })
+}
+
+/// Checks one of a skin's sounds is Ogg Vorbis that decodes, naming it in the
+/// error.
+///
+/// Bevy only decodes a clip when it first plays, and panics if it cannot, so
+/// the whole clip is decoded here once instead. The identification header is
+/// looked at first only to say plainly what a file that is not Vorbis is.
+fn decode_sound(bytes: Vec<u8>, name: &str) -> Result<AudioSource, SkinError> {
+    let error = |reason| SkinError::Sound {
+        path: name.to_string(),
+        reason,
+    };
+    // Capture pattern, version 0, the beginning-of-stream flag.
+    if bytes.len() < 27 || !bytes.starts_with(b"OggS") || bytes[4] != 0 || bytes[5] & 0x02 == 0 {
+        return Err(error("not an Ogg file"));
+    }
+    let packet = 27 + bytes[26] as usize;
+    if !bytes
+        .get(packet..)
+        .is_some_and(|rest| rest.starts_with(b"\x01vorbis"))
+    {
+        return Err(error("not Vorbis audio"));
+    }
+    // lewton is what Bevy decodes with. Reading through to the end here,
+    // rather than with Bevy's decoder, is what surfaces a bad packet: that
+    // decoder ends the clip quietly at one.
+    let mut stream = OggStreamReader::new(Cursor::new(bytes.as_slice()))
+        .map_err(|_| error("the Vorbis headers are corrupt"))?;
+    loop {
+        match stream.read_dec_packet_itl() {
+            Ok(Some(_)) => {}
+            Ok(None) => break,
+            Err(_) => return Err(error("the Vorbis audio is corrupt")),
+        }
+    }
+    Ok(AudioSource {
+        bytes: bytes.into(),
+    })
+}
+
 /// Loads `source`, falling back to the built-in skin if it fails.
 ///
 /// A broken user skin is a recoverable condition: the app is still useful with
@@ -153,8 +577,9 @@ pub fn load_or_builtin(
     source: &SkinSource,
     images: &mut Assets<Image>,
     layouts: &mut Assets<TextureAtlasLayout>,
+    audio: &mut Assets<AudioSource>,
 ) -> (Skin, StateTable) {
-    match read_skin(source).and_then(|raw| build_skin(raw, images, layouts)) {
+    match read_skin(source).and_then(|raw| build_skin(raw, images, layouts, audio)) {
         Ok(loaded) => loaded,
         Err(error) => {
             if *source == SkinSource::Builtin {
@@ -162,7 +587,7 @@ pub fn load_or_builtin(
             }
             warn!("{error}; falling back to the built-in skin");
             let raw = read_skin(&SkinSource::Builtin).expect("built-in skin is valid");
-            build_skin(raw, images, layouts).expect("built-in skin is valid")
+            build_skin(raw, images, layouts, audio).expect("built-in skin is valid")
         }
     }
 }
@@ -188,6 +613,21 @@ mod tests {
             .expect("sheet matches the manifest");
     }
 
+    /// Every item the built-in manifest names must be embedded, or the koala
+    /// would fail to load and there is nothing left to fall back to.
+    #[test]
+    fn builtin_items_are_embedded() {
+        let raw = read_skin(&SkinSource::Builtin).expect("built-in skin parses");
+        let kinds: Vec<ItemKind> = raw.geometry.items.iter().map(|item| item.kind).collect();
+        assert_eq!(kinds, ItemKind::ALL);
+        assert_eq!(raw.item_bytes.len(), kinds.len());
+        assert!(
+            raw.item_bytes
+                .iter()
+                .all(|bytes| bytes.starts_with(b"\x89PNG"))
+        );
+    }
+
     /// The whole point of the manifest: a skin with a different column count
     /// loads without a code change. Panda is 49 columns to koala's 61.
     #[test]
@@ -207,7 +647,9 @@ mod tests {
     /// and stayed there forever.
     #[test]
     fn shipped_skins_have_no_dead_end_states() {
-        use crate::core::brain::{BrainStep, PetBrain, plan_duration, step_brain};
+        use crate::core::brain::{
+            BrainInputs, BrainStep, PetBrain, TransitionBias, plan_duration, step_brain,
+        };
         use crate::core::rng::{PetRng, Seed};
         use std::time::Duration;
 
@@ -223,13 +665,17 @@ mod tests {
                     // clear the lock to avoid a false positive.
                     brain.locked = false;
                     let def = table.get(brain.state);
+                    let inputs = BrainInputs {
+                        playback_finished: true,
+                        dt: Duration::from_millis(50),
+                        ..BrainInputs::default()
+                    };
                     if let BrainStep::Enter(next) = step_brain(
                         &mut brain,
                         def,
-                        None,
-                        true,
-                        false,
-                        Duration::from_millis(50),
+                        &TransitionBias::NONE,
+                        table.tree(),
+                        inputs,
                         &mut rng,
                     ) {
                         seen.insert(next);
@@ -244,6 +690,138 @@ mod tests {
         }
     }
 
+    /// The koala's first chilling frame has transparent corners and a solid
+    /// middle; if masks were built wrong, one of these would flip.
+    #[test]
+    fn builtin_masks_follow_the_art() {
+        let raw = read_skin(&SkinSource::Builtin).expect("built-in skin parses");
+        let [(name, RawSheet::Png(bytes))] = raw.sheets.as_slice() else {
+            panic!("the built-in sheet is one PNG");
+        };
+        let sheet = decode_png(bytes, name).expect("sheet decodes");
+        let frames = grid_frames(&raw.geometry);
+        assert_eq!(
+            frames.len(),
+            (raw.geometry.columns * raw.geometry.rows) as usize
+        );
+
+        let first = frame_mask(&sheet, &frames[0].unwrap(), raw.geometry.frame_size);
+        assert!(!first.is_solid(UVec2::ZERO));
+        assert!(first.is_solid(raw.geometry.frame_size / 2));
+    }
+
+    /// A frame trimmed to the top-left quarter of a 40px frame is drawn up
+    /// and to the left of the pet's position, just where it was cut from.
+    #[test]
+    fn trimmed_frames_are_anchored_where_they_were_cut_from() {
+        let size = UVec2::splat(40);
+        let whole = FrameRect {
+            sheet: 0,
+            rect: URect::new(40, 0, 80, 40),
+            offset: UVec2::ZERO,
+        };
+        assert_eq!(frame_anchor(&whole, size), Vec2::ZERO);
+
+        let quarter = FrameRect {
+            sheet: 0,
+            rect: URect::new(100, 100, 120, 120),
+            offset: UVec2::ZERO,
+        };
+        // The anchor is at the quarter's bottom-right corner.
+        assert_eq!(frame_anchor(&quarter, size), Vec2::new(0.5, -0.5));
+
+        // Trimmed from the middle: a 10x20 rect at (10, 10) in the frame.
+        let middle = FrameRect {
+            sheet: 0,
+            rect: URect::new(0, 0, 10, 20),
+            offset: UVec2::new(10, 10),
+        };
+        assert_eq!(frame_anchor(&middle, size), Vec2::new(0.5, 0.0));
+    }
+
+    /// A silent mono Vorbis stream: its three headers and no audio, built by
+    /// hand. The first 58 bytes are the page holding the identification
+    /// header.
+    const SILENCE: &[u8] = b"\
+        \x4f\x67\x67\x53\x00\x02\x00\x00\x00\x00\x00\x00\x00\x00\x01\x00\
+        \x00\x00\x00\x00\x00\x00\x5f\x99\x08\x1c\x01\x1e\x01\x76\x6f\x72\
+        \x62\x69\x73\x00\x00\x00\x00\x01\x40\x1f\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x88\x01\x4f\x67\x67\x53\x00\x04\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x01\x00\x00\x00\
+        \x82\x8e\x11\xc2\x02\x10\x34\x03\x76\x6f\x72\x62\x69\x73\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x01\x05\x76\x6f\x72\x62\x69\x73\x00\x42\
+        \x43\x56\x01\x00\x02\x00\x00\x00\x00\x00\x00\x00\x10\x00\x00\x3a\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01";
+
+    /// Only an Ogg Vorbis stream that decodes to the end gets past the check;
+    /// anything else would panic Bevy's decoder the first time it played.
+    #[test]
+    fn sounds_must_be_ogg_vorbis() {
+        let page = |packet: &[u8]| {
+            let mut bytes = b"OggS\0\x02".to_vec();
+            bytes.resize(26, 0);
+            bytes.push(1);
+            bytes.push(packet.len() as u8);
+            bytes.extend_from_slice(packet);
+            bytes
+        };
+        assert!(decode_sound(SILENCE.to_vec(), "purr.ogg").is_ok());
+        assert!(matches!(
+            decode_sound(page(b"OpusHead"), "purr.ogg"),
+            Err(SkinError::Sound {
+                reason: "not Vorbis audio",
+                ..
+            })
+        ));
+        assert!(matches!(
+            decode_sound(BUILTIN_SHEET.to_vec(), "sheet.png"),
+            Err(SkinError::Sound {
+                reason: "not an Ogg file",
+                ..
+            })
+        ));
+    }
+
+    /// A file that starts like Vorbis but goes on as anything else is caught
+    /// at load, not when it first plays.
+    #[test]
+    fn a_corrupt_sound_is_an_error_at_load() {
+        let mut garbled = SILENCE[..58].to_vec();
+        garbled.extend_from_slice(&[0x5a; 64]);
+        assert!(matches!(
+            decode_sound(garbled, "purr.ogg"),
+            Err(SkinError::Sound {
+                reason: "the Vorbis headers are corrupt",
+                ..
+            })
+        ));
+        let truncated = SILENCE[..SILENCE.len() - 20].to_vec();
+        assert!(decode_sound(truncated, "purr.ogg").is_err());
+    }
+
+    /// A trimmed frame's mask still covers the whole frame, solid only where
+    /// the rect was.
+    #[test]
+    fn trimmed_masks_cover_the_untrimmed_frame() {
+        let pixels = SheetPixels {
+            size: UVec2::new(4, 2),
+            rgba: [255; 4 * 2 * 4].to_vec(),
+        };
+        let sheet = sheet_image(pixels);
+        let frame = FrameRect {
+            sheet: 0,
+            rect: URect::new(2, 0, 4, 2),
+            offset: UVec2::new(3, 1),
+        };
+        let mask = frame_mask(&sheet, &frame, UVec2::splat(6));
+        assert!(mask.is_solid(UVec2::new(3, 1)));
+        assert!(mask.is_solid(UVec2::new(4, 2)));
+        assert!(!mask.is_solid(UVec2::new(2, 1)));
+        assert!(!mask.is_solid(UVec2::new(5, 1)));
+        assert!(!mask.is_solid(UVec2::new(3, 3)));
+    }
+
     #[test]
     fn missing_skin_directory_is_an_error_not_a_panic() {
         let dir = PathBuf::from("/nonexistent/skin/dir");
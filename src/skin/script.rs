@@ -0,0 +1,365 @@
+//! Skin scripts: behaviour a table of weights cannot say.
+//!
+//! A skin may ship a Rhai script defining any of three hooks, each called for
+//! one pet at a time:
+//!
+//! - `on_enter(state)` just after the pet enters `state`, by name;
+//! - `on_intent(kind)` after the app has handled something the user did to
+//!   the pet, such as `"pet"` or `"feed"`;
+//! - `on_tick(dt)` every frame the pets are not paused, `dt` in seconds.
+//!
+//! A hook acts through a handful of functions and nothing else: `state()`,
+//! `x()` and `y()` read the pet; `enter(state)`, `walk_to(x, y)` and
+//! `say(text)` ask for something; `roll(n)` draws from the pet RNG, so a
+//! seeded run still reproduces. What a hook asks for is applied after the
+//! app's own handling, so it has the last word.
+//!
+//! The engine is sandboxed: no modules, so no file access; no clock; no
+//! `eval`; and limits on how long a hook runs and how much it allocates.
+//! Only the hooks run. Anything outside a function is never evaluated.
+
+use bevy::prelude::*;
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use super::manifest::SkinError;
+use crate::core::brain::PetState;
+use crate::core::rng::{PetRng, Seed};
+
+/// Operations one hook may take before it is stopped, so a loop that never
+/// ends costs one frame rather than the app.
+const MAX_OPERATIONS: u64 = 10_000;
+const MAX_CALL_LEVELS: usize = 16;
+/// Characters in a string, entries in an array or a map.
+const MAX_SIZE: usize = 1024;
+
+/// Something a hook asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptAction {
+    /// Interrupt into a state. Never `Dragged`, which the pointer owns.
+    Enter(PetState),
+    /// Walk to a point in world space.
+    WalkTo(Vec2),
+    Say(String),
+}
+
+/// A hook, and what it is called with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hook {
+    Enter(PetState),
+    Intent(&'static str),
+    Tick(Duration),
+}
+
+impl Hook {
+    /// The script functions, in [`Hook::index`] order.
+    const NAMES: [&str; 3] = ["on_enter", "on_intent", "on_tick"];
+
+    fn index(self) -> usize {
+        match self {
+            Hook::Enter(_) => 0,
+            Hook::Intent(_) => 1,
+            Hook::Tick(_) => 2,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        Self::NAMES[self.index()]
+    }
+}
+
+/// What a hook can see of the pet it was called for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PetView {
+    pub state: PetState,
+    pub position: Vec2,
+}
+
+/// One call in progress, shared with the functions the engine exposes. They
+/// must be `'static`, so they cannot borrow the caller's RNG: it is swapped in
+/// here for the length of the call and back out after.
+#[derive(Debug)]
+struct Call {
+    pet: PetView,
+    actions: Vec<ScriptAction>,
+    rng: PetRng,
+}
+
+/// A compiled skin script, ready to call.
+#[derive(Debug, Clone)]
+pub struct SkinScript {
+    engine: Arc<Engine>,
+    ast: AST,
+    call: Arc<Mutex<Call>>,
+    /// Which hooks the script defines, by [`Hook::index`]. Calling one that
+    /// is missing would be an error.
+    defined: [bool; 3],
+    /// Set by the first error. A broken `on_tick` would otherwise fail, and
+    /// be reported, every frame.
+    failed: Arc<AtomicBool>,
+}
+
+impl SkinScript {
+    /// Compiles one of a skin's scripts as read from disk, naming it in the
+    /// error.
+    pub fn load(bytes: &[u8], path: &str) -> Result<Self, SkinError> {
+        let source = std::str::from_utf8(bytes).map_err(|error| SkinError::Script {
+            path: path.to_string(),
+            message: error.to_string(),
+        })?;
+        Self::compile(source, path)
+    }
+
+    /// Compiles `source`, naming `path` in the error.
+    pub fn compile(source: &str, path: &str) -> Result<Self, SkinError> {
+        let call = Arc::new(Mutex::new(Call {
+            pet: PetView {
+                state: PetState::Idle,
+                position: Vec2::ZERO,
+            },
+            actions: Vec::new(),
+            // Never drawn from: the caller's RNG replaces it for every call.
+            rng: PetRng::from_seed(Seed(1)),
+        }));
+        let engine = sandbox(&call);
+        let ast = engine.compile(source).map_err(|error| SkinError::Script {
+            path: path.to_string(),
+            message: error.to_string(),
+        })?;
+
+        let defined = Hook::NAMES.map(|name| {
+            ast.iter_functions()
+                .any(|f| f.name == name && f.params.len() == 1)
+        });
+        Ok(Self {
+            engine: Arc::new(engine),
+            ast,
+            call,
+            defined,
+            failed: Arc::default(),
+        })
+    }
+
+    /// Whether the script defines `hook`, so a caller can skip gathering
+    /// what it would be called with.
+    pub fn defines(&self, hook: Hook) -> bool {
+        self.defined[hook.index()]
+    }
+
+    /// Calls `hook` for `pet`, returning what it asked for in the order it
+    /// asked. A script without the hook asks for nothing, and so does one
+    /// that has failed before: only its first error is returned.
+    pub fn run(
+        &self,
+        hook: Hook,
+        pet: PetView,
+        rng: &mut PetRng,
+    ) -> Result<Vec<ScriptAction>, Box<EvalAltResult>> {
+        if !self.defines(hook) || self.failed.load(Ordering::Relaxed) {
+            return Ok(Vec::new());
+        }
+        {
+            let mut call = lock(&self.call);
+            call.pet = pet;
+            call.actions.clear();
+            std::mem::swap(&mut call.rng, rng);
+        }
+        let argument: Dynamic = match hook {
+            Hook::Enter(state) => state_name(state).into(),
+            Hook::Intent(kind) => kind.into(),
+            Hook::Tick(dt) => dt.as_secs_f64().into(),
+        };
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            CallFnOptions::new().eval_ast(false),
+            &mut Scope::new(),
+            &self.ast,
+            hook.name(),
+            (argument,),
+        );
+
+        let mut call = lock(&self.call);
+        std::mem::swap(&mut call.rng, rng);
+        let actions = std::mem::take(&mut call.actions);
+        if result.is_err() {
+            self.failed.store(true, Ordering::Relaxed);
+        }
+        result.map(|_| actions)
+    }
+}
+
+/// A panic inside a hook's function cannot leave `Call` half-written in a
+/// way that matters: every call resets it first.
+fn lock(call: &Mutex<Call>) -> MutexGuard<'_, Call> {
+    call.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// An engine exposing only the pet API, within limits.
+fn sandbox(call: &Arc<Mutex<Call>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_SIZE)
+        .set_max_array_size(MAX_SIZE)
+        .set_max_map_size(MAX_SIZE)
+        .disable_symbol("eval")
+        .on_print(|text| info!("skin script: {text}"))
+        .on_debug(|text, _, at| debug!("skin script at {at}: {text}"));
+
+    let shared = Arc::clone(call);
+    engine.register_fn("state", move || state_name(lock(&shared).pet.state));
+    let shared = Arc::clone(call);
+    engine.register_fn("x", move || f64::from(lock(&shared).pet.position.x));
+    let shared = Arc::clone(call);
+    engine.register_fn("y", move || f64::from(lock(&shared).pet.position.y));
+
+    let shared = Arc::clone(call);
+    engine.register_fn("enter", move |name: &str| {
+        let state = state_named(name)
+            .filter(|&state| state != PetState::Dragged)
+            .ok_or_else(|| format!("enter: no state {name:?} a script can enter"))?;
+        lock(&shared).actions.push(ScriptAction::Enter(state));
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    let shared = Arc::clone(call);
+    engine.register_fn("walk_to", move |x: Dynamic, y: Dynamic| {
+        let to = Vec2::new(number(x)?, number(y)?);
+        lock(&shared).actions.push(ScriptAction::WalkTo(to));
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    let shared = Arc::clone(call);
+    engine.register_fn("say", move |text: &str| {
+        lock(&shared)
+            .actions
+            .push(ScriptAction::Say(text.to_string()));
+    });
+
+    let shared = Arc::clone(call);
+    engine.register_fn("roll", move |bound: i64| {
+        let bound = u32::try_from(bound)
+            .ok()
+            .filter(|&bound| bound > 0)
+            .ok_or_else(|| format!("roll: {bound} is not a positive bound"))?;
+        Ok::<_, Box<EvalAltResult>>(i64::from(lock(&shared).rng.roll(bound)))
+    });
+    engine
+}
+
+/// A coordinate as a script writes it, `3` or `3.5` alike.
+fn number(value: Dynamic) -> Result<f32, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|int| int as f64))
+        .map(|float| float as f32)
+        .map_err(|type_name| format!("expected a number, got {type_name}").into())
+}
+
+fn state_name(state: PetState) -> String {
+    format!("{state:?}")
+}
+
+fn state_named(name: &str) -> Option<PetState> {
+    PetState::ALL
+        .into_iter()
+        .find(|&state| state_name(state) == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(source: &str) -> SkinScript {
+        SkinScript::compile(source, "test.rhai").expect("compiles")
+    }
+
+    fn pet() -> PetView {
+        PetView {
+            state: PetState::Idle,
+            position: Vec2::new(10.0, -5.0),
+        }
+    }
+
+    #[test]
+    fn hooks_ask_for_actions_in_order() {
+        let script = compile(
+            r#"
+            fn on_intent(kind) {
+                if kind == "poke" {
+                    say("hey " + state());
+                    walk_to(x() + 100, 0);
+                    enter("Jumping");
+                }
+            }
+            "#,
+        );
+        let mut rng = PetRng::from_seed(Seed(1));
+        let actions = script
+            .run(Hook::Intent("poke"), pet(), &mut rng)
+            .expect("runs");
+        assert_eq!(
+            actions,
+            [
+                ScriptAction::Say("hey Idle".to_string()),
+                ScriptAction::WalkTo(Vec2::new(110.0, 0.0)),
+                ScriptAction::Enter(PetState::Jumping),
+            ]
+        );
+        let ignored = script.run(Hook::Intent("pet"), pet(), &mut rng);
+        assert!(ignored.expect("runs").is_empty());
+    }
+
+    #[test]
+    fn missing_hooks_are_skipped() {
+        let script = compile("fn on_tick(dt) { enter(\"Sitting\"); }");
+        assert!(script.defines(Hook::Tick(Duration::ZERO)));
+        assert!(!script.defines(Hook::Enter(PetState::Idle)));
+        let mut rng = PetRng::from_seed(Seed(1));
+        let entered = script.run(Hook::Enter(PetState::Idle), pet(), &mut rng);
+        assert!(entered.expect("runs").is_empty());
+    }
+
+    #[test]
+    fn rolls_come_from_the_pet_rng() {
+        let script = compile("fn on_tick(dt) { say(`${roll(1000)} ${roll(1000)}`); }");
+        let said = |seed| {
+            let mut rng = PetRng::from_seed(Seed(seed));
+            let actions = script.run(Hook::Tick(Duration::ZERO), pet(), &mut rng);
+            (actions.expect("runs"), rng.roll(1000))
+        };
+        assert_eq!(said(7), said(7), "seeded runs reproduce");
+        assert_ne!(said(7), said(8));
+    }
+
+    #[test]
+    fn a_failed_script_is_switched_off() {
+        let script = compile("fn on_tick(dt) { say(\"once\"); roll(-1); }");
+        let mut rng = PetRng::from_seed(Seed(1));
+        let tick = Hook::Tick(Duration::ZERO);
+        assert!(script.run(tick, pet(), &mut rng).is_err());
+        assert!(script.run(tick, pet(), &mut rng).expect("quiet").is_empty());
+    }
+
+    #[test]
+    fn scripts_are_sandboxed() {
+        let mut rng = PetRng::from_seed(Seed(1));
+        let run = |source: &str, rng: &mut PetRng| {
+            compile(source).run(Hook::Tick(Duration::ZERO), pet(), rng)
+        };
+        assert!(run("fn on_tick(dt) { loop {} }", &mut rng).is_err());
+        assert!(run("fn on_tick(dt) { enter(\"Dragged\"); }", &mut rng).is_err());
+        assert!(run("fn on_tick(dt) { enter(\"Flying\"); }", &mut rng).is_err());
+        assert!(run("fn on_tick(dt) { roll(0); }", &mut rng).is_err());
+        // Refused when compiled or when run, either will do.
+        for source in [
+            "fn on_tick(dt) { eval(\"say(`hi`)\") }",
+            "import \"secrets\" as s; fn on_tick(dt) { s::leak() }",
+            "fn on_tick(dt) { say(`${timestamp()}`) }",
+        ] {
+            let ran = SkinScript::compile(source, "x")
+                .map(|script| script.run(Hook::Tick(Duration::ZERO), pet(), &mut rng));
+            assert!(!matches!(ran, Ok(Ok(_))), "should refuse {source}");
+        }
+    }
+}
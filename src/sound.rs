@@ -0,0 +1,67 @@
+//! ECS glue for sound effects: playing the skin's clip for each cue.
+//!
+//! What counts as a cue and how often one may repeat is decided in
+//! `core::sound`. Playback is Bevy's: each clip is an entity that despawns
+//! itself when it ends.
+
+use bevy::audio::Volume;
+use bevy::prelude::*;
+
+use crate::config::Config;
+use crate::core::PetSystems;
+use crate::core::input::Intent;
+use crate::core::sound::{SoundCue, SoundLimiter};
+use crate::pet::StateEntered;
+use crate::skin::Skin;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        // After the brain, so a state entered this frame is heard this frame.
+        app.add_systems(Update, play_cues.after(PetSystems::Brain));
+    }
+}
+
+/// The cue an intent raises, if any.
+fn intent_cue(intent: &Intent) -> Option<SoundCue> {
+    match intent {
+        Intent::Pet { .. } => Some(SoundCue::Pet),
+        Intent::Poke { .. } => Some(SoundCue::Poke),
+        Intent::Grab { .. } => Some(SoundCue::Grab),
+        Intent::Release { .. } => Some(SoundCue::Release),
+        _ => None,
+    }
+}
+
+/// Plays the skin's clip for every cue raised this frame, at the configured
+/// volume.
+///
+/// Messages are read even while muted, so unmuting does not replay a backlog.
+fn play_cues(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<Config>,
+    skin: Res<Skin>,
+    mut intents: MessageReader<Intent>,
+    mut entries: MessageReader<StateEntered>,
+    mut limiter: Local<SoundLimiter>,
+) {
+    let cues = intents
+        .read()
+        .filter_map(intent_cue)
+        .chain(entries.read().map(|entry| SoundCue::Enter(entry.state)));
+    let level = config.sound.level();
+    for cue in cues {
+        let (Some(volume), Some(source)) = (level, skin.sound(cue)) else {
+            continue;
+        };
+        if !limiter.allow(cue, time.elapsed()) {
+            continue;
+        }
+        commands.spawn((
+            AudioPlayer(source.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::Linear(volume)),
+        ));
+    }
+}
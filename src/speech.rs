@@ -0,0 +1,169 @@
+//! ECS glue for speech bubbles: who says what, and drawing it above them.
+//!
+//! Wrapping, timing and where a bubble sits are decided in `core::speech`.
+//! A pet's [`Speech`] is the bubble as far as the rest of the app is
+//! concerned; the sprite and text drawn for it are only a mirror, redrawn
+//! when it changes, as the context menu is.
+
+use bevy::prelude::*;
+
+use crate::core::PetSystems;
+use crate::core::brain::StateTable;
+use crate::core::coords::SurfaceOrigin;
+use crate::core::input::Intent;
+use crate::core::rng::PetRng;
+use crate::core::speech::{FONT_SIZE, Speech, pick_phrase};
+use crate::pet::{Pet, StateEntered, speech_rect};
+use crate::skin::Skin;
+
+const BACKGROUND: Color = Color::srgba(0.98, 0.98, 0.96, 0.95);
+const TEXT: Color = Color::srgb(0.1, 0.1, 0.12);
+
+/// Above every pet, whose draw order counts up from zero.
+const BUBBLE_Z: f32 = 500.0;
+
+/// Ask for a pet to say something.
+#[derive(Message, Debug, Clone)]
+pub struct Say {
+    /// The pet to speak, or `None` for the oldest.
+    pub pet: Option<Entity>,
+    pub text: String,
+}
+
+pub struct SpeechPlugin;
+
+impl Plugin for SpeechPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Say>()
+            .add_systems(
+                Update,
+                (speak_on_entry, start_speech)
+                    .chain()
+                    .after(PetSystems::Brain),
+            )
+            .add_systems(
+                Update,
+                (dismiss_speech, tick_speech)
+                    .chain()
+                    .after(PetSystems::Normalize)
+                    .before(start_speech),
+            )
+            .add_systems(PostUpdate, draw_bubbles);
+    }
+}
+
+/// The drawn bubble of one pet's [`Speech`].
+#[derive(Component)]
+struct Bubble {
+    pet: Entity,
+}
+
+/// Sometimes says one of a state's phrases as a pet enters it.
+///
+/// A pet already speaking is left to finish, so a script's message is not cut
+/// short by small talk.
+fn speak_on_entry(
+    mut entries: MessageReader<StateEntered>,
+    table: Res<StateTable>,
+    mut rng: ResMut<PetRng>,
+    speaking: Query<(), With<Speech>>,
+    mut says: MessageWriter<Say>,
+) {
+    for entry in entries.read() {
+        if speaking.contains(entry.pet) {
+            continue;
+        }
+        if let Some(phrase) = pick_phrase(&table.get(entry.state).phrases, &mut rng) {
+            says.write(Say {
+                pet: Some(entry.pet),
+                text: phrase.to_string(),
+            });
+        }
+    }
+}
+
+/// Gives the asked-for pet its bubble, replacing whatever it was saying.
+fn start_speech(
+    mut commands: Commands,
+    mut says: MessageReader<Say>,
+    pets: Query<(Entity, &Transform), With<Pet>>,
+) {
+    for say in says.read() {
+        // Draw order is spawn order, so the lowest is the oldest pet.
+        let pet = say.pet.filter(|&pet| pets.contains(pet)).or_else(|| {
+            pets.iter()
+                .min_by(|a, b| a.1.translation.z.total_cmp(&b.1.translation.z))
+                .map(|(pet, _)| pet)
+        });
+        if let (Some(pet), Some(speech)) = (pet, Speech::new(&say.text)) {
+            commands.entity(pet).insert(speech);
+        }
+    }
+}
+
+/// Hides a bubble that was clicked.
+fn dismiss_speech(mut commands: Commands, mut intents: MessageReader<Intent>) {
+    for intent in intents.read() {
+        if let Intent::DismissSpeech { pet } = *intent {
+            commands.entity(pet).try_remove::<Speech>();
+        }
+    }
+}
+
+/// Hides bubbles that have been shown long enough.
+fn tick_speech(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut speakers: Query<(Entity, &mut Speech)>,
+) {
+    for (pet, mut speech) in &mut speakers {
+        if !speech.tick(time.delta()) {
+            commands.entity(pet).remove::<Speech>();
+        }
+    }
+}
+
+/// Keeps a drawn bubble over each speaking pet, and none over the others.
+fn draw_bubbles(
+    mut commands: Commands,
+    surface: Option<Res<SurfaceOrigin>>,
+    skin: Res<Skin>,
+    speakers: Query<(Entity, &Transform, Ref<Speech>), With<Pet>>,
+    mut drawn: Query<(Entity, &Bubble, &mut Transform), Without<Pet>>,
+) {
+    let Some(surface) = surface else { return };
+
+    let mut kept = Vec::new();
+    for (bubble, drawn_for, mut transform) in &mut drawn {
+        match speakers.get(drawn_for.pet) {
+            // Still saying the same thing: follow the pet.
+            Ok((_, pet_transform, speech)) if !speech.is_changed() => {
+                let rect = speech_rect(&skin, pet_transform, &speech, surface.size);
+                transform.translation = rect.center().extend(BUBBLE_Z);
+                kept.push(drawn_for.pet);
+            }
+            _ => commands.entity(bubble).despawn(),
+        }
+    }
+
+    for (pet, transform, speech) in &speakers {
+        if kept.contains(&pet) {
+            continue;
+        }
+        let rect = speech_rect(&skin, transform, &speech, surface.size);
+        commands
+            .spawn((
+                Bubble { pet },
+                Sprite::from_color(BACKGROUND, rect.size()),
+                Transform::from_translation(rect.center().extend(BUBBLE_Z)),
+            ))
+            .with_child((
+                Text2d::new(speech.lines.join("\n")),
+                TextFont::from_font_size(FONT_SIZE),
+                TextColor(TEXT),
+                // Already wrapped to the width the bubble was sized for.
+                TextLayout::no_wrap(),
+                Transform::from_xyz(0.0, 0.0, 1.0),
+            ));
+    }
+}
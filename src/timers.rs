@@ -0,0 +1,142 @@
+//! ECS glue for timers: counting them, and having a pet deliver each one.
+//!
+//! When a timer fires is decided in `core::timers`. Delivering it is an errand
+//! to the middle of the screen: the pet nearest it walks there with the
+//! ordinary `Walking` state, plays the timer's state, and says its message in
+//! a bubble that stays until clicked.
+//!
+//! Timers count `Time<Real>`. The default clock caps each frame's step, so a
+//! hitch, or the laptop sleeping, would push every timer back by the gap.
+
+use bevy::prelude::*;
+
+use crate::config::Config;
+use crate::core::PetSystems;
+use crate::core::brain::{PetBrain, PetState};
+use crate::core::items::{Errand, ErrandStep, step_errand};
+use crate::core::speech::Speech;
+use crate::core::timers::{Fired, Summons, TimerSpec, Timers, pick_messenger};
+use crate::pet::{MoveTarget, PendingInterrupt, Pet};
+
+/// Ask for a timer to start, counting from now.
+#[derive(Message, Debug, Clone)]
+pub struct AddTimer(pub TimerSpec);
+
+pub struct TimerPlugin;
+
+impl Plugin for TimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<AddTimer>()
+            .init_resource::<Timers>()
+            .add_systems(Startup, start_configured_timers)
+            .add_systems(
+                Update,
+                // Like errands, summonses are settled before new ones are
+                // handed out, so one started this frame is not read as
+                // abandoned before its walk begins.
+                (run_summons, add_timers, fire_timers)
+                    .chain()
+                    .after(PetSystems::Normalize)
+                    .before(PetSystems::Brain),
+            );
+    }
+}
+
+fn start_configured_timers(config: Res<Config>, time: Res<Time<Real>>, mut timers: ResMut<Timers>) {
+    for spec in &config.timers {
+        timers.add(spec.clone(), time.elapsed());
+    }
+}
+
+fn add_timers(
+    mut added: MessageReader<AddTimer>,
+    time: Res<Time<Real>>,
+    mut timers: ResMut<Timers>,
+) {
+    for AddTimer(spec) in added.read() {
+        timers.add(spec.clone(), time.elapsed());
+    }
+}
+
+/// Sends the pet nearest the middle of the screen to deliver each timer that
+/// went off this frame.
+fn fire_timers(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut timers: ResMut<Timers>,
+    mut pets: Query<
+        (
+            Entity,
+            &PetBrain,
+            &Transform,
+            &mut PendingInterrupt,
+            &mut MoveTarget,
+        ),
+        With<Pet>,
+    >,
+) {
+    for Fired { message, state } in timers.due(time.elapsed()) {
+        // A held pet is the user's, and one asleep for an absent user stays
+        // put. Either can still say the message where it is.
+        let mut unlocked = Vec::new();
+        let mut all = Vec::new();
+        for (pet, brain, transform, ..) in &pets {
+            let at = transform.translation.truncate();
+            all.push((pet, at));
+            if !brain.locked {
+                unlocked.push((pet, at));
+            }
+        }
+
+        if let Some(pet) = pick_messenger(&unlocked)
+            && let Ok((.., mut interrupt, mut target)) = pets.get_mut(pet)
+        {
+            // Walking somewhere else ends any errand; dropping it here lets
+            // the item go to another pet straight away.
+            target.0 = Some(Vec2::ZERO);
+            interrupt.0 = Some(PetState::Walking);
+            commands.entity(pet).remove::<Errand>().insert(Summons {
+                message,
+                then: state,
+            });
+        } else if let Some(pet) = pick_messenger(&all)
+            && let Some(speech) = Speech::pinned(&message)
+        {
+            commands.entity(pet).insert(speech);
+        }
+    }
+}
+
+type SummonsData<'a> = (
+    Entity,
+    &'a PetBrain,
+    &'a Summons,
+    &'a Transform,
+    &'a MoveTarget,
+    &'a mut PendingInterrupt,
+);
+
+/// Delivers timers whose pet arrived, and those whose pet was taken away on
+/// the way, where it stands: a reminder is not dropped because the pet was.
+fn run_summons(mut commands: Commands, mut pets: Query<SummonsData, With<Pet>>) {
+    for (pet, brain, summons, transform, target, mut interrupt) in &mut pets {
+        // As for an errand, a pending interrupt is the state the pet is about
+        // to be in.
+        let state = interrupt.0.unwrap_or(brain.state);
+        let distance = transform.translation.truncate().length();
+
+        let arrived = match step_errand(state, target.0.is_some(), distance) {
+            ErrandStep::Underway => continue,
+            ErrandStep::Arrived => true,
+            ErrandStep::Abandoned => false,
+        };
+        if arrived {
+            interrupt.0 = Some(summons.then);
+        }
+        let mut pet = commands.entity(pet);
+        pet.remove::<Summons>();
+        if let Some(speech) = Speech::pinned(&summons.message) {
+            pet.insert(speech);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! ECS glue for typing: pets reacting to how fast the user types.
+//!
+//! The rate, the pace and what a change of pace means are all `core::typing`;
+//! counting is the platform's. Counting is opt-in, and where it is off or the
+//! platform refuses there is no source and nothing here runs.
+
+use bevy::prelude::*;
+
+use crate::config::Config;
+use crate::core::PetSystems;
+use crate::core::brain::{PetBrain, PetState};
+use crate::core::input::GestureState;
+use crate::core::items::{Errand, ErrandStep, step_errand};
+use crate::core::timers::{Summons, pick_messenger};
+use crate::core::typing::{
+    Companion, KeystrokeSource, TypingMood, TypingRate, TypingReaction, beside_cursor,
+};
+use crate::pet::{MoveTarget, PendingInterrupt, Pet};
+use crate::platform::keyboard;
+use crate::skin::Skin;
+
+/// Where key presses are counted, for as long as the app counts them.
+#[derive(Resource)]
+struct Keystrokes(Box<dyn KeystrokeSource>);
+
+pub struct TypingPlugin;
+
+impl Plugin for TypingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TypingRate>()
+            .init_resource::<TypingMood>()
+            .add_systems(Startup, start_counting)
+            .add_systems(
+                Update,
+                // As for summonses, a companion is settled before a new one
+                // is sent.
+                (run_companions, react_to_typing)
+                    .chain()
+                    .run_if(resource_exists::<Keystrokes>)
+                    .after(PetSystems::Normalize)
+                    .before(PetSystems::Brain),
+            );
+    }
+}
+
+fn start_counting(mut commands: Commands, config: Res<Config>) {
+    if !config.typing.enabled {
+        return;
+    }
+    if let Some(source) = keyboard::listen() {
+        commands.insert_resource(Keystrokes(source));
+    }
+}
+
+type TypingPetData<'a> = (
+    Entity,
+    &'a PetBrain,
+    &'a Transform,
+    &'a mut PendingInterrupt,
+    &'a mut MoveTarget,
+    Has<Errand>,
+    Has<Summons>,
+    Has<Companion>,
+);
+
+/// Counts this frame's key presses and has pets react to a change of pace.
+#[allow(clippy::too_many_arguments)]
+fn react_to_typing(
+    mut commands: Commands,
+    mut source: ResMut<Keystrokes>,
+    mut rate: ResMut<TypingRate>,
+    mut mood: ResMut<TypingMood>,
+    time: Res<Time>,
+    config: Res<Config>,
+    skin: Res<Skin>,
+    gesture: Res<GestureState>,
+    mut pets: Query<TypingPetData, With<Pet>>,
+) {
+    let now = time.elapsed();
+    rate.record(now, source.0.poll());
+    let Some(reaction) = mood.update(&config.typing, rate.per_second, now) else {
+        return;
+    };
+
+    match reaction {
+        TypingReaction::Everyone(state) => {
+            // Pets on an errand finish it; held and sleeping pets stay put.
+            for (_, brain, _, mut interrupt, _, errand, summons, companion) in &mut pets {
+                if !brain.locked && interrupt.0.is_none() && !errand && !summons && !companion {
+                    interrupt.0 = Some(state);
+                }
+            }
+        }
+        TypingReaction::Beside(state) => {
+            // Without a cursor over the screen there is nowhere to sit.
+            let Some(cursor) = gesture.cursor else {
+                return;
+            };
+            let cursor = cursor.0;
+            let free: Vec<_> = pets
+                .iter()
+                .filter(|(_, brain, _, interrupt, _, errand, summons, companion)| {
+                    !brain.locked && interrupt.0.is_none() && !errand && !summons && !companion
+                })
+                .map(|(pet, _, transform, ..)| (pet, transform.translation.truncate() - cursor))
+                .collect();
+            // Nearest the cursor, the same choice as a timer's messenger.
+            let Some(pet) = pick_messenger(&free) else {
+                return;
+            };
+            let Ok((_, _, transform, mut interrupt, mut target, ..)) = pets.get_mut(pet) else {
+                return;
+            };
+            let gap = skin.frame_size().x * transform.scale.x;
+            let at = beside_cursor(cursor, transform.translation.truncate(), gap);
+            target.0 = Some(at);
+            interrupt.0 = Some(PetState::Walking);
+            commands.entity(pet).insert(Companion { at, then: state });
+        }
+    }
+}
+
+type CompanionData<'a> = (
+    Entity,
+    &'a PetBrain,
+    &'a Companion,
+    &'a Transform,
+    &'a MoveTarget,
+    &'a mut PendingInterrupt,
+);
+
+/// Has a companion that arrived play its state there. One taken away on the
+/// way just stops being a companion.
+fn run_companions(mut commands: Commands, mut pets: Query<CompanionData, With<Pet>>) {
+    for (pet, brain, companion, transform, target, mut interrupt) in &mut pets {
+        // As for an errand, a pending interrupt is the state the pet is about
+        // to be in.
+        let state = interrupt.0.unwrap_or(brain.state);
+        let distance = transform.translation.truncate().distance(companion.at);
+
+        match step_errand(state, target.0.is_some(), distance) {
+            ErrandStep::Underway => continue,
+            ErrandStep::Arrived => interrupt.0 = Some(companion.then),
+            ErrandStep::Abandoned => {}
+        }
+        commands.entity(pet).remove::<Companion>();
+    }
+}